@@ -3,7 +3,7 @@ use once_cell::sync::OnceCell;
 use anyhow::{Context, Result};
 
 mod server_config;
-pub use server_config::Config;
+pub use server_config::{Config, WorldConfig};
 
 // 在编译时将资源文件嵌入到可执行文件中
 const DEFAULT_CONFIG: &str = include_str!("../../../../assets/data/configs/main-config.toml");
@@ -107,4 +107,32 @@ pub fn reload_global_config() -> Result<()> {
     
     log::info!("Configuration reloaded successfully");
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 去掉后来新增的配置项,模拟旧版本生成的配置文件
+    fn old_config(keys: &[&str]) -> String {
+        DEFAULT_CONFIG
+            .lines()
+            .filter(|line| !keys.iter().any(|k| line.trim_start().starts_with(&format!("{} =", k))))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn parse_config_without_world_generator() {
+        let config: Config = toml::from_str(&old_config(&["seed", "generator", "flat_preset"])).unwrap();
+        let default = Config::default();
+        assert_eq!(config.game.world.seed, default.game.world.seed);
+        assert_eq!(config.game.world.generator, default.game.world.generator);
+        assert_eq!(config.game.world.flat_preset, default.game.world.flat_preset);
+    }
+
+    #[test]
+    fn parse_config_without_observed_state() {
+        let config: Config = toml::from_str(&old_config(&["observed_state"])).unwrap();
+        assert!(config.game.observed_state);
+    }
+}
@@ -68,6 +68,9 @@ pub struct GameConfig {
     pub network_compression_threshold: i32,
     pub verify_decompressed_packets: bool,
     pub chunk_render_distance: u8,
+    /// 玩家观察态:没有玩家观察的区块延迟处理方块更新
+    #[serde(default = "default_true")]
+    pub observed_state: bool,
     pub plugin_sync: bool,
     pub plugin_config_sync: bool,
     #[serde(rename="online-mode")]
@@ -85,6 +88,26 @@ pub struct WorldConfig {
     pub cache_ttl: u64,
     pub cache_capacity: u64,
     pub is_show_world_seed: bool,
+    #[serde(default)]
+    pub seed: i64,
+    /// 世界生成器: noise / flat / void
+    #[serde(default = "default_generator")]
+    pub generator: String,
+    /// 超平坦世界的预设字符串
+    #[serde(default = "default_flat_preset")]
+    pub flat_preset: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_generator() -> String {
+    "noise".to_string()
+}
+
+fn default_flat_preset() -> String {
+    "minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains".to_string()
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Dimensions {
@@ -207,6 +230,7 @@ impl Default for Config {
                 network_compression_threshold: 64,
                 verify_decompressed_packets: true,
                 chunk_render_distance: 12,
+                observed_state: true,
                 plugin_sync: true,
                 plugin_config_sync: false,
                 online_mode:true,
@@ -218,6 +242,9 @@ impl Default for Config {
                     cache_ttl: 60,
                     cache_capacity: 20_000,
                     is_show_world_seed: false,
+                    seed: 0,
+                    generator: default_generator(),
+                    flat_preset: default_flat_preset(),
                 },
                 dimensions: Dimensions {
                     allow_end: false,
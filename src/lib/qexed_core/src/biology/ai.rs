@@ -0,0 +1,1007 @@
+//! 生物 AI
+//!
+//! 与原版相同,每个生物有两个目标选择器:`goals` 决定行为(游荡、看向玩家、近战、逃跑、跟随),
+//! `targets` 决定攻击目标。每个目标占用若干控制位([`flag`]),优先级高(数值小)的目标可以打断
+//! 占用相同控制位的低优先级目标。目标只在 [`AiState`] 中写入路径与视线,由 [`tick_ai`]
+//! 最后统一换算成速度与朝向,之后的物理由 [`EntityStore::tick_world`] 处理。
+//!
+//! 寻路很昂贵,每刻所有生物共用 [`PATH_NODE_BUDGET`] 个节点,用完后本刻的寻路请求失败,
+//! 目标在之后的刻重试;生物的处理顺序每刻打乱,避免总是同一批生物拿不到预算。
+
+use std::{collections::HashMap, sync::Arc};
+
+use qexed_world::{
+    BlockPos, BlockSource, Dimension, block_registry,
+    pathfind::{self, NodeType, PathOptions},
+    physics,
+};
+use rand::{Rng, RngCore, seq::SliceRandom};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    biology::{
+        entity::{AiState, Attributes, BoundingBox, EntityStore, Health, Position, Velocity},
+        mob::{MobCategory, MobKind},
+        player::Player,
+    },
+    event::entity_tracker::EntitySnapshot,
+    utils::entity_ids::EntityHandle,
+};
+
+/// 每刻所有生物寻路可以访问的节点总数
+pub const PATH_NODE_BUDGET: usize = 2000;
+/// 受伤后记住伤害来源的刻数(与原版相同)
+pub const HURT_MEMORY: u32 = 100;
+/// 近战攻击的间隔(刻)
+const ATTACK_INTERVAL: u32 = 20;
+/// 与路径节点的水平距离小于这个值时视为到达
+const NODE_REACHED: f64 = 0.5;
+/// 在一个节点上停留超过这么多刻仍未到达下一个节点时放弃路径
+const STUCK_TICKS: u32 = 100;
+/// 寻路的最远距离
+const PATH_RANGE: f64 = 48.0;
+/// 着地时的加速度系数,与原版 `getFrictionInfluencedSpeed` 相同:0.216 / (0.6 × 0.91)³
+const GROUND_ACCELERATION: f64 = 0.216 / (0.546 * 0.546 * 0.546);
+/// 空中的加速度系数
+const AIR_ACCELERATION: f64 = 0.02;
+/// 玩家的眼睛高度
+const PLAYER_EYE_HEIGHT: f64 = 1.62;
+
+/// 目标占用的控制位
+pub mod flag {
+    pub const MOVE: u8 = 0x01;
+    pub const LOOK: u8 = 0x02;
+    pub const TARGET: u8 = 0x04;
+}
+
+/// AI 产生的、需要由调用方处理的动作
+#[derive(Debug, Clone, PartialEq)]
+pub enum AiAction {
+    /// 近战攻击玩家,攻击生物时直接扣除生命值
+    Attack {
+        attacker: EntityHandle,
+        target: EntityHandle,
+        damage: f32,
+    },
+}
+
+/// 目标运行时可以访问的状态
+pub struct AiContext<'a> {
+    pub handle: EntityHandle,
+    pub ai: &'a mut AiState,
+    pub store: &'a mut EntityStore,
+    pub source: &'a dyn BlockSource,
+    /// 同一世界与维度中的玩家
+    pub players: &'a [EntitySnapshot],
+    pub rng: &'a mut dyn RngCore,
+    /// 这一刻剩余的寻路节点数
+    pub budget: &'a mut usize,
+    pub actions: &'a mut Vec<AiAction>,
+}
+
+fn block_pos((x, y, z): (f64, f64, f64)) -> BlockPos {
+    BlockPos::new(x.floor() as i32, y.floor() as i32, z.floor() as i32)
+}
+
+fn distance_sq(a: (f64, f64, f64), b: (f64, f64, f64)) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)
+}
+
+impl AiContext<'_> {
+    pub fn position(&self) -> (f64, f64, f64) {
+        self.store
+            .get::<Position>(self.handle.id)
+            .map_or((0.0, 0.0, 0.0), |p| (p.x, p.y, p.z))
+    }
+
+    pub fn size(&self) -> (f64, f64) {
+        self.store
+            .get::<BoundingBox>(self.handle.id)
+            .map_or((0.6, 1.8), |b| (b.width, b.height))
+    }
+
+    pub fn attributes(&self) -> Option<Attributes> {
+        self.store.get::<Attributes>(self.handle.id).copied()
+    }
+
+    pub fn distance_sq(&self, to: (f64, f64, f64)) -> f64 {
+        distance_sq(self.position(), to)
+    }
+
+    /// 同一世界中的玩家或存活的生物的位置
+    pub fn entity_position(&self, handle: EntityHandle) -> Option<(f64, f64, f64)> {
+        if let Some(player) = self
+            .players
+            .iter()
+            .find(|p| p.entity_id == handle.id && p.generation == handle.generation)
+        {
+            return Some(player.position);
+        }
+        let me = self.store.info(self.handle.id)?;
+        let other = self.store.info(handle.id)?;
+        if other.handle != handle
+            || other.level != me.level
+            || other.dimension != me.dimension
+            || self
+                .store
+                .get::<Health>(handle.id)
+                .is_some_and(|h| h.is_dead())
+        {
+            return None;
+        }
+        let pos = self.store.get::<Position>(handle.id)?;
+        Some((pos.x, pos.y, pos.z))
+    }
+
+    /// `range` 格内最近的满足条件的玩家
+    pub fn nearest_player(
+        &self,
+        range: f64,
+        filter: impl Fn(&EntitySnapshot) -> bool,
+    ) -> Option<&EntitySnapshot> {
+        let me = self.position();
+        self.players
+            .iter()
+            .filter(|p| filter(p))
+            .map(|p| (distance_sq(me, p.position), p))
+            .filter(|(d, _)| *d <= range * range)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, p)| p)
+    }
+
+    /// 寻路到 `to` 并开始沿路径移动,预算用完或无路可走时返回 `false`
+    pub fn path_to(&mut self, to: (f64, f64, f64), speed: f64) -> bool {
+        if *self.budget == 0 {
+            return false;
+        }
+        let (width, height) = self.size();
+        let mut options = PathOptions::new(width, height);
+        options.max_nodes = options.max_nodes.min(*self.budget);
+        options.max_distance = PATH_RANGE;
+        let Some(path) = pathfind::find_path(
+            block_registry(),
+            &self.source,
+            block_pos(self.position()),
+            block_pos(to),
+            &options,
+        ) else {
+            return false;
+        };
+        *self.budget = self.budget.saturating_sub(path.visited);
+        if path.is_empty() {
+            return false;
+        }
+        self.ai.path = Some(path);
+        self.ai.path_index = 0;
+        self.ai.speed = speed;
+        true
+    }
+
+    pub fn is_navigating(&self) -> bool {
+        self.ai.path.is_some()
+    }
+
+    pub fn stop(&mut self) {
+        self.ai.path = None;
+    }
+
+    pub fn look_at(&mut self, at: (f64, f64, f64)) {
+        self.ai.look_at = Some(at);
+    }
+
+    /// 附近随机一个可以站立的位置,给出 `away_from` 时朝远离它的方向选取
+    pub fn random_position(
+        &mut self,
+        horizontal: i32,
+        vertical: i32,
+        away_from: Option<(f64, f64, f64)>,
+    ) -> Option<(f64, f64, f64)> {
+        let (x, y, z) = self.position();
+        let (width, height) = self.size();
+        let options = PathOptions::new(width, height);
+        for _ in 0..10 {
+            let (mut dx, mut dz) = (
+                self.rng.random_range(-horizontal..=horizontal),
+                self.rng.random_range(-horizontal..=horizontal),
+            );
+            if dx == 0 && dz == 0 {
+                continue;
+            }
+            if let Some((ax, _, az)) = away_from {
+                // 保证离开的方向与威胁相反
+                if (dx as f64) * (x - ax) < 0.0 {
+                    dx = -dx;
+                }
+                if (dz as f64) * (z - az) < 0.0 {
+                    dz = -dz;
+                }
+            }
+            let dy = self.rng.random_range(-vertical..=vertical);
+            let candidate = block_pos((x, y, z)).offset(dx, dy, dz);
+            for down in 0..=vertical {
+                let pos = candidate.offset(0, -down, 0);
+                match pathfind::node_type(block_registry(), &self.source, pos, &options) {
+                    Some(NodeType::Walkable) => {
+                        return Some((pos.x as f64 + 0.5, pos.y as f64, pos.z as f64 + 0.5));
+                    }
+                    Some(NodeType::Open) => continue,
+                    _ => break,
+                }
+            }
+        }
+        None
+    }
+
+    /// 近战攻击,目标是生物时直接结算伤害
+    pub fn attack(&mut self, target: EntityHandle) {
+        let damage = self.attributes().map_or(1.0, |a| a.attack_damage);
+        self.ai.attack_cooldown = ATTACK_INTERVAL;
+        if self.store.contains(target) {
+            self.store.hurt_by(target.id, damage, self.handle);
+        } else {
+            self.actions.push(AiAction::Attack {
+                attacker: self.handle,
+                target,
+                damage,
+            });
+        }
+    }
+}
+
+/// 生物的一个行为或目标选择
+pub trait Goal: Send + std::fmt::Debug {
+    fn name(&self) -> &'static str;
+    /// 占用的控制位
+    fn flags(&self) -> u8;
+    fn can_use(&mut self, ctx: &mut AiContext) -> bool;
+    fn can_continue(&mut self, ctx: &mut AiContext) -> bool {
+        self.can_use(ctx)
+    }
+    fn start(&mut self, _ctx: &mut AiContext) {}
+    fn stop(&mut self, _ctx: &mut AiContext) {}
+    fn tick(&mut self, _ctx: &mut AiContext) {}
+}
+
+#[derive(Debug)]
+struct Entry {
+    priority: u8,
+    goal: Box<dyn Goal>,
+    running: bool,
+}
+
+/// 按优先级选择运行的目标
+#[derive(Debug, Default)]
+pub struct GoalSelector {
+    entries: Vec<Entry>,
+}
+
+impl GoalSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加目标,`priority` 越小越优先
+    pub fn add(mut self, priority: u8, goal: impl Goal + 'static) -> Self {
+        self.entries.push(Entry {
+            priority,
+            goal: Box::new(goal),
+            running: false,
+        });
+        self.entries.sort_by_key(|e| e.priority);
+        self
+    }
+
+    /// 正在运行的目标
+    pub fn running(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.entries
+            .iter()
+            .filter(|e| e.running)
+            .map(|e| e.goal.name())
+    }
+
+    pub fn tick(&mut self, ctx: &mut AiContext) {
+        for entry in self.entries.iter_mut().filter(|e| e.running) {
+            if !entry.goal.can_continue(ctx) {
+                entry.goal.stop(ctx);
+                entry.running = false;
+            }
+        }
+        for i in 0..self.entries.len() {
+            let (priority, flags) = (self.entries[i].priority, self.entries[i].goal.flags());
+            if self.entries[i].running {
+                continue;
+            }
+            let conflicts: Vec<usize> = (0..self.entries.len())
+                .filter(|&j| self.entries[j].running && self.entries[j].goal.flags() & flags != 0)
+                .collect();
+            if conflicts
+                .iter()
+                .any(|&j| self.entries[j].priority <= priority)
+            {
+                continue;
+            }
+            if !self.entries[i].goal.can_use(ctx) {
+                continue;
+            }
+            for j in conflicts {
+                self.entries[j].goal.stop(ctx);
+                self.entries[j].running = false;
+            }
+            self.entries[i].goal.start(ctx);
+            self.entries[i].running = true;
+        }
+        for entry in self.entries.iter_mut().filter(|e| e.running) {
+            entry.goal.tick(ctx);
+        }
+    }
+}
+
+/// 生物的行为与目标选择器
+#[derive(Debug, Default)]
+pub struct Brain {
+    pub goals: GoalSelector,
+    pub targets: GoalSelector,
+}
+
+/// 随机游荡
+#[derive(Debug)]
+pub struct RandomStroll {
+    pub speed: f64,
+    /// 平均每隔多少刻尝试一次
+    pub interval: u32,
+    wanted: Option<(f64, f64, f64)>,
+}
+
+impl RandomStroll {
+    pub fn new(speed: f64, interval: u32) -> Self {
+        RandomStroll {
+            speed,
+            interval,
+            wanted: None,
+        }
+    }
+}
+
+impl Goal for RandomStroll {
+    fn name(&self) -> &'static str {
+        "random_stroll"
+    }
+    fn flags(&self) -> u8 {
+        flag::MOVE
+    }
+    fn can_use(&mut self, ctx: &mut AiContext) -> bool {
+        if ctx.rng.random_range(0..self.interval.max(1)) != 0 {
+            return false;
+        }
+        self.wanted = ctx.random_position(10, 7, None);
+        self.wanted.is_some()
+    }
+    fn can_continue(&mut self, ctx: &mut AiContext) -> bool {
+        ctx.is_navigating()
+    }
+    fn start(&mut self, ctx: &mut AiContext) {
+        if let Some(wanted) = self.wanted.take() {
+            ctx.path_to(wanted, self.speed);
+        }
+    }
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.stop();
+    }
+}
+
+/// 看向附近的玩家
+#[derive(Debug)]
+pub struct LookAtPlayer {
+    pub range: f64,
+    /// 每刻开始看向玩家的概率
+    pub probability: f32,
+    player: Option<EntityHandle>,
+    look_time: u32,
+}
+
+impl LookAtPlayer {
+    pub fn new(range: f64, probability: f32) -> Self {
+        LookAtPlayer {
+            range,
+            probability,
+            player: None,
+            look_time: 0,
+        }
+    }
+}
+
+impl Goal for LookAtPlayer {
+    fn name(&self) -> &'static str {
+        "look_at_player"
+    }
+    fn flags(&self) -> u8 {
+        flag::LOOK
+    }
+    fn can_use(&mut self, ctx: &mut AiContext) -> bool {
+        if ctx.rng.random::<f32>() >= self.probability {
+            return false;
+        }
+        self.player = ctx
+            .nearest_player(self.range, |_| true)
+            .map(|p| EntityHandle {
+                id: p.entity_id,
+                generation: p.generation,
+            });
+        self.player.is_some()
+    }
+    fn can_continue(&mut self, ctx: &mut AiContext) -> bool {
+        self.look_time > 0
+            && self
+                .player
+                .and_then(|p| ctx.entity_position(p))
+                .is_some_and(|pos| ctx.distance_sq(pos) <= self.range * self.range)
+    }
+    fn start(&mut self, ctx: &mut AiContext) {
+        self.look_time = 40 + ctx.rng.random_range(0..40);
+    }
+    fn stop(&mut self, ctx: &mut AiContext) {
+        self.player = None;
+        ctx.ai.look_at = None;
+    }
+    fn tick(&mut self, ctx: &mut AiContext) {
+        if let Some((x, y, z)) = self.player.and_then(|p| ctx.entity_position(p)) {
+            ctx.look_at((x, y + PLAYER_EYE_HEIGHT, z));
+        }
+        self.look_time = self.look_time.saturating_sub(1);
+    }
+}
+
+/// 追击 [`AiState::target`] 并近战攻击
+#[derive(Debug)]
+pub struct MeleeAttack {
+    pub speed: f64,
+    repath: u32,
+}
+
+impl MeleeAttack {
+    pub fn new(speed: f64) -> Self {
+        MeleeAttack { speed, repath: 0 }
+    }
+
+    fn reach_sq(ctx: &AiContext) -> f64 {
+        // 与原版相同:自身宽度的两倍的平方加上目标的宽度
+        (ctx.size().0 * 2.0).powi(2) + physics::PLAYER_WIDTH
+    }
+}
+
+impl Goal for MeleeAttack {
+    fn name(&self) -> &'static str {
+        "melee_attack"
+    }
+    fn flags(&self) -> u8 {
+        flag::MOVE | flag::LOOK
+    }
+    fn can_use(&mut self, ctx: &mut AiContext) -> bool {
+        let Some(target) = ctx.ai.target.and_then(|t| ctx.entity_position(t)) else {
+            return false;
+        };
+        ctx.distance_sq(target) <= Self::reach_sq(ctx) || ctx.path_to(target, self.speed)
+    }
+    fn can_continue(&mut self, ctx: &mut AiContext) -> bool {
+        ctx.ai
+            .target
+            .and_then(|t| ctx.entity_position(t))
+            .is_some_and(|pos| ctx.distance_sq(pos) <= PATH_RANGE * PATH_RANGE)
+    }
+    fn start(&mut self, _ctx: &mut AiContext) {
+        self.repath = 0;
+    }
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.stop();
+        ctx.ai.look_at = None;
+    }
+    fn tick(&mut self, ctx: &mut AiContext) {
+        let Some(target) = ctx.ai.target else {
+            return;
+        };
+        let Some((x, y, z)) = ctx.entity_position(target) else {
+            return;
+        };
+        ctx.look_at((x, y + PLAYER_EYE_HEIGHT, z));
+        if self.repath == 0 {
+            // 与原版相同,每 4 到 10 刻重新寻路追赶目标
+            self.repath = 4 + ctx.rng.random_range(0..7);
+            ctx.path_to((x, y, z), self.speed);
+        } else {
+            self.repath -= 1;
+        }
+        if ctx.distance_sq((x, y, z)) <= Self::reach_sq(ctx) && ctx.ai.attack_cooldown == 0 {
+            ctx.attack(target);
+        }
+    }
+}
+
+/// 受伤或玩家靠近时逃跑
+#[derive(Debug)]
+pub struct Flee {
+    pub speed: f64,
+    /// 玩家进入这个距离时逃跑,`None` 时只在受伤后逃跑
+    pub avoid_players: Option<f64>,
+}
+
+impl Flee {
+    pub fn new(speed: f64, avoid_players: Option<f64>) -> Self {
+        Flee {
+            speed,
+            avoid_players,
+        }
+    }
+
+    fn threat(&self, ctx: &AiContext) -> Option<Option<(f64, f64, f64)>> {
+        if ctx.ai.hurt_time > 0 {
+            return Some(ctx.ai.hurt_by.and_then(|h| ctx.entity_position(h)));
+        }
+        let range = self.avoid_players?;
+        ctx.nearest_player(range, |_| true)
+            .map(|p| Some(p.position))
+    }
+}
+
+impl Goal for Flee {
+    fn name(&self) -> &'static str {
+        "flee"
+    }
+    fn flags(&self) -> u8 {
+        flag::MOVE
+    }
+    fn can_use(&mut self, ctx: &mut AiContext) -> bool {
+        let Some(threat) = self.threat(ctx) else {
+            return false;
+        };
+        // 不知道伤害来源时向随机方向跑开
+        match ctx.random_position(5, 4, threat) {
+            Some(to) => ctx.path_to(to, self.speed),
+            None => false,
+        }
+    }
+    fn can_continue(&mut self, ctx: &mut AiContext) -> bool {
+        ctx.is_navigating()
+    }
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.stop();
+    }
+}
+
+/// 跟随 [`AiState::leader`];没有时跟随附近潜行的玩家(代替原版手持食物的引诱)
+#[derive(Debug)]
+pub struct Follow {
+    pub speed: f64,
+    pub range: f64,
+    /// 距离小于这个值时停下
+    pub stop_distance: f64,
+    repath: u32,
+}
+
+impl Follow {
+    pub fn new(speed: f64, range: f64, stop_distance: f64) -> Self {
+        Follow {
+            speed,
+            range,
+            stop_distance,
+            repath: 0,
+        }
+    }
+
+    fn leader(&self, ctx: &AiContext) -> Option<(f64, f64, f64)> {
+        if let Some(leader) = ctx.ai.leader {
+            return ctx.entity_position(leader);
+        }
+        use qexed_net::net_types::entity_metadata::Pose;
+        ctx.nearest_player(self.range, |p| p.metadata.pose() == Pose::Crouching)
+            .map(|p| p.position)
+    }
+
+    fn wants(&self, ctx: &AiContext) -> Option<(f64, f64, f64)> {
+        let pos = self.leader(ctx)?;
+        let d = ctx.distance_sq(pos);
+        (d > self.stop_distance.powi(2) && d <= (self.range * 2.0).powi(2)).then_some(pos)
+    }
+}
+
+impl Goal for Follow {
+    fn name(&self) -> &'static str {
+        "follow"
+    }
+    fn flags(&self) -> u8 {
+        flag::MOVE | flag::LOOK
+    }
+    fn can_use(&mut self, ctx: &mut AiContext) -> bool {
+        self.wants(ctx).is_some()
+    }
+    fn start(&mut self, _ctx: &mut AiContext) {
+        self.repath = 0;
+    }
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.stop();
+        ctx.ai.look_at = None;
+    }
+    fn tick(&mut self, ctx: &mut AiContext) {
+        let Some((x, y, z)) = self.leader(ctx) else {
+            return;
+        };
+        ctx.look_at((x, y + PLAYER_EYE_HEIGHT, z));
+        if self.repath == 0 {
+            self.repath = 10;
+            ctx.path_to((x, y, z), self.speed);
+        } else {
+            self.repath -= 1;
+        }
+    }
+}
+
+/// 把伤害来源设为攻击目标
+#[derive(Debug, Default)]
+pub struct HurtByTarget;
+
+impl Goal for HurtByTarget {
+    fn name(&self) -> &'static str {
+        "hurt_by_target"
+    }
+    fn flags(&self) -> u8 {
+        flag::TARGET
+    }
+    fn can_use(&mut self, ctx: &mut AiContext) -> bool {
+        ctx.ai.hurt_time > 0
+            && ctx.ai.hurt_by.is_some()
+            && ctx.ai.hurt_by != ctx.ai.target
+            && ctx
+                .ai
+                .hurt_by
+                .and_then(|h| ctx.entity_position(h))
+                .is_some()
+    }
+    fn can_continue(&mut self, ctx: &mut AiContext) -> bool {
+        ctx.ai.target.and_then(|t| ctx.entity_position(t)).is_some()
+    }
+    fn start(&mut self, ctx: &mut AiContext) {
+        ctx.ai.target = ctx.ai.hurt_by;
+    }
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.ai.target = None;
+    }
+}
+
+/// 把附近的玩家设为攻击目标
+#[derive(Debug)]
+pub struct NearestPlayerTarget {
+    pub range: f64,
+    candidate: Option<EntityHandle>,
+}
+
+impl NearestPlayerTarget {
+    pub fn new(range: f64) -> Self {
+        NearestPlayerTarget {
+            range,
+            candidate: None,
+        }
+    }
+}
+
+impl Goal for NearestPlayerTarget {
+    fn name(&self) -> &'static str {
+        "nearest_player_target"
+    }
+    fn flags(&self) -> u8 {
+        flag::TARGET
+    }
+    fn can_use(&mut self, ctx: &mut AiContext) -> bool {
+        // 与原版相同,平均每 10 刻检查一次
+        if ctx.rng.random_range(0..10) != 0 {
+            return false;
+        }
+        self.candidate = ctx
+            .nearest_player(self.range, |_| true)
+            .map(|p| EntityHandle {
+                id: p.entity_id,
+                generation: p.generation,
+            });
+        self.candidate.is_some()
+    }
+    fn can_continue(&mut self, ctx: &mut AiContext) -> bool {
+        ctx.ai
+            .target
+            .and_then(|t| ctx.entity_position(t))
+            .is_some_and(|pos| ctx.distance_sq(pos) <= self.range * self.range)
+    }
+    fn start(&mut self, ctx: &mut AiContext) {
+        ctx.ai.target = self.candidate.take();
+    }
+    fn stop(&mut self, ctx: &mut AiContext) {
+        ctx.ai.target = None;
+    }
+}
+
+/// 生物类型的默认 AI(与原版的目标与优先级一致,远程攻击暂时以近战代替)
+pub fn brain_for(kind: MobKind) -> Brain {
+    match kind.category() {
+        MobCategory::Monster => {
+            let follow_range = if kind == MobKind::Zombie { 35.0 } else { 16.0 };
+            Brain {
+                goals: GoalSelector::new()
+                    .add(2, MeleeAttack::new(1.0))
+                    .add(7, RandomStroll::new(1.0, 120))
+                    .add(8, LookAtPlayer::new(8.0, 0.02)),
+                targets: GoalSelector::new()
+                    .add(1, HurtByTarget)
+                    .add(2, NearestPlayerTarget::new(follow_range)),
+            }
+        }
+        MobCategory::Creature => {
+            let (panic, tempt) = match kind {
+                MobKind::Cow => (2.0, 1.25),
+                MobKind::Pig => (1.25, 1.2),
+                MobKind::Sheep => (1.25, 1.1),
+                _ => (1.4, 1.0),
+            };
+            Brain {
+                goals: GoalSelector::new()
+                    .add(1, Flee::new(panic, None))
+                    .add(4, Follow::new(tempt, 10.0, 2.5))
+                    .add(6, RandomStroll::new(1.0, 120))
+                    .add(7, LookAtPlayer::new(6.0, 0.02)),
+                targets: GoalSelector::new(),
+            }
+        }
+    }
+}
+
+/// 沿路径移动并转向视线目标
+fn steer(store: &mut EntityStore, id: i32, ai: &mut AiState) {
+    let speed = store
+        .get::<Attributes>(id)
+        .map_or(0.0, |a| a.movement_speed as f64);
+    let height = store.get::<BoundingBox>(id).map_or(1.8, |b| b.height);
+    let (Some(pos), Some(vel)) = (
+        store.get::<Position>(id).copied(),
+        store.get::<Velocity>(id),
+    ) else {
+        return;
+    };
+    let mut vel = *vel;
+    let mut yaw = pos.yaw;
+    if let Some(path) = &ai.path {
+        let node = path.nodes[ai.path_index.min(path.nodes.len() - 1)];
+        let (dx, dz) = (node.x as f64 + 0.5 - pos.x, node.z as f64 + 0.5 - pos.z);
+        let distance = (dx * dx + dz * dz).sqrt();
+        ai.path_index +=
+            usize::from(distance < NODE_REACHED && (pos.y - node.y as f64).abs() < 1.0);
+        if ai.path_index >= path.nodes.len() || ai.path_index > 0 && distance < NODE_REACHED {
+            ai.path_ticks = 0;
+        } else {
+            ai.path_ticks += 1;
+        }
+        if ai.path_index >= path.nodes.len() || ai.path_ticks > STUCK_TICKS {
+            ai.path = None;
+            ai.path_index = 0;
+            ai.path_ticks = 0;
+        } else if distance >= NODE_REACHED {
+            let s = speed * ai.speed;
+            let acceleration = if pos.on_ground {
+                s * s * GROUND_ACCELERATION
+            } else {
+                s * AIR_ACCELERATION
+            };
+            vel.x += dx / distance * acceleration;
+            vel.z += dz / distance * acceleration;
+            yaw = (dz.atan2(dx).to_degrees() - 90.0) as f32;
+            // 前方的节点高于台阶高度时起跳
+            if pos.on_ground && node.y as f64 > pos.y + physics::STEP_HEIGHT {
+                vel.y = physics::JUMP_VELOCITY;
+            }
+        }
+    }
+    let (head_yaw, pitch) = match ai.look_at {
+        Some((x, y, z)) => {
+            let (dx, dy, dz) = (x - pos.x, y - (pos.y + height * 0.85), z - pos.z);
+            (
+                (dz.atan2(dx).to_degrees() - 90.0) as f32,
+                (-dy.atan2((dx * dx + dz * dz).sqrt()).to_degrees()) as f32,
+            )
+        }
+        None => (yaw, 0.0),
+    };
+    if let Some(p) = store.get_mut::<Position>(id) {
+        (p.yaw, p.head_yaw, p.pitch) = (yaw, head_yaw, pitch);
+    }
+    store.insert(id, vel);
+}
+
+/// 运行 `level` 中 `dimension` 维度所有生物的 AI 一刻,在物理之前调用
+pub fn tick_ai(
+    store: &mut EntityStore,
+    level: &str,
+    dimension: Dimension,
+    source: &dyn BlockSource,
+    players: &[EntitySnapshot],
+    rng: &mut dyn RngCore,
+    budget: &mut usize,
+) -> Vec<AiAction> {
+    let players: Vec<EntitySnapshot> = players
+        .iter()
+        .filter(|p| p.level == level && p.dimension == dimension)
+        .cloned()
+        .collect();
+    let mut handles: Vec<EntityHandle> = store
+        .entities()
+        .filter(|e| e.level == level && e.dimension == dimension)
+        .map(|e| e.handle)
+        .filter(|h| !store.get::<Health>(h.id).is_some_and(|h| h.is_dead()))
+        .collect();
+    handles.sort_by_key(|h| h.id);
+    handles.shuffle(rng);
+    let mut actions = Vec::new();
+    for handle in handles {
+        let Some(mut brain) = store.remove::<Brain>(handle.id) else {
+            continue;
+        };
+        let Some(mut ai) = store.remove::<AiState>(handle.id) else {
+            store.insert(handle.id, brain);
+            continue;
+        };
+        ai.hurt_time = ai.hurt_time.saturating_sub(1);
+        ai.attack_cooldown = ai.attack_cooldown.saturating_sub(1);
+        {
+            let mut ctx = AiContext {
+                handle,
+                ai: &mut ai,
+                store,
+                source,
+                players: &players,
+                rng,
+                budget,
+                actions: &mut actions,
+            };
+            brain.targets.tick(&mut ctx);
+            brain.goals.tick(&mut ctx);
+        }
+        steer(store, handle.id, &mut ai);
+        store.insert(handle.id, ai);
+        store.insert(handle.id, brain);
+    }
+    actions
+}
+
+/// 结算生物对玩家的攻击
+///
+/// 还没有生命值同步(SetHealth)与死亡重生,暂时只记录攻击,不修改玩家的生命值,
+/// 以免客户端看不到伤害或玩家生命值归零后无法恢复。
+pub async fn apply_attacks(
+    players: &Mutex<HashMap<Uuid, Arc<Mutex<Player>>>>,
+    actions: Vec<AiAction>,
+) {
+    if actions.is_empty() {
+        return;
+    }
+    let players: Vec<_> = players.lock().await.values().cloned().collect();
+    for action in actions {
+        let AiAction::Attack { target, damage, .. } = action;
+        for player in &players {
+            let player = player.lock().await;
+            if player.entity_handle() == target {
+                log::debug!("玩家 {} 受到 {} 点伤害", player.username, damage);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{
+        biology::mob::{Cow, Zombie},
+        test_util::{player_at, stone_floor},
+        utils::entity_ids::EntityIds,
+    };
+
+    fn run(
+        store: &mut EntityStore,
+        ids: &mut EntityIds,
+        blocks: &HashMap<BlockPos, qexed_world::block::BlockState>,
+        players: &[EntitySnapshot],
+        rng: &mut StdRng,
+        ticks: usize,
+    ) -> Vec<AiAction> {
+        let mut actions = Vec::new();
+        for _ in 0..ticks {
+            let mut budget = PATH_NODE_BUDGET;
+            actions.extend(tick_ai(
+                store,
+                "world",
+                Dimension::Overworld,
+                blocks,
+                players,
+                rng,
+                &mut budget,
+            ));
+            store.tick_world(ids, "world", Dimension::Overworld, blocks);
+        }
+        actions
+    }
+
+    #[test]
+    fn zombie_chases_and_attacks_player() {
+        let blocks = stone_floor(-16..16, -16..16);
+        let mut ids = EntityIds::new();
+        let mut store = EntityStore::new();
+        let mut rng = StdRng::seed_from_u64(1);
+        let start = Position {
+            x: 0.5,
+            y: 64.0,
+            z: 0.5,
+            on_ground: true,
+            ..Default::default()
+        };
+        let zombie = store
+            .spawn(
+                &mut ids,
+                Zombie::new(),
+                "world",
+                Dimension::Overworld,
+                start,
+            )
+            .unwrap();
+        let player = player_at((8.5, 64.0, 0.5));
+
+        let actions = run(
+            &mut store,
+            &mut ids,
+            &blocks,
+            &[player],
+            &mut rng,
+            200,
+        );
+        assert_eq!(
+            store.get::<AiState>(zombie.id).unwrap().target,
+            Some(EntityHandle {
+                id: 100,
+                generation: 1
+            })
+        );
+        let pos = store.get::<Position>(zombie.id).unwrap();
+        assert!(pos.x > 6.0, "{:?}", pos);
+        assert!(!actions.is_empty());
+        assert!(actions.iter().all(|a| matches!(
+            a,
+            AiAction::Attack { attacker, damage, .. } if *attacker == zombie && *damage == 3.0
+        )));
+        // 攻击间隔 20 刻
+        assert!(actions.len() <= 200 / ATTACK_INTERVAL as usize);
+    }
+
+    #[test]
+    fn cow_panics_when_hurt() {
+        let blocks = stone_floor(-16..16, -16..16);
+        let mut ids = EntityIds::new();
+        let mut store = EntityStore::new();
+        let mut rng = StdRng::seed_from_u64(2);
+        let start = Position {
+            x: 0.5,
+            y: 64.0,
+            z: 0.5,
+            on_ground: true,
+            ..Default::default()
+        };
+        let cow = store
+            .spawn(&mut ids, Cow::new(), "world", Dimension::Overworld, start)
+            .unwrap();
+        run(&mut store, &mut ids, &blocks, &[], &mut rng, 1);
+        store.hurt(cow.id, 1.0);
+        run(&mut store, &mut ids, &blocks, &[], &mut rng, 1);
+        let brain = store.get::<Brain>(cow.id).unwrap();
+        assert_eq!(brain.goals.running().collect::<Vec<_>>(), vec!["flee"]);
+        run(&mut store, &mut ids, &blocks, &[], &mut rng, 40);
+        let pos = store.get::<Position>(cow.id).unwrap();
+        assert!((pos.x - 0.5).abs() + (pos.z - 0.5).abs() > 1.0, "{:?}", pos);
+    }
+}
@@ -0,0 +1,536 @@
+//! 实体组件存储
+//!
+//! 实体本身只是一个 [`EntityHandle`],数据按组件分表保存在 [`EntityStore`] 中,
+//! 物理、AI、追踪等系统只读写自己需要的组件。玩家仍由 [`super::player::Player`] 管理,
+//! 这里只保存服务端生成的生物。
+
+use std::collections::HashMap;
+
+use qexed_net::net_types::entity_metadata::EntityMetadata;
+use qexed_world::{Aabb, BlockSource, Dimension, block_registry, pathfind::Path, physics};
+use uuid::Uuid;
+
+use crate::{
+    biology::{
+        ai::{self, Brain},
+        mob::{MobKind, MobStats, MobType},
+    },
+    event::entity_tracker::EntitySnapshot,
+    utils::entity_ids::{EntityHandle, EntityIds},
+};
+
+/// 死亡后保留多少刻再移除,客户端在这段时间内播放死亡动画(与原版相同)
+pub const DEATH_TICKS: u32 = 20;
+/// 隐藏血量时发送给客户端的固定值
+pub const HIDDEN_HEALTH: f32 = 99999999.0;
+/// 着地时水平速度每刻保留的比例(方块滑度 0.6 × 空气阻力 0.91)
+const GROUND_FRICTION: f64 = 0.6 * 0.91;
+/// 空中水平速度每刻保留的比例
+const AIR_FRICTION: f64 = 0.91;
+
+/// 实体的身份信息,每个实体都有
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityInfo {
+    pub handle: EntityHandle,
+    pub uuid: Uuid,
+    pub kind: MobKind,
+    /// 所在的命名世界
+    pub level: String,
+    pub dimension: Dimension,
+}
+
+/// 位置与朝向,坐标为脚下中心
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub head_yaw: f32,
+    pub on_ground: bool,
+}
+
+/// 每刻的位移(格)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Velocity {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// 碰撞箱尺寸
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl BoundingBox {
+    pub fn at(&self, pos: &Position) -> Aabb {
+        Aabb::at_feet(pos.x, pos.y, pos.z, self.width, self.height)
+    }
+}
+
+/// 发送给客户端的元数据
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Metadata(pub EntityMetadata);
+
+/// 生命值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+    /// 发送给客户端时使用 [`HIDDEN_HEALTH`]
+    pub hidden: bool,
+    /// 死亡后经过的刻数
+    pub death_ticks: u32,
+}
+
+impl Health {
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+/// 战斗与移动属性
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attributes {
+    pub attack_damage: f32,
+    pub armor: f32,
+    pub movement_speed: f32,
+    pub can_swim: bool,
+}
+
+/// AI 的运行状态,由目标选择器写入意图,[`ai::tick_ai`] 换算成速度与朝向
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AiState {
+    /// 当前的攻击目标
+    pub target: Option<EntityHandle>,
+    /// 跟随的实体
+    pub leader: Option<EntityHandle>,
+    /// 正在沿着走的路径与下一个节点的下标
+    pub path: Option<Path>,
+    pub path_index: usize,
+    /// 停留在当前节点的刻数,过久时放弃路径
+    pub path_ticks: u32,
+    /// 沿路径移动时的速度倍率
+    pub speed: f64,
+    /// 看向的位置
+    pub look_at: Option<(f64, f64, f64)>,
+    /// 最近一次伤害的来源
+    pub hurt_by: Option<EntityHandle>,
+    /// 受伤后剩余的刻数,逃跑与反击据此触发
+    pub hurt_time: u32,
+    /// 距离下一次近战攻击的刻数
+    pub attack_cooldown: u32,
+    /// 附近没有玩家也没有受伤的刻数,过久时可能消失
+    pub no_action_ticks: u32,
+}
+
+/// 可以挂在实体上的组件
+pub trait Component: Sized + 'static {
+    fn column(store: &EntityStore) -> &HashMap<i32, Self>;
+    fn column_mut(store: &mut EntityStore) -> &mut HashMap<i32, Self>;
+}
+
+macro_rules! components {
+    ($($ty:ident => $field:ident),* $(,)?) => {
+        $(
+            impl Component for $ty {
+                fn column(store: &EntityStore) -> &HashMap<i32, Self> {
+                    &store.$field
+                }
+                fn column_mut(store: &mut EntityStore) -> &mut HashMap<i32, Self> {
+                    &mut store.$field
+                }
+            }
+        )*
+
+        impl EntityStore {
+            /// 移除实体的所有组件
+            fn remove_components(&mut self, id: i32) {
+                $(self.$field.remove(&id);)*
+            }
+        }
+    };
+}
+
+/// 所有生物实体的组件表,以实体 ID 为键
+#[derive(Debug, Default)]
+pub struct EntityStore {
+    info: HashMap<i32, EntityInfo>,
+    positions: HashMap<i32, Position>,
+    velocities: HashMap<i32, Velocity>,
+    boxes: HashMap<i32, BoundingBox>,
+    metadata: HashMap<i32, Metadata>,
+    health: HashMap<i32, Health>,
+    attributes: HashMap<i32, Attributes>,
+    ai: HashMap<i32, AiState>,
+    brains: HashMap<i32, Brain>,
+}
+
+components! {
+    Position => positions,
+    Velocity => velocities,
+    BoundingBox => boxes,
+    Metadata => metadata,
+    Health => health,
+    Attributes => attributes,
+    AiState => ai,
+    Brain => brains,
+}
+
+impl EntityStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.info.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.info.is_empty()
+    }
+
+    /// 句柄是否仍然指向存储中的实体
+    pub fn contains(&self, handle: EntityHandle) -> bool {
+        self.info
+            .get(&handle.id)
+            .is_some_and(|e| e.handle == handle)
+    }
+
+    pub fn info(&self, id: i32) -> Option<&EntityInfo> {
+        self.info.get(&id)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = &EntityInfo> {
+        self.info.values()
+    }
+
+    pub fn get<C: Component>(&self, id: i32) -> Option<&C> {
+        C::column(self).get(&id)
+    }
+
+    pub fn get_mut<C: Component>(&mut self, id: i32) -> Option<&mut C> {
+        C::column_mut(self).get_mut(&id)
+    }
+
+    /// 为实体添加或替换组件,实体不存在时返回 `false`
+    pub fn insert<C: Component>(&mut self, id: i32, component: C) -> bool {
+        if !self.info.contains_key(&id) {
+            return false;
+        }
+        C::column_mut(self).insert(id, component);
+        true
+    }
+
+    pub fn remove<C: Component>(&mut self, id: i32) -> Option<C> {
+        C::column_mut(self).remove(&id)
+    }
+
+    /// 拥有组件 `C` 的所有实体
+    pub fn iter<C: Component>(&self) -> impl Iterator<Item = (i32, &C)> {
+        C::column(self).iter().map(|(id, c)| (*id, c))
+    }
+
+    /// 在世界中生成一个生物
+    pub fn spawn<M: MobType>(
+        &mut self,
+        ids: &mut EntityIds,
+        mob: M,
+        level: &str,
+        dimension: Dimension,
+        position: Position,
+    ) -> anyhow::Result<EntityHandle> {
+        self.spawn_kind(ids, M::KIND, mob.into_stats(), level, dimension, position)
+    }
+
+    /// 按运行时的生物类型生成,用于自然生成等无法静态确定类型的场合
+    pub fn spawn_kind(
+        &mut self,
+        ids: &mut EntityIds,
+        kind: MobKind,
+        stats: MobStats,
+        level: &str,
+        dimension: Dimension,
+        position: Position,
+    ) -> anyhow::Result<EntityHandle> {
+        let handle = ids.allocate()?;
+        let id = handle.id;
+        self.info.insert(
+            id,
+            EntityInfo {
+                handle,
+                uuid: Uuid::new_v4(),
+                kind,
+                level: level.to_string(),
+                dimension,
+            },
+        );
+        let (width, height) = kind.size();
+        self.positions.insert(id, position);
+        self.velocities.insert(id, Velocity::default());
+        self.boxes.insert(id, BoundingBox { width, height });
+        self.attributes.insert(
+            id,
+            Attributes {
+                attack_damage: stats.attack_damage,
+                armor: stats.armor,
+                movement_speed: stats.movement_speed,
+                can_swim: stats.can_swim,
+            },
+        );
+        self.ai.insert(id, AiState::default());
+        self.brains.insert(id, ai::brain_for(kind));
+
+        let mut metadata = EntityMetadata::new();
+        if let Some(name) = &stats.display_name {
+            metadata.set_custom_name(Some(name));
+        }
+        if stats.show_name {
+            metadata.set_custom_name_visible(true);
+        }
+        self.metadata.insert(id, Metadata(metadata));
+        self.health.insert(
+            id,
+            Health {
+                current: stats.max_health,
+                max: stats.max_health,
+                hidden: stats.hide_health,
+                death_ticks: 0,
+            },
+        );
+        self.set_health(id, stats.health);
+        Ok(handle)
+    }
+
+    /// 是否被要求持久保留(例如有自定义名字),这样的生物不会消失,也不计入自然生成上限
+    pub fn is_persistence_required(&self, id: i32) -> bool {
+        self.metadata
+            .get(&id)
+            .is_some_and(|m| m.0.custom_name().is_some())
+    }
+
+    /// 移除实体并释放 ID,句柄已过期时返回 `false`
+    pub fn despawn(&mut self, ids: &mut EntityIds, handle: EntityHandle) -> bool {
+        if !self.contains(handle) {
+            return false;
+        }
+        self.info.remove(&handle.id);
+        self.remove_components(handle.id);
+        ids.release(handle)
+    }
+
+    /// 设置生命值并同步到元数据
+    pub fn set_health(&mut self, id: i32, health: f32) {
+        let Some(h) = self.health.get_mut(&id) else {
+            return;
+        };
+        h.current = health.clamp(0.0, h.max);
+        let shown = if h.hidden && !h.is_dead() {
+            HIDDEN_HEALTH
+        } else {
+            h.current
+        };
+        if let Some(Metadata(metadata)) = self.metadata.get_mut(&id) {
+            metadata.set_health(shown);
+        }
+    }
+
+    /// 受到伤害,按护甲减免(与原版相同,不计盔甲韧性),返回是否因此死亡
+    pub fn hurt(&mut self, id: i32, damage: f32) -> bool {
+        let Some(current) = self
+            .health
+            .get(&id)
+            .filter(|h| !h.is_dead())
+            .map(|h| h.current)
+        else {
+            return false;
+        };
+        let armor = self.attributes.get(&id).map_or(0.0, |a| a.armor);
+        let reduction = (armor / 5.0).max(armor - damage / 2.0).min(20.0) / 25.0;
+        self.set_health(id, current - damage * (1.0 - reduction));
+        if let Some(ai) = self.ai.get_mut(&id) {
+            ai.hurt_time = ai::HURT_MEMORY;
+            ai.no_action_ticks = 0;
+        }
+        self.health.get(&id).is_some_and(|h| h.is_dead())
+    }
+
+    /// 受到 `attacker` 的伤害,生物会记住伤害来源用于反击
+    pub fn hurt_by(&mut self, id: i32, damage: f32, attacker: EntityHandle) -> bool {
+        let died = self.hurt(id, damage);
+        if let Some(ai) = self.ai.get_mut(&id) {
+            ai.hurt_by = Some(attacker);
+        }
+        died
+    }
+
+    /// 推进 `level` 中 `dimension` 维度的实体一刻:死亡计时、重力与碰撞
+    ///
+    /// 所在区块已卸载的实体与死亡动画结束的实体会被移除并释放 ID。
+    pub fn tick_world(
+        &mut self,
+        ids: &mut EntityIds,
+        level: &str,
+        dimension: Dimension,
+        source: &impl BlockSource,
+    ) {
+        let handles: Vec<EntityHandle> = self
+            .info
+            .values()
+            .filter(|e| e.level == level && e.dimension == dimension)
+            .map(|e| e.handle)
+            .collect();
+        for handle in handles {
+            if !self.tick_entity(handle.id, source) {
+                self.despawn(ids, handle);
+            }
+        }
+    }
+
+    /// 返回实体是否应当保留
+    fn tick_entity(&mut self, id: i32, source: &impl BlockSource) -> bool {
+        if let Some(health) = self.health.get_mut(&id)
+            && health.is_dead()
+        {
+            health.death_ticks += 1;
+            return health.death_ticks < DEATH_TICKS;
+        }
+        let (Some(pos), Some(vel), Some(bb)) = (
+            self.positions.get_mut(&id),
+            self.velocities.get_mut(&id),
+            self.boxes.get(&id),
+        ) else {
+            return true;
+        };
+        vel.y = (vel.y - physics::GRAVITY) * physics::VERTICAL_DRAG;
+        let wanted = (vel.x, vel.y, vel.z);
+        let Some((dx, dy, dz)) =
+            physics::move_entity(block_registry(), source, &bb.at(pos), wanted, pos.on_ground)
+        else {
+            return false;
+        };
+        pos.x += dx;
+        pos.y += dy;
+        pos.z += dz;
+        pos.on_ground = wanted.1 < 0.0 && dy != wanted.1;
+        if dy != wanted.1 {
+            vel.y = 0.0;
+        }
+        let friction = if pos.on_ground {
+            GROUND_FRICTION
+        } else {
+            AIR_FRICTION
+        };
+        vel.x = if dx != wanted.0 {
+            0.0
+        } else {
+            vel.x * friction
+        };
+        vel.z = if dz != wanted.2 {
+            0.0
+        } else {
+            vel.z * friction
+        };
+        true
+    }
+
+    /// 所有生物实体这一刻的快照
+    pub fn snapshots(&self) -> Vec<EntitySnapshot> {
+        self.info
+            .values()
+            .filter_map(|info| {
+                let pos = self.positions.get(&info.handle.id)?;
+                Some(EntitySnapshot {
+                    entity_id: info.handle.id,
+                    generation: info.handle.generation,
+                    entity_type: info.kind.entity_type(),
+                    uuid: info.uuid,
+                    name: info.kind.name().to_string(),
+                    level: info.level.clone(),
+                    dimension: info.dimension,
+                    position: (pos.x, pos.y, pos.z),
+                    yaw: pos.yaw,
+                    pitch: pos.pitch,
+                    head_yaw: pos.head_yaw,
+                    on_ground: pos.on_ground,
+                    metadata: self
+                        .metadata
+                        .get(&info.handle.id)
+                        .map(|m| m.0.clone())
+                        .unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use qexed_net::net_types::entity_metadata::{MetadataValue, index};
+
+    use super::*;
+    use crate::biology::mob::{Cow, Zombie};
+    use crate::test_util::stone_floor;
+
+    #[test]
+    fn spawn_fall_hurt_and_despawn() {
+        let mut ids = EntityIds::new();
+        let mut store = EntityStore::new();
+        let blocks = stone_floor(-2..2, -2..2);
+        let start = Position {
+            x: 0.5,
+            y: 66.0,
+            z: 0.5,
+            ..Default::default()
+        };
+        let cow = store
+            .spawn(&mut ids, Cow::new(), "world", Dimension::Overworld, start)
+            .unwrap();
+        let zombie = store
+            .spawn(
+                &mut ids,
+                Zombie::new(),
+                "world",
+                Dimension::Overworld,
+                start,
+            )
+            .unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get::<BoundingBox>(cow.id).unwrap().height, 1.4);
+        assert_eq!(
+            store
+                .get::<Metadata>(cow.id)
+                .unwrap()
+                .0
+                .get(index::LIVING_HEALTH),
+            Some(&MetadataValue::Float(10.0))
+        );
+
+        // 落到地面上
+        for _ in 0..40 {
+            store.tick_world(&mut ids, "world", Dimension::Overworld, &blocks);
+        }
+        let pos = store.get::<Position>(cow.id).unwrap();
+        assert_eq!(pos.y, 64.0);
+        assert!(pos.on_ground);
+
+        // 僵尸有 2 点护甲:5 点伤害减免 1.6%
+        assert!(!store.hurt(zombie.id, 5.0));
+        assert!((store.get::<Health>(zombie.id).unwrap().current - 15.08).abs() < 1e-4);
+
+        // 死亡动画结束后移除并释放 ID
+        assert!(store.hurt(cow.id, 100.0));
+        for _ in 0..DEATH_TICKS {
+            store.tick_world(&mut ids, "world", Dimension::Overworld, &blocks);
+        }
+        assert!(!store.contains(cow));
+        assert!(store.get::<Position>(cow.id).is_none());
+        assert!(!ids.is_alive(cow));
+        assert_eq!(ids.live_count(), 1);
+        assert!(!store.despawn(&mut ids, cow));
+    }
+}
@@ -0,0 +1,386 @@
+//! 玩家物品栏
+//!
+//! 玩家物品栏是窗口 0,共 46 格,布局与原版相同。点击由客户端先行预测,服务端只校验预测的结果:
+//! 变化后的物品必须来自点击前已有的物品,且同种物品的总数不能增加;校验失败时保持原状并重新同步。
+//! 客户端只发送组件的哈希,这里按组件类型匹配点击前的物品,不校验哈希值。
+//!
+//! 存入数据库时每格保存物品ID、数量与带长度前缀编码的组件补丁,未实现的组件也能原样保留。
+
+use std::fmt;
+
+use bson::{Binary, spec::BinarySubtype};
+use bytes::BytesMut;
+use qexed_net::net_types::slot::Slot;
+use qexed_net::net_types::var_int::VarInt;
+use qexed_net::packet::decode::PacketReader;
+use qexed_net::packet::encode::PacketWriter;
+use qexed_net::packet::packet_pool::{ContainerClick, ContainerSetContent, ContainerSetSlot};
+use serde::{Deserialize, Serialize};
+
+/// 玩家物品栏的窗口ID
+pub const PLAYER_WINDOW: i32 = 0;
+/// 玩家物品栏的格数
+pub const SLOT_COUNT: usize = 46;
+/// 一格物品数量的上限(max_stack_size 组件的最大值)
+pub const MAX_COUNT: i32 = 99;
+
+/// 玩家物品栏窗口中各区域的格子下标
+pub mod slot {
+    use std::ops::Range;
+
+    /// 合成结果
+    pub const CRAFT_RESULT: usize = 0;
+    /// 2x2 合成格
+    pub const CRAFT_GRID: Range<usize> = 1..5;
+    /// 头、胸、腿、脚
+    pub const ARMOR: Range<usize> = 5..9;
+    pub const MAIN: Range<usize> = 9..36;
+    pub const HOTBAR: Range<usize> = 36..45;
+    pub const OFFHAND: usize = 45;
+}
+
+/// 物品栏操作被拒绝的原因
+#[derive(Debug, PartialEq)]
+pub enum InventoryError {
+    UnknownWindow(i32),
+    /// 客户端的状态ID落后,点击基于过期的内容
+    StateMismatch {
+        expected: i32,
+        got: i32,
+    },
+    InvalidSlot(i16),
+    /// 点击后出现了点击前没有的物品
+    UnknownItem(i32),
+    /// 点击后同种物品的总数增加
+    ItemsCreated(i32),
+    TooMany(i32),
+}
+impl std::error::Error for InventoryError {}
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InventoryError::UnknownWindow(id) => write!(f, "未知的窗口 {}", id),
+            InventoryError::StateMismatch { expected, got } => {
+                write!(f, "状态ID不一致: 应为 {},实际为 {}", expected, got)
+            }
+            InventoryError::InvalidSlot(slot) => write!(f, "无效的格子 {}", slot),
+            InventoryError::UnknownItem(id) => write!(f, "物品 {} 不是点击前已有的物品", id),
+            InventoryError::ItemsCreated(id) => write!(f, "物品 {} 的总数增加了", id),
+            InventoryError::TooMany(count) => write!(f, "物品数量 {} 超出上限", count),
+        }
+    }
+}
+
+/// 玩家物品栏
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredInventory", into = "StoredInventory")]
+pub struct Inventory {
+    slots: Vec<Slot>,
+    /// 光标上拿着的物品
+    pub carried: Slot,
+    /// 每次由服务端确认的变化后递增,客户端在点击时回传
+    state_id: i32,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Inventory {
+            slots: vec![Slot::empty(); SLOT_COUNT],
+            carried: Slot::empty(),
+            state_id: 0,
+        }
+    }
+
+    pub fn state_id(&self) -> i32 {
+        self.state_id
+    }
+
+    pub fn slots(&self) -> &[Slot] {
+        &self.slots
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Slot> {
+        self.slots.get(index)
+    }
+
+    /// 设置一格物品,需要另外用 [`Inventory::slot_packet`] 通知客户端
+    pub fn set(&mut self, index: usize, item: Slot) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            *slot = item;
+            self.next_state();
+        }
+    }
+
+    fn next_state(&mut self) {
+        self.state_id = (self.state_id + 1) & 0x7FFF;
+    }
+
+    /// 全部内容
+    pub fn content_packet(&self) -> ContainerSetContent {
+        let mut pk = ContainerSetContent::new();
+        pk.window_id = VarInt(PLAYER_WINDOW);
+        pk.state_id = VarInt(self.state_id);
+        pk.slots = self.slots.clone();
+        pk.carried_item = self.carried.clone();
+        pk
+    }
+
+    /// 单格内容
+    pub fn slot_packet(&self, index: usize) -> ContainerSetSlot {
+        let mut pk = ContainerSetSlot::new();
+        pk.window_id = VarInt(PLAYER_WINDOW);
+        pk.state_id = VarInt(self.state_id);
+        pk.slot = index as i16;
+        pk.item = self.slots.get(index).cloned().unwrap_or_default();
+        pk
+    }
+
+    fn slot_index(slot: i16) -> Result<usize, InventoryError> {
+        usize::try_from(slot)
+            .ok()
+            .filter(|i| *i < SLOT_COUNT)
+            .ok_or(InventoryError::InvalidSlot(slot))
+    }
+
+    /// 创造模式下设置一格物品,`slot` 为 -1 时是扔出物品
+    ///
+    /// 客户端已经显示了新的物品,因此不改变状态ID。
+    pub fn set_creative_slot(&mut self, slot: i16, item: Slot) -> Result<(), InventoryError> {
+        if item.count > MAX_COUNT {
+            return Err(InventoryError::TooMany(item.count));
+        }
+        if slot == -1 {
+            return Ok(());
+        }
+        let index = Self::slot_index(slot)?;
+        if index == slot::CRAFT_RESULT {
+            return Err(InventoryError::InvalidSlot(slot));
+        }
+        self.slots[index] = item;
+        Ok(())
+    }
+
+    /// 按客户端预测的结果完成一次点击,失败时物品栏不变
+    pub fn apply_click(&mut self, click: &ContainerClick) -> Result<(), InventoryError> {
+        if click.window_id.0 != PLAYER_WINDOW {
+            return Err(InventoryError::UnknownWindow(click.window_id.0));
+        }
+        if click.state_id.0 != self.state_id {
+            return Err(InventoryError::StateMismatch {
+                expected: self.state_id,
+                got: click.state_id.0,
+            });
+        }
+        let before: Vec<&Slot> = self.slots.iter().chain([&self.carried]).collect();
+        let resolve = |hashed: &qexed_net::net_types::slot::HashedSlot| {
+            if hashed.is_empty() {
+                return Ok(Slot::empty());
+            }
+            if hashed.count > MAX_COUNT {
+                return Err(InventoryError::TooMany(hashed.count));
+            }
+            before
+                .iter()
+                .find(|s| !s.is_empty() && hashed.matches(s))
+                .map(|s| s.with_count(hashed.count))
+                .ok_or(InventoryError::UnknownItem(hashed.item_id))
+        };
+        let mut after = self.slots.clone();
+        for changed in &click.changed_slots {
+            after[Self::slot_index(changed.slot)?] = resolve(&changed.item)?;
+        }
+        let carried = resolve(&click.carried_item)?;
+
+        // 扔出的物品直接消失,因此只要求总数不增加
+        let after_items: Vec<&Slot> = after.iter().chain([&carried]).collect();
+        for item in after_items.iter().filter(|s| !s.is_empty()) {
+            let total = |items: &[&Slot]| -> i32 {
+                items
+                    .iter()
+                    .filter(|s| !s.is_empty() && s.is_same_item(item))
+                    .map(|s| s.count)
+                    .sum()
+            };
+            if total(&after_items) > total(&before) {
+                return Err(InventoryError::ItemsCreated(item.item_id));
+            }
+        }
+
+        self.slots = after;
+        self.carried = carried;
+        self.next_state();
+        Ok(())
+    }
+}
+
+/// 数据库中保存的一格物品
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredItem {
+    /// 格子下标,-1 为光标上的物品
+    slot: i32,
+    id: i32,
+    count: i32,
+    /// 带长度前缀编码的组件补丁
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    components: Option<Binary>,
+}
+
+/// 数据库中保存的物品栏,只保存非空的格子
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredInventory {
+    #[serde(default)]
+    items: Vec<StoredItem>,
+}
+
+impl From<Inventory> for StoredInventory {
+    fn from(inventory: Inventory) -> Self {
+        let slots = inventory
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i as i32, s));
+        let items = slots
+            .chain([(-1, &inventory.carried)])
+            .filter(|(_, s)| !s.is_empty())
+            .map(|(slot, item)| {
+                let components = (!item.added.is_empty() || !item.removed.is_empty()).then(|| {
+                    let mut buf = BytesMut::new();
+                    item.write_components(&mut PacketWriter::new(&mut buf), true);
+                    Binary {
+                        subtype: BinarySubtype::Generic,
+                        bytes: buf.to_vec(),
+                    }
+                });
+                StoredItem {
+                    slot,
+                    id: item.item_id,
+                    count: item.count,
+                    components,
+                }
+            })
+            .collect();
+        StoredInventory { items }
+    }
+}
+
+impl From<StoredInventory> for Inventory {
+    fn from(stored: StoredInventory) -> Self {
+        let mut inventory = Inventory::new();
+        for stored in stored.items {
+            let mut item = Slot::new(stored.id, stored.count);
+            if let Some(components) = stored.components {
+                let mut bytes = components.bytes.as_slice();
+                if item
+                    .read_components(&mut PacketReader::new(Box::new(&mut bytes)), true)
+                    .is_none()
+                {
+                    log::warn!("物品 {} 的组件无法解析,已忽略", stored.id);
+                }
+            }
+            match stored.slot {
+                -1 => inventory.carried = item,
+                slot => match usize::try_from(slot)
+                    .ok()
+                    .and_then(|i| inventory.slots.get_mut(i))
+                {
+                    Some(s) => *s = item,
+                    None => log::warn!("物品栏中无效的格子 {},已忽略", slot),
+                },
+            }
+        }
+        inventory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qexed_net::net_types::slot::{DataComponent, HashedSlot};
+    use qexed_net::packet::packet_pool::ChangedSlot;
+
+    fn hashed(item: &Slot) -> HashedSlot {
+        HashedSlot {
+            count: item.count,
+            item_id: item.item_id,
+            added: item.added.iter().map(|c| (c.id(), 0)).collect(),
+            removed: item.removed.clone(),
+        }
+    }
+
+    fn click(state_id: i32, changed: &[(i16, Slot)], carried: &Slot) -> ContainerClick {
+        let mut pk = ContainerClick::new();
+        pk.state_id = VarInt(state_id);
+        pk.changed_slots = changed
+            .iter()
+            .map(|(slot, item)| ChangedSlot {
+                slot: *slot,
+                item: hashed(item),
+            })
+            .collect();
+        pk.carried_item = hashed(carried);
+        pk
+    }
+
+    #[test]
+    fn click_moves_items_without_creating_them() {
+        let mut sword = Slot::new(800, 1);
+        sword.set_component(DataComponent::Damage(12));
+        let mut inventory = Inventory::new();
+        inventory.set(36, Slot::new(1, 40));
+        inventory.set(37, sword.clone());
+        let state = inventory.state_id();
+
+        // 拿起一半石头
+        let pk = click(state, &[(36, Slot::new(1, 20))], &Slot::new(1, 20));
+        inventory.apply_click(&pk).unwrap();
+        assert_eq!(inventory.carried, Slot::new(1, 20));
+        assert_eq!(inventory.state_id(), state + 1);
+
+        // 过期的状态ID
+        assert!(matches!(
+            inventory.apply_click(&pk),
+            Err(InventoryError::StateMismatch { .. })
+        ));
+        // 凭空多出石头
+        let pk = click(
+            inventory.state_id(),
+            &[(9, Slot::new(1, 30))],
+            &Slot::new(1, 20),
+        );
+        assert_eq!(
+            inventory.apply_click(&pk),
+            Err(InventoryError::ItemsCreated(1))
+        );
+        // 没有的物品
+        let pk = click(
+            inventory.state_id(),
+            &[(9, Slot::new(2, 1))],
+            &Slot::new(1, 20),
+        );
+        assert_eq!(
+            inventory.apply_click(&pk),
+            Err(InventoryError::UnknownItem(2))
+        );
+        assert_eq!(inventory.get(9), Some(&Slot::empty()));
+
+        // 移动带组件的剑,组件按点击前的物品还原
+        let pk = click(
+            inventory.state_id(),
+            &[(37, Slot::empty()), (9, sword.clone())],
+            &Slot::new(1, 20),
+        );
+        inventory.apply_click(&pk).unwrap();
+        assert_eq!(inventory.get(9), Some(&sword));
+
+        let stored: Inventory =
+            bson::from_document(bson::to_document(&inventory).unwrap()).unwrap();
+        assert_eq!(stored.slots(), inventory.slots());
+        assert_eq!(stored.carried, inventory.carried);
+    }
+}
@@ -0,0 +1,336 @@
+//! 生物类型
+//!
+//! 每种生物是一个实现了 [`Biology`] 的类型,常量给出原版的默认属性;生成到世界之后,
+//! 属性拆分为 [`super::entity`] 中的各个组件保存。
+
+use super::Biology;
+
+/// 生物的分类,自然生成时各自计算上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MobCategory {
+    /// 敌对生物
+    Monster,
+    /// 友好的陆地动物
+    Creature,
+}
+
+impl MobCategory {
+    pub const ALL: [MobCategory; 2] = [MobCategory::Monster, MobCategory::Creature];
+
+    /// 生物群系生成表中的分类名
+    pub fn name(self) -> &'static str {
+        match self {
+            MobCategory::Monster => "monster",
+            MobCategory::Creature => "creature",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<MobCategory> {
+        MobCategory::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    /// 每 289 个(17×17)可生成区块的数量上限
+    pub fn max_count(self) -> usize {
+        match self {
+            MobCategory::Monster => 70,
+            MobCategory::Creature => 10,
+        }
+    }
+
+    /// 是否是友好生物
+    pub fn is_friendly(self) -> bool {
+        self == MobCategory::Creature
+    }
+
+    /// 持久的分类只定期生成,也不会因为远离玩家而消失
+    pub fn is_persistent(self) -> bool {
+        self == MobCategory::Creature
+    }
+
+    /// 离所有玩家超过这个距离时立即消失
+    pub fn despawn_distance(self) -> f64 {
+        128.0
+    }
+
+    /// 离所有玩家超过这个距离且长时间没有活动时随机消失
+    pub fn no_despawn_distance(self) -> f64 {
+        32.0
+    }
+}
+
+/// 生物可以调整的属性,生成时写入实体的组件
+#[derive(Debug, Clone, PartialEq)]
+pub struct MobStats {
+    pub entity_id: i32,
+    pub display_name: Option<String>,
+    pub show_name: bool,
+    pub hide_health: bool,
+    pub attack_damage: f32,
+    pub armor: f32,
+    pub max_health: f32,
+    pub health: f32,
+    pub movement_speed: f32,
+    pub can_swim: bool,
+}
+
+impl MobStats {
+    /// 按 `B` 的常量生成默认属性
+    pub fn defaults<B: Biology>() -> Self {
+        MobStats {
+            entity_id: -1,
+            display_name: None,
+            show_name: B::SHOW_NAME,
+            hide_health: B::HIDE_HEALTH,
+            attack_damage: B::DEFAULT_ATTACK_DAMAGE,
+            armor: B::DEFAULT_ARMOR,
+            max_health: B::DEFAULT_MAX_HEALTH,
+            health: B::DEFAULT_MAX_HEALTH,
+            movement_speed: B::DEFAULT_MOVEMENT_SPEED,
+            can_swim: B::DEFAULT_CAN_SWIM,
+        }
+    }
+}
+
+/// 可以生成到世界中的生物
+pub trait MobType: Biology {
+    const KIND: MobKind;
+    fn stats(&self) -> &MobStats;
+    fn into_stats(self) -> MobStats;
+}
+
+macro_rules! mobs {
+    ($(
+        $(#[$doc:meta])*
+        $ty:ident {
+            name: $name:literal,
+            description: $description:literal,
+            entity_type: $entity_type:literal,
+            size: ($width:literal, $height:literal),
+            category: $category:ident,
+            $($konst:ident: $kty:ty = $value:expr,)*
+        }
+    )*) => {
+        /// 所有生物类型
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum MobKind {
+            $($ty,)*
+        }
+
+        impl MobKind {
+            pub const ALL: &'static [MobKind] = &[$(MobKind::$ty,)*];
+
+            /// 生物名(含命名空间)
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(MobKind::$ty => <$ty as Biology>::NAME,)*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<MobKind> {
+                let name = name.strip_prefix("minecraft:").unwrap_or(name);
+                MobKind::ALL
+                    .iter()
+                    .copied()
+                    .find(|kind| kind.name().strip_prefix("minecraft:") == Some(name))
+            }
+
+            /// entity_type 注册表中的下标
+            pub fn entity_type(self) -> i32 {
+                match self {
+                    $(MobKind::$ty => $entity_type,)*
+                }
+            }
+
+            /// 碰撞箱的宽与高
+            pub fn size(self) -> (f64, f64) {
+                match self {
+                    $(MobKind::$ty => ($width, $height),)*
+                }
+            }
+
+            pub fn category(self) -> MobCategory {
+                match self {
+                    $(MobKind::$ty => MobCategory::$category,)*
+                }
+            }
+
+            pub fn default_stats(self) -> MobStats {
+                match self {
+                    $(MobKind::$ty => $ty::new().0,)*
+                }
+            }
+        }
+
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Clone, PartialEq)]
+            pub struct $ty(pub MobStats);
+
+            impl $ty {
+                pub fn new() -> Self {
+                    $ty(MobStats::defaults::<$ty>())
+                }
+            }
+
+            impl Default for $ty {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            impl Biology for $ty {
+                const NAME: &'static str = $name;
+                const DESCRIPTION: &'static str = $description;
+                $(const $konst: $kty = $value;)*
+
+                fn entity_id(&self) -> i32 {
+                    self.0.entity_id
+                }
+                fn set_entity_id(&mut self, id: i32) {
+                    self.0.entity_id = id;
+                }
+                fn set_display_name(&mut self, name: String) {
+                    self.0.display_name = Some(name);
+                }
+                fn set_show_name(&mut self, show: bool) {
+                    self.0.show_name = show;
+                }
+                fn set_attack_damage(&mut self, damage: f32) {
+                    self.0.attack_damage = damage;
+                }
+                fn set_armor(&mut self, armor: f32) {
+                    self.0.armor = armor;
+                }
+                fn set_max_health(&mut self, health: f32) {
+                    self.0.max_health = health.max(1.0);
+                    self.0.health = self.0.health.min(self.0.max_health);
+                }
+                fn set_health(&mut self, health: f32) {
+                    self.0.health = health.clamp(0.0, self.0.max_health);
+                }
+            }
+
+            impl MobType for $ty {
+                const KIND: MobKind = MobKind::$ty;
+                fn stats(&self) -> &MobStats {
+                    &self.0
+                }
+                fn into_stats(self) -> MobStats {
+                    self.0
+                }
+            }
+        )*
+    };
+}
+
+mobs! {
+    /// 僵尸
+    Zombie {
+        name: "minecraft:zombie",
+        description: "entity.minecraft.zombie",
+        entity_type: 144,
+        size: (0.6, 1.95),
+        category: Monster,
+        DEFAULT_ATTACK_DAMAGE: f32 = 3.0,
+        DEFAULT_ARMOR: f32 = 2.0,
+        DEFAULT_MOVEMENT_SPEED: f32 = 0.23,
+    }
+    /// 骷髅
+    Skeleton {
+        name: "minecraft:skeleton",
+        description: "entity.minecraft.skeleton",
+        entity_type: 108,
+        size: (0.6, 1.99),
+        category: Monster,
+        DEFAULT_ATTACK_DAMAGE: f32 = 2.0,
+        DEFAULT_MOVEMENT_SPEED: f32 = 0.25,
+    }
+    /// 苦力怕
+    Creeper {
+        name: "minecraft:creeper",
+        description: "entity.minecraft.creeper",
+        entity_type: 30,
+        size: (0.6, 1.7),
+        category: Monster,
+        DEFAULT_MOVEMENT_SPEED: f32 = 0.25,
+    }
+    /// 蜘蛛
+    Spider {
+        name: "minecraft:spider",
+        description: "entity.minecraft.spider",
+        entity_type: 117,
+        size: (1.4, 0.9),
+        category: Monster,
+        DEFAULT_ATTACK_DAMAGE: f32 = 2.0,
+        DEFAULT_MAX_HEALTH: f32 = 16.0,
+        DEFAULT_MOVEMENT_SPEED: f32 = 0.3,
+    }
+    /// 牛
+    Cow {
+        name: "minecraft:cow",
+        description: "entity.minecraft.cow",
+        entity_type: 28,
+        size: (0.9, 1.4),
+        category: Creature,
+        DEFAULT_MAX_HEALTH: f32 = 10.0,
+        DEFAULT_MOVEMENT_SPEED: f32 = 0.2,
+        DEFAULT_CAN_SWIM: bool = true,
+    }
+    /// 猪
+    Pig {
+        name: "minecraft:pig",
+        description: "entity.minecraft.pig",
+        entity_type: 95,
+        size: (0.9, 0.9),
+        category: Creature,
+        DEFAULT_MAX_HEALTH: f32 = 10.0,
+        DEFAULT_MOVEMENT_SPEED: f32 = 0.25,
+        DEFAULT_CAN_SWIM: bool = true,
+        DEFAULT_CAN_BE_RIDDEN: bool = true,
+    }
+    /// 羊
+    Sheep {
+        name: "minecraft:sheep",
+        description: "entity.minecraft.sheep",
+        entity_type: 104,
+        size: (0.9, 1.3),
+        category: Creature,
+        DEFAULT_MAX_HEALTH: f32 = 8.0,
+        DEFAULT_MOVEMENT_SPEED: f32 = 0.23,
+        DEFAULT_CAN_SWIM: bool = true,
+    }
+    /// 鸡
+    Chicken {
+        name: "minecraft:chicken",
+        description: "entity.minecraft.chicken",
+        entity_type: 25,
+        size: (0.4, 0.7),
+        category: Creature,
+        DEFAULT_MAX_HEALTH: f32 = 4.0,
+        DEFAULT_MOVEMENT_SPEED: f32 = 0.25,
+        DEFAULT_CAN_SWIM: bool = true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_and_setters() {
+        let mut zombie = Zombie::new();
+        assert_eq!(zombie.entity_id(), -1);
+        assert_eq!(zombie.0.attack_damage, 3.0);
+        assert_eq!(zombie.0.health, 20.0);
+        zombie.set_max_health(10.0);
+        assert_eq!(zombie.0.health, 10.0);
+        zombie.set_health(-5.0);
+        assert_eq!(zombie.0.health, 0.0);
+
+        assert_eq!(MobKind::from_name("minecraft:pig"), Some(MobKind::Pig));
+        assert_eq!(MobKind::from_name("spider"), Some(MobKind::Spider));
+        assert_eq!(MobKind::Spider.default_stats().max_health, 16.0);
+        assert_eq!(MobKind::Cow.category(), MobCategory::Creature);
+        assert_eq!(<Pig as MobType>::KIND, MobKind::Pig);
+    }
+}
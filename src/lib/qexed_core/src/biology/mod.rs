@@ -50,4 +50,9 @@ pub trait Biology {
     
 }
 
-pub mod player;
\ No newline at end of file
+pub mod ai;
+pub mod entity;
+pub mod inventory;
+pub mod mob;
+pub mod player;
+pub mod spawn;
\ No newline at end of file
@@ -1,6 +1,5 @@
 use bson::{spec::BinarySubtype, Binary};
 use mongodb::{bson::{self, doc, DateTime}, Collection, Database};
-use qexed_net::net_types::{position::Position, subdata::Subdata};
 
 use serde::{Deserialize, Serialize};
 use anyhow::{Context, Result};
@@ -14,18 +13,63 @@ pub struct Player {
     pub username: String,
     #[serde(skip)]
     pub entity_id: i32,
+    /// 实体 ID 的代数,ID 被其他实体复用后旧的引用可以据此识别
+    #[serde(skip)]
+    pub entity_generation: u32,
     pub level: u32,
     pub last_login: DateTime,
-    // 暂时没有物品
-    // pub inventory: Vec<Item>,
-    pub position: Position,// 位置
+    /// 物品栏
+    #[serde(default)]
+    pub inventory: crate::biology::inventory::Inventory,
+    /// 游戏模式,0:生存,1:创造,2:冒险,3:旁观
+    #[serde(default)]
+    pub game_mode: u8,
+    /// 是否允许飞行(不限游戏模式)
+    #[serde(default)]
+    pub may_fly: bool,
+    /// 手持的快捷栏格子,0 到 8
+    #[serde(skip)]
+    pub selected_slot: u8,
+    /// 位置(脚下中心),还没有进入过世界时为 `None`
+    #[serde(default, rename = "location")]
+    pub position: Option<(f64, f64, f64)>,
     pub yaw:f32,// 偏航角
     pub pitch:f32, // 俯仰角
     pub health: f32,
+    /// 是否着地
+    #[serde(skip)]
+    pub on_ground: bool,
     pub is_online: bool,
+    /// 所在的命名世界,为空时表示默认世界
+    #[serde(default)]
+    pub world: String,
+    /// 所在维度
+    #[serde(default, with = "dimension_as_name")]
+    pub dimension: qexed_world::Dimension,
+    /// 传送门冷却剩余的 tick
+    #[serde(skip)]
+    pub portal_cooldown: i32,
     // 其他与玩家无直接关系的属性
     #[serde(skip)]
-    pub conn:Option<std::sync::Arc<tokio::sync::Mutex<qexed_net::PacketListener>>>,
+    pub conn:Option<qexed_net::PacketSender>,
+    /// 已发送给客户端的区块视野
+    #[serde(skip)]
+    pub chunk_view: qexed_world::ChunkView,
+    /// 等待发送的区块
+    #[serde(skip)]
+    pub chunk_sender: qexed_world::ChunkSender,
+    /// 等待客户端确认的传送
+    #[serde(skip)]
+    pub teleports: crate::event::teleport::TeleportTracker,
+    /// 服务端移动校验的状态
+    #[serde(skip)]
+    pub physics: crate::event::physics::PhysicsState,
+    /// 这个玩家能看到的实体
+    #[serde(skip)]
+    pub tracker: crate::event::entity_tracker::EntityTracker,
+    /// 发送给其他玩家的元数据(皮肤部分、姿势、发光等)
+    #[serde(skip)]
+    pub metadata: qexed_net::net_types::entity_metadata::EntityMetadata,
 }
 
 impl Player {
@@ -34,15 +78,29 @@ impl Player {
             username: String::new(),
             uuid: Uuid::new_v4(),
             entity_id: -1,
+            entity_generation: 0,
             level: 0,
             last_login: DateTime::now(),
-            position: Position::new(),
+            position: None,
             health: 20.0, // 默认生命值
+            on_ground: false,
             yaw:0.0,
             pitch:0.0,
             is_online: false, // 默认不在线
-            // inventory: Vec::new(), // 暂时没有物品
+            world: String::new(),
+            dimension: qexed_world::Dimension::Overworld,
+            portal_cooldown: 0,
+            inventory: crate::biology::inventory::Inventory::new(),
+            game_mode: 0,
+            may_fly: false,
+            selected_slot: 0,
             conn:None,
+            chunk_view: qexed_world::ChunkView::new(),
+            chunk_sender: qexed_world::ChunkSender::new(),
+            teleports: crate::event::teleport::TeleportTracker::new(),
+            physics: crate::event::physics::PhysicsState::new(),
+            tracker: crate::event::entity_tracker::EntityTracker::new(),
+            metadata: qexed_net::net_types::entity_metadata::EntityMetadata::new(),
         }
     }
 
@@ -52,15 +110,37 @@ impl Player {
             username: String::new(),
             uuid,
             entity_id: -1,
+            entity_generation: 0,
             level: 0,
             last_login: DateTime::now(),
-            position: Position::new(),
+            position: None,
             yaw:0.0,
             pitch:0.0,            
             health: 20.0,
+            on_ground: false,
             is_online: false,
+            world: String::new(),
+            dimension: qexed_world::Dimension::Overworld,
+            portal_cooldown: 0,
             conn:None,
-            // inventory: Vec::new(),
+            chunk_view: qexed_world::ChunkView::new(),
+            chunk_sender: qexed_world::ChunkSender::new(),
+            teleports: crate::event::teleport::TeleportTracker::new(),
+            physics: crate::event::physics::PhysicsState::new(),
+            tracker: crate::event::entity_tracker::EntityTracker::new(),
+            metadata: qexed_net::net_types::entity_metadata::EntityMetadata::new(),
+            inventory: crate::biology::inventory::Inventory::new(),
+            game_mode: 0,
+            may_fly: false,
+            selected_slot: 0,
+        }
+    }
+
+    /// 实体 ID 与代数
+    pub fn entity_handle(&self) -> crate::utils::entity_ids::EntityHandle {
+        crate::utils::entity_ids::EntityHandle {
+            id: self.entity_id,
+            generation: self.entity_generation,
         }
     }
     /// 获取 MongoDB 玩家集合
@@ -123,9 +203,9 @@ impl Player {
     pub async fn update_position(
         &mut self,
         db: &Database,
-        position: Position,
+        position: (f64, f64, f64),
     ) -> Result<()> {
-        self.position = position;
+        self.position = Some(position);
         self.save(db).await
     }
 
@@ -140,6 +220,27 @@ impl Player {
         self.save(db).await
     }
 }
+mod dimension_as_name {
+    use qexed_world::Dimension;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    /// 维度保存为维度名,例如 `minecraft:the_nether`
+    pub fn serialize<S>(dimension: &Dimension, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(dimension.name())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Dimension, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        Dimension::from_name(&name)
+            .ok_or_else(|| D::Error::custom(format!("Unknown dimension: {}", name)))
+    }
+}
 mod uuid_as_binary {
     use bson::{Binary, spec::BinarySubtype};
     use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
@@ -0,0 +1,733 @@
+//! 生物的自然生成与消失
+//!
+//! 与原版 `NaturalSpawner` 相同:每刻统计世界中各分类的生物数量,上限按玩家 128 格内已加载的
+//! 区块数缩放(每 289 个区块为 [`MobCategory::max_count`])。未达到上限的分类在每个可生成区块中
+//! 随机选一个位置,尝试在周围生成三群生物;种类与群的大小来自所在生物群系的生成表,位置需要满足
+//! 光照与方块条件,且离玩家与世界出生点都在 24 格以上。
+//!
+//! 友好生物是持久的,只在 [`SpawnRules::persistent`] 的刻生成(原版每 400 刻一次),也不会因为远离
+//! 玩家而消失;敌对生物离所有玩家超过 128 格时立即消失,超过 32 格且 600 刻没有活动后随机消失。
+//!
+//! 生成表读取原版数据包 `worldgen/biome/*.json` 中的 `spawners` 字段,目录不存在时使用内置表。
+//! 内置表与原版的常见敌对生物和农场动物一致,但只包含已实现的生物。
+//! 下发给客户端的 RegistryData 只引用已知数据包中的条目,不带生成表,因此无法从中读取。
+//!
+//! 仓库不附带原版数据。需要完整的生成表时,从对应版本的原版服务端 jar 中解压
+//! `data/minecraft/worldgen/biome` 目录,放到服务端运行目录下的同一路径([`BIOME_DATA_PATH`])。
+
+use std::{collections::HashMap, path::Path};
+
+use once_cell::sync::OnceCell;
+use qexed_world::{
+    BlockPos, ChunkPos, Dimension, HeightmapType, LightKind, World,
+    biome::{self, Biome},
+    block, block_registry,
+    pathfind::{self, NodeType, PathOptions},
+    shape,
+};
+use rand::{Rng, RngCore, seq::SliceRandom};
+use serde::Deserialize;
+
+use crate::{
+    biology::{
+        entity::{AiState, EntityStore, Health, Position},
+        mob::{MobCategory, MobKind},
+    },
+    event::entity_tracker::EntitySnapshot,
+    utils::entity_ids::EntityIds,
+};
+
+/// 默认的生物群系数据目录,相对于服务端运行目录,与原版服务端 jar 中的路径相同
+pub const BIOME_DATA_PATH: &str = "data/minecraft/worldgen/biome";
+/// 离玩家多远以内的区块可以生成生物
+const SPAWN_RANGE: f64 = 128.0;
+/// 生物不会生成在离玩家或出生点这么近的地方
+const MIN_SPAWN_DISTANCE: f64 = 24.0;
+/// 计算上限时的基准区块数(17×17)
+const MAGIC_CHUNKS: usize = 17 * 17;
+/// 一次生成尝试最多生成的生物数
+const MAX_CLUSTER_SIZE: usize = 4;
+/// 没有活动超过这么多刻后才会随机消失
+const NO_ACTION_TICKS: u32 = 600;
+/// 生物不能站在这些方块上生成
+const NEVER_SPAWN_ON: &[&str] = &["minecraft:bedrock", "minecraft:barrier"];
+/// 动物只能生成在这些方块上(`#animals_spawnable_on`)
+const ANIMALS_SPAWNABLE_ON: &[&str] = &["minecraft:grass_block"];
+
+/// 生成表中的一项
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SpawnerData {
+    #[serde(rename = "type", deserialize_with = "mob_kind")]
+    pub kind: MobKind,
+    pub weight: u32,
+    #[serde(rename = "minCount")]
+    pub min_count: u32,
+    #[serde(rename = "maxCount")]
+    pub max_count: u32,
+}
+
+fn mob_kind<'de, D: serde::Deserializer<'de>>(d: D) -> Result<MobKind, D::Error> {
+    let name = String::deserialize(d)?;
+    MobKind::from_name(&name)
+        .ok_or_else(|| serde::de::Error::custom(format!("未实现的生物 {}", name)))
+}
+
+/// 各生物群系的生成表
+#[derive(Debug, Clone, Default)]
+pub struct SpawnTables {
+    biomes: HashMap<Biome, HashMap<MobCategory, Vec<SpawnerData>>>,
+}
+
+impl SpawnTables {
+    /// 内置的生成表
+    pub fn builtin() -> Self {
+        let spawner = |kind, weight| SpawnerData {
+            kind,
+            weight,
+            min_count: 4,
+            max_count: 4,
+        };
+        let monsters = vec![
+            spawner(MobKind::Spider, 100),
+            spawner(MobKind::Zombie, 95),
+            spawner(MobKind::Skeleton, 100),
+            spawner(MobKind::Creeper, 100),
+        ];
+        let animals = vec![
+            spawner(MobKind::Sheep, 12),
+            spawner(MobKind::Pig, 10),
+            spawner(MobKind::Chicken, 10),
+            spawner(MobKind::Cow, 8),
+        ];
+        // 下界、末地、蘑菇岛与深暗之域没有这些生物
+        const NO_MONSTERS: &[&str] = &[
+            "basalt_deltas",
+            "crimson_forest",
+            "deep_dark",
+            "end_barrens",
+            "end_highlands",
+            "end_midlands",
+            "mushroom_fields",
+            "nether_wastes",
+            "small_end_islands",
+            "soul_sand_valley",
+            "the_end",
+            "the_void",
+            "warped_forest",
+        ];
+        const FARM_ANIMALS: &[&str] = &[
+            "bamboo_jungle",
+            "birch_forest",
+            "dark_forest",
+            "flower_forest",
+            "forest",
+            "jungle",
+            "old_growth_birch_forest",
+            "old_growth_pine_taiga",
+            "old_growth_spruce_taiga",
+            "plains",
+            "savanna",
+            "savanna_plateau",
+            "sparse_jungle",
+            "sunflower_plains",
+            "swamp",
+            "taiga",
+            "windswept_forest",
+            "windswept_gravelly_hills",
+            "windswept_hills",
+            "windswept_savanna",
+        ];
+        let mut tables = SpawnTables::default();
+        for (id, name) in biome::BIOMES.iter().enumerate() {
+            let name = name.strip_prefix("minecraft:").unwrap_or(name);
+            let id = id as Biome;
+            if !NO_MONSTERS.contains(&name) {
+                tables.insert(id, MobCategory::Monster, monsters.clone());
+            }
+            if FARM_ANIMALS.contains(&name) {
+                tables.insert(id, MobCategory::Creature, animals.clone());
+            }
+        }
+        tables
+    }
+
+    /// 解析一个生物群系的 json,未实现的生物与分类会被跳过
+    pub fn read_biome(&mut self, biome: Biome, json: &str) -> Result<(), serde_json::Error> {
+        #[derive(Deserialize)]
+        struct BiomeJson {
+            #[serde(default)]
+            spawners: HashMap<String, Vec<serde_json::Value>>,
+        }
+        let root: BiomeJson = serde_json::from_str(json)?;
+        for (category, spawners) in root.spawners {
+            let Some(category) = MobCategory::from_name(&category) else {
+                continue;
+            };
+            let spawners = spawners
+                .into_iter()
+                .filter_map(|s| serde_json::from_value(s).ok())
+                .collect();
+            self.insert(biome, category, spawners);
+        }
+        Ok(())
+    }
+
+    /// 从原版数据包的生物群系目录加载,目录不存在时退回内置表
+    pub fn load(dir: impl AsRef<Path>) -> Self {
+        let dir = dir.as_ref();
+        if !dir.is_dir() {
+            log::warn!(
+                "未找到生物群系数据 {},使用内置生成表;可从原版服务端 jar 中解压该目录",
+                dir.display()
+            );
+            return Self::builtin();
+        }
+        let mut tables = SpawnTables::default();
+        for (id, name) in biome::BIOMES.iter().enumerate() {
+            let name = name.strip_prefix("minecraft:").unwrap_or(name);
+            let path = dir.join(format!("{}.json", name));
+            let Ok(json) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if let Err(e) = tables.read_biome(id as Biome, &json) {
+                log::warn!("生物群系数据 {} 解析失败: {}", path.display(), e);
+            }
+        }
+        log::info!(
+            "已加载生成表 {}: {} 个生物群系",
+            dir.display(),
+            tables.biomes.len()
+        );
+        tables
+    }
+
+    pub fn insert(&mut self, biome: Biome, category: MobCategory, spawners: Vec<SpawnerData>) {
+        self.biomes
+            .entry(biome)
+            .or_default()
+            .insert(category, spawners);
+    }
+
+    pub fn get(&self, biome: Biome, category: MobCategory) -> &[SpawnerData] {
+        self.biomes
+            .get(&biome)
+            .and_then(|c| c.get(&category))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// 按权重随机选择一项
+    pub fn pick(
+        &self,
+        biome: Biome,
+        category: MobCategory,
+        rng: &mut dyn RngCore,
+    ) -> Option<&SpawnerData> {
+        let spawners = self.get(biome, category);
+        let total: u32 = spawners.iter().map(|s| s.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.random_range(0..total);
+        spawners.iter().find(|s| {
+            if roll < s.weight {
+                return true;
+            }
+            roll -= s.weight;
+            false
+        })
+    }
+}
+
+static TABLES: OnceCell<SpawnTables> = OnceCell::new();
+
+/// 初始化全局生成表,只有第一次调用生效
+pub fn init_spawn_tables(dir: impl AsRef<Path>) {
+    let _ = TABLES.set(SpawnTables::load(dir));
+}
+
+/// 全局生成表,未初始化时使用内置表
+pub fn spawn_tables() -> &'static SpawnTables {
+    TABLES.get_or_init(SpawnTables::builtin)
+}
+
+/// 这一刻允许生成的分类与环境
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnRules {
+    /// 生成敌对生物
+    pub enemies: bool,
+    /// 生成友好生物
+    pub friendlies: bool,
+    /// 生成持久的分类
+    pub persistent: bool,
+    /// 天空光的减弱量,夜晚为 11;服务器还没有昼夜时按白天计算
+    pub sky_darken: u8,
+    /// 世界出生点,附近不生成生物
+    pub world_spawn: Option<BlockPos>,
+}
+
+impl SpawnRules {
+    fn allows(&self, category: MobCategory) -> bool {
+        (if category.is_friendly() {
+            self.friendlies
+        } else {
+            self.enemies
+        }) && (self.persistent || !category.is_persistent())
+    }
+}
+
+fn biome_at(world: &World, pos: BlockPos) -> Option<Biome> {
+    let chunk = world.get_chunk(pos.chunk_pos())?;
+    let chunk = chunk.read().unwrap();
+    chunk.get_biome(pos.local_x(), pos.y, pos.local_z())
+}
+
+fn nearest_player_sq(players: &[EntitySnapshot], (x, y, z): (f64, f64, f64)) -> Option<f64> {
+    players
+        .iter()
+        .map(|p| {
+            (p.position.0 - x).powi(2) + (p.position.1 - y).powi(2) + (p.position.2 - z).powi(2)
+        })
+        .min_by(f64::total_cmp)
+}
+
+/// 原版的 `getRawBrightness`
+fn brightness(world: &World, pos: BlockPos, sky_darken: u8) -> u8 {
+    let sky = if world.dimension.has_skylight() {
+        world.light(LightKind::Sky, pos).unwrap_or(0)
+    } else {
+        0
+    };
+    let block = world.light(LightKind::Block, pos).unwrap_or(0);
+    block.max(sky.saturating_sub(sky_darken))
+}
+
+/// 原版的 `Monster.isDarkEnoughToSpawn`
+fn is_dark_enough(world: &World, pos: BlockPos, sky_darken: u8, rng: &mut dyn RngCore) -> bool {
+    if world.dimension.has_skylight()
+        && world.light(LightKind::Sky, pos).unwrap_or(0) > rng.random_range(0..32)
+    {
+        return false;
+    }
+    // 下界对方块光没有限制
+    let block_limit = if world.dimension == Dimension::Nether {
+        15
+    } else {
+        0
+    };
+    if world.light(LightKind::Block, pos).unwrap_or(0) > block_limit {
+        return false;
+    }
+    brightness(world, pos, sky_darken) <= rng.random_range(0..=7)
+}
+
+/// 生物能否生成在 `pos`(脚下)
+fn can_spawn_at(
+    world: &World,
+    kind: MobKind,
+    pos: BlockPos,
+    rules: &SpawnRules,
+    rng: &mut dyn RngCore,
+) -> bool {
+    let (width, height) = kind.size();
+    let options = PathOptions::new(width, height);
+    if pathfind::node_type(block_registry(), world, pos, &options) != Some(NodeType::Walkable) {
+        return false;
+    }
+    let Some(below) = world
+        .get_block(pos.offset(0, -1, 0))
+        .and_then(|s| block_registry().name_of(s))
+    else {
+        return false;
+    };
+    if NEVER_SPAWN_ON.contains(&below.as_str()) {
+        return false;
+    }
+    match kind.category() {
+        MobCategory::Monster => is_dark_enough(world, pos, rules.sky_darken, rng),
+        MobCategory::Creature => {
+            ANIMALS_SPAWNABLE_ON.contains(&below.as_str())
+                && brightness(world, pos, rules.sky_darken) > 8
+        }
+    }
+}
+
+/// 在 `start` 周围尝试生成三群 `category` 的生物,返回生成的数量
+#[allow(clippy::too_many_arguments)]
+fn spawn_at(
+    store: &mut EntityStore,
+    ids: &mut EntityIds,
+    level: &str,
+    world: &World,
+    tables: &SpawnTables,
+    players: &[EntitySnapshot],
+    rules: &SpawnRules,
+    category: MobCategory,
+    start: BlockPos,
+    rng: &mut dyn RngCore,
+) -> usize {
+    // 起点在方块中时放弃
+    let Some(state) = world.get_block(start) else {
+        return 0;
+    };
+    if !block::is_air(state) && !shape::collision_boxes(block_registry(), state).is_empty() {
+        return 0;
+    }
+    let mut spawned = 0;
+    for _ in 0..3 {
+        let (mut x, mut z) = (start.x, start.z);
+        let mut spawner: Option<SpawnerData> = None;
+        let mut group_size = rng.random_range(1..=4);
+        let mut attempt = 0;
+        while attempt < group_size {
+            attempt += 1;
+            x += rng.random_range(0..6) - rng.random_range(0..6);
+            z += rng.random_range(0..6) - rng.random_range(0..6);
+            let pos = BlockPos::new(x, start.y, z);
+            let center = (x as f64 + 0.5, start.y as f64, z as f64 + 0.5);
+            let Some(distance_sq) = nearest_player_sq(players, center) else {
+                continue;
+            };
+            let near_spawn = rules.world_spawn.is_some_and(|s| {
+                let (dx, dy, dz) = (
+                    s.x as f64 + 0.5 - center.0,
+                    s.y as f64 - center.1,
+                    s.z as f64 + 0.5 - center.2,
+                );
+                dx * dx + dy * dy + dz * dz < MIN_SPAWN_DISTANCE * MIN_SPAWN_DISTANCE
+            });
+            if distance_sq <= MIN_SPAWN_DISTANCE * MIN_SPAWN_DISTANCE || near_spawn {
+                continue;
+            }
+            let Some(biome) = biome_at(world, pos) else {
+                continue;
+            };
+            let data = match &spawner {
+                Some(data) => data.clone(),
+                None => {
+                    let Some(data) = tables.pick(biome, category, rng).cloned() else {
+                        break;
+                    };
+                    group_size = data.min_count as usize
+                        + rng.random_range(0..=data.max_count.saturating_sub(data.min_count))
+                            as usize;
+                    spawner = Some(data.clone());
+                    data
+                }
+            };
+            // 生成后会立即消失的位置、群中途走出了能生成它的生物群系
+            if !category.is_persistent() && distance_sq > category.despawn_distance().powi(2) {
+                continue;
+            }
+            if !tables
+                .get(biome, category)
+                .iter()
+                .any(|s| s.kind == data.kind)
+            {
+                continue;
+            }
+            if !can_spawn_at(world, data.kind, pos, rules, rng) {
+                continue;
+            }
+            let yaw = rng.random_range(0.0..360.0);
+            let position = Position {
+                x: center.0,
+                y: center.1,
+                z: center.2,
+                yaw,
+                head_yaw: yaw,
+                on_ground: true,
+                ..Default::default()
+            };
+            if let Err(e) = store.spawn_kind(
+                ids,
+                data.kind,
+                data.kind.default_stats(),
+                level,
+                world.dimension,
+                position,
+            ) {
+                log::warn!("生物生成失败: {}", e);
+                return spawned;
+            }
+            spawned += 1;
+            if spawned >= MAX_CLUSTER_SIZE {
+                return spawned;
+            }
+        }
+    }
+    spawned
+}
+
+/// 运行一刻 `world` 的自然生成,返回生成的生物数
+#[allow(clippy::too_many_arguments)]
+pub fn tick_spawning(
+    store: &mut EntityStore,
+    ids: &mut EntityIds,
+    level: &str,
+    world: &World,
+    tables: &SpawnTables,
+    players: &[EntitySnapshot],
+    rules: &SpawnRules,
+    rng: &mut dyn RngCore,
+) -> usize {
+    let players: Vec<EntitySnapshot> = players
+        .iter()
+        .filter(|p| p.level == level && p.dimension == world.dimension)
+        .cloned()
+        .collect();
+    if players.is_empty() {
+        return 0;
+    }
+    let mut chunks: Vec<ChunkPos> = world
+        .loaded_chunks()
+        .into_iter()
+        .filter(|c| {
+            let (x, z) = (c.min_block_x() as f64 + 8.0, c.min_block_z() as f64 + 8.0);
+            players.iter().any(|p| {
+                (p.position.0 - x).powi(2) + (p.position.2 - z).powi(2) < SPAWN_RANGE * SPAWN_RANGE
+            })
+        })
+        .collect();
+    chunks.sort_by_key(|c| (c.x, c.z));
+    chunks.shuffle(rng);
+
+    let mut counts: HashMap<MobCategory, usize> = HashMap::new();
+    for info in store.entities() {
+        let id = info.handle.id;
+        if info.level == level
+            && info.dimension == world.dimension
+            && !store.get::<Health>(id).is_some_and(|h| h.is_dead())
+            && !store.is_persistence_required(id)
+        {
+            *counts.entry(info.kind.category()).or_default() += 1;
+        }
+    }
+
+    let mut total = 0;
+    for chunk in &chunks {
+        for category in MobCategory::ALL {
+            let cap = category.max_count() * chunks.len() / MAGIC_CHUNKS;
+            if !rules.allows(category) || counts.get(&category).copied().unwrap_or(0) >= cap {
+                continue;
+            }
+            let (x, z) = (rng.random_range(0..16), rng.random_range(0..16));
+            let Some(surface) = world.get_chunk(*chunk).map(|c| {
+                c.read()
+                    .unwrap()
+                    .heightmaps
+                    .get(HeightmapType::WorldSurface)
+                    .get(x, z)
+            }) else {
+                continue;
+            };
+            let min_y = world.dimension.min_y();
+            let y = rng.random_range(min_y..=surface.max(min_y));
+            if y < min_y + 1 {
+                continue;
+            }
+            let start = BlockPos::new(
+                chunk.min_block_x() + x as i32,
+                y,
+                chunk.min_block_z() + z as i32,
+            );
+            let spawned = spawn_at(
+                store, ids, level, world, tables, &players, rules, category, start, rng,
+            );
+            *counts.entry(category).or_default() += spawned;
+            total += spawned;
+        }
+    }
+    total
+}
+
+/// 让远离玩家的生物消失,返回消失的数量
+pub fn tick_despawn(
+    store: &mut EntityStore,
+    ids: &mut EntityIds,
+    level: &str,
+    dimension: Dimension,
+    players: &[EntitySnapshot],
+    rng: &mut dyn RngCore,
+) -> usize {
+    let players: Vec<EntitySnapshot> = players
+        .iter()
+        .filter(|p| p.level == level && p.dimension == dimension)
+        .cloned()
+        .collect();
+    let mut candidates: Vec<_> = store
+        .entities()
+        .filter(|e| e.level == level && e.dimension == dimension)
+        .map(|e| (e.handle, e.kind.category()))
+        .collect();
+    candidates.sort_by_key(|(h, _)| h.id);
+    let mut removed = 0;
+    for (handle, category) in candidates {
+        let id = handle.id;
+        if store.get::<Health>(id).is_some_and(|h| h.is_dead()) {
+            continue;
+        }
+        let persistent = category.is_persistent() || store.is_persistence_required(id);
+        let distance_sq = store
+            .get::<Position>(id)
+            .and_then(|p| nearest_player_sq(&players, (p.x, p.y, p.z)));
+        let Some(ai) = store.get_mut::<AiState>(id) else {
+            continue;
+        };
+        let Some(distance_sq) = distance_sq.filter(|_| !persistent) else {
+            ai.no_action_ticks = 0;
+            continue;
+        };
+        ai.no_action_ticks += 1;
+        let far = distance_sq > category.no_despawn_distance().powi(2);
+        let despawn = distance_sq > category.despawn_distance().powi(2)
+            || (far && ai.no_action_ticks > NO_ACTION_TICKS && rng.random_range(0..800) == 0);
+        if !far {
+            ai.no_action_ticks = 0;
+        }
+        if despawn && store.despawn(ids, handle) {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use qexed_world::{Chunk, block};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::test_util::player_at;
+
+    #[test]
+    fn read_biome_spawners() {
+        let mut tables = SpawnTables::default();
+        tables
+            .read_biome(
+                biome::PLAINS,
+                r#"{"spawners": {
+                    "creature": [{"type": "minecraft:cow", "weight": 8, "minCount": 4, "maxCount": 4},
+                                 {"type": "minecraft:horse", "weight": 1, "minCount": 2, "maxCount": 6}],
+                    "ambient": [{"type": "minecraft:bat", "weight": 10, "minCount": 8, "maxCount": 8}]
+                }, "spawn_costs": {}}"#,
+            )
+            .unwrap();
+        assert_eq!(
+            tables.get(biome::PLAINS, MobCategory::Creature),
+            &[SpawnerData {
+                kind: MobKind::Cow,
+                weight: 8,
+                min_count: 4,
+                max_count: 4
+            }]
+        );
+        assert!(tables.get(biome::PLAINS, MobCategory::Monster).is_empty());
+        let builtin = SpawnTables::builtin();
+        assert_eq!(builtin.get(biome::PLAINS, MobCategory::Creature).len(), 4);
+        assert!(
+            builtin
+                .get(biome::NETHER_WASTES, MobCategory::Monster)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn spawn_caps_and_despawn() {
+        // 9×9 个区块的草地,只有天空光
+        let world = World::new("world").with_generator(|pos| {
+            let mut chunk = Chunk::new(pos, biome::PLAINS);
+            for x in 0..16 {
+                for z in 0..16 {
+                    chunk.set_block(x, 63, z, block::GRASS_BLOCK);
+                }
+            }
+            chunk
+        });
+        for cx in -4..=4 {
+            for cz in -4..=4 {
+                world.get_or_create_chunk(ChunkPos::new(cx, cz));
+            }
+        }
+        let mut store = EntityStore::new();
+        let mut ids = EntityIds::new();
+        let mut rng = StdRng::seed_from_u64(3);
+        let players = [player_at((8.0, 64.0, 8.0))];
+        let mut rules = SpawnRules {
+            enemies: true,
+            friendlies: true,
+            persistent: false,
+            sky_darken: 0,
+            world_spawn: None,
+        };
+        let tables = SpawnTables::builtin();
+        for _ in 0..50 {
+            tick_spawning(
+                &mut store, &mut ids, "world", &world, &tables, &players, &rules, &mut rng,
+            );
+        }
+        // 白天的地表没有敌对生物,动物要等到持久分类的刻
+        assert!(store.is_empty());
+
+        rules.persistent = true;
+        for _ in 0..50 {
+            tick_spawning(
+                &mut store, &mut ids, "world", &world, &tables, &players, &rules, &mut rng,
+            );
+        }
+        // 81 个区块的上限是 10 × 81 / 289 = 2,一次最多生成 4 只
+        assert!(
+            (2..2 + MAX_CLUSTER_SIZE).contains(&store.len()),
+            "{}",
+            store.len()
+        );
+        for info in store.entities() {
+            assert_eq!(info.kind.category(), MobCategory::Creature);
+            let pos = store.get::<Position>(info.handle.id).unwrap();
+            assert_eq!(pos.y, 64.0);
+            let d = (pos.x - 8.0).powi(2) + (pos.z - 8.0).powi(2);
+            assert!(d > MIN_SPAWN_DISTANCE * MIN_SPAWN_DISTANCE);
+        }
+
+        // 动物是持久的,敌对生物远离玩家后消失
+        let far = Position {
+            x: 200.0,
+            y: 64.0,
+            ..Default::default()
+        };
+        let zombie = store
+            .spawn_kind(
+                &mut ids,
+                MobKind::Zombie,
+                MobKind::Zombie.default_stats(),
+                "world",
+                Dimension::Overworld,
+                far,
+            )
+            .unwrap();
+        let animals = store.len() - 1;
+        assert_eq!(
+            tick_despawn(
+                &mut store,
+                &mut ids,
+                "world",
+                Dimension::Overworld,
+                &players,
+                &mut rng
+            ),
+            1
+        );
+        assert!(!store.contains(zombie));
+        assert_eq!(store.len(), animals);
+        assert_eq!(
+            tick_despawn(
+                &mut store,
+                &mut ids,
+                "world",
+                Dimension::Overworld,
+                &[],
+                &mut rng
+            ),
+            0
+        );
+    }
+}
@@ -0,0 +1,633 @@
+//! 挖掘与放置方块
+//!
+//! 玩家的挖掘(PlayerAction)与放置(UseItemOn)都通过 [`BlockUpdater`] 修改世界,
+//! 与计划刻一样触发形状更新与邻居更新,红石电路也会在下一刻重新编译。
+//! 客户端会先行预测方块变化,处理后发送服务端的方块并用 BlockChangedAck 确认,
+//! 被拒绝的预测因此会回到服务端的状态。
+//!
+//! 物品ID对应的物品名来自数据生成器与 `blocks.json` 一起导出的 `registries.json`。
+//! 还没有挖掘速度与掉落物:生存模式下收到完成挖掘(或开始挖掘可以瞬间破坏的方块)时直接破坏方块。
+
+use std::{collections::HashMap, fmt, path::Path};
+
+use once_cell::sync::OnceCell;
+use qexed_net::net_types::{position::Position, slot::Slot, var_int::VarInt};
+use qexed_net::packet::packet_pool::{BlockChangedAck, BlockUpdate, PlayerAction, UseItemOn};
+use qexed_world::{
+    BlockPos, Direction, World,
+    block::{self, BlockState},
+    block_registry, physics,
+    registry::BlockRegistry,
+    shape,
+};
+use serde::Deserialize;
+
+use crate::{
+    biology::{inventory::slot, player::Player},
+    event::inventory::CREATIVE,
+    scheduler::BlockUpdater,
+};
+
+/// 默认的 `registries.json` 路径
+pub const ITEMS_REPORT_PATH: &str = "data/reports/registries.json";
+/// 玩家眼睛的高度
+const EYE_HEIGHT: f64 = 1.62;
+/// 眼睛到方块中心的最远距离(原版的交互距离 4.5 格加上容差)
+const MAX_REACH: f64 = 6.0;
+
+/// PlayerAction 的状态
+const START_DIGGING: i32 = 0;
+const CANCEL_DIGGING: i32 = 1;
+const FINISH_DIGGING: i32 = 2;
+
+/// 物品名与方块名不同的方块物品
+const ITEM_BLOCKS: &[(&str, &str)] = &[
+    ("minecraft:redstone", "minecraft:redstone_wire"),
+    ("minecraft:string", "minecraft:tripwire"),
+    ("minecraft:cocoa_beans", "minecraft:cocoa"),
+    ("minecraft:sweet_berries", "minecraft:sweet_berry_bush"),
+    ("minecraft:glow_berries", "minecraft:cave_vines"),
+    ("minecraft:wheat_seeds", "minecraft:wheat"),
+    ("minecraft:beetroot_seeds", "minecraft:beetroots"),
+    ("minecraft:carrot", "minecraft:carrots"),
+    ("minecraft:potato", "minecraft:potatoes"),
+    ("minecraft:pumpkin_seeds", "minecraft:pumpkin_stem"),
+    ("minecraft:melon_seeds", "minecraft:melon_stem"),
+];
+
+/// 放置时可以被直接替换的方块
+const REPLACEABLE: &[&str] = &[
+    "minecraft:cave_air",
+    "minecraft:void_air",
+    "minecraft:water",
+    "minecraft:lava",
+    "minecraft:short_grass",
+    "minecraft:tall_grass",
+    "minecraft:fern",
+    "minecraft:large_fern",
+    "minecraft:dead_bush",
+    "minecraft:vine",
+    "minecraft:seagrass",
+    "minecraft:tall_seagrass",
+    "minecraft:fire",
+    "minecraft:soul_fire",
+    "minecraft:light",
+    "minecraft:structure_void",
+];
+
+/// 生存模式下无法破坏的方块(硬度为 -1)
+const UNBREAKABLE: &[&str] = &[
+    "minecraft:bedrock",
+    "minecraft:barrier",
+    "minecraft:light",
+    "minecraft:end_portal",
+    "minecraft:end_portal_frame",
+    "minecraft:end_gateway",
+    "minecraft:nether_portal",
+    "minecraft:moving_piston",
+    "minecraft:command_block",
+    "minecraft:chain_command_block",
+    "minecraft:repeating_command_block",
+    "minecraft:structure_block",
+    "minecraft:jigsaw",
+    "minecraft:test_block",
+    "minecraft:test_instance_block",
+];
+
+/// 生存模式下开始挖掘就会破坏的方块(硬度为 0)
+const INSTANT_BREAK: &[&str] = &[
+    "minecraft:redstone_wire",
+    "minecraft:redstone_torch",
+    "minecraft:redstone_wall_torch",
+    "minecraft:repeater",
+    "minecraft:comparator",
+    "minecraft:torch",
+    "minecraft:wall_torch",
+    "minecraft:soul_torch",
+    "minecraft:soul_wall_torch",
+    "minecraft:tripwire",
+    "minecraft:tripwire_hook",
+    "minecraft:short_grass",
+    "minecraft:tall_grass",
+    "minecraft:fern",
+    "minecraft:large_fern",
+    "minecraft:dead_bush",
+    "minecraft:dandelion",
+    "minecraft:poppy",
+    "minecraft:wheat",
+    "minecraft:carrots",
+    "minecraft:potatoes",
+    "minecraft:beetroots",
+    "minecraft:nether_wart",
+    "minecraft:sweet_berry_bush",
+    "minecraft:sugar_cane",
+    "minecraft:slime_block",
+    "minecraft:honey_block",
+    "minecraft:tnt",
+    "minecraft:fire",
+    "minecraft:soul_fire",
+];
+
+/// 挖掘或放置被拒绝的原因
+#[derive(Debug, PartialEq)]
+pub enum InteractError {
+    /// 冒险与旁观模式不能修改方块
+    GameMode(u8),
+    TooFar(BlockPos),
+    NotLoaded(BlockPos),
+    Unbreakable(BlockPos),
+    InvalidFace(i32),
+    EmptyHand,
+    /// 手中的物品不是方块
+    NotPlaceable(i32),
+    /// 放置的位置已经有方块
+    Occupied(BlockPos),
+    /// 方块会与玩家重叠
+    Obstructed(BlockPos),
+}
+impl std::error::Error for InteractError {}
+impl fmt::Display for InteractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InteractError::GameMode(mode) => write!(f, "游戏模式 {} 不能修改方块", mode),
+            InteractError::TooFar(pos) => write!(f, "方块 {} 超出交互距离", pos),
+            InteractError::NotLoaded(pos) => write!(f, "方块 {} 所在的区块未加载", pos),
+            InteractError::Unbreakable(pos) => write!(f, "方块 {} 无法破坏", pos),
+            InteractError::InvalidFace(face) => write!(f, "无效的方块面 {}", face),
+            InteractError::EmptyHand => write!(f, "手中没有物品"),
+            InteractError::NotPlaceable(id) => write!(f, "物品 {} 不是方块", id),
+            InteractError::Occupied(pos) => write!(f, "{} 处已有方块", pos),
+            InteractError::Obstructed(pos) => write!(f, "{} 处的方块会与玩家重叠", pos),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RegistriesReport {
+    #[serde(rename = "minecraft:item")]
+    item: RegistryReport,
+}
+
+#[derive(Deserialize)]
+struct RegistryReport {
+    entries: HashMap<String, RegistryEntry>,
+}
+
+#[derive(Deserialize)]
+struct RegistryEntry {
+    protocol_id: i32,
+}
+
+/// 物品注册表,物品ID到物品名
+#[derive(Debug, Default)]
+pub struct ItemRegistry {
+    names: HashMap<i32, String>,
+}
+
+impl ItemRegistry {
+    /// 从 `registries.json` 的内容创建
+    pub fn from_report(json: &str) -> Result<Self, serde_json::Error> {
+        let report: RegistriesReport = serde_json::from_str(json)?;
+        Ok(ItemRegistry {
+            names: report
+                .item
+                .entries
+                .into_iter()
+                .map(|(name, entry)| (entry.protocol_id, name))
+                .collect(),
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_report(&json).map_err(|e| e.to_string())
+    }
+
+    pub fn name(&self, id: i32) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    /// 物品放置出的方块(默认状态),物品不是方块时返回 `None`
+    pub fn block_state(&self, registry: &BlockRegistry, id: i32) -> Option<BlockState> {
+        let item = self.name(id)?;
+        let name = ITEM_BLOCKS
+            .iter()
+            .find(|(i, _)| *i == item)
+            .map_or(item, |(_, b)| *b);
+        registry.default_state(name)
+    }
+}
+
+static ITEMS: OnceCell<ItemRegistry> = OnceCell::new();
+
+/// 初始化全局物品注册表,只有第一次调用生效;文件无法读取时玩家无法放置方块
+pub fn init_item_registry(path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let items = ItemRegistry::load(path).unwrap_or_else(|e| {
+        log::warn!(
+            "无法读取物品注册表 {}: {},玩家无法放置方块",
+            path.display(),
+            e
+        );
+        ItemRegistry::default()
+    });
+    let _ = ITEMS.set(items);
+}
+
+/// 全局物品注册表,未初始化时为空
+pub fn item_registry() -> &'static ItemRegistry {
+    ITEMS.get_or_init(ItemRegistry::default)
+}
+
+fn block_pos(location: &Position) -> BlockPos {
+    BlockPos::new(location.x(), location.y(), location.z())
+}
+
+fn is_named(registry: &BlockRegistry, state: BlockState, names: &[&str]) -> bool {
+    registry
+        .name_of(state)
+        .is_some_and(|name| names.contains(&name.as_str()))
+}
+
+fn replaceable(registry: &BlockRegistry, state: BlockState) -> bool {
+    block::is_air(state) || is_named(registry, state, REPLACEABLE)
+}
+
+/// 检查游戏模式与玩家眼睛到方块中心的距离
+fn check_reach(player: &Player, pos: BlockPos) -> Result<(), InteractError> {
+    if player.game_mode > CREATIVE {
+        return Err(InteractError::GameMode(player.game_mode));
+    }
+    let Some((x, y, z)) = player.position else {
+        return Err(InteractError::TooFar(pos));
+    };
+    let dx = pos.x as f64 + 0.5 - x;
+    let dy = pos.y as f64 + 0.5 - (y + EYE_HEIGHT);
+    let dz = pos.z as f64 + 0.5 - z;
+    if dx * dx + dy * dy + dz * dz > MAX_REACH * MAX_REACH {
+        return Err(InteractError::TooFar(pos));
+    }
+    Ok(())
+}
+
+/// 玩家手中物品所在的物品栏格子,`hand` 为 1 时是副手
+pub fn held_slot(player: &Player, hand: i32) -> usize {
+    if hand == 1 {
+        slot::OFFHAND
+    } else {
+        slot::HOTBAR.start + player.selected_slot as usize
+    }
+}
+
+/// 破坏方块
+pub fn dig(
+    world: &World,
+    registry: &BlockRegistry,
+    player: &Player,
+    pos: BlockPos,
+) -> Result<(), InteractError> {
+    check_reach(player, pos)?;
+    let state = world.get_block(pos).ok_or(InteractError::NotLoaded(pos))?;
+    if player.game_mode != CREATIVE && is_named(registry, state, UNBREAKABLE) {
+        return Err(InteractError::Unbreakable(pos));
+    }
+    let mut updater = BlockUpdater::new(world, registry);
+    updater.set_block(pos, block::AIR);
+    updater.flush();
+    Ok(())
+}
+
+/// 对着方块使用手中的物品:点击拉杆时切换拉杆,否则放置手中的方块,返回变化的方块
+///
+/// 生存模式下放置会消耗一个物品。
+pub fn use_item_on(
+    world: &World,
+    registry: &BlockRegistry,
+    items: &ItemRegistry,
+    player: &mut Player,
+    pk: &UseItemOn,
+) -> Result<BlockPos, InteractError> {
+    let clicked = block_pos(&pk.location);
+    check_reach(player, clicked)?;
+    let clicked_state = world
+        .get_block(clicked)
+        .ok_or(InteractError::NotLoaded(clicked))?;
+    if registry.name_of(clicked_state).as_deref() == Some("minecraft:lever") {
+        let powered = registry
+            .state_info(clicked_state)
+            .and_then(|info| info.property("powered").map(|p| p == "true"))
+            .unwrap_or_default();
+        let toggled = if powered { "false" } else { "true" };
+        if let Some(state) = registry.with_property(clicked_state, "powered", toggled) {
+            let mut updater = BlockUpdater::new(world, registry);
+            updater.set_block(clicked, state);
+            updater.flush();
+        }
+        return Ok(clicked);
+    }
+
+    let face = usize::try_from(pk.face.0)
+        .ok()
+        .and_then(|i| Direction::ALL.get(i).copied())
+        .ok_or(InteractError::InvalidFace(pk.face.0))?;
+    let index = held_slot(player, pk.hand.0);
+    let item = player.inventory.get(index).cloned().unwrap_or_default();
+    if item.is_empty() {
+        return Err(InteractError::EmptyHand);
+    }
+    let state = items
+        .block_state(registry, item.item_id)
+        .ok_or(InteractError::NotPlaceable(item.item_id))?;
+    let target = if replaceable(registry, clicked_state) {
+        clicked
+    } else {
+        clicked.relative(face)
+    };
+    let current = world
+        .get_block(target)
+        .ok_or(InteractError::NotLoaded(target))?;
+    if !replaceable(registry, current) || !target.in_height_range() {
+        return Err(InteractError::Occupied(target));
+    }
+    if let Some((x, y, z)) = player.position {
+        let body = physics::player_box(x, y, z);
+        let (bx, by, bz) = (target.x as f64, target.y as f64, target.z as f64);
+        if shape::collision_boxes(registry, state)
+            .iter()
+            .any(|b| b.offset(bx, by, bz).intersects(&body))
+        {
+            return Err(InteractError::Obstructed(target));
+        }
+    }
+    let mut updater = BlockUpdater::new(world, registry);
+    updater.set_block(target, state);
+    updater.flush();
+    drop(updater);
+    if player.game_mode != CREATIVE {
+        let left = item.count - 1;
+        let item = if left > 0 {
+            item.with_count(left)
+        } else {
+            Slot::empty()
+        };
+        player.inventory.set(index, item);
+    }
+    Ok(target)
+}
+
+/// 发送服务端的方块并确认客户端的预测
+async fn acknowledge(
+    socket: &mut qexed_net::PacketListener,
+    world: Option<&World>,
+    positions: &[BlockPos],
+    sequence: i32,
+) -> std::io::Result<()> {
+    for pos in positions {
+        let Some(state) = world.and_then(|w| w.get_block(*pos)) else {
+            continue;
+        };
+        let mut pk = BlockUpdate::new();
+        pk.location = Position::from_xyz(pos.x, pos.y, pos.z);
+        pk.block_id = VarInt(state as i32);
+        socket.send(&pk).await?;
+    }
+    let mut ack = BlockChangedAck::new();
+    ack.sequence = VarInt(sequence);
+    socket.send(&ack).await
+}
+
+/// 处理 PlayerAction 中的挖掘,`world` 为玩家所在的世界
+pub async fn player_action(
+    socket: &mut qexed_net::PacketListener,
+    player: &mut Player,
+    world: Option<&World>,
+    pk: &PlayerAction,
+) -> std::io::Result<()> {
+    let pos = block_pos(&pk.location);
+    let registry = block_registry();
+    let instant = player.game_mode == CREATIVE
+        || world
+            .and_then(|w| w.get_block(pos))
+            .is_some_and(|state| is_named(registry, state, INSTANT_BREAK));
+    let breaks = match pk.status.0 {
+        START_DIGGING => instant,
+        FINISH_DIGGING => true,
+        CANCEL_DIGGING => false,
+        // 丢弃物品、交换副手等不涉及方块
+        _ => return Ok(()),
+    };
+    if breaks {
+        let result = match world {
+            Some(world) => dig(world, registry, player, pos),
+            None => Err(InteractError::NotLoaded(pos)),
+        };
+        if let Err(e) = result {
+            log::debug!("{} 的挖掘被拒绝: {}", player.username, e);
+        }
+    }
+    acknowledge(socket, world, &[pos], pk.sequence.0).await
+}
+
+/// 处理 UseItemOn,`world` 为玩家所在的世界
+pub async fn use_item_on_packet(
+    socket: &mut qexed_net::PacketListener,
+    player: &mut Player,
+    world: Option<&World>,
+    pk: &UseItemOn,
+) -> std::io::Result<()> {
+    let clicked = block_pos(&pk.location);
+    let result = match world {
+        Some(world) => use_item_on(world, block_registry(), item_registry(), player, pk),
+        None => Err(InteractError::NotLoaded(clicked)),
+    };
+    let mut positions = vec![clicked];
+    match result {
+        Ok(pos) if pos != clicked => positions.push(pos),
+        Ok(_) => {}
+        Err(e) => {
+            log::debug!("{} 的放置被拒绝: {}", player.username, e);
+            // 客户端预测的方块可能在被点击的面外侧
+            if let Some(direction) = usize::try_from(pk.face.0)
+                .ok()
+                .and_then(|i| Direction::ALL.get(i))
+            {
+                positions.push(clicked.relative(*direction));
+            }
+        }
+    }
+    // 客户端已经预测了物品的消耗
+    if player.game_mode != CREATIVE {
+        let index = held_slot(player, pk.hand.0);
+        socket.send(&player.inventory.slot_packet(index)).await?;
+    }
+    acknowledge(socket, world, &positions, pk.sequence.0).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::stone_floor;
+    use qexed_world::{Chunk, ChunkPos};
+
+    const STONE: i32 = 1;
+    const SNOW: i32 = 7;
+    const STICK: i32 = 8;
+
+    fn items() -> ItemRegistry {
+        ItemRegistry::from_report(
+            r#"{"minecraft:item": {"entries": {
+                "minecraft:stone": {"protocol_id": 1},
+                "minecraft:snow": {"protocol_id": 7},
+                "minecraft:stick": {"protocol_id": 8}
+            }}}"#,
+        )
+        .unwrap()
+    }
+
+    fn world() -> World {
+        let world = World::new("world");
+        world.insert_chunk(Chunk::new(ChunkPos::new(0, 0), qexed_world::biome::PLAINS));
+        for (pos, state) in stone_floor(0..16, 0..16) {
+            world.set_block(pos, state).unwrap();
+        }
+        world
+    }
+
+    fn click(pos: BlockPos, face: Direction) -> UseItemOn {
+        let mut pk = UseItemOn::new();
+        pk.location = Position::from_xyz(pos.x, pos.y, pos.z);
+        pk.face = VarInt(Direction::ALL.iter().position(|d| *d == face).unwrap() as i32);
+        pk
+    }
+
+    #[test]
+    fn place_and_dig_update_neighbours() {
+        let registry = BlockRegistry::builtin();
+        let snow = registry.register_test_state("snow", &[("layers".to_string(), "1".to_string())]);
+        let items = items();
+        let world = world();
+        let grass = BlockPos::new(3, 63, 1);
+        world.set_block(grass, block::GRASS_BLOCK).unwrap();
+
+        let mut player = Player::new();
+        player.position = Some((1.5, 64.0, 1.5));
+        player.inventory.set(slot::HOTBAR.start, Slot::new(SNOW, 2));
+
+        // 放在草方块上,草方块经形状更新变为积雪的样子
+        let placed = use_item_on(
+            &world,
+            &registry,
+            &items,
+            &mut player,
+            &click(grass, Direction::Up),
+        );
+        assert_eq!(placed, Ok(grass.offset(0, 1, 0)));
+        assert_eq!(world.get_block(grass.offset(0, 1, 0)), Some(snow));
+        let snowy = world.get_block(grass).unwrap();
+        assert_eq!(
+            registry.state_info(snowy).unwrap().property("snowy"),
+            Some("true")
+        );
+        assert_eq!(player.inventory.get(slot::HOTBAR.start).unwrap().count, 1);
+
+        // 不能放在自己站的位置,也不能放置不是方块的物品
+        let floor = BlockPos::new(1, 63, 1);
+        player.selected_slot = 1;
+        player
+            .inventory
+            .set(slot::HOTBAR.start + 1, Slot::new(STONE, 1));
+        assert_eq!(
+            use_item_on(
+                &world,
+                &registry,
+                &items,
+                &mut player,
+                &click(floor, Direction::Up)
+            ),
+            Err(InteractError::Obstructed(floor.offset(0, 1, 0)))
+        );
+        player.selected_slot = 2;
+        player
+            .inventory
+            .set(slot::HOTBAR.start + 2, Slot::new(STICK, 1));
+        assert_eq!(
+            use_item_on(
+                &world,
+                &registry,
+                &items,
+                &mut player,
+                &click(floor, Direction::East)
+            ),
+            Err(InteractError::NotPlaceable(STICK))
+        );
+
+        // 挖掉积雪后草方块恢复
+        dig(&world, &registry, &player, grass.offset(0, 1, 0)).unwrap();
+        assert_eq!(world.get_block(grass.offset(0, 1, 0)), Some(block::AIR));
+        assert_eq!(world.get_block(grass), Some(block::GRASS_BLOCK));
+        assert_eq!(
+            dig(&world, &registry, &player, BlockPos::new(15, 63, 15)),
+            Err(InteractError::TooFar(BlockPos::new(15, 63, 15)))
+        );
+        world.set_block(floor, block::BEDROCK).unwrap();
+        assert_eq!(
+            dig(&world, &registry, &player, floor),
+            Err(InteractError::Unbreakable(floor))
+        );
+        player.game_mode = CREATIVE;
+        dig(&world, &registry, &player, floor).unwrap();
+        assert_eq!(world.get_block(floor), Some(block::AIR));
+    }
+
+    #[test]
+    fn levers_recompile_redstone() {
+        let registry = BlockRegistry::builtin();
+        let state = |name: &str, properties: &[(&str, &str)]| {
+            let properties: Vec<(String, String)> = properties
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            registry.register_test_state(name, &properties)
+        };
+        let lever = |powered| {
+            state(
+                "lever",
+                &[("face", "floor"), ("facing", "north"), ("powered", powered)],
+            )
+        };
+        let off = lever("false");
+        lever("true");
+        for power in (1..=15).rev() {
+            state("redstone_wire", &[("power", &power.to_string())]);
+        }
+        let wire = state("redstone_wire", &[("power", "0")]);
+        state("redstone_lamp", &[("lit", "true")]);
+        let lamp = state("redstone_lamp", &[("lit", "false")]);
+
+        let world = world();
+        let at = |x| BlockPos::new(x, 64, 1);
+        world.set_block(at(2), off).unwrap();
+        world.set_block(at(3), wire).unwrap();
+        world.set_block(at(4), lamp).unwrap();
+        let mut player = Player::new();
+        player.position = Some((1.5, 64.0, 3.5));
+
+        let lit = || {
+            let state = world.get_block(at(4)).unwrap();
+            registry.state_info(state).unwrap().property("lit") == Some("true")
+        };
+        let toggled = use_item_on(
+            &world,
+            &registry,
+            &items(),
+            &mut player,
+            &click(at(2), Direction::Up),
+        );
+        assert_eq!(toggled, Ok(at(2)));
+        for _ in 0..5 {
+            crate::scheduler::tick_world(&world, &registry, |_| true);
+        }
+        assert!(lit());
+        assert_eq!(world.redstone.lock().unwrap().circuit_count(), 1);
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use qexed_net::packet::packet_pool::{
+    BlockUpdate, ChunkBatchFinished, ChunkBatchStart, ForgetLevelChunk, LevelChunkWithLight,
+    SetChunkCacheCenter, SetChunkCacheRadius, UpdateLight,
+};
+use qexed_net::net_types::{position::Position, var_int::VarInt};
+use qexed_world::{BlockPos, ChunkPos, ChunkSender, ChunkView, Level, World, light::ALL_SECTIONS};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::biology::player::Player;
+
+/// 客户端视距不会低于这个值(原版同样限制为 2)
+pub const MIN_VIEW_DISTANCE: i32 = 2;
+
+/// 实际使用的视距:取服务端与客户端视距中较小的一个
+pub fn effective_view_distance(server: u8, client: i8) -> i32 {
+    let server = server as i32;
+    let client = if client <= 0 { server } else { client as i32 };
+    server.min(client).max(MIN_VIEW_DISTANCE)
+}
+
+/// 构建区块数据包,尚未生成的区块由世界的生成器生成
+pub fn chunk_packet(world: &World, pos: ChunkPos) -> LevelChunkWithLight {
+    let chunk = match world.load_chunk(pos) {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            log::error!("加载区块 {} 失败: {}", pos, e);
+            world.get_or_create_chunk(pos)
+        }
+    };
+    let chunk = chunk.read().unwrap();
+    LevelChunkWithLight {
+        chunk_x: pos.x,
+        chunk_z: pos.z,
+        data: chunk.to_network(world.dimension),
+        light: chunk.light.to_network(world.dimension, ALL_SECTIONS),
+    }
+}
+
+/// 把光照发生变化的段落发送给该世界中能看到这些区块的玩家,数据包只放入发送队列
+pub async fn broadcast_light_updates(
+    level: &str,
+    world: &World,
+    players: &Mutex<HashMap<Uuid, Arc<Mutex<Player>>>>,
+) {
+    let changes = world.take_light_changes();
+    if changes.is_empty() {
+        return;
+    }
+    let players: Vec<_> = players.lock().await.values().cloned().collect();
+    for player in players {
+        // 先取出连接再发送,避免同时持有玩家与连接的锁
+        let (conn, visible) = {
+            let player = player.lock().await;
+            let Some(conn) = player.conn.clone() else {
+                continue;
+            };
+            if player.world != level || player.dimension != world.dimension {
+                continue;
+            }
+            let visible: Vec<(ChunkPos, u32)> = changes
+                .iter()
+                .filter(|(pos, _)| player.chunk_view.is_sent(**pos))
+                .map(|(pos, mask)| (*pos, *mask))
+                .collect();
+            (conn, visible)
+        };
+        if visible.is_empty() {
+            continue;
+        }
+        for (pos, mask) in visible {
+            let Some(chunk) = world.get_chunk(pos) else {
+                continue;
+            };
+            let mut pk = UpdateLight::new();
+            pk.chunk_x = VarInt(pos.x);
+            pk.chunk_z = VarInt(pos.z);
+            pk.light = chunk.read().unwrap().light.to_network(world.dimension, mask);
+            let _ = conn.send(&pk);
+        }
+    }
+}
+
+/// 在世界中任意玩家视野内的区块,用于玩家观察态
+pub async fn observed_chunks(
+    level: &str,
+    world: &World,
+    players: &Mutex<HashMap<Uuid, Arc<Mutex<Player>>>>,
+) -> HashSet<ChunkPos> {
+    let players: Vec<_> = players.lock().await.values().cloned().collect();
+    let mut observed = HashSet::new();
+    for player in players {
+        let player = player.lock().await;
+        if player.world == level && player.dimension == world.dimension {
+            observed.extend(player.chunk_view.sent_chunks().copied());
+        }
+    }
+    observed
+}
+
+/// 世界中所有玩家的视野中心与视距,视野外的区块可以卸载
+pub async fn view_areas(
+    level: &str,
+    world: &World,
+    players: &Mutex<HashMap<Uuid, Arc<Mutex<Player>>>>,
+) -> Vec<(ChunkPos, i32)> {
+    let players: Vec<_> = players.lock().await.values().cloned().collect();
+    let mut areas = Vec::new();
+    for player in players {
+        let player = player.lock().await;
+        if player.world == level
+            && player.dimension == world.dimension
+            && let Some(center) = player.chunk_view.center()
+        {
+            areas.push((center, player.chunk_view.radius()));
+        }
+    }
+    areas
+}
+
+/// 把世界中变化的方块(由 [`World::take_block_changes`] 取出)发送给已经收到对应区块的玩家,
+/// 数据包只放入发送队列
+pub async fn broadcast_block_changes(
+    level: &str,
+    world: &World,
+    changes: &HashMap<ChunkPos, Vec<BlockPos>>,
+    players: &Mutex<HashMap<Uuid, Arc<Mutex<Player>>>>,
+) {
+    if changes.is_empty() {
+        return;
+    }
+    let players: Vec<_> = players.lock().await.values().cloned().collect();
+    for player in players {
+        let (conn, visible) = {
+            let player = player.lock().await;
+            let Some(conn) = player.conn.clone() else {
+                continue;
+            };
+            if player.world != level || player.dimension != world.dimension {
+                continue;
+            }
+            let visible: Vec<BlockPos> = changes
+                .iter()
+                .filter(|(pos, _)| player.chunk_view.is_sent(**pos))
+                .flat_map(|(_, blocks)| blocks.iter().copied())
+                .collect();
+            (conn, visible)
+        };
+        if visible.is_empty() {
+            continue;
+        }
+        for pos in visible {
+            let Some(state) = world.get_block(pos) else {
+                continue;
+            };
+            let mut pk = BlockUpdate::new();
+            pk.location = Position::from_xyz(pos.x, pos.y, pos.z);
+            pk.block_id = VarInt(state as i32);
+            let _ = conn.send(&pk);
+        }
+    }
+}
+
+/// 移动玩家的区块视野,新进入视野的区块加入发送队列,并让客户端遗忘离开视野的区块
+pub async fn update_chunk_view(
+    socket: &mut qexed_net::PacketListener,
+    view: &mut ChunkView,
+    sender: &mut ChunkSender,
+    center: ChunkPos,
+    radius: i32,
+) -> std::io::Result<()> {
+    let update = view.update(center, radius);
+    if update.is_empty() {
+        return Ok(());
+    }
+    if let Some(radius) = update.new_radius {
+        let mut pk = SetChunkCacheRadius::new();
+        pk.view_distance = VarInt(radius);
+        socket.send(&pk).await?;
+    }
+    if let Some(center) = update.new_center {
+        let mut pk = SetChunkCacheCenter::new();
+        pk.chunk_x = VarInt(center.x);
+        pk.chunk_z = VarInt(center.z);
+        socket.send(&pk).await?;
+    }
+    // 还在队列里的区块客户端从未收到过,无需遗忘
+    for pos in update.cancel {
+        sender.cancel(pos);
+    }
+    for pos in update.unload {
+        let mut pk = ForgetLevelChunk::new();
+        pk.chunk_x = pos.x;
+        pk.chunk_z = pos.z;
+        socket.send(&pk).await?;
+    }
+    sender.enqueue(update.load);
+    Ok(())
+}
+
+/// 按客户端的处理速度发送一批区块
+///
+/// 区块放入连接的发送队列后才记为已发送,批次也按实际放入的区块数计入未确认批次;
+/// 连接已关闭时没放进去的区块放回队列。
+pub fn send_chunk_batch(
+    socket: &qexed_net::PacketSender,
+    world: &World,
+    view: &mut ChunkView,
+    sender: &mut ChunkSender,
+) -> std::io::Result<()> {
+    let Some(center) = view.center() else {
+        return Ok(());
+    };
+    let batch = sender.next_batch(center);
+    if batch.is_empty() {
+        return Ok(());
+    }
+    if let Err(e) = socket.send(&ChunkBatchStart::new()) {
+        sender.enqueue(batch);
+        return Err(e);
+    }
+    let mut written = 0;
+    for pos in &batch {
+        if let Err(e) = socket.send(&chunk_packet(world, *pos)) {
+            sender.enqueue(batch[written..].iter().copied());
+            sender.on_batch_sent(written);
+            return Err(e);
+        }
+        view.mark_sent(*pos);
+        written += 1;
+    }
+    sender.on_batch_sent(written);
+    let mut finished = ChunkBatchFinished::new();
+    finished.batch_size = VarInt(written as i32);
+    socket.send(&finished)
+}
+
+/// 游戏刻的区块发送阶段:给每个在线玩家发送一批区块
+///
+/// 数据包只放入各连接的发送队列,不会等待客户端。
+pub async fn send_chunk_batches(
+    levels: &[Arc<Level>],
+    players: &Mutex<HashMap<Uuid, Arc<Mutex<Player>>>>,
+) {
+    let players: Vec<_> = players.lock().await.values().cloned().collect();
+    for player in players {
+        let mut player = player.lock().await;
+        let Some(conn) = player.conn.clone() else {
+            continue;
+        };
+        let Some(world) = levels
+            .iter()
+            .find(|level| level.name == player.world)
+            .and_then(|level| level.world(player.dimension))
+        else {
+            continue;
+        };
+        let player = &mut *player;
+        let _ = send_chunk_batch(&conn, world, &mut player.chunk_view, &mut player.chunk_sender);
+    }
+}
@@ -0,0 +1,195 @@
+use qexed_net::net_types::var_int::VarInt;
+use qexed_net::packet::packet_pool::{GameEvent, Respawn};
+use qexed_world::{BlockPos, BlockRegistry, ChunkPos, Dimension, Level, MAX_Y, World, block, block_registry};
+
+use crate::biology::player::Player;
+use crate::event::chunk_view::update_chunk_view;
+use crate::event::teleport::{TeleportFlags, teleport};
+
+/// 穿过传送门后的冷却时间(tick),冷却期间不会再次触发传送门
+pub const PORTAL_COOLDOWN: i32 = 300;
+
+/// 玩家所在方块的传送门通往的维度
+///
+/// 传送门方块只在完整的原版方块表中存在,服务端启动时已经保证加载了 `blocks.json`。
+pub fn portal_target(registry: &BlockRegistry, world: &World, x: f64, y: f64, z: f64) -> Option<Dimension> {
+    let pos = BlockPos::new(x.floor() as i32, y.floor() as i32, z.floor() as i32);
+    let name = registry.name_of(world.get_block(pos)?)?;
+    match (name.as_str(), world.dimension) {
+        ("minecraft:nether_portal", Dimension::Nether) => Some(Dimension::Overworld),
+        ("minecraft:nether_portal", Dimension::Overworld) => Some(Dimension::Nether),
+        ("minecraft:end_portal", Dimension::End) => Some(Dimension::Overworld),
+        ("minecraft:end_portal", Dimension::Overworld) => Some(Dimension::End),
+        _ => None,
+    }
+}
+
+/// 计算从 `from` 维度的 (x, z) 穿过传送门后在 `to` 维度中的落脚点
+pub fn portal_destination(level: &Level, from: Dimension, to: Dimension, x: f64, z: f64) -> (f64, f64, f64) {
+    match (from, to) {
+        // 进入末地总是落在黑曜石平台上,离开末地则回到主世界出生点
+        (_, Dimension::End) | (Dimension::End, _) => spawn_point(level, to),
+        _ => {
+            let Some(target) = level.world(to) else {
+                return spawn_point(level, Dimension::Overworld);
+            };
+            let scale = from.coordinate_scale() / to.coordinate_scale();
+            let (x, z) = ((x * scale).floor(), (z * scale).floor());
+            let y = safe_y(target, x as i32, z as i32);
+            (x + 0.5, y as f64, z + 0.5)
+        }
+    }
+}
+
+/// 维度的出生点
+///
+/// 末地是黑曜石平台;主世界使用世界设置中的出生点,未设置时取原点附近可以站立的位置并保存;
+/// 下界是原点附近可以站立的位置。
+pub fn spawn_point(level: &Level, dimension: Dimension) -> (f64, f64, f64) {
+    let world = match level.world(dimension) {
+        Some(world) => world,
+        None => level.overworld(),
+    };
+    match world.dimension {
+        Dimension::End => {
+            let spawn = qexed_worldgen::end::END_SPAWN;
+            (spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5)
+        }
+        Dimension::Overworld => {
+            let spawn = level.spawn().unwrap_or_else(|| {
+                let spawn = BlockPos::new(0, safe_y(world, 0, 0), 0);
+                if let Err(e) = level.set_spawn(spawn) {
+                    log::error!("保存世界 {} 的出生点失败: {}", level.name, e);
+                }
+                spawn
+            });
+            (spawn.x as f64 + 0.5, spawn.y as f64, spawn.z as f64 + 0.5)
+        }
+        Dimension::Nether => (0.5, safe_y(world, 0, 0) as f64, 0.5),
+    }
+}
+
+/// 从上往下找到该列第一个可以站立的高度(脚下是实心方块,上方两格是空气)
+///
+/// 下界从基岩顶棚之下开始找;找不到时在海平面上方放一块黑曜石作为落脚点。
+pub fn safe_y(world: &World, x: i32, z: i32) -> i32 {
+    let dimension = world.dimension;
+    if let Err(e) = world.load_chunk(ChunkPos::new(x >> 4, z >> 4)) {
+        log::error!("加载区块 ({}, {}) 失败: {}", x >> 4, z >> 4, e);
+    }
+    let is_air = |y: i32| {
+        world
+            .get_block(BlockPos::new(x, y, z))
+            .is_none_or(block::is_air)
+    };
+    let is_solid = |y: i32| {
+        world
+            .get_block(BlockPos::new(x, y, z))
+            .is_some_and(|s| !block::is_air(s) && s != block::LAVA && s != block::WATER)
+    };
+    let top = match dimension {
+        Dimension::Nether => qexed_worldgen::nether::ROOF_Y - 2,
+        _ => (dimension.min_y() + dimension.height() - 2).min(MAX_Y - 1),
+    };
+    for y in (dimension.min_y() + 1..=top).rev() {
+        if is_solid(y - 1) && is_air(y) && is_air(y + 1) {
+            return y;
+        }
+    }
+    let y = dimension.sea_level().max(dimension.min_y()) + 1;
+    let _ = world.set_block(BlockPos::new(x, y - 1, z), obsidian());
+    for dy in 0..2 {
+        let _ = world.set_block(BlockPos::new(x, y + dy, z), block::AIR);
+    }
+    y
+}
+
+/// 黑曜石;只有未初始化注册表的测试中才会用石头代替
+fn obsidian() -> block::BlockState {
+    block_registry().default_state("obsidian").unwrap_or(block::STONE)
+}
+
+/// 把玩家送往某个命名世界的某个维度,`position` 为 `None` 时送往该维度的出生点
+///
+/// 发送 Respawn 后客户端会丢弃所有区块,这里清空视野并在新位置重新排队发送区块。
+/// 切换维度与切换世界都使用这一流程。
+pub async fn change_world(
+    socket: &mut qexed_net::PacketListener,
+    player: &mut Player,
+    level: &Level,
+    dimension: Dimension,
+    position: Option<(f64, f64, f64)>,
+    radius: i32,
+) -> std::io::Result<()> {
+    let Some(world) = level.world(dimension) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("世界 {} 未启用维度 {}", level.name, dimension.name()),
+        ));
+    };
+    let (x, y, z) = position.unwrap_or_else(|| spawn_point(level, dimension));
+    let mut respawn = Respawn::new();
+    respawn.dimension_type = VarInt(dimension.type_id());
+    respawn.dimension_name = dimension.name().to_string();
+    respawn.is_flat = world.is_flat;
+    respawn.portal_cooldown = VarInt(player.portal_cooldown);
+    respawn.sea_level = VarInt(dimension.sea_level());
+    respawn.data_kept = Respawn::KEEP_ATTRIBUTES | Respawn::KEEP_METADATA;
+    socket.send(&respawn).await?;
+
+    player.world = level.name.clone();
+    player.dimension = dimension;
+    player.chunk_view.clear();
+    player.chunk_sender.clear();
+    player.tracker.clear_entities();
+
+    let rotation = (player.yaw, player.pitch);
+    teleport(socket, player, (x, y, z), rotation, (0.0, 0.0, 0.0), TeleportFlags::empty()).await?;
+    // 等待区块加载
+    let mut ge = GameEvent::new();
+    ge.event = 13;
+    socket.send(&ge).await?;
+
+    let center = ChunkPos::from_entity_pos(x, z);
+    // 区块由游戏刻的区块发送阶段发出
+    update_chunk_view(socket, &mut player.chunk_view, &mut player.chunk_sender, center, radius).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qexed_world::Chunk;
+
+    #[test]
+    fn portals_resolve_with_full_registry() {
+        let registry = BlockRegistry::from_report(
+            r#"{
+                "minecraft:air": {"states": [{"id": 0, "default": true}]},
+                "minecraft:nether_portal": {"states": [
+                    {"id": 7000, "default": true, "properties": {"axis": "x"}},
+                    {"id": 7001, "properties": {"axis": "z"}}
+                ]},
+                "minecraft:end_portal": {"states": [{"id": 7002, "default": true}]}
+            }"#,
+        )
+        .unwrap();
+        let nether_portal = registry.default_state("nether_portal").unwrap();
+        let end_portal = registry.default_state("end_portal").unwrap();
+
+        let overworld = World::new("world");
+        overworld.insert_chunk(Chunk::new(ChunkPos::new(0, 0), qexed_world::biome::PLAINS));
+        overworld.set_block(BlockPos::new(1, 70, 1), nether_portal).unwrap();
+        overworld.set_block(BlockPos::new(2, 70, 2), end_portal).unwrap();
+        assert_eq!(portal_target(&registry, &overworld, 1.5, 70.2, 1.5), Some(Dimension::Nether));
+        assert_eq!(portal_target(&registry, &overworld, 2.5, 70.0, 2.5), Some(Dimension::End));
+        assert_eq!(portal_target(&registry, &overworld, 3.5, 70.0, 3.5), None);
+
+        let nether = World::new("world_nether").with_dimension(Dimension::Nether);
+        nether.insert_chunk(Chunk::new(ChunkPos::new(0, 0), qexed_world::biome::PLAINS));
+        let axis_z = registry
+            .state_id("nether_portal", &[("axis".to_string(), "z".to_string())])
+            .unwrap();
+        nether.set_block(BlockPos::new(1, 70, 1), axis_z).unwrap();
+        assert_eq!(portal_target(&registry, &nether, 1.5, 70.0, 1.5), Some(Dimension::Overworld));
+    }
+}
@@ -0,0 +1,603 @@
+//! 实体追踪
+//!
+//! 每个观察者维护一组可见实体:与观察者在同一世界与维度、所在区块已在观察者的区块视野中,且在追踪距离内。
+//! 每刻用实体的快照与上一次发送的状态比较:进入范围的实体发送 AddEntity,离开的发送 RemoveEntities;
+//! 位置变化以 1/4096 格为单位发送相对移动,超出 i16 范围、着地状态变化或每隔 [`SYNC_INTERVAL`] 刻
+//! 改为发送绝对位置,校正累计的误差。元数据在生成时整体发送,之后只发送变化的项。
+//! 玩家列表包含所有在线玩家,与可见范围无关。
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use qexed_net::net_types::{entity_metadata::EntityMetadata, var_int::VarInt};
+use qexed_net::packet::packet_pool::{
+    AddEntity, EntityPositionSync, MoveEntityPos, MoveEntityPosRot, MoveEntityRot, PlayerInfoEntry,
+    PlayerInfoRemove, PlayerInfoUpdate, RemoveEntities, RotateHead, SetEntityData,
+};
+use qexed_world::{ChunkPos, Dimension};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::biology::{entity::EntityStore, player::Player};
+
+/// 玩家的追踪距离(格),实际还受观察者区块视野的限制(与原版相同)
+pub const PLAYER_TRACKING_RANGE: f64 = 512.0;
+/// 每隔多少刻发送一次绝对位置(与原版相同)
+pub const SYNC_INTERVAL: u32 = 400;
+
+/// 实体在某一刻的状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySnapshot {
+    pub entity_id: i32,
+    /// 实体 ID 的代数,ID 被复用时与已发送的实体区分
+    pub generation: u32,
+    /// entity_type 注册表中的下标
+    pub entity_type: i32,
+    pub uuid: Uuid,
+    pub name: String,
+    /// 所在的命名世界
+    pub level: String,
+    pub dimension: Dimension,
+    pub position: (f64, f64, f64),
+    pub yaw: f32,
+    pub pitch: f32,
+    pub head_yaw: f32,
+    pub on_ground: bool,
+    pub metadata: EntityMetadata,
+}
+
+impl EntitySnapshot {
+    /// 已进入世界的玩家的快照,还在登录中的玩家返回 `None`
+    pub fn of_player(player: &Player) -> Option<Self> {
+        player.conn.as_ref()?;
+        Some(EntitySnapshot {
+            entity_id: player.entity_id,
+            generation: player.entity_generation,
+            entity_type: AddEntity::PLAYER,
+            uuid: player.uuid,
+            name: player.username.clone(),
+            level: player.world.clone(),
+            dimension: player.dimension,
+            position: player.position?,
+            yaw: player.yaw,
+            pitch: player.pitch,
+            // 玩家的头部朝向就是视角
+            head_yaw: player.yaw,
+            on_ground: player.on_ground,
+            metadata: player.metadata.clone(),
+        })
+    }
+}
+
+/// 角度转换为 1/256 圈
+pub fn to_angle(degrees: f32) -> u8 {
+    (degrees * 256.0 / 360.0).floor() as i32 as u8
+}
+
+/// 坐标转换为 1/4096 格
+fn encode(position: (f64, f64, f64)) -> (i64, i64, i64) {
+    let e = |v: f64| (v * 4096.0).round() as i64;
+    (e(position.0), e(position.1), e(position.2))
+}
+
+/// 已发送给观察者的实体状态
+#[derive(Debug, Clone, PartialEq)]
+struct Tracked {
+    generation: u32,
+    position: (i64, i64, i64),
+    yaw: u8,
+    pitch: u8,
+    head_yaw: u8,
+    on_ground: bool,
+    metadata: EntityMetadata,
+    since_sync: u32,
+}
+
+impl Tracked {
+    fn new(entity: &EntitySnapshot) -> Self {
+        Tracked {
+            generation: entity.generation,
+            position: encode(entity.position),
+            yaw: to_angle(entity.yaw),
+            pitch: to_angle(entity.pitch),
+            head_yaw: to_angle(entity.head_yaw),
+            on_ground: entity.on_ground,
+            metadata: entity.metadata.clone(),
+            since_sync: 0,
+        }
+    }
+}
+
+/// 追踪产生的数据包,按顺序发送
+#[derive(Debug, PartialEq)]
+pub enum TrackerPacket {
+    PlayerInfoUpdate(PlayerInfoUpdate),
+    PlayerInfoRemove(PlayerInfoRemove),
+    AddEntity(AddEntity),
+    RemoveEntities(RemoveEntities),
+    MoveEntityPos(MoveEntityPos),
+    MoveEntityPosRot(MoveEntityPosRot),
+    MoveEntityRot(MoveEntityRot),
+    EntityPositionSync(EntityPositionSync),
+    RotateHead(RotateHead),
+    SetEntityData(SetEntityData),
+}
+
+impl TrackerPacket {
+    pub fn send(&self, socket: &qexed_net::PacketSender) -> std::io::Result<()> {
+        match self {
+            TrackerPacket::PlayerInfoUpdate(pk) => socket.send(pk),
+            TrackerPacket::PlayerInfoRemove(pk) => socket.send(pk),
+            TrackerPacket::AddEntity(pk) => socket.send(pk),
+            TrackerPacket::RemoveEntities(pk) => socket.send(pk),
+            TrackerPacket::MoveEntityPos(pk) => socket.send(pk),
+            TrackerPacket::MoveEntityPosRot(pk) => socket.send(pk),
+            TrackerPacket::MoveEntityRot(pk) => socket.send(pk),
+            TrackerPacket::EntityPositionSync(pk) => socket.send(pk),
+            TrackerPacket::RotateHead(pk) => socket.send(pk),
+            TrackerPacket::SetEntityData(pk) => socket.send(pk),
+        }
+    }
+}
+
+/// 每个观察者的追踪状态
+#[derive(Debug, Default)]
+pub struct EntityTracker {
+    /// 已加入观察者玩家列表的玩家
+    listed: HashSet<Uuid>,
+    /// 观察者可见的实体
+    tracked: HashMap<i32, Tracked>,
+}
+
+impl EntityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_tracking(&self, entity_id: i32) -> bool {
+        self.tracked.contains_key(&entity_id)
+    }
+
+    /// 切换维度时客户端会丢弃所有实体,之后重新生成
+    pub fn clear_entities(&mut self) {
+        self.tracked.clear();
+    }
+
+    /// 实体是否对观察者可见
+    fn can_see(viewer: &Player, entity: &EntitySnapshot) -> bool {
+        let Some((x, _, z)) = viewer.position else {
+            return false;
+        };
+        let (ex, _, ez) = entity.position;
+        entity.entity_id != viewer.entity_id
+            && entity.level == viewer.world
+            && entity.dimension == viewer.dimension
+            && viewer.chunk_view.is_sent(ChunkPos::from_entity_pos(ex, ez))
+            && (ex - x).abs().max((ez - z).abs()) <= PLAYER_TRACKING_RANGE
+    }
+
+    /// 比较这一刻的快照与已发送的状态,返回需要发送给观察者的数据包
+    ///
+    /// `entities` 包含所有在线的玩家(包括观察者自己)与生物,其中的玩家用于维护玩家列表。
+    pub fn update(&mut self, viewer: &Player, entities: &[EntitySnapshot]) -> Vec<TrackerPacket> {
+        let mut packets = Vec::new();
+
+        // 玩家列表:玩家实体生成之前客户端必须已经知道这个玩家
+        let players = entities
+            .iter()
+            .filter(|e| e.entity_type == AddEntity::PLAYER);
+        let online: HashSet<Uuid> = players.clone().map(|e| e.uuid).collect();
+        let left: Vec<Uuid> = self.listed.difference(&online).copied().collect();
+        let joined: Vec<PlayerInfoEntry> = players
+            .filter(|e| !self.listed.contains(&e.uuid))
+            .map(|e| {
+                let mut entry = PlayerInfoEntry::new();
+                entry.uuid = e.uuid;
+                entry.name = e.name.clone();
+                entry.listed = true;
+                entry
+            })
+            .collect();
+        if !left.is_empty() {
+            let mut pk = PlayerInfoRemove::new();
+            pk.uuids = left;
+            packets.push(TrackerPacket::PlayerInfoRemove(pk));
+        }
+        if !joined.is_empty() {
+            let mut pk = PlayerInfoUpdate::new();
+            pk.actions = PlayerInfoUpdate::ADD_PLAYER | PlayerInfoUpdate::UPDATE_LISTED;
+            pk.entries = joined;
+            packets.push(TrackerPacket::PlayerInfoUpdate(pk));
+        }
+        self.listed = online;
+
+        let visible: HashMap<i32, &EntitySnapshot> = entities
+            .iter()
+            .filter(|e| Self::can_see(viewer, e))
+            .map(|e| (e.entity_id, e))
+            .collect();
+        let removed: Vec<VarInt> = self
+            .tracked
+            .iter()
+            .filter(|(id, tracked)| {
+                // ID 被复用时先移除旧实体再生成新实体
+                visible
+                    .get(id)
+                    .is_none_or(|e| e.generation != tracked.generation)
+            })
+            .map(|(id, _)| VarInt(*id))
+            .collect();
+        if !removed.is_empty() {
+            for id in &removed {
+                self.tracked.remove(&id.0);
+            }
+            let mut pk = RemoveEntities::new();
+            pk.entity_ids = removed;
+            packets.push(TrackerPacket::RemoveEntities(pk));
+        }
+
+        let mut visible: Vec<_> = visible.into_values().collect();
+        visible.sort_by_key(|e| e.entity_id);
+        for entity in visible {
+            match self.tracked.get_mut(&entity.entity_id) {
+                Some(tracked) => Self::update_tracked(tracked, entity, &mut packets),
+                None => {
+                    let tracked = Tracked::new(entity);
+                    let mut pk = AddEntity::new();
+                    pk.entity_id = VarInt(entity.entity_id);
+                    pk.uuid = entity.uuid;
+                    pk.entity_type = VarInt(entity.entity_type);
+                    (pk.x, pk.y, pk.z) = entity.position;
+                    pk.yaw = tracked.yaw;
+                    pk.pitch = tracked.pitch;
+                    pk.head_yaw = tracked.head_yaw;
+                    packets.push(TrackerPacket::AddEntity(pk));
+                    if !entity.metadata.is_empty() {
+                        let mut pk = SetEntityData::new();
+                        pk.entity_id = VarInt(entity.entity_id);
+                        pk.metadata = entity.metadata.clone();
+                        packets.push(TrackerPacket::SetEntityData(pk));
+                    }
+                    self.tracked.insert(entity.entity_id, tracked);
+                }
+            }
+        }
+        packets
+    }
+
+    fn update_tracked(
+        tracked: &mut Tracked,
+        entity: &EntitySnapshot,
+        packets: &mut Vec<TrackerPacket>,
+    ) {
+        let entity_id = VarInt(entity.entity_id);
+        let position = encode(entity.position);
+        let (yaw, pitch, head_yaw) = (
+            to_angle(entity.yaw),
+            to_angle(entity.pitch),
+            to_angle(entity.head_yaw),
+        );
+        let delta = (
+            position.0 - tracked.position.0,
+            position.1 - tracked.position.1,
+            position.2 - tracked.position.2,
+        );
+        let moved = delta != (0, 0, 0);
+        let rotated = (yaw, pitch) != (tracked.yaw, tracked.pitch);
+        let in_range = |d: i64| i16::try_from(d).is_ok();
+        tracked.since_sync += 1;
+
+        if tracked.since_sync >= SYNC_INTERVAL
+            || entity.on_ground != tracked.on_ground
+            || !(in_range(delta.0) && in_range(delta.1) && in_range(delta.2))
+        {
+            let mut pk = EntityPositionSync::new();
+            pk.entity_id = VarInt(entity.entity_id);
+            (pk.x, pk.y, pk.z) = entity.position;
+            pk.yaw = entity.yaw;
+            pk.pitch = entity.pitch;
+            pk.on_ground = entity.on_ground;
+            packets.push(TrackerPacket::EntityPositionSync(pk));
+            tracked.since_sync = 0;
+        } else if moved && rotated {
+            packets.push(TrackerPacket::MoveEntityPosRot(MoveEntityPosRot {
+                entity_id,
+                delta_x: delta.0 as i16,
+                delta_y: delta.1 as i16,
+                delta_z: delta.2 as i16,
+                yaw,
+                pitch,
+                on_ground: entity.on_ground,
+            }));
+        } else if moved {
+            packets.push(TrackerPacket::MoveEntityPos(MoveEntityPos {
+                entity_id,
+                delta_x: delta.0 as i16,
+                delta_y: delta.1 as i16,
+                delta_z: delta.2 as i16,
+                on_ground: entity.on_ground,
+            }));
+        } else if rotated {
+            packets.push(TrackerPacket::MoveEntityRot(MoveEntityRot {
+                entity_id,
+                yaw,
+                pitch,
+                on_ground: entity.on_ground,
+            }));
+        }
+        if head_yaw != tracked.head_yaw {
+            let mut pk = RotateHead::new();
+            pk.entity_id = VarInt(entity.entity_id);
+            pk.head_yaw = head_yaw;
+            packets.push(TrackerPacket::RotateHead(pk));
+        }
+        let changed = entity.metadata.changes(&tracked.metadata);
+        if !changed.is_empty() {
+            let mut pk = SetEntityData::new();
+            pk.entity_id = VarInt(entity.entity_id);
+            pk.metadata = changed;
+            packets.push(TrackerPacket::SetEntityData(pk));
+            tracked.metadata = entity.metadata.clone();
+        }
+        tracked.position = position;
+        (tracked.yaw, tracked.pitch, tracked.head_yaw) = (yaw, pitch, head_yaw);
+        tracked.on_ground = entity.on_ground;
+    }
+}
+
+/// 所有已进入世界的玩家的快照
+pub async fn player_snapshots(
+    players: &Mutex<HashMap<Uuid, Arc<Mutex<Player>>>>,
+) -> Vec<EntitySnapshot> {
+    let players: Vec<_> = players.lock().await.values().cloned().collect();
+    let mut snapshots = Vec::with_capacity(players.len());
+    for player in &players {
+        if let Some(snapshot) = EntitySnapshot::of_player(&*player.lock().await) {
+            snapshots.push(snapshot);
+        }
+    }
+    snapshots
+}
+
+/// 每刻更新所有玩家看到的实体
+///
+/// 数据包放入各连接的发送队列,不会等待正在读取客户端数据包的连接任务。
+pub async fn track_entities(
+    players: &Mutex<HashMap<Uuid, Arc<Mutex<Player>>>>,
+    mobs: &Mutex<EntityStore>,
+) {
+    let mut entities = mobs.lock().await.snapshots();
+    entities.extend(player_snapshots(players).await);
+    let players: Vec<_> = players.lock().await.values().cloned().collect();
+    for player in players {
+        let (conn, packets) = {
+            let mut player = player.lock().await;
+            let Some(conn) = player.conn.clone() else {
+                continue;
+            };
+            let mut tracker = std::mem::take(&mut player.tracker);
+            let packets = tracker.update(&player, &entities);
+            player.tracker = tracker;
+            (conn, packets)
+        };
+        for pk in &packets {
+            let _ = pk.send(&conn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(entity_id: i32, position: (f64, f64, f64)) -> (Player, EntitySnapshot) {
+        let mut player = Player::new();
+        player.entity_id = entity_id;
+        player.username = format!("p{}", entity_id);
+        player.position = Some(position);
+        let view = player
+            .chunk_view
+            .update(ChunkPos::from_entity_pos(position.0, position.2), 4);
+        for pos in view.load {
+            player.chunk_view.mark_sent(pos);
+        }
+        let snapshot = EntitySnapshot {
+            entity_id,
+            generation: 1,
+            entity_type: AddEntity::PLAYER,
+            uuid: player.uuid,
+            name: player.username.clone(),
+            level: player.world.clone(),
+            dimension: player.dimension,
+            position,
+            yaw: 0.0,
+            pitch: 0.0,
+            head_yaw: 0.0,
+            on_ground: true,
+            metadata: EntityMetadata::new(),
+        };
+        (player, snapshot)
+    }
+
+    #[test]
+    fn spawn_move_and_remove() {
+        let (viewer, me) = player(1, (0.5, 64.0, 0.5));
+        let (_, mut other) = player(2, (4.5, 64.0, 4.5));
+        let mut tracker = EntityTracker::new();
+
+        // 先加入玩家列表(包括自己),再生成实体
+        let packets = tracker.update(&viewer, &[me.clone(), other.clone()]);
+        assert_eq!(packets.len(), 2);
+        match &packets[0] {
+            TrackerPacket::PlayerInfoUpdate(pk) => assert_eq!(pk.entries.len(), 2),
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(&packets[1], TrackerPacket::AddEntity(pk) if pk.entity_id.0 == 2));
+        assert!(tracker.is_tracking(2));
+        assert!(!tracker.is_tracking(1));
+
+        // 没有变化时不发送
+        assert!(
+            tracker
+                .update(&viewer, &[me.clone(), other.clone()])
+                .is_empty()
+        );
+
+        other.position.0 += 0.25;
+        other.yaw = 90.0;
+        other.head_yaw = 90.0;
+        let packets = tracker.update(&viewer, &[me.clone(), other.clone()]);
+        match &packets[..] {
+            [
+                TrackerPacket::MoveEntityPosRot(pk),
+                TrackerPacket::RotateHead(head),
+            ] => {
+                assert_eq!((pk.delta_x, pk.delta_y, pk.yaw), (1024, 0, 64));
+                assert_eq!(head.head_yaw, 64);
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // 超出 i16 范围时发送绝对位置
+        other.position.0 += 10.0;
+        let packets = tracker.update(&viewer, &[me.clone(), other.clone()]);
+        assert!(matches!(&packets[..], [TrackerPacket::EntityPositionSync(pk)] if pk.x == 14.75));
+
+        // 离开视野后移除,下线后从玩家列表移除
+        other.position = (500.0, 64.0, 0.5);
+        let packets = tracker.update(&viewer, &[me.clone(), other.clone()]);
+        assert!(
+            matches!(&packets[..], [TrackerPacket::RemoveEntities(pk)] if pk.entity_ids == vec![VarInt(2)])
+        );
+        let packets = tracker.update(&viewer, std::slice::from_ref(&me));
+        assert!(
+            matches!(&packets[..], [TrackerPacket::PlayerInfoRemove(pk)] if pk.uuids == vec![other.uuid])
+        );
+    }
+
+    #[test]
+    fn reused_id_respawns() {
+        let (viewer, me) = player(1, (0.5, 64.0, 0.5));
+        let (_, old) = player(2, (1.5, 64.0, 1.5));
+        let mut tracker = EntityTracker::new();
+        tracker.update(&viewer, &[me.clone(), old.clone()]);
+
+        // 旧玩家下线,新玩家在同一刻拿到了同一个 ID
+        let (_, mut new) = player(2, (1.5, 64.0, 1.5));
+        new.generation = 2;
+        let packets = tracker.update(&viewer, &[me.clone(), new.clone()]);
+        match &packets[..] {
+            [
+                TrackerPacket::PlayerInfoRemove(_),
+                TrackerPacket::PlayerInfoUpdate(_),
+                TrackerPacket::RemoveEntities(removed),
+                TrackerPacket::AddEntity(added),
+            ] => {
+                assert_eq!(removed.entity_ids, vec![VarInt(2)]);
+                assert_eq!(added.uuid, new.uuid);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn metadata_on_spawn_and_change() {
+        use qexed_net::net_types::entity_metadata::{MetadataValue, Pose, index, skin};
+
+        let (viewer, me) = player(1, (0.5, 64.0, 0.5));
+        let (_, mut other) = player(2, (1.5, 64.0, 1.5));
+        other.metadata.set_skin_parts(skin::ALL);
+        let mut tracker = EntityTracker::new();
+        let packets = tracker.update(&viewer, &[me.clone(), other.clone()]);
+        assert!(
+            matches!(&packets[2], TrackerPacket::SetEntityData(pk) if pk.metadata.skin_parts() == skin::ALL)
+        );
+
+        // 之后只发送变化的项
+        other.metadata.set_glowing(true);
+        other.metadata.set_pose(Pose::Crouching);
+        let packets = tracker.update(&viewer, &[me.clone(), other.clone()]);
+        match &packets[..] {
+            [TrackerPacket::SetEntityData(pk)] => assert_eq!(
+                pk.metadata.entries,
+                vec![
+                    (index::FLAGS, MetadataValue::Byte(0x40)),
+                    (index::POSE, MetadataValue::Pose(Pose::Crouching)),
+                ]
+            ),
+            other => panic!("{:?}", other),
+        }
+        assert!(
+            tracker
+                .update(&viewer, &[me.clone(), other.clone()])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn periodic_sync() {
+        let (viewer, me) = player(1, (0.5, 64.0, 0.5));
+        let (_, mut other) = player(2, (1.5, 64.0, 1.5));
+        let mut tracker = EntityTracker::new();
+        tracker.update(&viewer, &[me.clone(), other.clone()]);
+        let mut syncs = 0;
+        for _ in 0..SYNC_INTERVAL * 2 {
+            other.position.2 += 0.001;
+            for pk in tracker.update(&viewer, &[me.clone(), other.clone()]) {
+                match pk {
+                    TrackerPacket::EntityPositionSync(_) => syncs += 1,
+                    TrackerPacket::MoveEntityPos(_) => {}
+                    other => panic!("{:?}", other),
+                }
+            }
+        }
+        assert_eq!(syncs, 2);
+        assert_eq!(to_angle(-90.0), 192);
+    }
+
+    /// 连接任务持有监听器的锁等待客户端时,追踪的数据包仍然通过发送队列送达
+    #[tokio::test]
+    async fn tracking_does_not_wait_for_the_reader() {
+        use tokio::io::AsyncReadExt;
+
+        let server = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = tokio::net::TcpStream::connect(server.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (socket, addr) = server.accept().await.unwrap();
+        let listener = Arc::new(Mutex::new(qexed_net::PacketListener::new(socket, addr)));
+        let sender = listener.lock().await.sender();
+        let _reader = tokio::spawn({
+            let listener = Arc::clone(&listener);
+            async move { listener.lock().await.read().await }
+        });
+        tokio::task::yield_now().await;
+
+        let players = Mutex::new(HashMap::new());
+        for (id, position) in [(1, (0.5, 64.0, 0.5)), (2, (4.5, 64.0, 4.5))] {
+            let (mut player, _) = player(id, position);
+            player.uuid = Uuid::from_u128(id as u128);
+            player.conn = Some(sender.clone());
+            players
+                .lock()
+                .await
+                .insert(player.uuid, Arc::new(Mutex::new(player)));
+        }
+        let mobs = Mutex::new(EntityStore::new());
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            track_entities(&players, &mobs),
+        )
+        .await
+        .expect("追踪不应等待连接任务");
+
+        let mut buf = [0u8; 64];
+        let n = tokio::time::timeout(std::time::Duration::from_secs(5), client.read(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(n > 0);
+    }
+}
@@ -0,0 +1,61 @@
+//! 物品栏同步与编辑
+//!
+//! 进入世界时发送玩家物品栏的全部内容。客户端的点击与创造模式编辑被拒绝时,
+//! 重新发送服务端的内容让客户端回到正确的状态。
+
+use qexed_net::packet::packet_pool::{ContainerClick, SetCarriedItem, SetCreativeModeSlot};
+
+use crate::biology::player::Player;
+
+/// 创造模式
+pub const CREATIVE: u8 = 1;
+
+/// 发送玩家物品栏的全部内容
+pub async fn send_inventory(
+    socket: &mut qexed_net::PacketListener,
+    player: &Player,
+) -> std::io::Result<()> {
+    socket.send(&player.inventory.content_packet()).await
+}
+
+/// 处理 ContainerClick
+pub async fn container_click(
+    socket: &mut qexed_net::PacketListener,
+    player: &mut Player,
+    pk: &ContainerClick,
+) -> std::io::Result<()> {
+    if let Err(e) = player.inventory.apply_click(pk) {
+        log::debug!("{} 的物品栏点击被拒绝: {}", player.username, e);
+        return send_inventory(socket, player).await;
+    }
+    Ok(())
+}
+
+/// 处理 SetCreativeModeSlot,只在创造模式下接受
+pub async fn creative_slot(
+    socket: &mut qexed_net::PacketListener,
+    player: &mut Player,
+    pk: &SetCreativeModeSlot,
+) -> std::io::Result<()> {
+    let result = match &pk.item {
+        _ if player.game_mode != CREATIVE => Err("不在创造模式".to_string()),
+        None => Err("无法解析的物品".to_string()),
+        Some(item) => player
+            .inventory
+            .set_creative_slot(pk.slot, item.clone())
+            .map_err(|e| e.to_string()),
+    };
+    if let Err(e) = result {
+        log::debug!("{} 的创造模式物品栏编辑被拒绝: {}", player.username, e);
+        return send_inventory(socket, player).await;
+    }
+    Ok(())
+}
+
+/// 处理 SetCarriedItem,记录手持的快捷栏格子
+pub fn set_carried_item(player: &mut Player, pk: &SetCarriedItem) {
+    match u8::try_from(pk.slot) {
+        Ok(slot) if slot < 9 => player.selected_slot = slot,
+        _ => log::debug!("{} 选择了无效的快捷栏格子 {}", player.username, pk.slot),
+    }
+}
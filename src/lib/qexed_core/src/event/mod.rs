@@ -2,4 +2,12 @@ pub mod plugin_message;
 pub mod plugin_channels;
 pub mod status;
 pub mod join_server;
-pub mod chat_message;
\ No newline at end of file
+pub mod chat_message;
+pub mod chunk_view;
+pub mod block_interact;
+pub mod dimension;
+pub mod inventory;
+pub mod entity_tracker;
+pub mod movement;
+pub mod physics;
+pub mod teleport;
\ No newline at end of file
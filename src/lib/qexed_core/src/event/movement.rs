@@ -0,0 +1,226 @@
+use crate::biology::player::Player;
+use crate::event::physics::{MoveRules, check_move};
+use crate::event::teleport::{TeleportFlags, teleport};
+
+/// 移动数据包 flags 中表示着地的位
+pub const FLAG_ON_GROUND: i8 = 0x01;
+/// 单个移动数据包允许移动距离的平方,超过时视为移动过快(与原版相同)
+pub const MAX_MOVE_SQUARED: f64 = 100.0;
+/// 坐标的绝对值上限,超出世界边界的数据包一律拒绝
+const MAX_COORDINATE: f64 = 3.0e7;
+
+/// 一次移动的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    Accepted,
+    /// 坐标无效或移动过快,需要把玩家拉回原处
+    Rejected,
+    /// 还在等待传送确认,数据包被忽略
+    Ignored,
+}
+
+/// 校验移动数据包并更新玩家状态,`position` 与 `rotation` 为 `None` 表示数据包中没有这一项
+///
+/// 还没有记录位置的玩家(刚进入世界)接受任意合法坐标。
+/// `world` 为玩家所在的世界时还会按服务端的碰撞与重力校验移动,世界关闭了移动校验时传入 `None`。
+/// 视角只在移动被接受后才更新。
+pub fn apply_move(
+    player: &mut Player,
+    position: Option<(f64, f64, f64)>,
+    rotation: Option<(f32, f32)>,
+    flags: i8,
+    world: Option<&qexed_world::World>,
+) -> Move {
+    if player.teleports.is_pending() {
+        return Move::Ignored;
+    }
+    if let Some((x, y, z)) = position {
+        if ![x, y, z].iter().all(|v| v.is_finite())
+            || x.abs() > MAX_COORDINATE
+            || z.abs() > MAX_COORDINATE
+        {
+            return Move::Rejected;
+        }
+        if let Some((ox, oy, oz)) = player.position {
+            let distance = (x - ox).powi(2) + (y - oy).powi(2) + (z - oz).powi(2);
+            if distance > MAX_MOVE_SQUARED {
+                return Move::Rejected;
+            }
+        }
+    }
+    if let Some((yaw, pitch)) = rotation
+        && (!yaw.is_finite() || !pitch.is_finite())
+    {
+        return Move::Rejected;
+    }
+    let on_ground = flags & FLAG_ON_GROUND != 0;
+    if let (Some(world), Some(from)) = (world, player.position) {
+        let to = position.unwrap_or(from);
+        player.physics.rules = MoveRules::for_player(player.game_mode, player.may_fly);
+        if let Err(violation) = check_move(&mut player.physics, world, from, to, on_ground) {
+            log::warn!("{} 移动违规: {}", player.username, violation.name());
+            return Move::Rejected;
+        }
+    }
+    if position.is_some() {
+        player.position = position;
+    }
+    if let Some((yaw, pitch)) = rotation {
+        player.yaw = yaw % 360.0;
+        player.pitch = pitch.clamp(-90.0, 90.0);
+    }
+    player.on_ground = on_ground;
+    Move::Accepted
+}
+
+/// 校验并应用移动,被拒绝时传送玩家回到上一次的位置
+pub async fn handle_move(
+    socket: &mut qexed_net::PacketListener,
+    player: &mut Player,
+    position: Option<(f64, f64, f64)>,
+    rotation: Option<(f32, f32)>,
+    flags: i8,
+    world: Option<&qexed_world::World>,
+) -> std::io::Result<Move> {
+    let result = apply_move(player, position, rotation, flags, world);
+    if result == Move::Rejected {
+        log::warn!("{} 移动无效,已拉回", player.username);
+        if let Some(position) = player.position {
+            let rotation = (player.yaw, player.pitch);
+            teleport(
+                socket,
+                player,
+                position,
+                rotation,
+                (0.0, 0.0, 0.0),
+                TeleportFlags::empty(),
+            )
+            .await?;
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::teleport::TeleportTarget;
+
+    #[test]
+    fn rejects_large_and_invalid_moves() {
+        let mut player = Player::new();
+        assert_eq!(
+            apply_move(
+                &mut player,
+                Some((100.5, 64.0, -20.5)),
+                None,
+                FLAG_ON_GROUND,
+                None
+            ),
+            Move::Accepted
+        );
+        assert_eq!(player.position, Some((100.5, 64.0, -20.5)));
+        assert!(player.on_ground);
+
+        assert_eq!(
+            apply_move(
+                &mut player,
+                Some((103.0, 65.0, -18.0)),
+                Some((450.0, 120.0)),
+                0,
+                None
+            ),
+            Move::Accepted
+        );
+        assert_eq!((player.yaw, player.pitch), (90.0, 90.0));
+        assert!(!player.on_ground);
+
+        // 一个数据包移动超过 10 格,被拒绝的移动不改变视角
+        assert_eq!(
+            apply_move(
+                &mut player,
+                Some((120.0, 65.0, -18.0)),
+                Some((45.0, 0.0)),
+                0,
+                None
+            ),
+            Move::Rejected
+        );
+        assert_eq!((player.yaw, player.pitch), (90.0, 90.0));
+        assert_eq!(
+            apply_move(&mut player, Some((f64::NAN, 65.0, -18.0)), None, 0, None),
+            Move::Rejected
+        );
+        assert_eq!(player.position, Some((103.0, 65.0, -18.0)));
+
+        // 只转动视角
+        assert_eq!(
+            apply_move(&mut player, None, Some((10.0, -5.0)), 0, None),
+            Move::Accepted
+        );
+        assert_eq!(player.position, Some((103.0, 65.0, -18.0)));
+        assert_eq!((player.yaw, player.pitch), (10.0, -5.0));
+
+        // 传送确认之前的移动被忽略
+        player.teleports.start(
+            TeleportTarget {
+                position: (0.5, 64.0, 0.5),
+                rotation: (0.0, 0.0),
+            },
+            std::time::Instant::now(),
+        );
+        assert_eq!(
+            apply_move(&mut player, Some((104.0, 65.0, -18.0)), None, 0, None),
+            Move::Ignored
+        );
+        assert_eq!(player.position, Some((103.0, 65.0, -18.0)));
+    }
+
+    #[test]
+    fn world_rejects_moving_into_blocks() {
+        use qexed_world::{BlockPos, Chunk, ChunkPos, World, block};
+
+        let world = World::new("world");
+        world.insert_chunk(Chunk::new(ChunkPos::new(0, 0), qexed_world::biome::PLAINS));
+        for x in 0..16 {
+            for z in 0..16 {
+                world
+                    .set_block(BlockPos::new(x, 63, z), block::STONE)
+                    .unwrap();
+            }
+        }
+        world
+            .set_block(BlockPos::new(3, 64, 1), block::STONE)
+            .unwrap();
+        world
+            .set_block(BlockPos::new(3, 65, 1), block::STONE)
+            .unwrap();
+
+        let mut player = Player::new();
+        player.position = Some((1.5, 64.0, 1.5));
+        assert_eq!(
+            apply_move(
+                &mut player,
+                Some((3.5, 64.0, 1.5)),
+                Some((90.0, 10.0)),
+                FLAG_ON_GROUND,
+                Some(&world)
+            ),
+            Move::Rejected
+        );
+        assert_eq!(player.position, Some((1.5, 64.0, 1.5)));
+        assert_eq!((player.yaw, player.pitch), (0.0, 0.0));
+
+        assert_eq!(
+            apply_move(
+                &mut player,
+                Some((2.0, 64.0, 1.5)),
+                Some((90.0, 10.0)),
+                FLAG_ON_GROUND,
+                Some(&world)
+            ),
+            Move::Accepted
+        );
+        assert_eq!((player.yaw, player.pitch), (90.0, 10.0));
+    }
+}
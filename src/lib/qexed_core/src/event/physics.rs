@@ -0,0 +1,396 @@
+//! 服务端移动校验
+//!
+//! 用 [`qexed_world::physics`] 按服务端的方块重新计算玩家的移动,客户端报告的位置与着地状态
+//! 偏差超过容差时判定为违规(穿墙、飞行或伪造着地),由调用方把玩家拉回上一次的位置。
+//! 液体、攀爬类方块、蜘蛛网等会改变移动方式的方块附近不做重力检查;区块没有加载时跳过全部检查。
+//! 可以飞行的玩家(创造模式或有飞行能力)不做飞行与着地检查,旁观模式的玩家不做任何检查。
+
+use qexed_world::{
+    Aabb, BlockSource,
+    physics::{self, GRAVITY, JUMP_VELOCITY, STEP_HEIGHT, VERTICAL_DRAG},
+    registry::{BlockRegistry, block_registry},
+};
+
+/// 服务端计算的位置与客户端报告的位置之间允许的距离平方(与原版相同)
+const MOVED_WRONGLY_SQUARED: f64 = 0.0625;
+/// 竖直方向允许的误差
+const VERTICAL_TOLERANCE: f64 = 0.05;
+/// 客户端报告着地时,脚下这个距离内需要有碰撞箱
+const GROUND_TOLERANCE: f64 = 0.03;
+
+/// 会改变移动方式、不按普通重力校验的方块
+const SPECIAL_MOVEMENT: &[&str] = &[
+    "water",
+    "lava",
+    "bubble_column",
+    "ladder",
+    "vine",
+    "twisting_vines",
+    "twisting_vines_plant",
+    "weeping_vines",
+    "weeping_vines_plant",
+    "cave_vines",
+    "cave_vines_plant",
+    "scaffolding",
+    "cobweb",
+    "powder_snow",
+    "sweet_berry_bush",
+    "kelp",
+    "kelp_plant",
+    "seagrass",
+    "tall_seagrass",
+    "honey_block",
+    "slime_block",
+];
+
+/// 移动违规的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// 进入或穿过了方块
+    NoClip,
+    /// 上升过快或悬空
+    Fly,
+    /// 报告着地但脚下没有方块
+    GroundSpoof,
+}
+
+impl Violation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Violation::NoClip => "穿墙",
+            Violation::Fly => "飞行",
+            Violation::GroundSpoof => "伪造着地",
+        }
+    }
+}
+
+/// 由游戏模式与能力决定的校验规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MoveRules {
+    /// 全部检查
+    #[default]
+    Walking,
+    /// 可以飞行,只检查穿墙
+    Flying,
+    /// 旁观模式可以穿过方块,不做检查
+    Spectator,
+}
+
+impl MoveRules {
+    /// 游戏模式 0:生存,1:创造,2:冒险,3:旁观
+    pub fn for_player(game_mode: u8, may_fly: bool) -> Self {
+        match game_mode {
+            3 => MoveRules::Spectator,
+            1 => MoveRules::Flying,
+            _ if may_fly => MoveRules::Flying,
+            _ => MoveRules::Walking,
+        }
+    }
+}
+
+/// 每个玩家的物理状态
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PhysicsState {
+    /// 上一次移动的竖直速度
+    pub velocity_y: f64,
+    /// 服务端判断的着地状态
+    pub on_ground: bool,
+    /// 离开地面后累计下落的距离
+    pub fall_distance: f64,
+    /// 需要做的检查
+    pub rules: MoveRules,
+}
+
+impl PhysicsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 传送或拉回后重新开始计算速度,下落距离保留
+    pub fn reset(&mut self) {
+        self.velocity_y = 0.0;
+        self.on_ground = false;
+    }
+}
+
+/// 实体附近是否有会改变移动方式的方块
+fn special_movement(
+    registry: &BlockRegistry,
+    source: &impl BlockSource,
+    entity: &Aabb,
+) -> Option<bool> {
+    // 向下多取一点以包含脚下的黏液块、蜂蜜块与床
+    let area = entity.inflate(1.0e-3).expand_towards(0.0, -0.5, 0.0);
+    let blocks = physics::blocks_in(source, &area)?;
+    Some(blocks.into_iter().any(|(_, state)| {
+        let Some(info) = registry.state_info(state) else {
+            return false;
+        };
+        let name = info.name.strip_prefix("minecraft:").unwrap_or(&info.name);
+        SPECIAL_MOVEMENT.contains(&name)
+            || name.ends_with("_bed")
+            || info.property("waterlogged") == Some("true")
+    }))
+}
+
+/// 脚下 [`GROUND_TOLERANCE`] 内是否有碰撞箱
+fn grounded(registry: &BlockRegistry, source: &impl BlockSource, entity: &Aabb) -> Option<bool> {
+    let below = Aabb::new(
+        entity.min_x,
+        entity.min_y - GROUND_TOLERANCE,
+        entity.min_z,
+        entity.max_x,
+        entity.min_y,
+        entity.max_z,
+    );
+    Some(!physics::collisions(registry, source, &below)?.is_empty())
+}
+
+/// 校验从 `from` 到 `to` 的移动并更新状态,返回这次落地时累计的下落距离(供摔落伤害使用)
+///
+/// 违规时状态不变,调用方应当把玩家拉回 `from`。
+pub fn check_move(
+    state: &mut PhysicsState,
+    source: &impl BlockSource,
+    from: (f64, f64, f64),
+    to: (f64, f64, f64),
+    client_on_ground: bool,
+) -> Result<f64, Violation> {
+    match simulate(state, source, from, to, client_on_ground) {
+        Some(result) => result,
+        // 区块没有加载,无法校验
+        None => {
+            state.velocity_y = to.1 - from.1;
+            state.on_ground = client_on_ground;
+            Ok(0.0)
+        }
+    }
+}
+
+fn simulate(
+    state: &mut PhysicsState,
+    source: &impl BlockSource,
+    from: (f64, f64, f64),
+    to: (f64, f64, f64),
+    client_on_ground: bool,
+) -> Option<Result<f64, Violation>> {
+    let (dx, dy, dz) = (to.0 - from.0, to.1 - from.1, to.2 - from.2);
+    if state.rules == MoveRules::Spectator {
+        state.velocity_y = dy;
+        state.on_ground = false;
+        state.fall_distance = 0.0;
+        return Some(Ok(0.0));
+    }
+    let registry = block_registry();
+    let from_box = physics::player_box(from.0, from.1, from.2);
+    let to_box = physics::player_box(to.0, to.1, to.2);
+
+    // 已经卡在方块里的玩家允许移出来
+    if !physics::is_colliding(registry, source, &from_box)? {
+        if physics::is_colliding(registry, source, &to_box)? {
+            return Some(Err(Violation::NoClip));
+        }
+        let moved =
+            physics::move_entity(registry, source, &from_box, (dx, dy, dz), state.on_ground)?;
+        if (moved.0 - dx).powi(2) + (moved.2 - dz).powi(2) > MOVED_WRONGLY_SQUARED {
+            return Some(Err(Violation::NoClip));
+        }
+    }
+
+    let on_ground = grounded(registry, source, &to_box)?;
+    let special = special_movement(registry, source, &from_box)?
+        || special_movement(registry, source, &to_box)?;
+    if !special && state.rules == MoveRules::Walking {
+        if client_on_ground && !on_ground {
+            return Some(Err(Violation::GroundSpoof));
+        }
+        // 着地时可以起跳或走上台阶,空中只能按重力减速;落地时按碰撞截断后的位移比较
+        let max_dy = if state.on_ground {
+            JUMP_VELOCITY.max(STEP_HEIGHT)
+        } else {
+            let expected = (state.velocity_y - GRAVITY) * VERTICAL_DRAG;
+            physics::move_entity(registry, source, &from_box, (dx, expected, dz), false)?.1
+        };
+        if dy > max_dy + VERTICAL_TOLERANCE {
+            return Some(Err(Violation::Fly));
+        }
+    }
+
+    state.velocity_y = dy;
+    state.on_ground = on_ground;
+    if special {
+        state.fall_distance = 0.0;
+    } else if dy < 0.0 {
+        state.fall_distance -= dy;
+    }
+    let landed = if on_ground {
+        std::mem::take(&mut state.fall_distance)
+    } else {
+        0.0
+    };
+    Some(Ok(landed))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use qexed_world::{BlockPos, block};
+
+    use super::*;
+    use crate::test_util::stone_floor;
+
+    fn ground() -> HashMap<BlockPos, block::BlockState> {
+        let mut blocks = stone_floor(-4..4, -4..4);
+        blocks.insert(BlockPos::new(2, 64, 0), block::STONE);
+        blocks.insert(BlockPos::new(2, 65, 0), block::STONE);
+        blocks
+    }
+
+    #[test]
+    fn walk_jump_and_fall() {
+        let blocks = ground();
+        let mut state = PhysicsState::new();
+        state.on_ground = true;
+        assert_eq!(
+            check_move(
+                &mut state,
+                &blocks,
+                (0.5, 64.0, 0.5),
+                (0.7, 64.0, 0.5),
+                true
+            ),
+            Ok(0.0)
+        );
+
+        // 起跳,然后按重力落回地面
+        let mut y = 64.0;
+        let mut vy = JUMP_VELOCITY;
+        let mut landed = None;
+        for _ in 0..20 {
+            let next = (y + vy).max(64.0);
+            let result = check_move(
+                &mut state,
+                &blocks,
+                (0.7, y, 0.5),
+                (0.7, next, 0.5),
+                next == 64.0,
+            );
+            assert!(result.is_ok(), "{:?}", result);
+            y = next;
+            vy = (vy - GRAVITY) * VERTICAL_DRAG;
+            if y == 64.0 {
+                landed = result.ok();
+                break;
+            }
+        }
+        assert!(landed.unwrap() > 1.0);
+        assert_eq!(state.fall_distance, 0.0);
+    }
+
+    #[test]
+    fn detects_fly_noclip_and_ground_spoof() {
+        let blocks = ground();
+        let mut state = PhysicsState::new();
+        state.on_ground = true;
+
+        // 离地后悬停
+        assert!(
+            check_move(
+                &mut state,
+                &blocks,
+                (0.5, 64.0, 0.5),
+                (0.5, 64.4, 0.5),
+                false
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            check_move(
+                &mut state,
+                &blocks,
+                (0.5, 64.4, 0.5),
+                (0.5, 64.8, 0.5),
+                false
+            ),
+            Err(Violation::Fly)
+        );
+        assert_eq!(
+            check_move(
+                &mut state,
+                &blocks,
+                (0.5, 64.4, 0.5),
+                (0.5, 64.4, 0.5),
+                true
+            ),
+            Err(Violation::GroundSpoof)
+        );
+
+        // 走进或穿过两格高的墙
+        let mut state = PhysicsState::new();
+        state.on_ground = true;
+        assert_eq!(
+            check_move(
+                &mut state,
+                &blocks,
+                (1.5, 64.0, 0.5),
+                (2.5, 64.0, 0.5),
+                true
+            ),
+            Err(Violation::NoClip)
+        );
+        assert_eq!(
+            check_move(
+                &mut state,
+                &blocks,
+                (1.5, 64.0, 0.5),
+                (3.5, 64.0, 0.5),
+                true
+            ),
+            Err(Violation::NoClip)
+        );
+        // 卡在方块里的玩家可以移出来
+        assert!(
+            check_move(
+                &mut state,
+                &blocks,
+                (2.5, 64.0, 0.5),
+                (3.5, 64.0, 0.5),
+                true
+            )
+            .is_ok()
+        );
+
+        // 没有加载的区块不校验
+        struct Unloaded;
+        impl BlockSource for Unloaded {
+            fn block(&self, _: BlockPos) -> Option<block::BlockState> {
+                None
+            }
+        }
+        let mut state = PhysicsState::new();
+        assert!(
+            check_move(
+                &mut state,
+                &Unloaded,
+                (100.5, 90.0, 0.5),
+                (100.5, 99.0, 0.5),
+                true
+            )
+            .is_ok()
+        );
+
+        // 创造模式可以飞行但不能穿墙,旁观模式都可以
+        let mut state = PhysicsState::new();
+        state.rules = MoveRules::for_player(1, false);
+        let fly = (0.5, 64.4, 0.5);
+        assert!(check_move(&mut state, &blocks, fly, (0.5, 65.4, 0.5), false).is_ok());
+        let wall = ((1.5, 64.0, 0.5), (2.5, 64.0, 0.5));
+        assert_eq!(
+            check_move(&mut state, &blocks, wall.0, wall.1, false),
+            Err(Violation::NoClip)
+        );
+        state.rules = MoveRules::for_player(3, false);
+        assert!(check_move(&mut state, &blocks, wall.0, wall.1, false).is_ok());
+    }
+}
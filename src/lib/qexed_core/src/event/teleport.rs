@@ -0,0 +1,268 @@
+//! 传送确认
+//!
+//! 服务端每次发送 PlayerPosition 都带有递增的传送 ID,客户端收到后回复同样 ID 的 AcceptTeleportation。
+//! 与原版相同只等待最近一次传送:确认之前客户端发来的移动数据包都是基于旧位置的,一律忽略。
+//! 超过一段时间没有确认时按绝对坐标重新发送,多次重发仍没有确认就断开连接。
+
+use std::time::{Duration, Instant};
+
+use bitflags::bitflags;
+use qexed_net::net_types::var_int::VarInt;
+use qexed_net::packet::packet_pool::{DisconnectPlay, PlayerPosition};
+
+use crate::biology::player::Player;
+
+/// 没有确认时重发的间隔(原版为 20 刻)
+pub const RESEND_AFTER: Duration = Duration::from_secs(1);
+/// 最多重发的次数,之后断开连接
+pub const MAX_RESENDS: u32 = 5;
+
+bitflags! {
+    /// PlayerPosition 的 flags:置位的项是相对于当前值的增量
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct TeleportFlags: i32 {
+        const X = 0x01;
+        const Y = 0x02;
+        const Z = 0x04;
+        const YAW = 0x08;
+        const PITCH = 0x10;
+        const VELOCITY_X = 0x20;
+        const VELOCITY_Y = 0x40;
+        const VELOCITY_Z = 0x80;
+        /// 先按视角的变化旋转速度再叠加
+        const ROTATE_VELOCITY = 0x100;
+    }
+}
+
+/// 传送后的绝对位置与视角
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TeleportTarget {
+    pub position: (f64, f64, f64),
+    pub rotation: (f32, f32),
+}
+
+/// 等待确认的传送
+#[derive(Debug, Clone, PartialEq)]
+struct Pending {
+    id: i32,
+    target: TeleportTarget,
+    sent: Instant,
+    resends: u32,
+}
+
+/// 每个玩家的传送确认状态
+#[derive(Debug, Default)]
+pub struct TeleportTracker {
+    last_id: i32,
+    pending: Option<Pending>,
+}
+
+/// [`TeleportTracker::check`] 的结果
+#[derive(Debug, PartialEq)]
+pub enum TeleportCheck {
+    Idle,
+    /// 还在等待确认
+    Waiting,
+    /// 需要重发
+    Resend(PlayerPosition),
+    /// 重发次数用尽
+    TimedOut,
+}
+
+impl TeleportTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 是否有还没确认的传送
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    fn next_id(&mut self) -> i32 {
+        self.last_id = self.last_id.wrapping_add(1);
+        self.last_id
+    }
+
+    /// 记录一次传送并返回传送 ID,新的传送会取代还没确认的传送
+    pub fn start(&mut self, target: TeleportTarget, now: Instant) -> i32 {
+        let id = self.next_id();
+        self.pending = Some(Pending {
+            id,
+            target,
+            sent: now,
+            resends: 0,
+        });
+        id
+    }
+
+    /// 客户端确认传送,ID 匹配时返回传送后的位置与视角
+    pub fn accept(&mut self, id: i32) -> Option<TeleportTarget> {
+        match &self.pending {
+            Some(pending) if pending.id == id => self.pending.take().map(|p| p.target),
+            _ => None,
+        }
+    }
+
+    /// 检查等待中的传送是否超时
+    pub fn check(&mut self, now: Instant) -> TeleportCheck {
+        let Some(pending) = &mut self.pending else {
+            return TeleportCheck::Idle;
+        };
+        if now.duration_since(pending.sent) < RESEND_AFTER {
+            return TeleportCheck::Waiting;
+        }
+        if pending.resends >= MAX_RESENDS {
+            self.pending = None;
+            return TeleportCheck::TimedOut;
+        }
+        self.last_id = self.last_id.wrapping_add(1);
+        pending.id = self.last_id;
+        pending.sent = now;
+        pending.resends += 1;
+        // 重发时按绝对坐标,避免相对位移被叠加两次
+        let mut pp = PlayerPosition::new();
+        pp.teleport_id = VarInt(pending.id);
+        (pp.x, pp.y, pp.z) = pending.target.position;
+        (pp.yaw, pp.pitch) = pending.target.rotation;
+        TeleportCheck::Resend(pp)
+    }
+}
+
+/// 按 `flags` 计算传送后的位置与视角
+fn resolve(
+    player: &Player,
+    (x, y, z): (f64, f64, f64),
+    (yaw, pitch): (f32, f32),
+    flags: TeleportFlags,
+) -> TeleportTarget {
+    let (ox, oy, oz) = player.position.unwrap_or_default();
+    let relative = |flag: TeleportFlags, base: f64, value: f64| {
+        if flags.contains(flag) {
+            base + value
+        } else {
+            value
+        }
+    };
+    TeleportTarget {
+        position: (
+            relative(TeleportFlags::X, ox, x),
+            relative(TeleportFlags::Y, oy, y),
+            relative(TeleportFlags::Z, oz, z),
+        ),
+        rotation: (
+            relative(TeleportFlags::YAW, player.yaw as f64, yaw as f64) as f32,
+            relative(TeleportFlags::PITCH, player.pitch as f64, pitch as f64) as f32,
+        ),
+    }
+}
+
+/// 传送玩家
+///
+/// `flags` 中置位的坐标、视角与速度分量是相对值;确认之前玩家的移动数据包会被忽略。
+pub async fn teleport(
+    socket: &mut qexed_net::PacketListener,
+    player: &mut Player,
+    position: (f64, f64, f64),
+    rotation: (f32, f32),
+    velocity: (f64, f64, f64),
+    flags: TeleportFlags,
+) -> std::io::Result<()> {
+    let target = resolve(player, position, rotation, flags);
+    let id = player.teleports.start(target, Instant::now());
+    let mut pp = PlayerPosition::new();
+    pp.teleport_id = VarInt(id);
+    (pp.x, pp.y, pp.z) = position;
+    (pp.vel_x, pp.vel_y, pp.vel_z) = velocity;
+    (pp.yaw, pp.pitch) = rotation;
+    pp.flags = flags.bits();
+    socket.send(&pp).await
+}
+
+/// 处理 AcceptTeleportation,ID 匹配时玩家到达传送后的位置
+pub fn accept_teleport(player: &mut Player, id: i32) -> bool {
+    let Some(target) = player.teleports.accept(id) else {
+        return false;
+    };
+    player.position = Some(target.position);
+    (player.yaw, player.pitch) = target.rotation;
+    player.physics.reset();
+    true
+}
+
+/// 每个客户端刻检查一次,超时重发或断开连接;断开时返回 `false`
+pub async fn check_teleport(
+    socket: &mut qexed_net::PacketListener,
+    player: &mut Player,
+) -> std::io::Result<bool> {
+    match player.teleports.check(Instant::now()) {
+        TeleportCheck::Idle | TeleportCheck::Waiting => Ok(true),
+        TeleportCheck::Resend(pp) => {
+            socket.send(&pp).await?;
+            Ok(true)
+        }
+        TeleportCheck::TimedOut => {
+            log::warn!("{} 没有确认传送,断开连接", player.username);
+            socket.send(&DisconnectPlay::text("传送确认超时")).await?;
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(position: (f64, f64, f64), rotation: (f32, f32)) -> TeleportTarget {
+        TeleportTarget { position, rotation }
+    }
+
+    #[test]
+    fn resend_then_time_out() {
+        let start = Instant::now();
+        let mut tracker = TeleportTracker::new();
+        let first = tracker.start(target((0.5, 64.0, 0.5), (0.0, 0.0)), start);
+        let second = tracker.start(target((8.5, 64.0, 8.5), (90.0, 0.0)), start);
+        assert!(second > first);
+        // 旧的传送已被取代
+        assert_eq!(tracker.accept(first), None);
+        assert_eq!(tracker.check(start), TeleportCheck::Waiting);
+
+        let mut id = second;
+        for i in 1..=MAX_RESENDS {
+            match tracker.check(start + RESEND_AFTER * i) {
+                TeleportCheck::Resend(pp) => {
+                    assert!(pp.teleport_id.0 > id);
+                    assert_eq!((pp.x, pp.z, pp.yaw), (8.5, 8.5, 90.0));
+                    id = pp.teleport_id.0;
+                }
+                other => panic!("第 {} 次应当重发: {:?}", i, other),
+            }
+        }
+        assert_eq!(
+            tracker.check(start + RESEND_AFTER * (MAX_RESENDS + 1)),
+            TeleportCheck::TimedOut
+        );
+        assert!(!tracker.is_pending());
+    }
+
+    #[test]
+    fn relative_flags() {
+        let mut player = Player::new();
+        player.position = Some((10.0, 64.0, -3.0));
+        player.yaw = 45.0;
+        let resolved = resolve(
+            &player,
+            (1.0, 70.0, -1.0),
+            (10.0, 20.0),
+            TeleportFlags::X | TeleportFlags::Z | TeleportFlags::YAW,
+        );
+        assert_eq!(resolved, target((11.0, 70.0, -4.0), (55.0, 20.0)));
+
+        let id = player.teleports.start(resolved, Instant::now());
+        assert!(!accept_teleport(&mut player, id + 1));
+        assert!(accept_teleport(&mut player, id));
+        assert_eq!(player.position, Some((11.0, 70.0, -4.0)));
+        assert!(!player.teleports.is_pending());
+    }
+}
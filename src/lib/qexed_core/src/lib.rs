@@ -1,6 +1,9 @@
 pub mod biology;
 pub mod event;
 pub mod registry;
+pub mod scheduler;
+#[cfg(test)]
+pub(crate) mod test_util;
 pub mod update_tags;
 pub mod utils;
 // 在 crate 根导出宏和类型
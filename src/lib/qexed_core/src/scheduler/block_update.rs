@@ -0,0 +1,351 @@
+//! 方块更新
+//!
+//! 见 `doc/block_update.md`。通过 [`BlockUpdater`] 修改方块时依次进行:
+//! 1. 形状更新:相邻方块根据新方块调整自己的状态(例如草方块上方放了雪就变为 `snowy=true`),
+//!    形状发生变化的方块继续向外传播;
+//! 2. 邻居更新:通知相邻方块旁边发生了变化,由方块自己决定要做什么(通常是安排一个计划刻)。
+//!
+//! 邻居更新放在队列中按广度优先处理,长链更新不会造成栈溢出。
+//! 计划刻保存在世界的 [`TickQueue`] 中,每个游戏刻由 [`tick_world`] 执行。
+
+use std::{collections::VecDeque, sync::MutexGuard};
+
+use qexed_world::{
+    BlockPos, ChunkPos, Direction, TickQueue, World,
+    block::{self, BlockState},
+    registry::BlockRegistry,
+};
+
+/// 一次处理中最多执行的邻居更新数,与原版相同
+const MAX_NEIGHBOR_UPDATES: usize = 1_000_000;
+/// 形状更新的最大传播深度,与原版相同
+const MAX_SHAPE_DEPTH: usize = 512;
+
+/// 方块的更新行为,默认什么都不做
+pub trait BlockBehaviour: Send + Sync {
+    /// 方块被放置后
+    fn on_place(&self, _updater: &mut BlockUpdater, _pos: BlockPos, _state: BlockState) {}
+
+    /// 相邻的 `from` 处方块发生了变化
+    fn neighbor_changed(
+        &self,
+        _updater: &mut BlockUpdater,
+        _pos: BlockPos,
+        _state: BlockState,
+        _from: BlockPos,
+    ) {
+    }
+
+    /// `direction` 方向上的相邻方块变为 `neighbor` 后自身应有的状态
+    fn update_shape(
+        &self,
+        _registry: &BlockRegistry,
+        state: BlockState,
+        _direction: Direction,
+        _neighbor: BlockState,
+    ) -> BlockState {
+        state
+    }
+
+    /// 计划刻到期
+    fn tick(&self, _updater: &mut BlockUpdater, _pos: BlockPos, _state: BlockState) {}
+}
+
+/// 受重力影响的方块:下方为空时下落
+///
+/// 还没有实体系统,方块直接移动到落点,不生成下落的方块实体。
+struct FallingBlock;
+
+/// 下落的延迟(刻)
+const FALL_DELAY: u64 = 2;
+
+impl FallingBlock {
+    fn can_fall_through(registry: &BlockRegistry, state: BlockState) -> bool {
+        if block::is_air(state) {
+            return true;
+        }
+        matches!(
+            registry.name_of(state).as_deref(),
+            Some(
+                "minecraft:cave_air"
+                    | "minecraft:void_air"
+                    | "minecraft:water"
+                    | "minecraft:lava"
+                    | "minecraft:fire"
+            )
+        )
+    }
+}
+
+impl BlockBehaviour for FallingBlock {
+    fn on_place(&self, updater: &mut BlockUpdater, pos: BlockPos, state: BlockState) {
+        updater.schedule_tick(pos, state, FALL_DELAY, 0);
+    }
+
+    fn neighbor_changed(
+        &self,
+        updater: &mut BlockUpdater,
+        pos: BlockPos,
+        state: BlockState,
+        _from: BlockPos,
+    ) {
+        updater.schedule_tick(pos, state, FALL_DELAY, 0);
+    }
+
+    fn tick(&self, updater: &mut BlockUpdater, pos: BlockPos, state: BlockState) {
+        let min_y = updater.world().dimension.min_y();
+        let mut landing = pos;
+        while landing.y > min_y
+            && updater
+                .block(landing.relative(Direction::Down))
+                .is_some_and(|s| Self::can_fall_through(updater.registry(), s))
+        {
+            landing = landing.relative(Direction::Down);
+        }
+        if landing != pos {
+            updater.set_block(pos, block::AIR);
+            updater.set_block(landing, state);
+        }
+    }
+}
+
+/// 草方块、灰化土、菌丝:上方是雪时显示为积雪的样子
+struct SnowyDirt;
+
+impl BlockBehaviour for SnowyDirt {
+    fn update_shape(
+        &self,
+        registry: &BlockRegistry,
+        state: BlockState,
+        direction: Direction,
+        neighbor: BlockState,
+    ) -> BlockState {
+        if direction != Direction::Up {
+            return state;
+        }
+        let snowy = matches!(
+            registry.name_of(neighbor).as_deref(),
+            Some("minecraft:snow" | "minecraft:snow_block")
+        );
+        registry
+            .with_property(state, "snowy", if snowy { "true" } else { "false" })
+            .unwrap_or(state)
+    }
+}
+
+/// 方块对应的更新行为
+pub fn behaviour(
+    registry: &BlockRegistry,
+    state: BlockState,
+) -> Option<&'static dyn BlockBehaviour> {
+    if block::is_air(state) {
+        return None;
+    }
+    match registry.name_of(state)?.as_str() {
+        "minecraft:sand"
+        | "minecraft:red_sand"
+        | "minecraft:gravel"
+        | "minecraft:suspicious_sand"
+        | "minecraft:suspicious_gravel" => Some(&FallingBlock),
+        "minecraft:grass_block" | "minecraft:podzol" | "minecraft:mycelium" => Some(&SnowyDirt),
+        _ => None,
+    }
+}
+
+/// 修改方块并传播更新
+///
+/// 持有世界计划刻队列的锁,同一世界同一时间只有一个更新器在工作。
+pub struct BlockUpdater<'w> {
+    world: &'w World,
+    registry: &'w BlockRegistry,
+    ticks: MutexGuard<'w, TickQueue>,
+    /// 待处理的邻居更新 (被通知的方块, 发生变化的方块)
+    neighbor_updates: VecDeque<(BlockPos, BlockPos)>,
+}
+
+impl<'w> BlockUpdater<'w> {
+    pub fn new(world: &'w World, registry: &'w BlockRegistry) -> Self {
+        BlockUpdater {
+            world,
+            registry,
+            ticks: world.block_ticks.lock().unwrap(),
+            neighbor_updates: VecDeque::new(),
+        }
+    }
+
+    pub fn world(&self) -> &'w World {
+        self.world
+    }
+
+    pub fn registry(&self) -> &'w BlockRegistry {
+        self.registry
+    }
+
+    /// 读取方块,区块未加载时返回 `None`
+    pub fn block(&self, pos: BlockPos) -> Option<BlockState> {
+        self.world.get_block(pos)
+    }
+
+    /// 为 `pos` 处的方块安排计划刻
+    pub fn schedule_tick(
+        &mut self,
+        pos: BlockPos,
+        state: BlockState,
+        delay: u64,
+        priority: i32,
+    ) -> bool {
+        self.ticks.schedule(pos, state, delay, priority)
+    }
+
+    /// 修改方块并进行形状更新,邻居更新在 [`flush`](Self::flush) 时处理
+    ///
+    /// 方块没有变化或区块未加载时返回 `false`。
+    pub fn set_block(&mut self, pos: BlockPos, state: BlockState) -> bool {
+        if !self.set_block_shaped(pos, state, 0) {
+            return false;
+        }
+        if let Some(b) = behaviour(self.registry, state) {
+            b.on_place(self, pos, state);
+        }
+        true
+    }
+
+    fn set_block_shaped(&mut self, pos: BlockPos, state: BlockState, depth: usize) -> bool {
+        match self.world.set_block(pos, state) {
+            Ok(old) if old != state => {}
+            _ => return false,
+        }
+        self.world.redstone.lock().unwrap().mark_dirty(pos);
+        self.update_neighbors(pos);
+        if depth >= MAX_SHAPE_DEPTH {
+            return true;
+        }
+        for direction in Direction::ALL {
+            let neighbor = pos.relative(direction);
+            let Some(current) = self.block(neighbor) else {
+                continue;
+            };
+            let Some(b) = behaviour(self.registry, current) else {
+                continue;
+            };
+            let shaped = b.update_shape(self.registry, current, direction.opposite(), state);
+            if shaped != current {
+                self.set_block_shaped(neighbor, shaped, depth + 1);
+            }
+        }
+        true
+    }
+
+    /// 通知 `pos` 周围的方块它发生了变化
+    pub fn update_neighbors(&mut self, pos: BlockPos) {
+        for direction in Direction::ALL {
+            self.neighbor_updates
+                .push_back((pos.relative(direction), pos));
+        }
+    }
+
+    /// 处理所有待处理的邻居更新,返回处理的数量
+    pub fn flush(&mut self) -> usize {
+        let mut count = 0;
+        while let Some((pos, from)) = self.neighbor_updates.pop_front() {
+            count += 1;
+            if count > MAX_NEIGHBOR_UPDATES {
+                log::warn!(
+                    "世界 {} 的邻居更新超过 {} 次,放弃剩余的更新",
+                    self.world.name,
+                    MAX_NEIGHBOR_UPDATES
+                );
+                self.neighbor_updates.clear();
+                break;
+            }
+            let Some(state) = self.block(pos) else {
+                continue;
+            };
+            if let Some(b) = behaviour(self.registry, state) {
+                b.neighbor_changed(self, pos, state, from);
+            }
+        }
+        count
+    }
+}
+
+/// 推进世界的计划刻与红石电路一个游戏刻,返回执行的计划刻数量
+///
+/// `observed` 判断区块附近是否有玩家(玩家观察态);关闭玩家观察态时传入总是返回 `true` 的函数。
+/// 红石不受玩家观察态影响。
+pub fn tick_world(
+    world: &World,
+    registry: &BlockRegistry,
+    observed: impl Fn(ChunkPos) -> bool,
+) -> usize {
+    let mut updater = BlockUpdater::new(world, registry);
+    // 区块未加载时计划刻也暂存,等区块加载并有人观察时再执行
+    let due = updater
+        .ticks
+        .advance(|pos| world.is_loaded(pos) && observed(pos));
+    let mut count = 0;
+    for tick in due {
+        let Some(state) = updater.block(tick.pos) else {
+            continue;
+        };
+        // 方块已经被替换
+        if state != tick.block {
+            continue;
+        }
+        if let Some(b) = behaviour(registry, state) {
+            b.tick(&mut updater, tick.pos, state);
+            count += 1;
+        }
+        updater.flush();
+    }
+    drop(updater);
+    // 红石回放的是显示状态,直接写入世界,不再触发方块更新与重新编译
+    let changes = world.redstone.lock().unwrap().tick(registry, world);
+    for (pos, state) in changes {
+        let _ = world.set_block(pos, state);
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qexed_world::Chunk;
+
+    #[test]
+    fn sand_falls_and_grass_gets_snowy() {
+        let registry = BlockRegistry::builtin();
+        let world = World::new("world");
+        world.insert_chunk(Chunk::new(ChunkPos::new(0, 0), qexed_world::biome::PLAINS));
+        let floor = BlockPos::new(2, 60, 2);
+        world.set_block(floor, block::STONE).unwrap();
+
+        let sand = BlockPos::new(2, 70, 2);
+        {
+            let mut updater = BlockUpdater::new(&world, &registry);
+            updater.set_block(sand, block::SAND);
+            updater.flush();
+        }
+        // 没有人观察时不会下落
+        for _ in 0..5 {
+            tick_world(&world, &registry, |_| false);
+        }
+        assert_eq!(world.get_block(sand), Some(block::SAND));
+        assert_eq!(tick_world(&world, &registry, |_| true), 1);
+        assert_eq!(world.get_block(sand), Some(block::AIR));
+        assert_eq!(world.get_block(floor.offset(0, 1, 0)), Some(block::SAND));
+
+        let grass = BlockPos::new(5, 64, 5);
+        let snow = registry.register_test_state("snow", &[("layers".to_string(), "1".to_string())]);
+        let mut updater = BlockUpdater::new(&world, &registry);
+        updater.set_block(grass, block::GRASS_BLOCK);
+        updater.set_block(grass.offset(0, 1, 0), snow);
+        let snowy = updater.block(grass).unwrap();
+        assert_eq!(
+            registry.state_info(snowy).unwrap().property("snowy"),
+            Some("true")
+        );
+        updater.set_block(grass.offset(0, 1, 0), block::AIR);
+        assert_eq!(updater.block(grass), Some(block::GRASS_BLOCK));
+    }
+}
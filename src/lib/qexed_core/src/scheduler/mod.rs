@@ -0,0 +1,7 @@
+pub mod block_update;
+pub mod random_tick;
+pub mod tick_loop;
+
+pub use block_update::{BlockBehaviour, BlockUpdater, tick_world};
+pub use random_tick::RandomTickScheduler;
+pub use tick_loop::{TickLoop, TickPhase, TickReport, TickStats};
\ No newline at end of file
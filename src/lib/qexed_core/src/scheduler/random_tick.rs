@@ -0,0 +1,385 @@
+//! 随机刻调度
+//!
+//! 见 `doc/random_tick.md`:不在每个 tick 随机挑选方块,而是为每个会生长的方块预先算好
+//! 下一次随机刻的时间(`random_tick_time`),到期时由时间轮异步触发。
+//! 到期时间以 Unix 毫秒保存在区块数据中,区块加载时按离线期间经过的时间补算生长,
+//! 例如昨天 11:00 记录在 12:00 成熟的可可豆,今天加载区块时就已经是成熟状态。
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use qexed_world::{
+    BlockPos, Chunk, ChunkPos, Level, World, block::BlockState, block_registry,
+    registry::BlockRegistry,
+};
+
+use crate::utils::{prd::QuadraticPRD, random_tick::random_tick_time, time_wheel::TimeWheel};
+
+/// 时间轮每个槽的粒度
+const WHEEL_TICK_MS: u64 = 1000;
+/// 随机刻间隔最长 120 秒,一圈 128 个槽足够
+const WHEEL_SLOTS: usize = 128;
+
+/// 简单概率生长的作物:每次随机刻有固定的概率长大一级
+struct Crop {
+    name: &'static str,
+    max_age: u8,
+    probability: f64,
+}
+
+/// 可可豆、下界疣、甜浆果丛(概率来自 wiki 作物机制)
+const CROPS: [Crop; 3] = [
+    Crop {
+        name: "minecraft:cocoa",
+        max_age: 2,
+        probability: 0.2,
+    },
+    Crop {
+        name: "minecraft:nether_wart",
+        max_age: 3,
+        probability: 0.1,
+    },
+    Crop {
+        name: "minecraft:sweet_berry_bush",
+        max_age: 3,
+        probability: 0.2,
+    },
+];
+
+/// 当前时间(Unix 毫秒)
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+/// 方块对应的作物与生长等级
+fn crop_of(registry: &BlockRegistry, state: BlockState) -> Option<(usize, u8)> {
+    let info = registry.state_info(state)?;
+    let crop = CROPS.iter().position(|c| c.name == info.name)?;
+    let age = info.property("age")?.parse().ok()?;
+    Some((crop, age))
+}
+
+/// 同一方块指定生长等级的状态
+fn with_age(registry: &BlockRegistry, state: BlockState, age: u8) -> Option<BlockState> {
+    registry.with_property(state, "age", &age.to_string())
+}
+
+/// 从 `from` 开始下一次随机刻的时间
+fn next_due(from: i64, speed: usize) -> i64 {
+    from + (random_tick_time(rand::random::<f64>(), speed) * 1000.0) as i64
+}
+
+struct Task {
+    world: Weak<World>,
+    pos: BlockPos,
+    /// 与区块中保存的到期时间不一致时说明任务已过时
+    due: i64,
+    speed: usize,
+}
+
+/// 随机刻调度器,所有世界共用一个
+pub struct RandomTickScheduler {
+    wheel: Mutex<TimeWheel<Task>>,
+    /// 每种作物一个 PRD 状态机
+    prds: Vec<Mutex<QuadraticPRD>>,
+    registry: &'static BlockRegistry,
+}
+
+impl Default for RandomTickScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RandomTickScheduler {
+    pub fn new() -> Self {
+        Self::with_registry(block_registry())
+    }
+
+    /// 使用指定的方块注册表识别作物
+    pub fn with_registry(registry: &'static BlockRegistry) -> Self {
+        RandomTickScheduler {
+            wheel: Mutex::new(TimeWheel::new(
+                WHEEL_TICK_MS,
+                WHEEL_SLOTS,
+                now_millis().max(0) as u64,
+            )),
+            prds: CROPS
+                .iter()
+                .map(|c| {
+                    let max_attempts = (2.0 / c.probability).ceil() as u32;
+                    Mutex::new(QuadraticPRD::new(c.probability, max_attempts, 0.001).unwrap())
+                })
+                .collect(),
+            registry,
+        }
+    }
+
+    /// 挂接命名世界的所有维度,随机刻速度取自世界的 `randomTickSpeed` 游戏规则
+    pub fn attach_level(self: &Arc<Self>, level: &Level) {
+        let speed = level.game_rules().random_tick_speed;
+        for world in level.worlds() {
+            self.attach(world, speed);
+        }
+    }
+
+    /// 在区块加载时补算离线期间的生长并继续调度;`speed` 为 0 时不再生长
+    pub fn attach(self: &Arc<Self>, world: &Arc<World>, speed: i32) {
+        let scheduler = Arc::clone(self);
+        let weak = Arc::downgrade(world);
+        let speed = speed.max(0) as usize;
+        if !world.set_load_hook(move |chunk| scheduler.catch_up(&weak, chunk, speed, now_millis())) {
+            log::warn!("世界 {} 已设置过区块加载钩子", world.name);
+        }
+    }
+
+    /// 为刚放下的作物安排随机刻,方块不是未成熟的作物时返回 `false`
+    pub fn schedule(&self, world: &Arc<World>, pos: BlockPos, speed: i32) -> bool {
+        if speed <= 0 {
+            return false;
+        }
+        let Some(chunk) = world.get_chunk(pos.chunk_pos()) else {
+            return false;
+        };
+        let mut chunk = chunk.write().unwrap();
+        let state = chunk.get_block(pos.local_x(), pos.y, pos.local_z());
+        match crop_of(self.registry, state) {
+            Some((crop, age)) if age < CROPS[crop].max_age => {}
+            _ => return false,
+        }
+        let due = next_due(now_millis(), speed as usize);
+        chunk.schedule_random_tick(pos, due);
+        self.insert(Task {
+            world: Arc::downgrade(world),
+            pos,
+            due,
+            speed: speed as usize,
+        });
+        true
+    }
+
+    /// 为一个游戏刻内变化过的方块中新出现的未成熟作物安排随机刻,返回安排的数量
+    ///
+    /// 已经安排过随机刻的作物(例如刚长大一级)保持原来的到期时间。
+    pub fn schedule_changes(
+        &self,
+        world: &Arc<World>,
+        changes: &HashMap<ChunkPos, Vec<BlockPos>>,
+        speed: i32,
+    ) -> usize {
+        let mut count = 0;
+        for (chunk_pos, blocks) in changes {
+            let Some(chunk) = world.get_chunk(*chunk_pos) else {
+                continue;
+            };
+            for pos in blocks {
+                if chunk.read().unwrap().random_ticks.contains_key(pos) {
+                    continue;
+                }
+                if self.schedule(world, *pos, speed) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    fn insert(&self, task: Task) {
+        self.wheel
+            .lock()
+            .unwrap()
+            .insert(task.due.max(0) as u64, task);
+    }
+
+    /// 等待调度的随机刻数量
+    pub fn pending(&self) -> usize {
+        self.wheel.lock().unwrap().len()
+    }
+
+    /// 一次随机刻,返回新的生长等级
+    fn random_tick(&self, crop: usize, age: u8) -> u8 {
+        if self.prds[crop].lock().unwrap().try_once() {
+            age + 1
+        } else {
+            age
+        }
+    }
+
+    /// 补算区块中所有到期的随机刻,并把未成熟的作物重新放入时间轮
+    fn catch_up(&self, world: &Weak<World>, chunk: &mut Chunk, speed: usize, now: i64) {
+        if speed == 0 {
+            return;
+        }
+        let scheduled: Vec<(BlockPos, i64)> =
+            chunk.random_ticks.iter().map(|(p, d)| (*p, *d)).collect();
+        for (pos, mut due) in scheduled {
+            let (x, z) = (pos.local_x(), pos.local_z());
+            let state = chunk.get_block(x, pos.y, z);
+            let Some((crop, age)) = crop_of(self.registry, state) else {
+                chunk.cancel_random_tick(&pos);
+                continue;
+            };
+            let max_age = CROPS[crop].max_age;
+            let mut grown = age;
+            while due <= now && grown < max_age {
+                grown = self.random_tick(crop, grown);
+                due = next_due(due, speed);
+            }
+            if grown != age
+                && let Some(state) = with_age(self.registry, state, grown)
+            {
+                chunk.set_block(x, pos.y, z, state);
+            }
+            if grown >= max_age {
+                chunk.cancel_random_tick(&pos);
+                continue;
+            }
+            chunk.schedule_random_tick(pos, due);
+            self.insert(Task {
+                world: world.clone(),
+                pos,
+                due,
+                speed,
+            });
+        }
+    }
+
+    /// 触发一个到期的随机刻
+    fn fire(&self, task: Task) {
+        // 世界或区块已卸载时不处理,区块下次加载时会补算
+        let Some(world) = task.world.upgrade() else {
+            return;
+        };
+        let Some(chunk) = world.get_chunk(task.pos.chunk_pos()) else {
+            return;
+        };
+        let mut chunk = chunk.write().unwrap();
+        if chunk.random_ticks.get(&task.pos) != Some(&task.due) {
+            return;
+        }
+        let (x, z) = (task.pos.local_x(), task.pos.local_z());
+        let state = chunk.get_block(x, task.pos.y, z);
+        let Some((crop, age)) = crop_of(self.registry, state) else {
+            chunk.cancel_random_tick(&task.pos);
+            return;
+        };
+        let grown = self.random_tick(crop, age);
+        // 作物生长不改变光照,直接修改区块
+        if grown != age
+            && let Some(state) = with_age(self.registry, state, grown)
+        {
+            chunk.set_block(x, task.pos.y, z, state);
+        }
+        if grown >= CROPS[crop].max_age {
+            chunk.cancel_random_tick(&task.pos);
+            return;
+        }
+        let due = next_due(task.due.max(now_millis() - WHEEL_TICK_MS as i64), task.speed);
+        chunk.schedule_random_tick(task.pos, due);
+        drop(chunk);
+        self.insert(Task { due, ..task });
+    }
+
+    /// 推进到 `now`(Unix 毫秒)并触发所有到期的随机刻,返回触发的数量
+    pub fn advance(&self, now: i64) -> usize {
+        let due = self.wheel.lock().unwrap().advance(now.max(0) as u64);
+        let count = due.len();
+        for task in due {
+            self.fire(task);
+        }
+        count
+    }
+
+    /// 在后台每秒推进一次时间轮
+    pub fn run(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(WHEEL_TICK_MS));
+            loop {
+                interval.tick().await;
+                let scheduler = Arc::clone(&self);
+                let _ = tokio::task::spawn_blocking(move || scheduler.advance(now_millis())).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cocoa(registry: &BlockRegistry, age: u8) -> BlockState {
+        registry.register_test_state(
+            "cocoa",
+            &[
+                ("age".to_string(), age.to_string()),
+                ("facing".to_string(), "north".to_string()),
+            ],
+        )
+    }
+
+    #[test]
+    fn catch_up_after_offline() {
+        let registry = Box::leak(Box::new(BlockRegistry::builtin()));
+        let scheduler = Arc::new(RandomTickScheduler::with_registry(registry));
+        let world = Arc::new(World::new("world"));
+        let states: Vec<BlockState> = (0..=2).map(|age| cocoa(registry, age)).collect();
+        let pos = BlockPos::new(3, 70, -5);
+        let mut chunk = Chunk::new(pos.chunk_pos(), qexed_world::biome::JUNGLE);
+        chunk.set_block(pos.local_x(), pos.y, pos.local_z(), states[0]);
+        // 一天前安排的随机刻,加载时应当早已成熟
+        let now = now_millis();
+        chunk.schedule_random_tick(pos, now - 86_400_000);
+        scheduler.catch_up(&Arc::downgrade(&world), &mut chunk, 3, now);
+        assert_eq!(chunk.get_block(pos.local_x(), pos.y, pos.local_z()), states[2]);
+        assert!(chunk.random_ticks.is_empty());
+        assert_eq!(scheduler.pending(), 0);
+
+        // 还没到期的只放入时间轮
+        chunk.set_block(pos.local_x(), pos.y, pos.local_z(), states[1]);
+        chunk.schedule_random_tick(pos, now + 30_000);
+        scheduler.catch_up(&Arc::downgrade(&world), &mut chunk, 3, now);
+        assert_eq!(chunk.get_block(pos.local_x(), pos.y, pos.local_z()), states[1]);
+        assert_eq!(scheduler.pending(), 1);
+
+        // 到期后由时间轮触发,直到成熟
+        world.insert_chunk(chunk);
+        let mut time = now + 30_000;
+        while scheduler.pending() > 0 && time < now + 3_600_000 {
+            scheduler.advance(time);
+            time += 1000;
+        }
+        let chunk = world.get_chunk(ChunkPos::new(0, -1)).unwrap();
+        let chunk = chunk.read().unwrap();
+        assert_eq!(chunk.get_block(pos.local_x(), pos.y, pos.local_z()), states[2]);
+        assert!(chunk.random_ticks.is_empty());
+    }
+
+    #[test]
+    fn placed_crops_are_scheduled() {
+        let registry = Box::leak(Box::new(BlockRegistry::builtin()));
+        let scheduler = RandomTickScheduler::with_registry(registry);
+        let world = Arc::new(World::new("world"));
+        let pos = BlockPos::new(3, 70, -5);
+        world.insert_chunk(Chunk::new(pos.chunk_pos(), qexed_world::biome::JUNGLE));
+        world.set_block(pos, cocoa(registry, 0)).unwrap();
+        world.set_block(pos.offset(1, 0, 0), cocoa(registry, 2)).unwrap();
+        world.set_block(pos.offset(2, 0, 0), qexed_world::block::STONE).unwrap();
+
+        // 只有未成熟的作物会被安排,已安排的不重复安排
+        let changes = world.take_block_changes();
+        assert_eq!(scheduler.schedule_changes(&world, &changes, 3), 1);
+        assert_eq!(scheduler.schedule_changes(&world, &changes, 3), 0);
+        assert_eq!(scheduler.pending(), 1);
+        let chunk = world.get_chunk(pos.chunk_pos()).unwrap();
+        assert!(chunk.read().unwrap().random_ticks.contains_key(&pos));
+        // randomTickSpeed 为 0 时不生长
+        world.set_block(pos.offset(0, 1, 0), cocoa(registry, 1)).unwrap();
+        let changes = world.take_block_changes();
+        assert_eq!(scheduler.schedule_changes(&world, &changes, 0), 0);
+    }
+}
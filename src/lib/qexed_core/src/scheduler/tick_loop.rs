@@ -0,0 +1,338 @@
+//! 游戏刻主循环
+//!
+//! 按配置的 `game.tps` 以固定频率运行,每一刻依次执行实体、世界与区块发送三个阶段。
+//! 数据包仍由各连接任务在收到时处理,主循环只负责按刻推进的工作。
+//! 某一刻超时后,后面的刻会不等待连续执行以追上进度;落后太多时直接丢弃积压的刻并警告服务器过载。
+//! 每一刻各阶段的耗时都会记录下来,命令与监控可以通过 [`TickStats`] 读取 MSPT 与 TPS。
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// 落后超过这个时间就不再追赶
+const MAX_CATCH_UP: Duration = Duration::from_secs(2);
+/// 计算 MSPT 与 TPS 时使用最近多少刻
+const SAMPLE_TICKS: usize = 100;
+
+/// 每一刻的执行阶段,按执行顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickPhase {
+    /// 实体
+    Entities,
+    /// 计划刻、方块更新与红石
+    World,
+    /// 向玩家发送方块与光照的变化,并按客户端的处理速度发送区块
+    ChunkSend,
+}
+
+impl TickPhase {
+    pub const ALL: [TickPhase; 3] = [TickPhase::Entities, TickPhase::World, TickPhase::ChunkSend];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TickPhase::Entities => "entities",
+            TickPhase::World => "world",
+            TickPhase::ChunkSend => "chunk_send",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 固定频率的时钟
+#[derive(Debug)]
+pub struct TickClock {
+    interval: Duration,
+    next: Instant,
+}
+
+impl TickClock {
+    /// `tps` 为 0 时按原版的 20 处理
+    pub fn new(tps: u16, now: Instant) -> Self {
+        let tps = if tps == 0 { 20 } else { tps };
+        TickClock {
+            interval: Duration::from_secs(1) / tps as u32,
+            next: now,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// 到下一刻开始还需等待的时间,落后时为零
+    pub fn until_next(&self, now: Instant) -> Duration {
+        self.next.saturating_duration_since(now)
+    }
+
+    /// 一刻执行完后计算下一刻的开始时间,返回因落后太多而丢弃的刻数
+    pub fn advance(&mut self, now: Instant) -> u64 {
+        self.next += self.interval;
+        let behind = now.saturating_duration_since(self.next);
+        if behind <= MAX_CATCH_UP {
+            return 0;
+        }
+        let skipped = (behind.as_nanos() / self.interval.as_nanos()) as u64;
+        self.next = now;
+        skipped
+    }
+}
+
+/// 一刻中各阶段的耗时
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TickTiming {
+    pub total: Duration,
+    pub phases: [Duration; TickPhase::ALL.len()],
+}
+
+/// 正在执行的一刻,用于记录各阶段的耗时
+#[derive(Debug)]
+pub struct TickTimer {
+    start: Instant,
+    current: Option<(TickPhase, Instant)>,
+    timing: TickTiming,
+}
+
+impl TickTimer {
+    fn new(start: Instant) -> Self {
+        TickTimer {
+            start,
+            current: None,
+            timing: TickTiming::default(),
+        }
+    }
+
+    /// 结束上一个阶段并进入 `phase`
+    pub fn enter(&mut self, phase: TickPhase) {
+        let now = Instant::now();
+        self.close(now);
+        self.current = Some((phase, now));
+    }
+
+    fn close(&mut self, now: Instant) {
+        if let Some((phase, since)) = self.current.take() {
+            self.timing.phases[phase.index()] += now - since;
+        }
+    }
+
+    fn finish(mut self, now: Instant) -> TickTiming {
+        self.close(now);
+        self.timing.total = now - self.start;
+        self.timing
+    }
+}
+
+/// 最近若干刻的统计,由主循环写入,命令与监控读取
+#[derive(Debug)]
+pub struct TickStats {
+    interval: Duration,
+    inner: Mutex<StatsInner>,
+}
+
+#[derive(Debug, Default)]
+struct StatsInner {
+    /// 最近的刻:开始时间与耗时
+    samples: VecDeque<(Instant, TickTiming)>,
+    ticks: u64,
+    /// 超过一刻时长的刻数
+    overloaded: u64,
+    skipped: u64,
+}
+
+impl TickStats {
+    pub fn new(interval: Duration) -> Self {
+        TickStats {
+            interval,
+            inner: Mutex::new(StatsInner::default()),
+        }
+    }
+
+    pub fn record(&self, start: Instant, timing: TickTiming) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.ticks += 1;
+        if timing.total > self.interval {
+            inner.overloaded += 1;
+        }
+        if inner.samples.len() == SAMPLE_TICKS {
+            inner.samples.pop_front();
+        }
+        inner.samples.push_back((start, timing));
+    }
+
+    pub fn record_skipped(&self, skipped: u64) {
+        self.inner.lock().unwrap().skipped += skipped;
+    }
+
+    /// 当前的统计结果
+    pub fn report(&self) -> TickReport {
+        let inner = self.inner.lock().unwrap();
+        let n = inner.samples.len();
+        let millis = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mut report = TickReport {
+            target_tps: 1.0 / self.interval.as_secs_f64(),
+            ticks: inner.ticks,
+            overloaded: inner.overloaded,
+            skipped: inner.skipped,
+            ..TickReport::default()
+        };
+        if n == 0 {
+            return report;
+        }
+        for (_, timing) in &inner.samples {
+            report.mspt += millis(timing.total);
+            report.max_mspt = report.max_mspt.max(millis(timing.total));
+            for (sum, phase) in report.phases.iter_mut().zip(timing.phases) {
+                *sum += millis(phase);
+            }
+        }
+        report.mspt /= n as f64;
+        for sum in &mut report.phases {
+            *sum /= n as f64;
+        }
+        // 用最近这些刻的开始时间计算实际频率,只有一刻时按目标频率处理
+        let first = inner.samples.front().map(|(t, _)| *t);
+        let last = inner.samples.back().map(|(t, _)| *t);
+        report.tps = match (first, last) {
+            (Some(first), Some(last)) if n > 1 && last > first => {
+                ((n - 1) as f64 / (last - first).as_secs_f64()).min(report.target_tps)
+            }
+            _ => report.target_tps,
+        };
+        report
+    }
+}
+
+/// TPS 与 MSPT 报告,时间单位为毫秒
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TickReport {
+    pub target_tps: f64,
+    pub tps: f64,
+    /// 最近若干刻的平均耗时
+    pub mspt: f64,
+    pub max_mspt: f64,
+    /// 各阶段的平均耗时,顺序与 [`TickPhase::ALL`] 相同
+    pub phases: [f64; TickPhase::ALL.len()],
+    /// 启动以来执行的刻数
+    pub ticks: u64,
+    /// 超时的刻数
+    pub overloaded: u64,
+    /// 因落后太多而丢弃的刻数
+    pub skipped: u64,
+}
+
+impl fmt::Display for TickReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TPS: {:.1}/{:.0} MSPT: {:.2}ms (最大 {:.2}ms)",
+            self.tps, self.target_tps, self.mspt, self.max_mspt
+        )?;
+        for (phase, ms) in TickPhase::ALL.iter().zip(self.phases) {
+            write!(f, " {}: {:.2}ms", phase.name(), ms)?;
+        }
+        Ok(())
+    }
+}
+
+/// 游戏刻主循环
+pub struct TickLoop {
+    clock: TickClock,
+    stats: Arc<TickStats>,
+}
+
+impl TickLoop {
+    pub fn new(tps: u16) -> Self {
+        let clock = TickClock::new(tps, Instant::now());
+        let stats = Arc::new(TickStats::new(clock.interval()));
+        TickLoop { clock, stats }
+    }
+
+    pub fn stats(&self) -> Arc<TickStats> {
+        Arc::clone(&self.stats)
+    }
+
+    /// 等到下一刻开始,返回这一刻的计时器
+    pub async fn wait(&self) -> TickTimer {
+        let delay = self.clock.until_next(Instant::now());
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        TickTimer::new(Instant::now())
+    }
+
+    /// 记录这一刻的耗时并安排下一刻
+    pub fn finish(&mut self, timer: TickTimer) -> TickTiming {
+        let now = Instant::now();
+        let start = timer.start;
+        let timing = timer.finish(now);
+        self.stats.record(start, timing);
+        let skipped = self.clock.advance(now);
+        if skipped > 0 {
+            log::warn!(
+                "无法跟上!服务器是否过载?落后 {}ms,跳过 {} 刻",
+                skipped as u128 * self.clock.interval().as_millis(),
+                skipped
+            );
+            self.stats.record_skipped(skipped);
+        }
+        timing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_catches_up_then_skips() {
+        let start = Instant::now();
+        let mut clock = TickClock::new(20, start);
+        assert_eq!(clock.interval(), Duration::from_millis(50));
+        assert_eq!(clock.until_next(start), Duration::ZERO);
+
+        // 按时完成的刻等待到下一刻
+        assert_eq!(clock.advance(start + Duration::from_millis(10)), 0);
+        assert_eq!(
+            clock.until_next(start + Duration::from_millis(10)),
+            Duration::from_millis(40)
+        );
+
+        // 超时 200ms:不等待,连续追赶
+        let late = start + Duration::from_millis(300);
+        assert_eq!(clock.advance(late), 0);
+        assert_eq!(clock.until_next(late), Duration::ZERO);
+
+        // 落后 3 秒:丢弃积压的刻,从现在重新开始
+        let stalled = start + Duration::from_millis(3150);
+        assert_eq!(clock.advance(stalled), 60);
+        assert_eq!(clock.until_next(stalled), Duration::ZERO);
+        assert_eq!(clock.advance(stalled), 0);
+        assert_eq!(clock.until_next(stalled), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn report_averages_samples() {
+        let stats = TickStats::new(Duration::from_millis(50));
+        let start = Instant::now();
+        for i in 0..3u32 {
+            let mut timing = TickTiming {
+                total: Duration::from_millis(20 + 20 * i as u64),
+                ..TickTiming::default()
+            };
+            timing.phases[TickPhase::World.index()] = timing.total;
+            stats.record(start + Duration::from_millis(100) * i, timing);
+        }
+        let report = stats.report();
+        assert_eq!(report.ticks, 3);
+        assert_eq!(report.overloaded, 1);
+        assert!((report.mspt - 40.0).abs() < 1e-6);
+        assert!((report.max_mspt - 60.0).abs() < 1e-6);
+        assert!((report.phases[TickPhase::World.index()] - 40.0).abs() < 1e-6);
+        assert!((report.tps - 10.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,28 @@
+//! 测试用的玩家与场景
+
+use qexed_net::packet::packet_pool::AddEntity;
+use qexed_world::Dimension;
+use uuid::Uuid;
+
+use crate::event::entity_tracker::EntitySnapshot;
+
+pub use qexed_world::test_util::stone_floor;
+
+/// 默认世界主世界中站在地面上的玩家
+pub fn player_at(position: (f64, f64, f64)) -> EntitySnapshot {
+    EntitySnapshot {
+        entity_id: 100,
+        generation: 1,
+        entity_type: AddEntity::PLAYER,
+        uuid: Uuid::new_v4(),
+        name: "p".into(),
+        level: "world".into(),
+        dimension: Dimension::Overworld,
+        position,
+        yaw: 0.0,
+        pitch: 0.0,
+        head_yaw: 0.0,
+        on_ground: true,
+        metadata: Default::default(),
+    }
+}
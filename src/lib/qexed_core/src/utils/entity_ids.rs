@@ -0,0 +1,110 @@
+//! 实体 ID 的分配与回收
+//!
+//! ID 由 [`Alloci32`] 分配,释放后优先复用最小的空闲 ID。每个 ID 额外记录一个代数,
+//! 每次重新分配时加一:持有 [`EntityHandle`] 的一方可以据此判断 ID 是否已被别的实体复用,
+//! 重复释放或释放过期的句柄都会被忽略,不会把正在使用的 ID 放回分配器。
+
+use std::collections::HashMap;
+
+use crate::utils::alloci32::{ALLOC, Alloci32};
+
+/// 实体 ID 与分配时的代数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EntityHandle {
+    pub id: i32,
+    pub generation: u32,
+}
+
+/// 实体 ID 分配器
+#[derive(Debug)]
+pub struct EntityIds {
+    alloc: Alloci32,
+    /// 每个用过的 ID 最近一次的代数,以及是否正在使用
+    generations: HashMap<i32, (u32, bool)>,
+    live: usize,
+}
+
+impl Default for EntityIds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntityIds {
+    pub fn new() -> Self {
+        EntityIds {
+            alloc: Alloci32::new(),
+            generations: HashMap::new(),
+            live: 0,
+        }
+    }
+
+    /// 分配一个 ID
+    pub fn allocate(&mut self) -> anyhow::Result<EntityHandle> {
+        let id = self.alloc.get()?;
+        let entry = self.generations.entry(id).or_insert((0, false));
+        entry.0 = entry.0.wrapping_add(1);
+        entry.1 = true;
+        self.live += 1;
+        Ok(EntityHandle {
+            id,
+            generation: entry.0,
+        })
+    }
+
+    /// 句柄是否仍然指向正在使用的 ID
+    pub fn is_alive(&self, handle: EntityHandle) -> bool {
+        self.generations.get(&handle.id) == Some(&(handle.generation, true))
+    }
+
+    /// 释放 ID,句柄已过期或已释放过时返回 `false`
+    pub fn release(&mut self, handle: EntityHandle) -> bool {
+        if !self.is_alive(handle) {
+            log::warn!(
+                "忽略过期的实体 ID {}(代数 {})",
+                handle.id,
+                handle.generation
+            );
+            return false;
+        }
+        if let Some(entry) = self.generations.get_mut(&handle.id) {
+            entry.1 = false;
+        }
+        self.alloc.delete(handle.id);
+        self.live -= 1;
+        true
+    }
+
+    /// 正在使用的 ID 数量
+    pub fn live_count(&self) -> usize {
+        self.live
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_lowest_and_reject_stale() {
+        let mut ids = EntityIds::new();
+        let a = ids.allocate().unwrap();
+        let b = ids.allocate().unwrap();
+        let c = ids.allocate().unwrap();
+        assert_eq!((a.id, b.id, c.id), (0, 1, 2));
+
+        assert!(ids.release(b));
+        // 重复释放不会把 ID 放回两次
+        assert!(!ids.release(b));
+        let d = ids.allocate().unwrap();
+        assert_eq!(d.id, 1);
+        assert_eq!(d.generation, b.generation + 1);
+        assert!(!ids.is_alive(b));
+        assert!(ids.is_alive(d));
+        // 过期的句柄不能释放复用后的 ID
+        assert!(!ids.release(b));
+        assert!(ids.is_alive(d));
+        assert_eq!(ids.allocate().unwrap().id, 3);
+        assert_eq!(ids.live_count(), 4);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod alloci32;
+pub mod entity_ids;
 pub mod mongo_dbconnection_pool;
 pub mod random_tick;
 pub mod prd;
-pub mod build_server_status;
\ No newline at end of file
+pub mod build_server_status;
+pub mod time_wheel;
\ No newline at end of file
@@ -0,0 +1,85 @@
+/// 时间轮
+///
+/// 把到期时间按固定粒度散列到环形的槽中,插入是 O(1),推进时只检查经过的槽。
+/// 超过一圈的任务留在槽中,直到真正到期的那一圈才会被取出。
+pub struct TimeWheel<T> {
+    /// 每个槽对应的毫秒数
+    tick_ms: u64,
+    /// 槽中保存 (到期的刻, 任务)
+    slots: Vec<Vec<(u64, T)>>,
+    /// 下一个要处理的刻
+    current: u64,
+    len: usize,
+}
+
+impl<T> TimeWheel<T> {
+    /// `now_ms` 之前到期的任务会在第一次推进时取出
+    pub fn new(tick_ms: u64, slot_count: usize, now_ms: u64) -> Self {
+        assert!(tick_ms > 0 && slot_count > 0);
+        TimeWheel {
+            tick_ms,
+            slots: (0..slot_count).map(|_| Vec::new()).collect(),
+            current: now_ms / tick_ms,
+            len: 0,
+        }
+    }
+
+    /// 加入一个在 `due_ms` 到期的任务,已经过期的任务在下一次推进时取出
+    pub fn insert(&mut self, due_ms: u64, item: T) {
+        let tick = (due_ms / self.tick_ms).max(self.current);
+        let slot = (tick % self.slots.len() as u64) as usize;
+        self.slots[slot].push((tick, item));
+        self.len += 1;
+    }
+
+    /// 推进到 `now_ms`,取出所有到期的任务
+    pub fn advance(&mut self, now_ms: u64) -> Vec<T> {
+        let target = now_ms / self.tick_ms;
+        if target < self.current {
+            return Vec::new();
+        }
+        let count = self.slots.len() as u64;
+        // 经过超过一圈时每个槽只需检查一次
+        let steps = (target - self.current + 1).min(count);
+        let mut due = Vec::new();
+        for tick in self.current..self.current + steps {
+            let slot = &mut self.slots[(tick % count) as usize];
+            let mut i = 0;
+            while i < slot.len() {
+                if slot[i].0 <= target {
+                    due.push(slot.swap_remove(i).1);
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        self.current = target + 1;
+        self.len -= due.len();
+        due
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[test]
+fn time_wheel_rounds() {
+    let mut wheel = TimeWheel::new(1000, 8, 10_000);
+    wheel.insert(9_000, "过期");
+    wheel.insert(12_500, "2秒后");
+    wheel.insert(12_500 + 8_000, "一圈后");
+    wheel.insert(100_000, "很久以后");
+    assert_eq!(wheel.advance(10_000), vec!["过期"]);
+    assert!(wheel.advance(11_999).is_empty());
+    assert_eq!(wheel.advance(12_000), vec!["2秒后"]);
+    assert!(wheel.advance(19_000).is_empty());
+    assert_eq!(wheel.advance(20_000), vec!["一圈后"]);
+    assert_eq!(wheel.len(), 1);
+    assert_eq!(wheel.advance(1_000_000), vec!["很久以后"]);
+    assert!(wheel.is_empty());
+}
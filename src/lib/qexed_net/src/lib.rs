@@ -1,6 +1,8 @@
 use std::io::ErrorKind;
 use std::net::SocketAddr;
 use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::mpsc;
 use tokio::{io::AsyncReadExt, net::TcpListener};
 use std::io::{Error, Result};
 use bytes::{BytesMut, Buf};
@@ -26,83 +28,153 @@ pub async fn new_tcp_server(ip:&str,port:u16)->Result<TcpListener>{
     log::info!("服务器监听在 {}:{}", ip, port);
     Ok(conn)
 }
-// 压缩阈值：当数据包长度超过此值时启用压缩
+/// 发送队列中的消息,按放入的顺序由写任务处理
+enum Outbound {
+    Packet(bytes::Bytes),
+    /// 之后的数据包按该阈值压缩
+    Compression(Option<usize>),
+    Shutdown,
+}
+
+/// 连接的发送端,可以克隆后交给游戏刻等其他任务
+///
+/// 数据包编码后放入队列立即返回,由连接的写任务写入套接字,发送方不会等待客户端,
+/// 也不需要和读数据包的连接任务争用锁。连接关闭后发送返回错误。
+#[derive(Clone)]
+pub struct PacketSender {
+    tx: mpsc::UnboundedSender<Outbound>,
+}
+
+impl PacketSender {
+    pub fn send<T: Packet>(&self, packet: &T) -> Result<()> {
+        let mut buf = BytesMut::new();
+        let mut writer = PacketWriter::new(&mut buf);
+        writer.varint(&VarInt(packet.id().try_into().unwrap()));
+        packet.serialize(&mut writer); // 序列化数据包
+        self.push(Outbound::Packet(buf.freeze()))
+    }
+
+    /// 发送完队列中已有的数据包后关闭连接
+    pub fn shutdown(&self) {
+        let _ = self.push(Outbound::Shutdown);
+    }
+
+    /// 写任务是否已经退出
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+
+    fn push(&self, message: Outbound) -> Result<()> {
+        self.tx
+            .send(message)
+            .map_err(|_| Error::new(ErrorKind::BrokenPipe, "Connection closed"))
+    }
+}
+
+/// 写任务:依次把队列中的数据包加上长度(按需压缩)写入套接字
+async fn write_loop(mut socket: OwnedWriteHalf, mut rx: mpsc::UnboundedReceiver<Outbound>) {
+    let mut compression = None;
+    while let Some(message) = rx.recv().await {
+        match message {
+            Outbound::Packet(data) => {
+                let frame = match compression {
+                    Some(threshold) => frame_compressed(data, threshold),
+                    None => frame_uncompressed(data),
+                };
+                let result = match frame {
+                    Ok(frame) => socket.write_all(&frame).await,
+                    Err(e) => Err(e),
+                };
+                if result.is_err() {
+                    break;
+                }
+            }
+            Outbound::Compression(threshold) => compression = threshold,
+            Outbound::Shutdown => {
+                let _ = socket.shutdown().await;
+                break;
+            }
+        }
+    }
+}
+
+fn frame_uncompressed(data: bytes::Bytes) -> Result<BytesMut> {
+    let mut buf = BytesMut::new();
+    write_varint(data.len() as i32, &mut buf); // 长度字段
+    buf.put(data);
+    Ok(buf)
+}
+
+fn frame_compressed(data: bytes::Bytes, threshold: usize) -> Result<BytesMut> {
+    let mut buf = BytesMut::new();
+
+    if data.len() >= threshold {
+        // 压缩数据
+        let mut encoder = ZlibEncoder::new(&data[..], Compression::default());
+        let mut compressed = Vec::new();
+        encoder.read_to_end(&mut compressed)?;
+
+        // 写入数据长度 (VarInt)
+        write_varint(data.len() as i32, &mut buf); // 未压缩长度
+        buf.put_slice(&compressed);
+    } else {
+        // 小数据包不压缩
+        write_varint(0, &mut buf); // 0 表示未压缩
+        buf.put(data);
+    }
+
+    // 写入总长度 (VarInt)
+    let mut final_buf = BytesMut::new();
+    write_varint(buf.len() as i32, &mut final_buf);
+    final_buf.put(buf);
+    Ok(final_buf)
+}
+
+/// 一个客户端连接:读取数据包,发送的数据包经由 [`PacketSender`] 的队列写出
 pub struct PacketListener {
-    pub socket: TcpStream,
+    socket: OwnedReadHalf,
     pub socketaddr: SocketAddr,
     pub player:Option<player::Player>,
     compression_threshold:usize,
     buffer: BytesMut,
     compression_enabled: bool, // 是否启用压缩
+    sender: PacketSender,
 }
 
 impl PacketListener {
+    /// 需要在 tokio 运行时中调用,会启动连接的写任务
     pub fn new(socket: TcpStream, socketaddr: SocketAddr) -> Self {
+        let (reader, writer) = socket.into_split();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(write_loop(writer, rx));
         Self {
-            socket,
+            socket: reader,
             socketaddr,
             buffer: BytesMut::with_capacity(4096),
             compression_enabled: false, // 默认不启用压缩
             compression_threshold:0,
             player: None,
+            sender: PacketSender { tx },
         }
     }
 
+    /// 连接的发送端
+    pub fn sender(&self) -> PacketSender {
+        self.sender.clone()
+    }
+
     // 启用或禁用压缩
     pub fn set_compression(&mut self, enabled: bool,compression_threshold:usize) {
         self.compression_enabled = enabled;
         self.compression_threshold =compression_threshold;
+        let _ = self
+            .sender
+            .push(Outbound::Compression(enabled.then_some(compression_threshold)));
     }
 
+    /// 放入发送队列,不等待写入套接字
     pub async fn send<T: Packet>(&mut self, packet: &T) -> Result<()> {
-        let mut buf = BytesMut::new();
-        let mut writer = PacketWriter::new(&mut buf);
-        writer.varint(&VarInt(packet.id().try_into().unwrap()));
-        packet.serialize(&mut writer); // 序列化数据包
-        self.send_raw(buf.freeze()).await
-    }
-
-    async fn send_raw(&mut self, data: bytes::Bytes) -> Result<()> {
-        if self.compression_enabled {
-            self.send_compressed(data).await
-        } else {
-            self.send_uncompressed(data).await
-        }
-    }
-
-    async fn send_uncompressed(&mut self, data: bytes::Bytes) -> Result<()> {
-        let mut buf = BytesMut::new();
-        write_varint(data.len() as i32, &mut buf); // 长度字段
-        buf.put(data);
-        self.socket.write_all(&buf).await?;
-        Ok(())
-    }
-
-    async fn send_compressed(&mut self, data: bytes::Bytes) -> Result<()> {
-        let mut buf = BytesMut::new();
-        
-        if data.len() >= self.compression_threshold {
-            // 压缩数据
-            let mut encoder = ZlibEncoder::new(&data[..], Compression::default());
-            let mut compressed = Vec::new();
-            encoder.read_to_end(&mut compressed)?;
-            
-            // 写入数据长度 (VarInt)
-            write_varint(data.len() as i32, &mut buf); // 未压缩长度
-            buf.put_slice(&compressed);
-        } else {
-            // 小数据包不压缩
-            write_varint(0, &mut buf); // 0 表示未压缩
-            buf.put(data);
-        }
-        
-        // 写入总长度 (VarInt)
-        let mut final_buf = BytesMut::new();
-        write_varint(buf.len() as i32, &mut final_buf);
-        final_buf.put(buf);
-        
-        self.socket.write_all(&final_buf).await?;
-        Ok(())
+        self.sender.send(packet)
     }
 
     pub async fn read(&mut self) -> Result<Vec<u8>> {
@@ -189,8 +261,10 @@ impl PacketListener {
             Ok(decompressed)
         }
     }
+    /// 发送完队列中已有的数据包后关闭连接,之后的读取会因连接关闭而失败
     pub async fn shutdown(&mut self)->anyhow::Result<(),std::io::Error>{
-        self.socket.shutdown().await
+        self.sender.shutdown();
+        Ok(())
     }
 }
 
@@ -299,4 +373,42 @@ pub fn read_packet(data: Vec<u8>,status:PacketState)->anyhow::Result<Box<dyn Pac
     //             // }
     //         }
     //     });
-    // }
\ No newline at end of file
+    // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::packet_pool::KeepAliveClientPlay;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn sender_does_not_wait_for_reader() {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(server.local_addr().unwrap()).await.unwrap();
+        let (socket, socketaddr) = server.accept().await.unwrap();
+        let conn = Arc::new(tokio::sync::Mutex::new(PacketListener::new(socket, socketaddr)));
+        let sender = conn.lock().await.sender();
+        // 连接任务持有锁等待客户端的数据包
+        let reader = tokio::spawn({
+            let conn = Arc::clone(&conn);
+            async move { conn.lock().await.read().await }
+        });
+        tokio::task::yield_now().await;
+
+        let mut pk = KeepAliveClientPlay::new();
+        pk.alive_id = 7;
+        sender.send(&pk).unwrap();
+        let mut frame = [0u8; 10];
+        client.read_exact(&mut frame).await.unwrap();
+        assert_eq!(frame[0], 9);
+        assert_eq!(frame[1] as u32, pk.id());
+        assert_eq!(frame[2..], 7u64.to_be_bytes());
+
+        // 关闭后客户端读到 EOF,读任务随客户端断开而结束
+        sender.shutdown();
+        assert_eq!(client.read(&mut frame).await.unwrap(), 0);
+        drop(client);
+        assert!(reader.await.unwrap().is_err());
+        assert!(sender.send(&pk).is_err());
+    }
+}
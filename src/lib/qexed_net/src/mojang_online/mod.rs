@@ -8,7 +8,8 @@ use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
 // 全局缓存 (线程安全)
-static PROFILE_CACHE: Lazy<Arc<Mutex<HashMap<Uuid, Option<MojangProfile>>>>> =
+type ProfileCache = Arc<Mutex<HashMap<Uuid, Option<MojangProfile>>>>;
+static PROFILE_CACHE: Lazy<ProfileCache> =
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
 // 定义 Mojang 用户资料结构
@@ -28,10 +29,8 @@ pub async fn query_mojang_for_usernames(uuids: Uuid) -> Result<MojangProfile, an
     let client = Client::new();
 
     // 先尝试从缓存获取
-    if let Some(cached) = get_from_cache(&uuids) {
-        if let Some(profile) = cached {
-            return Ok(profile);
-        }
+    if let Some(Some(profile)) = get_from_cache(&uuids) {
+        return Ok(profile);
     }
 
     // 缓存未命中，查询 Mojang API
@@ -1,5 +1,5 @@
 use crate::{
-    net_types::{subdata::Subdata, var_int::VarInt},
+    net_types::subdata::Subdata,
     packet::{decode::PacketReader, encode::PacketWriter},
 };
 #[derive(Debug, Default, PartialEq)]
@@ -8,7 +8,7 @@ pub struct BlockEntities {
     pub xz: u8,
     pub y: u16,
     pub entity_type: VarInt,
-    pub nbt: Vec<crab_nbt::Nbt>,
+    pub nbt: crab_nbt::Nbt,
 }
 impl Subdata for BlockEntities {
     fn new() -> Self {
@@ -16,7 +16,7 @@ impl Subdata for BlockEntities {
             xz:0,
             y:0,
             entity_type:VarInt(0),
-            nbt:vec![],
+            nbt:crab_nbt::Nbt::default(),
         }
     }
     fn serialize(&self, w: &mut PacketWriter) {
@@ -2,7 +2,6 @@ use crate::{
     net_types::{block_entities::BlockEntities, heightmap::Heightmaps, subdata::Subdata},
     packet::{decode::PacketReader, encode::PacketWriter},
 };
-use serde::{Deserialize, Serialize};
 #[derive(Debug, Default, PartialEq)]
 pub struct Chunk {
     pub heightmaps: Vec<Heightmaps>,
@@ -0,0 +1,657 @@
+//! 实体元数据
+//!
+//! 元数据是一组按下标排列的值,每项为 下标(u8)、类型 ID(VarInt)、值,以 0xFF 结束。
+//! 常用的下标与标志位见 [`index`]、[`flags`] 与 [`skin`]。
+
+use crate::{
+    net_types::{position::Position, slot::Slot, subdata::Subdata, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 元数据列表的结束标记
+const END: u8 = 0xFF;
+
+/// 常用的元数据下标
+pub mod index {
+    /// 实体标志位,见 [`super::flags`]
+    pub const FLAGS: u8 = 0;
+    /// 剩余空气
+    pub const AIR_SUPPLY: u8 = 1;
+    /// 自定义名称
+    pub const CUSTOM_NAME: u8 = 2;
+    /// 是否总是显示自定义名称
+    pub const CUSTOM_NAME_VISIBLE: u8 = 3;
+    pub const SILENT: u8 = 4;
+    pub const NO_GRAVITY: u8 = 5;
+    /// 姿势,见 [`super::Pose`]
+    pub const POSE: u8 = 6;
+    /// 在细雪中冻结的刻数
+    pub const TICKS_FROZEN: u8 = 7;
+    /// 生物手部状态
+    pub const LIVING_HAND_STATE: u8 = 8;
+    /// 生物的生命值
+    pub const LIVING_HEALTH: u8 = 9;
+    /// 生物的 AI 标志位(无 AI、左撇子、攻击中)
+    pub const MOB_FLAGS: u8 = 15;
+    /// 玩家显示的皮肤部分,见 [`super::skin`]
+    pub const PLAYER_SKIN_PARTS: u8 = 17;
+    /// 玩家的主手(0 左手,1 右手)
+    pub const PLAYER_MAIN_HAND: u8 = 18;
+}
+
+/// 下标 [`index::FLAGS`] 中的标志位
+pub mod flags {
+    pub const ON_FIRE: u8 = 0x01;
+    pub const CROUCHING: u8 = 0x02;
+    pub const SPRINTING: u8 = 0x08;
+    pub const SWIMMING: u8 = 0x10;
+    pub const INVISIBLE: u8 = 0x20;
+    pub const GLOWING: u8 = 0x40;
+    pub const FALL_FLYING: u8 = 0x80;
+}
+
+/// 下标 [`index::PLAYER_SKIN_PARTS`] 中的皮肤部分
+pub mod skin {
+    pub const CAPE: u8 = 0x01;
+    pub const JACKET: u8 = 0x02;
+    pub const LEFT_SLEEVE: u8 = 0x04;
+    pub const RIGHT_SLEEVE: u8 = 0x08;
+    pub const LEFT_PANTS: u8 = 0x10;
+    pub const RIGHT_PANTS: u8 = 0x20;
+    pub const HAT: u8 = 0x40;
+    pub const ALL: u8 = 0x7F;
+}
+
+/// 实体姿势
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Pose {
+    #[default]
+    Standing,
+    FallFlying,
+    Sleeping,
+    Swimming,
+    SpinAttack,
+    Crouching,
+    LongJumping,
+    Dying,
+    Croaking,
+    UsingTongue,
+    Sitting,
+    Roaring,
+    Sniffing,
+    Emerging,
+    Digging,
+    Sliding,
+    Shooting,
+    Inhaling,
+}
+
+impl Pose {
+    const ALL: [Pose; 18] = [
+        Pose::Standing,
+        Pose::FallFlying,
+        Pose::Sleeping,
+        Pose::Swimming,
+        Pose::SpinAttack,
+        Pose::Crouching,
+        Pose::LongJumping,
+        Pose::Dying,
+        Pose::Croaking,
+        Pose::UsingTongue,
+        Pose::Sitting,
+        Pose::Roaring,
+        Pose::Sniffing,
+        Pose::Emerging,
+        Pose::Digging,
+        Pose::Sliding,
+        Pose::Shooting,
+        Pose::Inhaling,
+    ];
+
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_id(id: i32) -> Option<Pose> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|i| Pose::ALL.get(i).copied())
+    }
+}
+
+/// 实体元数据的值
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    Byte(i8),
+    VarInt(i32),
+    VarLong(i64),
+    Float(f32),
+    String(String),
+    /// 文本组件(NBT)
+    Text(crab_nbt::Nbt),
+    /// 可选的文本组件(NBT)
+    OptionalText(Option<crab_nbt::Nbt>),
+    Slot(Slot),
+    Boolean(bool),
+    /// 绕 x、y、z 轴的旋转角度
+    Rotations(f32, f32, f32),
+    Position(Position),
+    OptionalPosition(Option<Position>),
+    /// 方向 ID(下、上、北、南、西、东)
+    Direction(i32),
+    OptionalUuid(Option<uuid::Uuid>),
+    BlockState(i32),
+    /// 可选的方块状态,空气表示没有
+    OptionalBlockState(Option<i32>),
+    Nbt(crab_nbt::Nbt),
+    /// 村民类型、职业、等级
+    VillagerData(i32, i32, i32),
+    OptionalVarInt(Option<i32>),
+    Pose(Pose),
+    CatVariant(i32),
+    CowVariant(i32),
+    WolfVariant(i32),
+    WolfSoundVariant(i32),
+    FrogVariant(i32),
+    PigVariant(i32),
+    ChickenVariant(i32),
+    /// 维度名与位置
+    OptionalGlobalPosition(Option<(String, Position)>),
+    /// 注册表中的画,不支持内联定义的画
+    PaintingVariant(i32),
+    SnifferState(i32),
+    ArmadilloState(i32),
+    Vector3(f32, f32, f32),
+    Quaternion(f32, f32, f32, f32),
+}
+
+fn write_varlong(w: &mut PacketWriter, value: i64) {
+    let mut value = value as u64;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            w.u8(byte);
+            return;
+        }
+        w.u8(byte | 0x80);
+    }
+}
+
+fn read_varlong(r: &mut PacketReader) -> i64 {
+    let mut value = 0u64;
+    for shift in (0..70).step_by(7) {
+        let byte = r.u8();
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    value as i64
+}
+
+/// 网络中的 NBT 没有根标签名
+fn read_nbt(r: &mut PacketReader) -> Option<crab_nbt::Nbt> {
+    crab_nbt::Nbt::read_unnamed(&mut *r.buf).ok()
+}
+
+impl MetadataValue {
+    /// 协议中的类型 ID
+    pub fn type_id(&self) -> i32 {
+        match self {
+            MetadataValue::Byte(_) => 0,
+            MetadataValue::VarInt(_) => 1,
+            MetadataValue::VarLong(_) => 2,
+            MetadataValue::Float(_) => 3,
+            MetadataValue::String(_) => 4,
+            MetadataValue::Text(_) => 5,
+            MetadataValue::OptionalText(_) => 6,
+            MetadataValue::Slot(_) => 7,
+            MetadataValue::Boolean(_) => 8,
+            MetadataValue::Rotations(..) => 9,
+            MetadataValue::Position(_) => 10,
+            MetadataValue::OptionalPosition(_) => 11,
+            MetadataValue::Direction(_) => 12,
+            MetadataValue::OptionalUuid(_) => 13,
+            MetadataValue::BlockState(_) => 14,
+            MetadataValue::OptionalBlockState(_) => 15,
+            MetadataValue::Nbt(_) => 16,
+            MetadataValue::VillagerData(..) => 19,
+            MetadataValue::OptionalVarInt(_) => 20,
+            MetadataValue::Pose(_) => 21,
+            MetadataValue::CatVariant(_) => 22,
+            MetadataValue::CowVariant(_) => 23,
+            MetadataValue::WolfVariant(_) => 24,
+            MetadataValue::WolfSoundVariant(_) => 25,
+            MetadataValue::FrogVariant(_) => 26,
+            MetadataValue::PigVariant(_) => 27,
+            MetadataValue::ChickenVariant(_) => 28,
+            MetadataValue::OptionalGlobalPosition(_) => 29,
+            MetadataValue::PaintingVariant(_) => 30,
+            MetadataValue::SnifferState(_) => 31,
+            MetadataValue::ArmadilloState(_) => 32,
+            MetadataValue::Vector3(..) => 33,
+            MetadataValue::Quaternion(..) => 34,
+        }
+    }
+
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.varint(&VarInt(self.type_id()));
+        match self {
+            MetadataValue::Byte(v) => w.i8(*v),
+            MetadataValue::VarInt(v)
+            | MetadataValue::Direction(v)
+            | MetadataValue::BlockState(v)
+            | MetadataValue::CatVariant(v)
+            | MetadataValue::CowVariant(v)
+            | MetadataValue::WolfVariant(v)
+            | MetadataValue::WolfSoundVariant(v)
+            | MetadataValue::FrogVariant(v)
+            | MetadataValue::PigVariant(v)
+            | MetadataValue::ChickenVariant(v)
+            | MetadataValue::SnifferState(v)
+            | MetadataValue::ArmadilloState(v) => w.varint(&VarInt(*v)),
+            MetadataValue::VarLong(v) => write_varlong(w, *v),
+            MetadataValue::Float(v) => w.f32(*v),
+            MetadataValue::String(v) => w.string(v),
+            MetadataValue::Text(v) | MetadataValue::Nbt(v) => w.serialize(v),
+            MetadataValue::OptionalText(v) => w.option(v.as_ref()),
+            MetadataValue::Slot(v) => w.serialize(v),
+            MetadataValue::Boolean(v) => w.bool(*v),
+            MetadataValue::Rotations(x, y, z) | MetadataValue::Vector3(x, y, z) => {
+                w.f32(*x);
+                w.f32(*y);
+                w.f32(*z);
+            }
+            MetadataValue::Position(v) => w.serialize(v),
+            MetadataValue::OptionalPosition(v) => w.option(v.as_ref()),
+            MetadataValue::OptionalUuid(v) => w.option(v.as_ref()),
+            // 0 表示空气
+            MetadataValue::OptionalBlockState(v) => w.varint(&VarInt(v.unwrap_or(0))),
+            MetadataValue::VillagerData(kind, profession, level) => {
+                w.varint(&VarInt(*kind));
+                w.varint(&VarInt(*profession));
+                w.varint(&VarInt(*level));
+            }
+            MetadataValue::OptionalVarInt(v) => w.varint(&VarInt(v.map_or(0, |v| v + 1))),
+            MetadataValue::Pose(v) => w.varint(&VarInt(v.id())),
+            MetadataValue::OptionalGlobalPosition(v) => {
+                w.bool(v.is_some());
+                if let Some((dimension, pos)) = v {
+                    w.string(dimension);
+                    w.serialize(pos);
+                }
+            }
+            // 0 表示内联定义,注册表中的画为下标 + 1
+            MetadataValue::PaintingVariant(v) => w.varint(&VarInt(v + 1)),
+            MetadataValue::Quaternion(x, y, z, angle) => {
+                w.f32(*x);
+                w.f32(*y);
+                w.f32(*z);
+                w.f32(*angle);
+            }
+        }
+    }
+
+    /// 读取类型 ID 与值,无法解析的值返回 `None`
+    ///
+    /// 粒子(17、18)的参数长度取决于粒子类型,内联的画与带数据组件的物品同样无法解析。
+    fn deserialize(r: &mut PacketReader) -> Option<Self> {
+        Some(match r.varint().0 {
+            0 => MetadataValue::Byte(r.i8()),
+            1 => MetadataValue::VarInt(r.varint().0),
+            2 => MetadataValue::VarLong(read_varlong(r)),
+            3 => MetadataValue::Float(r.f32()),
+            4 => MetadataValue::String(r.string()),
+            5 => MetadataValue::Text(read_nbt(r)?),
+            6 => MetadataValue::OptionalText(if r.bool() { Some(read_nbt(r)?) } else { None }),
+            7 => MetadataValue::Slot(Slot::read(r)?),
+            8 => MetadataValue::Boolean(r.bool()),
+            9 => MetadataValue::Rotations(r.f32(), r.f32(), r.f32()),
+            10 => MetadataValue::Position(r.deserialize()),
+            11 => MetadataValue::OptionalPosition(r.option()),
+            12 => MetadataValue::Direction(r.varint().0),
+            13 => MetadataValue::OptionalUuid(r.option()),
+            14 => MetadataValue::BlockState(r.varint().0),
+            15 => MetadataValue::OptionalBlockState(Some(r.varint().0).filter(|v| *v != 0)),
+            16 => MetadataValue::Nbt(read_nbt(r)?),
+            19 => MetadataValue::VillagerData(r.varint().0, r.varint().0, r.varint().0),
+            20 => MetadataValue::OptionalVarInt(Some(r.varint().0 - 1).filter(|v| *v >= 0)),
+            21 => MetadataValue::Pose(Pose::from_id(r.varint().0)?),
+            22 => MetadataValue::CatVariant(r.varint().0),
+            23 => MetadataValue::CowVariant(r.varint().0),
+            24 => MetadataValue::WolfVariant(r.varint().0),
+            25 => MetadataValue::WolfSoundVariant(r.varint().0),
+            26 => MetadataValue::FrogVariant(r.varint().0),
+            27 => MetadataValue::PigVariant(r.varint().0),
+            28 => MetadataValue::ChickenVariant(r.varint().0),
+            29 => MetadataValue::OptionalGlobalPosition(if r.bool() {
+                Some((r.string(), r.deserialize()))
+            } else {
+                None
+            }),
+            30 => MetadataValue::PaintingVariant(Some(r.varint().0 - 1).filter(|v| *v >= 0)?),
+            31 => MetadataValue::SnifferState(r.varint().0),
+            32 => MetadataValue::ArmadilloState(r.varint().0),
+            33 => MetadataValue::Vector3(r.f32(), r.f32(), r.f32()),
+            34 => MetadataValue::Quaternion(r.f32(), r.f32(), r.f32(), r.f32()),
+            _ => return None,
+        })
+    }
+}
+
+/// 纯文本的文本组件
+pub fn text_component(text: &str) -> crab_nbt::Nbt {
+    let mut compound = crab_nbt::NbtCompound::new();
+    compound.put("text".to_string(), text.to_string());
+    crab_nbt::Nbt::new(String::new(), compound)
+}
+
+/// 实体元数据:按下标排列的一组值
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntityMetadata {
+    pub entries: Vec<(u8, MetadataValue)>,
+}
+
+impl EntityMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, index: u8) -> Option<&MetadataValue> {
+        self.entries
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, v)| v)
+    }
+
+    /// 设置下标 `index` 的值,返回值是否发生了变化
+    pub fn set(&mut self, index: u8, value: MetadataValue) -> bool {
+        match self.entries.iter_mut().find(|(i, _)| *i == index) {
+            Some((_, old)) if *old == value => false,
+            Some((_, old)) => {
+                *old = value;
+                true
+            }
+            None => {
+                self.entries.push((index, value));
+                self.entries.sort_by_key(|(i, _)| *i);
+                true
+            }
+        }
+    }
+
+    /// 链式设置,用于构造元数据
+    pub fn with(mut self, index: u8, value: MetadataValue) -> Self {
+        self.set(index, value);
+        self
+    }
+
+    pub fn remove(&mut self, index: u8) -> Option<MetadataValue> {
+        let i = self.entries.iter().position(|(i, _)| *i == index)?;
+        Some(self.entries.remove(i).1)
+    }
+
+    /// 与 `old` 相比发生变化的项
+    pub fn changes(&self, old: &EntityMetadata) -> EntityMetadata {
+        EntityMetadata {
+            entries: self
+                .entries
+                .iter()
+                .filter(|(i, v)| old.get(*i) != Some(v))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// 实体标志位,未设置时为 0
+    pub fn flags(&self) -> u8 {
+        match self.get(index::FLAGS) {
+            Some(MetadataValue::Byte(v)) => *v as u8,
+            _ => 0,
+        }
+    }
+
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.flags() & flag != 0
+    }
+
+    /// 打开或关闭 [`flags`] 中的标志位
+    pub fn set_flag(&mut self, flag: u8, on: bool) -> bool {
+        let flags = if on {
+            self.flags() | flag
+        } else {
+            self.flags() & !flag
+        };
+        self.set(index::FLAGS, MetadataValue::Byte(flags as i8))
+    }
+
+    pub fn set_on_fire(&mut self, on: bool) -> bool {
+        self.set_flag(flags::ON_FIRE, on)
+    }
+
+    pub fn set_invisible(&mut self, on: bool) -> bool {
+        self.set_flag(flags::INVISIBLE, on)
+    }
+
+    pub fn set_glowing(&mut self, on: bool) -> bool {
+        self.set_flag(flags::GLOWING, on)
+    }
+
+    /// 自定义名称,`None` 时清除
+    pub fn set_custom_name(&mut self, name: Option<&str>) -> bool {
+        self.set(
+            index::CUSTOM_NAME,
+            MetadataValue::OptionalText(name.map(text_component)),
+        )
+    }
+
+    /// 自定义名称的纯文本,不是纯文本组件时返回 `None`
+    pub fn custom_name(&self) -> Option<String> {
+        match self.get(index::CUSTOM_NAME) {
+            Some(MetadataValue::OptionalText(Some(text))) => text.get_string("text").cloned(),
+            _ => None,
+        }
+    }
+
+    pub fn set_custom_name_visible(&mut self, visible: bool) -> bool {
+        self.set(index::CUSTOM_NAME_VISIBLE, MetadataValue::Boolean(visible))
+    }
+
+    pub fn pose(&self) -> Pose {
+        match self.get(index::POSE) {
+            Some(MetadataValue::Pose(pose)) => *pose,
+            _ => Pose::Standing,
+        }
+    }
+
+    pub fn set_pose(&mut self, pose: Pose) -> bool {
+        self.set(index::POSE, MetadataValue::Pose(pose))
+    }
+
+    pub fn set_health(&mut self, health: f32) -> bool {
+        self.set(index::LIVING_HEALTH, MetadataValue::Float(health))
+    }
+
+    /// 玩家显示的皮肤部分,见 [`skin`]
+    pub fn set_skin_parts(&mut self, parts: u8) -> bool {
+        self.set(index::PLAYER_SKIN_PARTS, MetadataValue::Byte(parts as i8))
+    }
+
+    pub fn skin_parts(&self) -> u8 {
+        match self.get(index::PLAYER_SKIN_PARTS) {
+            Some(MetadataValue::Byte(v)) => *v as u8,
+            _ => 0,
+        }
+    }
+}
+
+impl Subdata for EntityMetadata {
+    fn new() -> Self {
+        EntityMetadata::new()
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        for (index, value) in &self.entries {
+            w.u8(*index);
+            value.serialize(w);
+        }
+        w.u8(END);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.entries.clear();
+        loop {
+            let index = r.u8();
+            if index == END {
+                break;
+            }
+            // 无法解析的值长度未知,后面的项只能丢弃
+            let Some(value) = MetadataValue::deserialize(r) else {
+                break;
+            };
+            self.entries.push((index, value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+
+    fn encode(metadata: &EntityMetadata) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        metadata.serialize(&mut PacketWriter::new(&mut buf));
+        buf.to_vec()
+    }
+
+    /// 解码并返回剩余的字节
+    fn decode(bytes: &[u8]) -> (EntityMetadata, Vec<u8>) {
+        let mut slice = bytes;
+        let mut metadata = EntityMetadata::new();
+        metadata.deserialize(&mut PacketReader::new(Box::new(&mut slice)));
+        (metadata, slice.to_vec())
+    }
+
+    fn nbt_int(key: &str, value: i32) -> crab_nbt::Nbt {
+        let mut compound = crab_nbt::NbtCompound::new();
+        compound.put(key.to_string(), value);
+        crab_nbt::Nbt::new(String::new(), compound)
+    }
+
+    #[test]
+    fn every_value_type_round_trips() {
+        let mut slot = Slot::new(1, 3);
+        slot.set_component(crate::net_types::slot::DataComponent::Damage(5));
+        let values = vec![
+            MetadataValue::Byte(-3),
+            MetadataValue::VarInt(300),
+            MetadataValue::VarLong(-1),
+            MetadataValue::Float(1.5),
+            MetadataValue::String("名字".to_string()),
+            MetadataValue::Text(text_component("hello")),
+            MetadataValue::OptionalText(Some(text_component("hi"))),
+            MetadataValue::OptionalText(None),
+            MetadataValue::Slot(slot),
+            MetadataValue::Slot(Slot::empty()),
+            MetadataValue::Boolean(true),
+            MetadataValue::Rotations(1.0, -2.0, 3.5),
+            MetadataValue::Position(Position::from_xyz(-5, 64, 1000)),
+            MetadataValue::OptionalPosition(Some(Position::from_xyz(1, -60, -2))),
+            MetadataValue::OptionalPosition(None),
+            MetadataValue::Direction(3),
+            MetadataValue::OptionalUuid(Some(uuid::Uuid::from_u128(0x1234_5678))),
+            MetadataValue::OptionalUuid(None),
+            MetadataValue::BlockState(86),
+            MetadataValue::OptionalBlockState(Some(1)),
+            MetadataValue::OptionalBlockState(None),
+            MetadataValue::Nbt(nbt_int("count", 7)),
+            MetadataValue::VillagerData(2, 5, 3),
+            MetadataValue::OptionalVarInt(Some(0)),
+            MetadataValue::OptionalVarInt(None),
+            MetadataValue::Pose(Pose::Crouching),
+            MetadataValue::CatVariant(1),
+            MetadataValue::CowVariant(2),
+            MetadataValue::WolfVariant(3),
+            MetadataValue::WolfSoundVariant(4),
+            MetadataValue::FrogVariant(5),
+            MetadataValue::PigVariant(6),
+            MetadataValue::ChickenVariant(7),
+            MetadataValue::OptionalGlobalPosition(Some((
+                "minecraft:the_nether".to_string(),
+                Position::from_xyz(8, 70, -8),
+            ))),
+            MetadataValue::OptionalGlobalPosition(None),
+            MetadataValue::PaintingVariant(0),
+            MetadataValue::SnifferState(2),
+            MetadataValue::ArmadilloState(1),
+            MetadataValue::Vector3(0.5, 1.0, -0.5),
+            MetadataValue::Quaternion(0.0, 0.0, 0.0, 1.0),
+        ];
+        for value in values {
+            let metadata = EntityMetadata::new().with(4, value.clone());
+            let bytes = encode(&metadata);
+            assert_eq!(bytes[0], 4);
+            assert_eq!(bytes.last(), Some(&END), "{:?}", value);
+            let (decoded, rest) = decode(&bytes);
+            assert_eq!(decoded, metadata, "{:?}", value);
+            assert!(rest.is_empty(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn byte_layout() {
+        let metadata = EntityMetadata::new()
+            .with(index::LIVING_HEALTH, MetadataValue::Float(20.0))
+            .with(index::FLAGS, MetadataValue::Byte(flags::INVISIBLE as i8))
+            .with(index::POSE, MetadataValue::Pose(Pose::Sleeping));
+        assert_eq!(
+            encode(&metadata),
+            vec![
+                0x00, 0x00, 0x20, // 标志位
+                0x06, 0x15, 0x02, // 姿势
+                0x09, 0x03, 0x41, 0xA0, 0x00, 0x00, // 生命值
+                0xFF,
+            ]
+        );
+
+        // 可选值与 VarLong 的编码
+        let bytes = |value: MetadataValue| encode(&EntityMetadata::new().with(0, value));
+        assert_eq!(
+            bytes(MetadataValue::VarLong(300)),
+            vec![0, 2, 0xAC, 0x02, 0xFF]
+        );
+        assert_eq!(
+            bytes(MetadataValue::OptionalVarInt(None)),
+            vec![0, 20, 0, 0xFF]
+        );
+        assert_eq!(
+            bytes(MetadataValue::OptionalVarInt(Some(4))),
+            vec![0, 20, 5, 0xFF]
+        );
+        assert_eq!(
+            bytes(MetadataValue::OptionalBlockState(None)),
+            vec![0, 15, 0, 0xFF]
+        );
+        assert_eq!(
+            bytes(MetadataValue::PaintingVariant(2)),
+            vec![0, 30, 3, 0xFF]
+        );
+    }
+
+    #[test]
+    fn terminator_ends_the_list() {
+        assert_eq!(encode(&EntityMetadata::new()), vec![0xFF]);
+        let (decoded, rest) = decode(&[0xFF, 0x01, 0x02]);
+        assert!(decoded.is_empty());
+        assert_eq!(rest, vec![0x01, 0x02]);
+
+        // 无法解析的值(粒子)之后的项被丢弃
+        let (decoded, _) = decode(&[0x00, 0x00, 0x01, 0x05, 0x11, 0x00, 0x06, 0x15, 0x00, 0xFF]);
+        assert_eq!(decoded.entries, vec![(0, MetadataValue::Byte(1))]);
+    }
+}
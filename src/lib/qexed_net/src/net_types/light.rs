@@ -1,8 +1,7 @@
 use crate::{
-    net_types::{bitset::Bitset, subdata::Subdata, var_int::VarInt},
+    net_types::{bitset::Bitset, subdata::Subdata},
     packet::{decode::PacketReader, encode::PacketWriter},
 };
-use serde::{Deserialize, Serialize};
 #[derive(Debug, Default, PartialEq)]
 pub struct Light {
     pub sky_light_mask: Bitset,
@@ -6,4 +6,6 @@ pub mod chunk;
 pub mod heightmap;
 pub mod block_entities;
 pub mod light;
-pub mod bitset;
\ No newline at end of file
+pub mod bitset;
+pub mod entity_metadata;
+pub mod slot;
\ No newline at end of file
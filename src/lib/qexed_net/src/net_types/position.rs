@@ -3,12 +3,26 @@ use crate::{
     packet::{decode::PacketReader, encode::PacketWriter},
 };
 use serde::{Deserialize, Serialize};
-#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Position {
     x: i32,
     y: i32,
     z: i32,
 }
+impl Position {
+    pub fn from_xyz(x: i32, y: i32, z: i32) -> Self {
+        Position { x, y, z }
+    }
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+    pub fn z(&self) -> i32 {
+        self.z
+    }
+}
 impl Subdata for Position {
     fn new() -> Self {
         Position { x: 0, y: 0, z: 0 }
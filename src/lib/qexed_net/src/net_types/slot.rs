@@ -0,0 +1,553 @@
+//! 物品堆
+//!
+//! 1.21 起物品的附加数据是结构化的数据组件:物品堆带有一个相对于物品默认组件的补丁,
+//! 由新增(或覆盖)的组件与被移除的组件类型组成。
+//!
+//! - 服务端发出的物品([`Slot`] 的 [`Subdata`] 实现)组件不带长度,只有实现了的组件能解析;
+//! - 客户端发来的物品(创造模式物品栏)每个组件带长度前缀,未实现的组件以原始字节保留;
+//! - 物品栏点击中客户端只发送组件的哈希,见 [`HashedSlot`]。
+
+use bytes::{Buf, BytesMut};
+use crab_nbt::Nbt;
+
+use crate::{
+    net_types::{subdata::Subdata, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// data_component_type 注册表中的下标
+pub mod component {
+    pub const CUSTOM_DATA: i32 = 0;
+    pub const MAX_STACK_SIZE: i32 = 1;
+    pub const MAX_DAMAGE: i32 = 2;
+    pub const DAMAGE: i32 = 3;
+    pub const UNBREAKABLE: i32 = 4;
+    pub const CUSTOM_NAME: i32 = 5;
+    pub const ITEM_NAME: i32 = 6;
+    pub const ITEM_MODEL: i32 = 7;
+    pub const LORE: i32 = 8;
+    pub const RARITY: i32 = 9;
+    pub const ENCHANTMENTS: i32 = 10;
+    pub const REPAIR_COST: i32 = 16;
+    pub const CREATIVE_SLOT_LOCK: i32 = 17;
+    pub const ENCHANTMENT_GLINT_OVERRIDE: i32 = 18;
+}
+
+/// 物品稀有度,决定名字的颜色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+}
+
+impl Rarity {
+    pub fn id(self) -> i32 {
+        self as i32
+    }
+
+    pub fn from_id(id: i32) -> Option<Rarity> {
+        [Rarity::Common, Rarity::Uncommon, Rarity::Rare, Rarity::Epic]
+            .get(usize::try_from(id).ok()?)
+            .copied()
+    }
+}
+
+/// 一个数据组件
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataComponent {
+    CustomData(Nbt),
+    MaxStackSize(i32),
+    MaxDamage(i32),
+    Damage(i32),
+    Unbreakable,
+    /// 文本组件
+    CustomName(Nbt),
+    ItemName(Nbt),
+    ItemModel(String),
+    Lore(Vec<Nbt>),
+    Rarity(Rarity),
+    /// 魔咒注册表下标与等级
+    Enchantments(Vec<(i32, i32)>),
+    RepairCost(i32),
+    CreativeSlotLock,
+    EnchantmentGlintOverride(bool),
+    /// 未实现的组件,保存编码后的数据
+    Raw {
+        id: i32,
+        data: Vec<u8>,
+    },
+}
+
+fn read_nbt(r: &mut PacketReader) -> Option<Nbt> {
+    Nbt::read_unnamed(&mut *r.buf).ok()
+}
+
+impl DataComponent {
+    pub fn id(&self) -> i32 {
+        match self {
+            DataComponent::CustomData(_) => component::CUSTOM_DATA,
+            DataComponent::MaxStackSize(_) => component::MAX_STACK_SIZE,
+            DataComponent::MaxDamage(_) => component::MAX_DAMAGE,
+            DataComponent::Damage(_) => component::DAMAGE,
+            DataComponent::Unbreakable => component::UNBREAKABLE,
+            DataComponent::CustomName(_) => component::CUSTOM_NAME,
+            DataComponent::ItemName(_) => component::ITEM_NAME,
+            DataComponent::ItemModel(_) => component::ITEM_MODEL,
+            DataComponent::Lore(_) => component::LORE,
+            DataComponent::Rarity(_) => component::RARITY,
+            DataComponent::Enchantments(_) => component::ENCHANTMENTS,
+            DataComponent::RepairCost(_) => component::REPAIR_COST,
+            DataComponent::CreativeSlotLock => component::CREATIVE_SLOT_LOCK,
+            DataComponent::EnchantmentGlintOverride(_) => component::ENCHANTMENT_GLINT_OVERRIDE,
+            DataComponent::Raw { id, .. } => *id,
+        }
+    }
+
+    /// 写入组件的值(不含类型)
+    fn write_value(&self, w: &mut PacketWriter) {
+        match self {
+            DataComponent::CustomData(v)
+            | DataComponent::CustomName(v)
+            | DataComponent::ItemName(v) => w.serialize(v),
+            DataComponent::MaxStackSize(v)
+            | DataComponent::MaxDamage(v)
+            | DataComponent::Damage(v)
+            | DataComponent::RepairCost(v) => w.varint(&VarInt(*v)),
+            DataComponent::Unbreakable | DataComponent::CreativeSlotLock => {}
+            DataComponent::ItemModel(v) => w.string(v),
+            DataComponent::Lore(lines) => {
+                w.varint(&VarInt(lines.len() as i32));
+                for line in lines {
+                    w.serialize(line);
+                }
+            }
+            DataComponent::Rarity(v) => w.varint(&VarInt(v.id())),
+            DataComponent::Enchantments(v) => {
+                w.varint(&VarInt(v.len() as i32));
+                for (id, level) in v {
+                    w.varint(&VarInt(*id));
+                    w.varint(&VarInt(*level));
+                }
+            }
+            DataComponent::EnchantmentGlintOverride(v) => w.bool(*v),
+            DataComponent::Raw { data, .. } => w.byte_all(data.clone()),
+        }
+    }
+
+    /// 读取 `id` 类型组件的值,未实现的类型或数据无效时返回 `None`
+    fn read_value(id: i32, r: &mut PacketReader) -> Option<Self> {
+        Some(match id {
+            component::CUSTOM_DATA => DataComponent::CustomData(read_nbt(r)?),
+            component::MAX_STACK_SIZE => DataComponent::MaxStackSize(r.varint().0),
+            component::MAX_DAMAGE => DataComponent::MaxDamage(r.varint().0),
+            component::DAMAGE => DataComponent::Damage(r.varint().0),
+            component::UNBREAKABLE => DataComponent::Unbreakable,
+            component::CUSTOM_NAME => DataComponent::CustomName(read_nbt(r)?),
+            component::ITEM_NAME => DataComponent::ItemName(read_nbt(r)?),
+            component::ITEM_MODEL => DataComponent::ItemModel(r.string()),
+            component::LORE => {
+                let len = r.varint().0;
+                let mut lines = Vec::new();
+                for _ in 0..len {
+                    lines.push(read_nbt(r)?);
+                }
+                DataComponent::Lore(lines)
+            }
+            component::RARITY => DataComponent::Rarity(Rarity::from_id(r.varint().0)?),
+            component::ENCHANTMENTS => {
+                let len = r.varint().0;
+                let mut enchantments = Vec::new();
+                for _ in 0..len {
+                    enchantments.push((r.varint().0, r.varint().0));
+                }
+                DataComponent::Enchantments(enchantments)
+            }
+            component::REPAIR_COST => DataComponent::RepairCost(r.varint().0),
+            component::CREATIVE_SLOT_LOCK => DataComponent::CreativeSlotLock,
+            component::ENCHANTMENT_GLINT_OVERRIDE => {
+                DataComponent::EnchantmentGlintOverride(r.bool())
+            }
+            _ => return None,
+        })
+    }
+
+    /// 从带长度前缀的数据读取,未实现的类型保留原始字节
+    fn read_delimited(id: i32, r: &mut PacketReader) -> Option<Self> {
+        let len = usize::try_from(r.varint().0).ok()?;
+        if r.buf.remaining() < len {
+            return None;
+        }
+        let data = r.buf.copy_to_bytes(len).to_vec();
+        let mut slice = data.as_slice();
+        let parsed = DataComponent::read_value(id, &mut PacketReader::new(Box::new(&mut slice)));
+        match parsed {
+            Some(component) if slice.is_empty() => Some(component),
+            _ => Some(DataComponent::Raw { id, data }),
+        }
+    }
+}
+
+/// 物品栏中的一格物品,数量为 0 时表示空
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Slot {
+    pub count: i32,
+    /// item 注册表中的下标
+    pub item_id: i32,
+    /// 新增或覆盖默认值的组件
+    pub added: Vec<DataComponent>,
+    /// 被移除的默认组件类型
+    pub removed: Vec<i32>,
+}
+
+impl Slot {
+    pub fn new(item_id: i32, count: i32) -> Self {
+        Slot {
+            count,
+            item_id,
+            added: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count <= 0
+    }
+
+    /// 数量改为 `count` 的副本
+    pub fn with_count(&self, count: i32) -> Self {
+        Slot {
+            count,
+            ..self.clone()
+        }
+    }
+
+    /// 物品与组件都相同,可以堆叠在一起
+    pub fn is_same_item(&self, other: &Slot) -> bool {
+        self.item_id == other.item_id && self.added == other.added && self.removed == other.removed
+    }
+
+    pub fn component(&self, id: i32) -> Option<&DataComponent> {
+        self.added.iter().find(|c| c.id() == id)
+    }
+
+    /// 设置组件,覆盖同类型的组件
+    pub fn set_component(&mut self, component: DataComponent) {
+        let id = component.id();
+        self.removed.retain(|r| *r != id);
+        match self.added.iter_mut().find(|c| c.id() == id) {
+            Some(c) => *c = component,
+            None => self.added.push(component),
+        }
+    }
+
+    /// 移除组件,包括物品的默认组件
+    pub fn remove_component(&mut self, id: i32) {
+        self.added.retain(|c| c.id() != id);
+        if !self.removed.contains(&id) {
+            self.removed.push(id);
+        }
+    }
+
+    /// 写入组件补丁,`delimited` 时每个组件带长度前缀
+    pub fn write_components(&self, w: &mut PacketWriter, delimited: bool) {
+        w.varint(&VarInt(self.added.len() as i32));
+        w.varint(&VarInt(self.removed.len() as i32));
+        for component in &self.added {
+            w.varint(&VarInt(component.id()));
+            if delimited {
+                let mut buf = BytesMut::new();
+                component.write_value(&mut PacketWriter::new(&mut buf));
+                w.varint(&VarInt(buf.len() as i32));
+                w.byte_all(buf.to_vec());
+            } else {
+                component.write_value(w);
+            }
+        }
+        for id in &self.removed {
+            w.varint(&VarInt(*id));
+        }
+    }
+
+    /// 读取组件补丁,遇到无法解析的组件时返回 `None`
+    pub fn read_components(&mut self, r: &mut PacketReader, delimited: bool) -> Option<()> {
+        let added = r.varint().0;
+        let removed = r.varint().0;
+        if added < 0 || removed < 0 {
+            return None;
+        }
+        self.added.clear();
+        self.removed.clear();
+        for _ in 0..added {
+            let id = r.varint().0;
+            self.added.push(if delimited {
+                DataComponent::read_delimited(id, r)?
+            } else {
+                DataComponent::read_value(id, r)?
+            });
+        }
+        for _ in 0..removed {
+            self.removed.push(r.varint().0);
+        }
+        Some(())
+    }
+
+    fn read_with(r: &mut PacketReader, delimited: bool) -> Option<Self> {
+        let count = r.varint().0;
+        if count <= 0 {
+            return Some(Slot::empty());
+        }
+        let mut slot = Slot::new(r.varint().0, count);
+        slot.read_components(r, delimited)?;
+        Some(slot)
+    }
+
+    fn write_with(&self, w: &mut PacketWriter, delimited: bool) {
+        if self.is_empty() {
+            w.varint(&VarInt(0));
+            return;
+        }
+        w.varint(&VarInt(self.count));
+        w.varint(&VarInt(self.item_id));
+        self.write_components(w, delimited);
+    }
+
+    /// 读取服务端格式的物品,带有未实现的组件时返回 `None`
+    pub fn read(r: &mut PacketReader) -> Option<Self> {
+        Slot::read_with(r, false)
+    }
+
+    /// 读取客户端发来的、组件带长度前缀的物品
+    pub fn read_delimited(r: &mut PacketReader) -> Option<Self> {
+        Slot::read_with(r, true)
+    }
+
+    pub fn write_delimited(&self, w: &mut PacketWriter) {
+        self.write_with(w, true)
+    }
+}
+
+impl Subdata for Slot {
+    fn new() -> Self {
+        Slot::empty()
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        self.write_with(w, false)
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        *self = Slot::read(r).unwrap_or_default();
+    }
+}
+
+/// 物品栏点击中客户端预测的物品:组件只有类型与哈希,服务端据此与已知的物品比对
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HashedSlot {
+    /// 为 0 时表示空
+    pub count: i32,
+    pub item_id: i32,
+    /// 新增组件的类型与 CRC32C 哈希
+    pub added: Vec<(i32, i32)>,
+    pub removed: Vec<i32>,
+}
+
+impl HashedSlot {
+    pub fn is_empty(&self) -> bool {
+        self.count <= 0
+    }
+
+    /// 组件类型与 `slot` 一致(不校验哈希)
+    pub fn matches(&self, slot: &Slot) -> bool {
+        let mut added: Vec<i32> = self.added.iter().map(|(id, _)| *id).collect();
+        let mut known: Vec<i32> = slot.added.iter().map(DataComponent::id).collect();
+        let (mut removed, mut known_removed) = (self.removed.clone(), slot.removed.clone());
+        added.sort_unstable();
+        known.sort_unstable();
+        removed.sort_unstable();
+        known_removed.sort_unstable();
+        self.item_id == slot.item_id && added == known && removed == known_removed
+    }
+}
+
+impl Subdata for HashedSlot {
+    fn new() -> Self {
+        HashedSlot::default()
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.bool(!self.is_empty());
+        if self.is_empty() {
+            return;
+        }
+        w.varint(&VarInt(self.item_id));
+        w.varint(&VarInt(self.count));
+        w.varint(&VarInt(self.added.len() as i32));
+        for (id, hash) in &self.added {
+            w.varint(&VarInt(*id));
+            w.i32(*hash);
+        }
+        w.varint(&VarInt(self.removed.len() as i32));
+        for id in &self.removed {
+            w.varint(&VarInt(*id));
+        }
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        *self = HashedSlot::default();
+        if !r.bool() {
+            return;
+        }
+        self.item_id = r.varint().0;
+        self.count = r.varint().0;
+        for _ in 0..r.varint().0 {
+            self.added.push((r.varint().0, r.i32()));
+        }
+        for _ in 0..r.varint().0 {
+            self.removed.push(r.varint().0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crab_nbt::NbtCompound;
+
+    use super::*;
+
+    fn encode(f: impl FnOnce(&mut PacketWriter)) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        f(&mut PacketWriter::new(&mut buf));
+        buf.to_vec()
+    }
+
+    /// 解码并确认所有字节都被读完
+    fn decode<T>(bytes: &[u8], f: impl FnOnce(&mut PacketReader) -> T) -> T {
+        let mut slice = bytes;
+        let value = f(&mut PacketReader::new(Box::new(&mut slice)));
+        assert!(slice.is_empty(), "剩余 {} 字节", slice.len());
+        value
+    }
+
+    fn text(text: &str) -> Nbt {
+        let mut compound = NbtCompound::new();
+        compound.put("text".to_string(), text.to_string());
+        Nbt::new(String::new(), compound)
+    }
+
+    fn enchanted_sword() -> Slot {
+        let mut inner = NbtCompound::new();
+        inner.put("level".to_string(), 3);
+        let mut custom = NbtCompound::new();
+        custom.put("owner".to_string(), "qexed".to_string());
+        custom.put("stats".to_string(), inner);
+
+        let mut slot = Slot::new(850, 1);
+        slot.set_component(DataComponent::CustomData(Nbt::new(String::new(), custom)));
+        slot.set_component(DataComponent::Lore(vec![text("第一行"), text("第二行")]));
+        slot.set_component(DataComponent::Enchantments(vec![(12, 5), (3, 1)]));
+        slot.set_component(DataComponent::Rarity(Rarity::Epic));
+        slot.set_component(DataComponent::Unbreakable);
+        slot.remove_component(component::REPAIR_COST);
+        slot.remove_component(component::MAX_DAMAGE);
+        slot
+    }
+
+    #[test]
+    fn empty_slot() {
+        let bytes = encode(|w| w.serialize(&Slot::empty()));
+        assert_eq!(bytes, vec![0]);
+        assert_eq!(decode(&bytes, Slot::read), Some(Slot::empty()));
+        // 数量不为正时不再有后续字段
+        assert_eq!(encode(|w| w.serialize(&Slot::new(5, 0))), vec![0]);
+        assert_eq!(decode(&[0], Slot::read_delimited), Some(Slot::empty()));
+    }
+
+    #[test]
+    fn added_and_removed_components() {
+        let mut slot = Slot::new(1, 64);
+        slot.set_component(DataComponent::Damage(5));
+        slot.set_component(DataComponent::EnchantmentGlintOverride(true));
+        slot.remove_component(component::RARITY);
+        let bytes = encode(|w| w.serialize(&slot));
+        assert_eq!(
+            bytes,
+            vec![
+                64, 1, // 数量与物品
+                2, 1, // 新增与移除的组件数
+                3, 5, // damage
+                18, 1, // enchantment_glint_override
+                9, // 移除 rarity
+            ]
+        );
+        assert_eq!(decode(&bytes, Slot::read), Some(slot.clone()));
+
+        // 重新设置被移除的组件会把它从移除列表中拿掉
+        slot.set_component(DataComponent::Rarity(Rarity::Rare));
+        assert!(slot.removed.is_empty());
+        slot.remove_component(component::DAMAGE);
+        assert_eq!(slot.removed, vec![component::DAMAGE]);
+        assert!(slot.component(component::DAMAGE).is_none());
+    }
+
+    #[test]
+    fn nested_components_round_trip() {
+        let slot = enchanted_sword();
+        let bytes = encode(|w| w.serialize(&slot));
+        assert_eq!(decode(&bytes, Slot::read), Some(slot.clone()));
+
+        let delimited = encode(|w| slot.write_delimited(w));
+        assert_eq!(decode(&delimited, Slot::read_delimited), Some(slot));
+    }
+
+    #[test]
+    fn unknown_components() {
+        // 服务端格式无法跳过未实现的组件
+        let unknown = encode(|w| {
+            w.varint(&VarInt(1));
+            w.varint(&VarInt(1));
+            w.varint(&VarInt(1));
+            w.varint(&VarInt(0));
+            w.varint(&VarInt(60));
+            w.u8(7);
+        });
+        let mut slice = unknown.as_slice();
+        assert_eq!(
+            Slot::read(&mut PacketReader::new(Box::new(&mut slice))),
+            None
+        );
+
+        // 带长度前缀时保留原始字节并原样写回
+        let mut slot = Slot::new(1, 1);
+        slot.added.push(DataComponent::Raw {
+            id: 60,
+            data: vec![7, 8, 9],
+        });
+        let bytes = encode(|w| slot.write_delimited(w));
+        assert_eq!(bytes, vec![1, 1, 1, 0, 60, 3, 7, 8, 9]);
+        assert_eq!(decode(&bytes, Slot::read_delimited), Some(slot));
+    }
+
+    #[test]
+    fn hashed_slot_round_trip() {
+        let hashed = HashedSlot {
+            count: 1,
+            item_id: 850,
+            added: vec![(0, -12345), (8, 42)],
+            removed: vec![16],
+        };
+        let bytes = encode(|w| w.serialize(&hashed));
+        assert_eq!(decode(&bytes, |r| r.deserialize::<HashedSlot>()), hashed);
+        assert_eq!(encode(|w| w.serialize(&HashedSlot::default())), vec![0]);
+
+        let mut slot = Slot::new(850, 1);
+        slot.set_component(DataComponent::CustomData(text("x")));
+        slot.set_component(DataComponent::Lore(vec![]));
+        slot.remove_component(16);
+        assert!(hashed.matches(&slot));
+        slot.remove_component(component::LORE);
+        assert!(!hashed.matches(&slot));
+    }
+}
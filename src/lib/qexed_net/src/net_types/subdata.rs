@@ -1,5 +1,3 @@
-use std::ops::Sub;
-
 use crab_nbt::Nbt;
 
 use crate::{
@@ -149,7 +147,7 @@ impl Subdata for String {
         "".to_owned()
     }
     fn serialize(&self, w: &mut PacketWriter) {
-        w.string(&self);
+        w.string(self);
     }
 
     fn deserialize(&mut self, r: &mut PacketReader) {
@@ -161,7 +159,7 @@ impl Subdata for serde_json::Value {
         serde_json::Value::Null
     }
     fn serialize(&self, w: &mut PacketWriter) {
-        w.json(&self);
+        w.json(self);
     }
 
     fn deserialize(&mut self, r: &mut PacketReader) {
@@ -173,7 +171,7 @@ impl Subdata for uuid::Uuid {
         uuid::Uuid::nil()
     }
     fn serialize(&self, w: &mut PacketWriter) {
-        w.uuid(&self);
+        w.uuid(self);
     }
 
     fn deserialize(&mut self, r: &mut PacketReader) {
@@ -185,7 +183,7 @@ impl Subdata for VarInt {
         net_types::var_int::VarInt(0)
     }
     fn serialize(&self, w: &mut PacketWriter) {
-        w.varint(&self);
+        w.varint(self);
     }
 
     fn deserialize(&mut self, r: &mut PacketReader) {
@@ -56,14 +56,14 @@ impl<'a> PacketReader<'a> {
     pub fn option_string(&mut self)->Option<String> {
         let is_have = self.bool();
         if is_have {
-            return Some(self.string());
+            Some(self.string())
         } else {
-            return None;
+            None
         }
     }
     pub fn json(&mut self) ->serde_json::Value{
         let word = self.string();
-        return serde_json::json!(word)
+        serde_json::json!(word)
     }
     pub fn uuid(&mut self) -> uuid::Uuid {
         // 创建 16 字节数组
@@ -73,17 +73,14 @@ impl<'a> PacketReader<'a> {
     }
     pub fn varint(&mut self) -> VarInt {
         let mut value = 0;
-        let mut position = 0;
         
-        for _ in 0..5 {
+        for position in 0..5 {
             let byte = self.buf.get_u8();
             value |= (byte as i32 & 0x7F) << (7 * position);
             
             if (byte & 0x80) == 0 {
                 return VarInt(value);
             }
-            
-            position += 1;
         }
         
         panic!("Invalid VarInt");
@@ -92,16 +89,15 @@ impl<'a> PacketReader<'a> {
     pub fn fixed_bytes<const N: usize>(&mut self) -> [u8; N] {
         // 检查是否有足够的数据
         let mut result = [0u8; N];
-        for i in 0..N {
-            result[i] = self.u8();
+        for byte in &mut result {
+            *byte = self.u8();
         }
         result
     }
     pub fn vec<T:Subdata>(&mut self)-> Vec<T>{
         let len = self.varint().0 as usize; // 获取 VarInt 的值
         // 清空现有属性并预分配空间
-        let mut value: Vec<T> = vec![];
-        value.reserve(len);
+        let mut value: Vec<T> = Vec::with_capacity(len);
         // 遍历读取每个属性
         for _ in 0..len {
             let mut a = T::new();
@@ -122,6 +118,6 @@ impl<'a> PacketReader<'a> {
         }
         let mut v =  T::new();
         v.deserialize(self);
-        return Some(v)
+        Some(v)
     }
 }
\ No newline at end of file
@@ -89,8 +89,8 @@ impl<'a> PacketWriter<'a> {
     /// 读取固定长度的字节数组
     pub fn fixed_bytes<const N: usize>(&mut self,value:&[u8; N]) {
         // 检查是否有足够的数据
-        for i in 0..N {
-            self.u8(value[i]);
+        for byte in value {
+            self.u8(*byte);
         }
     }
     pub fn vec<T:Subdata>(&mut self,value: &Vec<T>){
@@ -0,0 +1,86 @@
+use crate::{
+    net_types::{packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 生成实体
+///
+/// 视角为 1/256 圈的角度,速度以 1/8000 格每刻为单位。
+#[derive(Debug, Default, PartialEq)]
+pub struct AddEntity {
+    pub entity_id: VarInt,
+    pub uuid: uuid::Uuid,
+    /// entity_type 注册表中的下标
+    pub entity_type: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub pitch: u8,
+    pub yaw: u8,
+    pub head_yaw: u8,
+    /// 与实体类型有关的附加数据
+    pub data: VarInt,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+impl AddEntity {
+    /// entity_type 注册表中玩家的下标
+    pub const PLAYER: i32 = 148;
+
+    pub fn new() -> Self {
+        AddEntity {
+            entity_id: VarInt(0),
+            uuid: uuid::Uuid::nil(),
+            entity_type: VarInt(0),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            pitch: 0,
+            yaw: 0,
+            head_yaw: 0,
+            data: VarInt(0),
+            velocity_x: 0,
+            velocity_y: 0,
+            velocity_z: 0,
+        }
+    }
+}
+impl Packet for AddEntity {
+    fn id(&self) -> u32 {
+        0x01
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.entity_id);
+        w.serialize(&self.uuid);
+        w.serialize(&self.entity_type);
+        w.serialize(&self.x);
+        w.serialize(&self.y);
+        w.serialize(&self.z);
+        w.serialize(&self.pitch);
+        w.serialize(&self.yaw);
+        w.serialize(&self.head_yaw);
+        w.serialize(&self.data);
+        w.serialize(&self.velocity_x);
+        w.serialize(&self.velocity_y);
+        w.serialize(&self.velocity_z);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.entity_id = r.deserialize();
+        self.uuid = r.deserialize();
+        self.entity_type = r.deserialize();
+        self.x = r.deserialize();
+        self.y = r.deserialize();
+        self.z = r.deserialize();
+        self.pitch = r.deserialize();
+        self.yaw = r.deserialize();
+        self.head_yaw = r.deserialize();
+        self.data = r.deserialize();
+        self.velocity_x = r.deserialize();
+        self.velocity_y = r.deserialize();
+        self.velocity_z = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{
+    net_types::{packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 确认客户端预测的方块变化已经处理,客户端之后以服务端发送的方块为准
+#[derive(Debug, Default, PartialEq)]
+pub struct BlockChangedAck {
+    pub sequence: VarInt,
+}
+impl BlockChangedAck {
+    pub fn new() -> Self {
+        BlockChangedAck {
+            sequence: VarInt(0),
+        }
+    }
+}
+impl Packet for BlockChangedAck {
+    fn id(&self) -> u32 {
+        0x04
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.sequence);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.sequence = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
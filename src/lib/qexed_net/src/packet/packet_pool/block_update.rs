@@ -0,0 +1,36 @@
+use crate::{
+    net_types::{packet::Packet, position::Position, subdata::Subdata, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 单个方块变化
+#[derive(Debug, Default, PartialEq)]
+pub struct BlockUpdate {
+    pub location: Position,
+    /// 新的方块状态ID
+    pub block_id: VarInt,
+}
+impl BlockUpdate {
+    pub fn new() -> Self {
+        BlockUpdate {
+            location: Position::new(),
+            block_id: VarInt(0),
+        }
+    }
+}
+impl Packet for BlockUpdate {
+    fn id(&self) -> u32 {
+        0x08
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.location);
+        w.serialize(&self.block_id);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.location = r.deserialize();
+        self.block_id = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     net_types::{
-        self, chunk::Chunk, light::Light, packet::Packet, subdata::Subdata, var_int::VarInt,
+        self, packet::Packet, subdata::Subdata, var_int::VarInt,
     },
     packet::{decode::PacketReader, encode::PacketWriter},
 };
@@ -104,6 +104,7 @@ impl Packet for PlayerChatStC {
     }
 }
 */
+#[allow(dead_code)]
 #[derive(Debug, Default, PartialEq)]
 pub struct UnknownPlayerChat{
     pub message_id:VarInt,
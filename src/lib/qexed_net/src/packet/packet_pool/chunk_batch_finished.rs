@@ -0,0 +1,31 @@
+use crate::{
+    net_types::{packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 区块批次结束,告知客户端本批次发送的区块数量
+#[derive(Debug, Default, PartialEq)]
+pub struct ChunkBatchFinished {
+    pub batch_size: VarInt,
+}
+impl ChunkBatchFinished {
+    pub fn new() -> Self {
+        ChunkBatchFinished {
+            batch_size: VarInt(0),
+        }
+    }
+}
+impl Packet for ChunkBatchFinished {
+    fn id(&self) -> u32 {
+        0x0B
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.batch_size);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.batch_size = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
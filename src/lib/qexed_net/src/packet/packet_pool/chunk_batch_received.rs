@@ -0,0 +1,31 @@
+use crate::{
+    net_types::packet::Packet,
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 客户端处理完一个区块批次后回复,携带其期望的每 tick 区块数
+#[derive(Debug, Default, PartialEq)]
+pub struct ChunkBatchReceived {
+    pub chunks_per_tick: f32,
+}
+impl ChunkBatchReceived {
+    pub fn new() -> Self {
+        ChunkBatchReceived {
+            chunks_per_tick: 0.0,
+        }
+    }
+}
+impl Packet for ChunkBatchReceived {
+    fn id(&self) -> u32 {
+        0x0A
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.f32(self.chunks_per_tick);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.chunks_per_tick = r.f32();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -3,7 +3,7 @@ use crate::{
     packet::{decode::PacketReader, encode::PacketWriter},
 };
 
-/// 数据包请求开始
+/// 区块批次开始,之后发送的区块属于同一批次,直到 `ChunkBatchFinished`
 #[derive(Debug, Default, PartialEq)]
 pub struct ChunkBatchStart {}
 impl ChunkBatchStart {
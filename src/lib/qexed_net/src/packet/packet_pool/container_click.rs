@@ -0,0 +1,80 @@
+use crate::{
+    net_types::{packet::Packet, slot::HashedSlot, subdata::Subdata, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 由客户端发至服务端,点击容器中的格子
+///
+/// 客户端会先在本地完成点击,再把预测的结果(变化的格子与光标上的物品)发给服务端
+#[derive(Debug, Default, PartialEq)]
+pub struct ContainerClick {
+    pub window_id: VarInt,
+    /// 客户端最后收到的状态ID
+    pub state_id: VarInt,
+    /// -999 表示点击窗口外
+    pub slot: i16,
+    pub button: i8,
+    /// 0 普通点击,1 Shift 点击,2 数字键交换,3 中键,4 丢弃,5 拖动,6 双击
+    pub mode: VarInt,
+    pub changed_slots: Vec<ChangedSlot>,
+    pub carried_item: HashedSlot,
+}
+impl ContainerClick {
+    pub fn new() -> Self {
+        ContainerClick {
+            window_id: VarInt(0),
+            state_id: VarInt(0),
+            slot: 0,
+            button: 0,
+            mode: VarInt(0),
+            changed_slots: vec![],
+            carried_item: HashedSlot::default(),
+        }
+    }
+}
+impl Packet for ContainerClick {
+    fn id(&self) -> u32 {
+        0x11
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.varint(&self.window_id);
+        w.varint(&self.state_id);
+        w.i16(self.slot);
+        w.i8(self.button);
+        w.varint(&self.mode);
+        w.vec(&self.changed_slots);
+        w.serialize(&self.carried_item);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.window_id = r.varint();
+        self.state_id = r.varint();
+        self.slot = r.i16();
+        self.button = r.i8();
+        self.mode = r.varint();
+        self.changed_slots = r.vec();
+        self.carried_item = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// 点击后客户端预测的格子内容
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ChangedSlot {
+    pub slot: i16,
+    pub item: HashedSlot,
+}
+impl Subdata for ChangedSlot {
+    fn new() -> Self {
+        ChangedSlot::default()
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.i16(self.slot);
+        w.serialize(&self.item);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.slot = r.i16();
+        self.item = r.deserialize();
+    }
+}
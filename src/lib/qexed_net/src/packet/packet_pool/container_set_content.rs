@@ -0,0 +1,45 @@
+use crate::{
+    net_types::{packet::Packet, slot::Slot, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 容器的全部内容,玩家物品栏的窗口ID为 0
+#[derive(Debug, Default, PartialEq)]
+pub struct ContainerSetContent {
+    pub window_id: VarInt,
+    /// 客户端在之后的点击中回传,用于判断双方是否同步
+    pub state_id: VarInt,
+    pub slots: Vec<Slot>,
+    /// 光标上拿着的物品
+    pub carried_item: Slot,
+}
+impl ContainerSetContent {
+    pub fn new() -> Self {
+        ContainerSetContent {
+            window_id: VarInt(0),
+            state_id: VarInt(0),
+            slots: vec![],
+            carried_item: Slot::empty(),
+        }
+    }
+}
+impl Packet for ContainerSetContent {
+    fn id(&self) -> u32 {
+        0x12
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.varint(&self.window_id);
+        w.varint(&self.state_id);
+        w.vec(&self.slots);
+        w.serialize(&self.carried_item);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.window_id = r.varint();
+        self.state_id = r.varint();
+        self.slots = r.vec();
+        self.carried_item = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{
+    net_types::{packet::Packet, slot::Slot, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 单个格子的物品变化
+#[derive(Debug, Default, PartialEq)]
+pub struct ContainerSetSlot {
+    pub window_id: VarInt,
+    pub state_id: VarInt,
+    pub slot: i16,
+    pub item: Slot,
+}
+impl ContainerSetSlot {
+    pub fn new() -> Self {
+        ContainerSetSlot {
+            window_id: VarInt(0),
+            state_id: VarInt(0),
+            slot: 0,
+            item: Slot::empty(),
+        }
+    }
+}
+impl Packet for ContainerSetSlot {
+    fn id(&self) -> u32 {
+        0x14
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.varint(&self.window_id);
+        w.varint(&self.state_id);
+        w.i16(self.slot);
+        w.serialize(&self.item);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.window_id = r.varint();
+        self.state_id = r.varint();
+        self.slot = r.i16();
+        self.item = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
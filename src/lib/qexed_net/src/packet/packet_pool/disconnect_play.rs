@@ -1,31 +1,40 @@
 use crate::{
-    net_types::{packet::Packet},
+    net_types::packet::Packet,
     packet::{decode::PacketReader, encode::PacketWriter},
 };
 
-/// 配置阶段取消连接数据包
-/// 由客户端发至服务端
+/// 游戏阶段断开连接数据包
+/// 由服务端发至客户端,`reason` 为文本组件
 #[derive(Debug, Default, PartialEq)]
-pub struct disconnect_play {
-    pub reason:serde_json::Value,
+pub struct DisconnectPlay {
+    pub reason: crab_nbt::Nbt,
 }
-impl disconnect_play {
+impl DisconnectPlay {
     pub fn new() -> Self {
-        disconnect_play {
-            reason:serde_json::Value::Null,
+        DisconnectPlay {
+            reason: crab_nbt::nbt!("", {}),
+        }
+    }
+
+    /// 纯文本的断开原因
+    pub fn text(reason: &str) -> Self {
+        let mut text = crab_nbt::NbtCompound::new();
+        text.put("text".to_string(), reason.to_string());
+        DisconnectPlay {
+            reason: crab_nbt::Nbt::new(String::new(), text),
         }
     }
 }
-impl Packet for disconnect_play {
+impl Packet for DisconnectPlay {
     fn id(&self) -> u32 {
-        0x02
+        0x1C
     }
     fn serialize(&self, w: &mut PacketWriter) {
-        w.json(&self.reason);
+        w.serialize(&self.reason);
     }
 
     fn deserialize(&mut self, r: &mut PacketReader) {
-        self.reason = r.json();
+        self.reason = r.deserialize();
     }
     fn as_any(&self) -> &dyn std::any::Any {
         self
@@ -0,0 +1,67 @@
+use crate::{
+    net_types::{packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 实体的绝对位置,相对移动超出范围或需要校正累计误差时发送
+#[derive(Debug, Default, PartialEq)]
+pub struct EntityPositionSync {
+    pub entity_id: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub velocity_x: f64,
+    pub velocity_y: f64,
+    pub velocity_z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+impl EntityPositionSync {
+    pub fn new() -> Self {
+        EntityPositionSync {
+            entity_id: VarInt(0),
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            velocity_z: 0.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            on_ground: false,
+        }
+    }
+}
+impl Packet for EntityPositionSync {
+    fn id(&self) -> u32 {
+        0x1F
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.entity_id);
+        w.serialize(&self.x);
+        w.serialize(&self.y);
+        w.serialize(&self.z);
+        w.serialize(&self.velocity_x);
+        w.serialize(&self.velocity_y);
+        w.serialize(&self.velocity_z);
+        w.serialize(&self.yaw);
+        w.serialize(&self.pitch);
+        w.serialize(&self.on_ground);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.entity_id = r.deserialize();
+        self.x = r.deserialize();
+        self.y = r.deserialize();
+        self.z = r.deserialize();
+        self.velocity_x = r.deserialize();
+        self.velocity_y = r.deserialize();
+        self.velocity_z = r.deserialize();
+        self.yaw = r.deserialize();
+        self.pitch = r.deserialize();
+        self.on_ground = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
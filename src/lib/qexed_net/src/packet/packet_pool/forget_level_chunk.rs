@@ -0,0 +1,35 @@
+use crate::{
+    net_types::packet::Packet,
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 通知客户端卸载区块(注意协议中 z 在前)
+#[derive(Debug, Default, PartialEq)]
+pub struct ForgetLevelChunk {
+    pub chunk_z: i32,
+    pub chunk_x: i32,
+}
+impl ForgetLevelChunk {
+    pub fn new() -> Self {
+        ForgetLevelChunk {
+            chunk_z: 0,
+            chunk_x: 0,
+        }
+    }
+}
+impl Packet for ForgetLevelChunk {
+    fn id(&self) -> u32 {
+        0x21
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.chunk_z);
+        w.serialize(&self.chunk_x);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.chunk_z = r.deserialize();
+        self.chunk_x = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
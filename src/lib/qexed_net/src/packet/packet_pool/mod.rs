@@ -5,6 +5,7 @@ mod ping;
 mod loginstart;
 mod encryption;
 mod disconnectlogin;
+mod disconnect_play;
 mod login_success;
 mod login_acknowledged;
 mod plugin_message;
@@ -20,12 +21,36 @@ mod player_position;
 mod accept_teleportation;
 mod move_player_pos;
 mod move_player_pos_rot;
+mod move_player_rot;
+mod move_player_status_only;
 mod game_event;
 mod set_chunk_cache_center;
 mod level_chunk_with_light;
 mod keep_alive;
 mod chat_message;
 mod login_compression;
+mod forget_level_chunk;
+mod set_chunk_cache_radius;
+mod chunk_batch_finished;
+mod chunk_batch_received;
+mod update_light;
+mod respawn;
+mod block_update;
+mod add_entity;
+mod remove_entities;
+mod move_entity;
+mod entity_position_sync;
+mod rotate_head;
+mod player_info;
+mod set_entity_data;
+mod container_set_content;
+mod container_set_slot;
+mod set_creative_mode_slot;
+mod container_click;
+mod player_action;
+mod use_item_on;
+mod set_carried_item;
+mod block_changed_ack;
 pub use handshake::Handshake;
 pub use nullpacket::NullPacket;
 pub use status::StatusRequest;
@@ -36,6 +61,7 @@ pub use loginstart::LoginStart;
 pub use encryption::EncryptionRequest;
 pub use encryption::EncryptionResponse;
 pub use disconnectlogin::DisconnectLogin;
+pub use disconnect_play::DisconnectPlay;
 pub use login_success::LoginSuccess;
 pub use login_acknowledged::LoginAcknowledged;
 pub use plugin_message::PluginMessage;
@@ -57,10 +83,40 @@ pub use player_position::PlayerPosition;
 pub use accept_teleportation::AcceptTeleportation;
 pub use move_player_pos::MovePlayerPos;
 pub use move_player_pos_rot::MovePlayerPosRot;
+pub use move_player_rot::MovePlayerRot;
+pub use move_player_status_only::MovePlayerStatusOnly;
 pub use game_event::GameEvent;
 pub use set_chunk_cache_center::SetChunkCacheCenter;
 pub use level_chunk_with_light::LevelChunkWithLight;
 pub use keep_alive::KeepAliveClientPlay;
 pub use keep_alive::KeepAliveServerPlay;
 pub use chat_message::ChatMessageCtS;
-pub use login_compression::LoginCompression;
\ No newline at end of file
+pub use login_compression::LoginCompression;
+pub use forget_level_chunk::ForgetLevelChunk;
+pub use set_chunk_cache_radius::SetChunkCacheRadius;
+pub use chunk_batch_finished::ChunkBatchFinished;
+pub use chunk_batch_received::ChunkBatchReceived;
+pub use update_light::UpdateLight;
+pub use respawn::Respawn;
+pub use block_update::BlockUpdate;
+pub use login_success::Property;
+pub use add_entity::AddEntity;
+pub use remove_entities::RemoveEntities;
+pub use move_entity::MoveEntityPos;
+pub use move_entity::MoveEntityPosRot;
+pub use move_entity::MoveEntityRot;
+pub use entity_position_sync::EntityPositionSync;
+pub use rotate_head::RotateHead;
+pub use player_info::PlayerInfoUpdate;
+pub use player_info::PlayerInfoEntry;
+pub use player_info::PlayerInfoRemove;
+pub use set_entity_data::SetEntityData;
+pub use container_set_content::ContainerSetContent;
+pub use container_set_slot::ContainerSetSlot;
+pub use set_creative_mode_slot::SetCreativeModeSlot;
+pub use container_click::ContainerClick;
+pub use container_click::ChangedSlot;
+pub use player_action::PlayerAction;
+pub use use_item_on::UseItemOn;
+pub use set_carried_item::SetCarriedItem;
+pub use block_changed_ack::BlockChangedAck;
@@ -0,0 +1,138 @@
+use crate::{
+    net_types::{packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 实体相对移动,位移以 1/4096 格为单位
+#[derive(Debug, Default, PartialEq)]
+pub struct MoveEntityPos {
+    pub entity_id: VarInt,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub on_ground: bool,
+}
+impl MoveEntityPos {
+    pub fn new() -> Self {
+        MoveEntityPos {
+            entity_id: VarInt(0),
+            delta_x: 0,
+            delta_y: 0,
+            delta_z: 0,
+            on_ground: false,
+        }
+    }
+}
+impl Packet for MoveEntityPos {
+    fn id(&self) -> u32 {
+        0x2E
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.entity_id);
+        w.serialize(&self.delta_x);
+        w.serialize(&self.delta_y);
+        w.serialize(&self.delta_z);
+        w.serialize(&self.on_ground);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.entity_id = r.deserialize();
+        self.delta_x = r.deserialize();
+        self.delta_y = r.deserialize();
+        self.delta_z = r.deserialize();
+        self.on_ground = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// 实体相对移动并转动视角
+#[derive(Debug, Default, PartialEq)]
+pub struct MoveEntityPosRot {
+    pub entity_id: VarInt,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    /// 1/256 圈的角度
+    pub yaw: u8,
+    pub pitch: u8,
+    pub on_ground: bool,
+}
+impl MoveEntityPosRot {
+    pub fn new() -> Self {
+        MoveEntityPosRot {
+            entity_id: VarInt(0),
+            delta_x: 0,
+            delta_y: 0,
+            delta_z: 0,
+            yaw: 0,
+            pitch: 0,
+            on_ground: false,
+        }
+    }
+}
+impl Packet for MoveEntityPosRot {
+    fn id(&self) -> u32 {
+        0x2F
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.entity_id);
+        w.serialize(&self.delta_x);
+        w.serialize(&self.delta_y);
+        w.serialize(&self.delta_z);
+        w.serialize(&self.yaw);
+        w.serialize(&self.pitch);
+        w.serialize(&self.on_ground);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.entity_id = r.deserialize();
+        self.delta_x = r.deserialize();
+        self.delta_y = r.deserialize();
+        self.delta_z = r.deserialize();
+        self.yaw = r.deserialize();
+        self.pitch = r.deserialize();
+        self.on_ground = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// 实体转动视角
+#[derive(Debug, Default, PartialEq)]
+pub struct MoveEntityRot {
+    pub entity_id: VarInt,
+    pub yaw: u8,
+    pub pitch: u8,
+    pub on_ground: bool,
+}
+impl MoveEntityRot {
+    pub fn new() -> Self {
+        MoveEntityRot {
+            entity_id: VarInt(0),
+            yaw: 0,
+            pitch: 0,
+            on_ground: false,
+        }
+    }
+}
+impl Packet for MoveEntityRot {
+    fn id(&self) -> u32 {
+        0x31
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.entity_id);
+        w.serialize(&self.yaw);
+        w.serialize(&self.pitch);
+        w.serialize(&self.on_ground);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.entity_id = r.deserialize();
+        self.yaw = r.deserialize();
+        self.pitch = r.deserialize();
+        self.on_ground = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{
+    net_types::packet::Packet,
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 玩家只转动视角
+#[derive(Debug, Default, PartialEq)]
+pub struct MovePlayerRot {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub flags: i8,
+}
+impl MovePlayerRot {
+    pub fn new() -> Self {
+        MovePlayerRot {
+            yaw: 0.0,
+            pitch: 0.0,
+            flags: 0,
+        }
+    }
+}
+impl Packet for MovePlayerRot {
+    fn id(&self) -> u32 {
+        0x1F
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.yaw);
+        w.serialize(&self.pitch);
+        w.serialize(&self.flags);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.yaw = r.deserialize();
+        self.pitch = r.deserialize();
+        self.flags = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{
+    net_types::packet::Packet,
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 玩家没有移动也没有转动视角,只更新是否着地
+#[derive(Debug, Default, PartialEq)]
+pub struct MovePlayerStatusOnly {
+    pub flags: i8,
+}
+impl MovePlayerStatusOnly {
+    pub fn new() -> Self {
+        MovePlayerStatusOnly { flags: 0 }
+    }
+}
+impl Packet for MovePlayerStatusOnly {
+    fn id(&self) -> u32 {
+        0x20
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.flags);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.flags = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
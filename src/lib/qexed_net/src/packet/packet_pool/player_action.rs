@@ -0,0 +1,46 @@
+use crate::{
+    net_types::{packet::Packet, position::Position, subdata::Subdata, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 由客户端发至服务端,开始、取消或完成挖掘方块等动作
+#[derive(Debug, Default, PartialEq)]
+pub struct PlayerAction {
+    /// 0:开始挖掘,1:取消挖掘,2:完成挖掘,3:丢弃物品堆,4:丢弃物品,5:使用完物品,6:交换副手
+    pub status: VarInt,
+    pub location: Position,
+    /// 方块的哪个面,0 到 5 依次为下、上、北、南、西、东
+    pub face: i8,
+    /// 方块变化的序号,处理后通过 BlockChangedAck 确认
+    pub sequence: VarInt,
+}
+impl PlayerAction {
+    pub fn new() -> Self {
+        PlayerAction {
+            status: VarInt(0),
+            location: Position::new(),
+            face: 0,
+            sequence: VarInt(0),
+        }
+    }
+}
+impl Packet for PlayerAction {
+    fn id(&self) -> u32 {
+        0x28
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.status);
+        w.serialize(&self.location);
+        w.i8(self.face);
+        w.serialize(&self.sequence);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.status = r.deserialize();
+        self.location = r.deserialize();
+        self.face = r.i8();
+        self.sequence = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
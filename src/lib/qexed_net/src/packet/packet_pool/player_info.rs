@@ -0,0 +1,166 @@
+use crate::{
+    net_types::{packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+use super::login_success::Property;
+
+/// 更新玩家列表,`actions` 中置位的项按顺序写在每个条目中
+#[derive(Debug, Default, PartialEq)]
+pub struct PlayerInfoUpdate {
+    pub actions: u8,
+    pub entries: Vec<PlayerInfoEntry>,
+}
+
+/// 玩家列表中的一个玩家
+#[derive(Debug, Default, PartialEq)]
+pub struct PlayerInfoEntry {
+    pub uuid: uuid::Uuid,
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub game_mode: VarInt,
+    pub listed: bool,
+    /// 延迟(毫秒)
+    pub latency: VarInt,
+    pub display_name: Option<crab_nbt::Nbt>,
+    pub list_priority: VarInt,
+    pub show_hat: bool,
+}
+
+impl PlayerInfoUpdate {
+    pub const ADD_PLAYER: u8 = 0x01;
+    /// 聊天签名会话,暂不支持,总是写入空值
+    pub const INITIALIZE_CHAT: u8 = 0x02;
+    pub const UPDATE_GAME_MODE: u8 = 0x04;
+    pub const UPDATE_LISTED: u8 = 0x08;
+    pub const UPDATE_LATENCY: u8 = 0x10;
+    pub const UPDATE_DISPLAY_NAME: u8 = 0x20;
+    pub const UPDATE_LIST_PRIORITY: u8 = 0x40;
+    pub const UPDATE_HAT: u8 = 0x80;
+
+    pub fn new() -> Self {
+        PlayerInfoUpdate {
+            actions: 0,
+            entries: vec![],
+        }
+    }
+}
+impl PlayerInfoEntry {
+    pub fn new() -> Self {
+        PlayerInfoEntry {
+            uuid: uuid::Uuid::nil(),
+            name: "".to_string(),
+            properties: vec![],
+            game_mode: VarInt(0),
+            listed: false,
+            latency: VarInt(0),
+            display_name: None,
+            list_priority: VarInt(0),
+            show_hat: false,
+        }
+    }
+}
+impl Packet for PlayerInfoUpdate {
+    fn id(&self) -> u32 {
+        0x3F
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        let has = |action: u8| self.actions & action != 0;
+        w.u8(self.actions);
+        w.varint(&VarInt(self.entries.len() as i32));
+        for entry in &self.entries {
+            w.uuid(&entry.uuid);
+            if has(Self::ADD_PLAYER) {
+                w.string(&entry.name);
+                w.vec(&entry.properties);
+            }
+            if has(Self::INITIALIZE_CHAT) {
+                w.bool(false);
+            }
+            if has(Self::UPDATE_GAME_MODE) {
+                w.serialize(&entry.game_mode);
+            }
+            if has(Self::UPDATE_LISTED) {
+                w.bool(entry.listed);
+            }
+            if has(Self::UPDATE_LATENCY) {
+                w.serialize(&entry.latency);
+            }
+            if has(Self::UPDATE_DISPLAY_NAME) {
+                w.option(entry.display_name.as_ref());
+            }
+            if has(Self::UPDATE_LIST_PRIORITY) {
+                w.serialize(&entry.list_priority);
+            }
+            if has(Self::UPDATE_HAT) {
+                w.bool(entry.show_hat);
+            }
+        }
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        let actions = r.u8();
+        self.actions = actions;
+        let has = |action: u8| actions & action != 0;
+        let len = r.varint().0;
+        self.entries = (0..len)
+            .map(|_| {
+                let mut entry = PlayerInfoEntry::new();
+                entry.uuid = r.uuid();
+                if has(Self::ADD_PLAYER) {
+                    entry.name = r.string();
+                    entry.properties = r.vec();
+                }
+                if has(Self::INITIALIZE_CHAT) {
+                    r.bool();
+                }
+                if has(Self::UPDATE_GAME_MODE) {
+                    entry.game_mode = r.deserialize();
+                }
+                if has(Self::UPDATE_LISTED) {
+                    entry.listed = r.bool();
+                }
+                if has(Self::UPDATE_LATENCY) {
+                    entry.latency = r.deserialize();
+                }
+                if has(Self::UPDATE_DISPLAY_NAME) {
+                    entry.display_name = r.option();
+                }
+                if has(Self::UPDATE_LIST_PRIORITY) {
+                    entry.list_priority = r.deserialize();
+                }
+                if has(Self::UPDATE_HAT) {
+                    entry.show_hat = r.bool();
+                }
+                entry
+            })
+            .collect();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// 从玩家列表中移除玩家
+#[derive(Debug, Default, PartialEq)]
+pub struct PlayerInfoRemove {
+    pub uuids: Vec<uuid::Uuid>,
+}
+impl PlayerInfoRemove {
+    pub fn new() -> Self {
+        PlayerInfoRemove { uuids: vec![] }
+    }
+}
+impl Packet for PlayerInfoRemove {
+    fn id(&self) -> u32 {
+        0x3E
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.vec(&self.uuids);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.uuids = r.vec();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{
+    net_types::{packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 移除实体
+#[derive(Debug, Default, PartialEq)]
+pub struct RemoveEntities {
+    pub entity_ids: Vec<VarInt>,
+}
+impl RemoveEntities {
+    pub fn new() -> Self {
+        RemoveEntities { entity_ids: vec![] }
+    }
+}
+impl Packet for RemoveEntities {
+    fn id(&self) -> u32 {
+        0x46
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.vec(&self.entity_ids);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.entity_ids = r.vec();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
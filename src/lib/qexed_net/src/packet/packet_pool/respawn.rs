@@ -0,0 +1,91 @@
+use crate::{
+    net_types::{packet::Packet, position::Position, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 重生/切换维度
+///
+/// 除 `data_kept` 外的字段与 LoginPlay 中的出生信息相同。
+#[derive(Debug, Default, PartialEq)]
+pub struct Respawn {
+    pub dimension_type: VarInt,
+    pub dimension_name: String,
+    pub hashed_seed: i64,
+    pub game_mode: u8,
+    pub previous_game_mode: i8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub has_death_location: bool,
+    pub death_dimension_name: Option<String>,
+    pub death_position: Option<Position>,
+    pub portal_cooldown: VarInt,
+    pub sea_level: VarInt,
+    /// 保留的数据: 0x01 属性, 0x02 实体元数据
+    pub data_kept: u8,
+}
+impl Respawn {
+    /// 保留属性
+    pub const KEEP_ATTRIBUTES: u8 = 0x01;
+    /// 保留实体元数据
+    pub const KEEP_METADATA: u8 = 0x02;
+
+    pub fn new() -> Self {
+        Respawn {
+            dimension_type: VarInt(0),
+            dimension_name: "".to_owned(),
+            hashed_seed: 0,
+            game_mode: 0,
+            previous_game_mode: -1,
+            is_debug: false,
+            is_flat: false,
+            has_death_location: false,
+            death_dimension_name: None,
+            death_position: None,
+            portal_cooldown: VarInt(0),
+            sea_level: VarInt(0),
+            data_kept: 0,
+        }
+    }
+}
+impl Packet for Respawn {
+    fn id(&self) -> u32 {
+        0x4B
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.dimension_type);
+        w.serialize(&self.dimension_name);
+        w.serialize(&self.hashed_seed);
+        w.serialize(&self.game_mode);
+        w.serialize(&self.previous_game_mode);
+        w.serialize(&self.is_debug);
+        w.serialize(&self.is_flat);
+        w.serialize(&self.has_death_location);
+        if self.has_death_location {
+            w.option_string(self.death_dimension_name.as_deref());
+            w.option(self.death_position.as_ref());
+        }
+        w.serialize(&self.portal_cooldown);
+        w.serialize(&self.sea_level);
+        w.serialize(&self.data_kept);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.dimension_type = r.deserialize();
+        self.dimension_name = r.deserialize();
+        self.hashed_seed = r.deserialize();
+        self.game_mode = r.deserialize();
+        self.previous_game_mode = r.deserialize();
+        self.is_debug = r.deserialize();
+        self.is_flat = r.deserialize();
+        self.has_death_location = r.deserialize();
+        if self.has_death_location {
+            self.death_dimension_name = r.option_string();
+            self.death_position = r.option();
+        }
+        self.portal_cooldown = r.deserialize();
+        self.sea_level = r.deserialize();
+        self.data_kept = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,36 @@
+use crate::{
+    net_types::{packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 实体头部朝向
+#[derive(Debug, Default, PartialEq)]
+pub struct RotateHead {
+    pub entity_id: VarInt,
+    /// 1/256 圈的角度
+    pub head_yaw: u8,
+}
+impl RotateHead {
+    pub fn new() -> Self {
+        RotateHead {
+            entity_id: VarInt(0),
+            head_yaw: 0,
+        }
+    }
+}
+impl Packet for RotateHead {
+    fn id(&self) -> u32 {
+        0x4C
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.entity_id);
+        w.serialize(&self.head_yaw);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.entity_id = r.deserialize();
+        self.head_yaw = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
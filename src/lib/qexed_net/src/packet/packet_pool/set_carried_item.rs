@@ -0,0 +1,30 @@
+use crate::{
+    net_types::packet::Packet,
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 由客户端发至服务端,切换手持的快捷栏格子
+#[derive(Debug, Default, PartialEq)]
+pub struct SetCarriedItem {
+    /// 快捷栏格子,0 到 8
+    pub slot: i16,
+}
+impl SetCarriedItem {
+    pub fn new() -> Self {
+        SetCarriedItem { slot: 0 }
+    }
+}
+impl Packet for SetCarriedItem {
+    fn id(&self) -> u32 {
+        0x34
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.i16(self.slot);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.slot = r.i16();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
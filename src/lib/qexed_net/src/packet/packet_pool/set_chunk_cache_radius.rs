@@ -0,0 +1,31 @@
+use crate::{
+    net_types::{packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 通知客户端服务端的视距
+#[derive(Debug, Default, PartialEq)]
+pub struct SetChunkCacheRadius {
+    pub view_distance: VarInt,
+}
+impl SetChunkCacheRadius {
+    pub fn new() -> Self {
+        SetChunkCacheRadius {
+            view_distance: VarInt(0),
+        }
+    }
+}
+impl Packet for SetChunkCacheRadius {
+    fn id(&self) -> u32 {
+        0x58
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.view_distance);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.view_distance = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{
+    net_types::{packet::Packet, slot::Slot},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 由客户端发至服务端,创造模式下直接设置物品栏中的物品
+#[derive(Debug, Default, PartialEq)]
+pub struct SetCreativeModeSlot {
+    /// 玩家物品栏窗口中的格子,-1 表示扔出物品
+    pub slot: i16,
+    /// 无法解析时为 `None`
+    pub item: Option<Slot>,
+}
+impl SetCreativeModeSlot {
+    pub fn new() -> Self {
+        SetCreativeModeSlot {
+            slot: 0,
+            item: Some(Slot::empty()),
+        }
+    }
+}
+impl Packet for SetCreativeModeSlot {
+    fn id(&self) -> u32 {
+        0x37
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.i16(self.slot);
+        self.item.clone().unwrap_or_default().write_delimited(w);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.slot = r.i16();
+        self.item = Slot::read_delimited(r);
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{
+    net_types::{entity_metadata::EntityMetadata, packet::Packet, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 实体元数据,只需要包含发生变化的项
+#[derive(Debug, Default, PartialEq)]
+pub struct SetEntityData {
+    pub entity_id: VarInt,
+    pub metadata: EntityMetadata,
+}
+impl SetEntityData {
+    pub fn new() -> Self {
+        SetEntityData {
+            entity_id: VarInt(0),
+            metadata: EntityMetadata::new(),
+        }
+    }
+}
+impl Packet for SetEntityData {
+    fn id(&self) -> u32 {
+        0x5C
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.entity_id);
+        w.serialize(&self.metadata);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.entity_id = r.deserialize();
+        self.metadata = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{
+    net_types::{light::Light, packet::Packet, subdata::Subdata, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 更新区块中部分段落的光照
+#[derive(Debug, Default, PartialEq)]
+pub struct UpdateLight {
+    pub chunk_x: VarInt,
+    pub chunk_z: VarInt,
+    pub light: Light,
+}
+impl UpdateLight {
+    pub fn new() -> Self {
+        UpdateLight {
+            chunk_x: VarInt(0),
+            chunk_z: VarInt(0),
+            light: Light::new(),
+        }
+    }
+}
+impl Packet for UpdateLight {
+    fn id(&self) -> u32 {
+        0x2A
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.chunk_x);
+        w.serialize(&self.chunk_z);
+        w.serialize(&self.light);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.chunk_x = r.deserialize();
+        self.chunk_z = r.deserialize();
+        self.light = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
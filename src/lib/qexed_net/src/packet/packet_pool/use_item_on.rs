@@ -0,0 +1,70 @@
+use crate::{
+    net_types::{packet::Packet, position::Position, subdata::Subdata, var_int::VarInt},
+    packet::{decode::PacketReader, encode::PacketWriter},
+};
+
+/// 由客户端发至服务端,对着方块使用手中的物品(放置方块等)
+#[derive(Debug, Default, PartialEq)]
+pub struct UseItemOn {
+    /// 0:主手,1:副手
+    pub hand: VarInt,
+    /// 被点击的方块
+    pub location: Position,
+    /// 被点击的面,0 到 5 依次为下、上、北、南、西、东
+    pub face: VarInt,
+    /// 点击位置在方块内的坐标(0 到 1)
+    pub cursor_x: f32,
+    pub cursor_y: f32,
+    pub cursor_z: f32,
+    /// 玩家头部是否在方块内
+    pub inside_block: bool,
+    /// 是否点击到了世界边界
+    pub world_border_hit: bool,
+    /// 方块变化的序号,处理后通过 BlockChangedAck 确认
+    pub sequence: VarInt,
+}
+impl UseItemOn {
+    pub fn new() -> Self {
+        UseItemOn {
+            hand: VarInt(0),
+            location: Position::new(),
+            face: VarInt(0),
+            cursor_x: 0.0,
+            cursor_y: 0.0,
+            cursor_z: 0.0,
+            inside_block: false,
+            world_border_hit: false,
+            sequence: VarInt(0),
+        }
+    }
+}
+impl Packet for UseItemOn {
+    fn id(&self) -> u32 {
+        0x3F
+    }
+    fn serialize(&self, w: &mut PacketWriter) {
+        w.serialize(&self.hand);
+        w.serialize(&self.location);
+        w.serialize(&self.face);
+        w.f32(self.cursor_x);
+        w.f32(self.cursor_y);
+        w.f32(self.cursor_z);
+        w.bool(self.inside_block);
+        w.bool(self.world_border_hit);
+        w.serialize(&self.sequence);
+    }
+    fn deserialize(&mut self, r: &mut PacketReader) {
+        self.hand = r.deserialize();
+        self.location = r.deserialize();
+        self.face = r.deserialize();
+        self.cursor_x = r.f32();
+        self.cursor_y = r.f32();
+        self.cursor_z = r.f32();
+        self.inside_block = r.bool();
+        self.world_border_hit = r.bool();
+        self.sequence = r.deserialize();
+    }
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -7,10 +7,20 @@ pub fn id_to_packet(id: u32) -> Box<dyn Packet> {
     match id {
         0x00 => Box::new(crate::packet::packet_pool::AcceptTeleportation::new()),
         0x08 => Box::new(crate::packet::packet_pool::ChatMessageCtS::new()),
+        0x0A => Box::new(crate::packet::packet_pool::ChunkBatchReceived::new()),
+        // 服务端方向的 0x0C 是 ClientTickEnd,同样没有内容,复用空数据包
         0x0C => Box::new(crate::packet::packet_pool::ChunkBatchStart::new()),
+        0x0D => Box::new(crate::packet::packet_pool::ClientInformationCtoS::new()),
+        0x11 => Box::new(crate::packet::packet_pool::ContainerClick::new()),
         0x1B => Box::new(crate::packet::packet_pool::KeepAliveServerPlay::new()),
         0x1D => Box::new(crate::packet::packet_pool::MovePlayerPos::new()),
         0x1E => Box::new(crate::packet::packet_pool::MovePlayerPosRot::new()),
+        0x1F => Box::new(crate::packet::packet_pool::MovePlayerRot::new()),
+        0x20 => Box::new(crate::packet::packet_pool::MovePlayerStatusOnly::new()),
+        0x28 => Box::new(crate::packet::packet_pool::PlayerAction::new()),
+        0x34 => Box::new(crate::packet::packet_pool::SetCarriedItem::new()),
+        0x37 => Box::new(crate::packet::packet_pool::SetCreativeModeSlot::new()),
+        0x3F => Box::new(crate::packet::packet_pool::UseItemOn::new()),
         _ => Box::new(crate::packet::packet_pool::NullPacket::new()),
     }
 }
\ No newline at end of file
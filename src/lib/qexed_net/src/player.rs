@@ -11,6 +11,16 @@ pub struct Player {
     pub locale: String,
     /// 视野距离
     pub view_distance:i8,
+    /// 显示的皮肤部分
+    pub skin_parts:u8,
+    /// 主手(0 左手,1 右手)
+    pub main_hand:i32,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Player {
@@ -21,6 +31,8 @@ impl Player {
             client_type:"".to_owned(),
             locale: "zh_cn".to_owned(),
             view_distance:21,
+            skin_parts:0x7F,
+            main_hand:1,
         }
     }
 }
\ No newline at end of file
@@ -1,10 +1,9 @@
 use anyhow::{Ok, Result};
 use log::info;
 use mongodb::Collection;
-use qexed_core::utils::alloci32::ALLOC;
 use qexed_net::net_types::packet::Packet;
 use qexed_net::net_types::var_int::VarInt;
-use qexed_net::packet::packet_pool::{GameEvent, KeepAliveServerPlay, LevelChunkWithLight, LoginCompression, PlayerPosition};
+use qexed_net::packet::packet_pool::{GameEvent, KeepAliveServerPlay, LoginCompression};
 use qexed_net::{
     mojang_online::query_mojang_for_usernames, net_types::packet::PacketState,
     packet::packet_pool::DisconnectLogin, player::Player, read_packet,
@@ -35,14 +34,20 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
     let mut players: i64 = 0;
     let config = Arc::new(Mutex::new(qexed_config::get_global_config()?));
     // 维护实体id信息
-    let alloc_entity_id: Arc<Mutex<qexed_core::utils::alloci32::Alloci32>> =
-        Arc::new(Mutex::new(qexed_core::utils::alloci32::Alloci32::new()));
+    let alloc_entity_id: Arc<Mutex<qexed_core::utils::entity_ids::EntityIds>> =
+        Arc::new(Mutex::new(qexed_core::utils::entity_ids::EntityIds::new()));
+    // 服务端生成的生物
+    let mobs_raw = Arc::new(Mutex::new(qexed_core::biology::entity::EntityStore::new()));
     // 连接monggodb
     // 创建连接池
     let pool = Arc::new(Mutex::new(
         qexed_core::utils::mongo_dbconnection_pool::MongoDBConnectionPool::new().await?,
     ));
     qexed_core::registry::get_registry_data_packets();
+    // 没有完整的原版方块表时拒绝启动,避免存档中的方块丢失
+    qexed_world::init_block_registry(qexed_world::registry::BLOCKS_REPORT_PATH)?;
+    qexed_core::event::block_interact::init_item_registry(qexed_core::event::block_interact::ITEMS_REPORT_PATH);
+    qexed_core::biology::spawn::init_spawn_tables(qexed_core::biology::spawn::BIOME_DATA_PATH);
     if let Some(p2) = qexed_core::update_tags::get_update_tags_packet()
         .as_any()
         .downcast_ref::<qexed_net::packet::packet_pool::UpdateTags>()
@@ -58,11 +63,160 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
 
     // 获取默认数据库
     let player_map_raw  = Arc::new(Mutex::new(HashMap::new()));
+    // 世界管理器:每个命名世界保存在 db_path 下以世界名命名的子目录中
+    let world_config = config.lock().await.game.world.clone();
+    let dimensions = config.lock().await.game.dimensions.clone();
+    // 主世界总是启用,下界与末地按配置启用
+    let mut enabled = Vec::new();
+    if dimensions.allow_hell {
+        enabled.push(qexed_world::Dimension::Nether);
+    }
+    if dimensions.allow_end {
+        enabled.push(qexed_world::Dimension::End);
+    }
+    // 随机刻调度器:世界打开时挂接,区块加载时补算离线期间的作物生长,新放下的作物在游戏刻中安排
+    let random_ticks = Arc::new(qexed_core::scheduler::RandomTickScheduler::new());
+    let attach_ticks = Arc::clone(&random_ticks);
+    let worlds_raw = Arc::new(
+        qexed_world::WorldManager::new(
+            qexed_world::StorageOptions::from_config(&world_config),
+            enabled,
+            qexed_worldgen::chunk_factory,
+        )
+        .with_open_hook(move |level| attach_ticks.attach_level(level)),
+    );
+    // 默认世界第一次创建时使用配置中的生成器设置
+    let default_world_raw = Arc::new(world_config.world.clone());
+    worlds_raw.load_or_create(&default_world_raw, qexed_world::WorldSettings::from_config(&world_config))?;
+    Arc::clone(&random_ticks).run();
+    // 定时保存世界
+    let autosave_worlds = Arc::clone(&worlds_raw);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let worlds = Arc::clone(&autosave_worlds);
+            match tokio::task::spawn_blocking(move || worlds.save_all()).await {
+                std::result::Result::Ok(std::result::Result::Ok(n)) if n > 0 => log::info!("已自动保存 {} 个区块", n),
+                std::result::Result::Ok(Err(e)) => log::error!("自动保存失败: {}", e),
+                _ => {}
+            }
+        }
+    });
+    // 定时卸载不在任何玩家视野内的区块,被修改过的区块卸载前先保存
+    let unload_worlds = Arc::clone(&worlds_raw);
+    let unload_players = Arc::clone(&player_map_raw);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            for level in unload_worlds.levels() {
+                for world in level.worlds() {
+                    let areas = qexed_core::event::chunk_view::view_areas(&level.name, world, &unload_players).await;
+                    let world = Arc::clone(world);
+                    let keep = move |pos: qexed_world::ChunkPos| areas.iter().any(|(center, radius)| center.chebyshev_distance(&pos) <= *radius);
+                    match tokio::task::spawn_blocking(move || world.unload_unused(keep)).await {
+                        std::result::Result::Ok(std::result::Result::Ok(n)) if n > 0 => log::debug!("世界 {} 卸载了 {} 个区块", level.name, n),
+                        std::result::Result::Ok(Err(e)) => log::error!("卸载区块失败: {}", e),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    });
+    // 游戏刻主循环:按 game.tps 执行实体与计划刻,广播方块与光照变化并发送区块
+    let tick_worlds = Arc::clone(&worlds_raw);
+    let tick_players = Arc::clone(&player_map_raw);
+    let tick_mobs = Arc::clone(&mobs_raw);
+    let tick_entity_ids = Arc::clone(&alloc_entity_id);
+    let tick_random_ticks = Arc::clone(&random_ticks);
+    let observed_state = config.lock().await.game.observed_state;
+    let mut tick_loop = qexed_core::scheduler::TickLoop::new(config.lock().await.game.tps);
+    let tick_stats = tick_loop.stats();
+    tokio::spawn(async move {
+        use qexed_core::scheduler::TickPhase;
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::from_os_rng();
+        let mut game_time: u64 = 0;
+        loop {
+            let mut timer = tick_loop.wait().await;
+            timer.enter(TickPhase::Entities);
+            let levels = tick_worlds.levels();
+            let players = qexed_core::event::entity_tracker::player_snapshots(&tick_players).await;
+            let mut actions = Vec::new();
+            {
+                use qexed_core::biology::ai;
+                let mut mobs = tick_mobs.lock().await;
+                let mut ids = tick_entity_ids.lock().await;
+                // 寻路预算由所有世界共用
+                let mut budget = ai::PATH_NODE_BUDGET;
+                for level in &levels {
+                    let do_mob_spawning = level.game_rules().do_mob_spawning;
+                    for world in level.worlds() {
+                        qexed_core::biology::spawn::tick_despawn(&mut mobs, &mut ids, &level.name, world.dimension, &players, &mut rng);
+                        if do_mob_spawning {
+                            // 友好生物与原版相同每 400 刻生成一次;还没有昼夜,天空光按白天计算
+                            let rules = qexed_core::biology::spawn::SpawnRules {
+                                enemies: true,
+                                friendlies: true,
+                                persistent: game_time.is_multiple_of(400),
+                                sky_darken: 0,
+                                world_spawn: level.spawn().filter(|_| world.dimension == qexed_world::Dimension::Overworld),
+                            };
+                            qexed_core::biology::spawn::tick_spawning(&mut mobs, &mut ids, &level.name, world, qexed_core::biology::spawn::spawn_tables(), &players, &rules, &mut rng);
+                        }
+                        actions.extend(ai::tick_ai(&mut mobs, &level.name, world.dimension, &**world, &players, &mut rng, &mut budget));
+                        mobs.tick_world(&mut ids, &level.name, world.dimension, &**world);
+                    }
+                }
+            }
+            game_time += 1;
+            qexed_core::biology::ai::apply_attacks(&tick_players, actions).await;
+            qexed_core::event::entity_tracker::track_entities(&tick_players, &tick_mobs).await;
+            timer.enter(TickPhase::World);
+            for level in &levels {
+                for world in level.worlds() {
+                    // 玩家观察态关闭时所有区块都实时处理
+                    let observed = if observed_state {
+                        Some(qexed_core::event::chunk_view::observed_chunks(&level.name, world, &tick_players).await)
+                    } else {
+                        None
+                    };
+                    qexed_core::scheduler::tick_world(world, qexed_world::block_registry(), |pos| observed.as_ref().is_none_or(|o| o.contains(&pos)));
+                }
+            }
+            timer.enter(TickPhase::ChunkSend);
+            for level in &levels {
+                for world in level.worlds() {
+                    // 新放下的作物在这里安排随机刻
+                    let changes = world.take_block_changes();
+                    tick_random_ticks.schedule_changes(world, &changes, level.game_rules().random_tick_speed);
+                    qexed_core::event::chunk_view::broadcast_block_changes(&level.name, world, &changes, &tick_players).await;
+                    qexed_core::event::chunk_view::broadcast_light_updates(&level.name, world, &tick_players).await;
+                }
+            }
+            qexed_core::event::chunk_view::send_chunk_batches(&levels, &tick_players).await;
+            tick_loop.finish(timer);
+        }
+    });
+    // 每分钟输出一次 TPS 与 MSPT
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            log::info!("{}", tick_stats.report());
+        }
+    });
     while let std::result::Result::Ok((socket, socketaddr)) = tcplistener.accept().await {
         let player_map = Arc::clone(&player_map_raw);
         let alloc_entity_id = Arc::clone(&alloc_entity_id);
         let config = Arc::clone(&config);
         let pool = Arc::clone(&pool);
+        let worlds = Arc::clone(&worlds_raw);
+        let default_world = Arc::clone(&default_world_raw);
         
         tokio::spawn(async move {
             let mongo_pool = pool.lock().await;
@@ -76,7 +230,6 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
             let now: DateTime<Utc> = Utc::now();
             let mut alive_id: Arc<Mutex<u64>> = Arc::new(Mutex::new(now.timestamp_millis() as u64));
             let mut is_login_finish = false;
-            let mut teleport_id_i32: i32 = 0;
             let mut first_tp: bool = false;
             let mut first_move: bool = false;
             loop {
@@ -90,7 +243,7 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                     }
                     player_conn.is_online = false;
                     player_conn.save(&db).await.unwrap();
-                    player_map.lock().await.remove(&player_conn.uuid);
+                    player_leave(&player_map, &alloc_entity_id, &player_conn).await;
                     if is_login_finish{
                         log::info!("玩家 {}[{}] 退出了游戏",player_conn.username,player_conn.uuid);
                     }
@@ -107,6 +260,7 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                         socketaddr.ip(),
                         socketaddr.port()
                     );
+                    player_leave(&player_map, &alloc_entity_id, &player_conn).await;
                     return;
                 }
                 let packet3 = packet2.unwrap();
@@ -235,6 +389,8 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                                 if let Some(player) = &mut packet_socket.player{
                                     player.locale = pk.locale.clone();
                                     player.view_distance = pk.view_distance;
+                                    player.skin_parts = pk.displayed_skin_parts;
+                                    player.main_hand = pk.main_hand.0;
                                     // 发 SelectKnownPacks 数据包
                                     let mut select_known_packs = qexed_net::packet::packet_pool::SelectKnownPacks::new();
                                     select_known_packs.known_packs =vec![
@@ -285,34 +441,46 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                                 if let Some(p2) = qexed_core::update_tags::get_update_tags_packet().as_any().downcast_ref::<qexed_net::packet::packet_pool::UpdateTags>() {
                                     let _ = packet_socket.send(p2).await;
                                 }
-                                let entity_result = alloc_entity_id.lock().await.get();
+                                let entity_result = alloc_entity_id.lock().await.allocate();
                                 if entity_result.is_err() {
                                     log::error!("获取实体ID失败");
                                     // 发送disconnected 数据包,这里暂时没写
                                     return;
                                 }
                                 let username = player_conn.username.clone();
-                                let entity_id = entity_result.unwrap();
+                                let entity = entity_result.unwrap();
                                 *player_conn = match qexed_core::biology::player::Player::load_or_create(&db, player_conn.uuid).await {
                                     Result::Ok(player) => player,
                                     Err(e) => {
                                         log::error!("加载或创建玩家失败: {:?}", e);
+                                        alloc_entity_id.lock().await.release(entity);
                                         // 发送disconnected 数据包,这里暂时没写
                                         return;
                                     }
                                 };
-                                player_conn.entity_id = entity_id;
+                                player_conn.entity_id = entity.id;
+                                player_conn.entity_generation = entity.generation;
                                 player_conn.username = username;
                                 player_conn.is_online = true;
+                                if let Some(player) = &packet_socket.player {
+                                    player_conn.metadata.set_skin_parts(player.skin_parts);
+                                    player_conn.metadata.set(
+                                        qexed_net::net_types::entity_metadata::index::PLAYER_MAIN_HAND,
+                                        qexed_net::net_types::entity_metadata::MetadataValue::Byte(player.main_hand as i8),
+                                    );
+                                }
+                                // 所在世界无法加载或所在维度已被禁用时回到默认世界的主世界
+                                let level = player_level(&worlds, &default_world, &mut player_conn);
+                                let dimension = player_conn.dimension;
 
                                 // 发送 FinishConfigurationStoC 数据包
                                 let finish_configuration = qexed_net::packet::packet_pool::FinishConfigurationStoC::new();
                                 let _ = packet_socket.send(&finish_configuration).await;
                                 // 发送 LoginPlay 数据包
                                 let mut login_play = qexed_net::packet::packet_pool::LoginPlay::new();
-                                login_play.entity_id = entity_id;
+                                login_play.entity_id = entity.id;
                                 login_play.is_hardcore = false;// 硬核模式对我们暂时没啥用
-                                login_play.dimension_names = vec!["minecraft:overworld".to_string(),"minecraft:the_end".to_string(),"minecraft:the_nether".to_string()];
+                                login_play.dimension_names = level.dimensions().map(|d| d.name().to_string()).collect();
                                 login_play.max_player = qexed_net::net_types::var_int::VarInt(config.lock().await.game.max_player as i32);
                                 login_play.view_distance = qexed_net::net_types::var_int::VarInt(config.lock().await.game.chunk_render_distance as i32);
                                 login_play.simulation_distance = qexed_net::net_types::var_int::VarInt(config.lock().await.game.chunk_render_distance as i32);
@@ -320,18 +488,17 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                                 login_play.enable_respawn_screen = false; // 启用重生界面
                                 // TODO: 这里需要后续代码实现,这里暂时快速开发不做设置兼容
                                 login_play.do_limited_crafting = false; // 限制制作，Doc都说没使用那就不管
-                                login_play.dimension_type = VarInt(0); // 维度类型,暂时不管
-                                login_play.dimension_name = "minecraft:overworld".to_string();
+                                login_play.dimension_type = VarInt(dimension.type_id()); // 维度类型,dimension_type 注册表中的下标
+                                login_play.dimension_name = dimension.name().to_string();
                                 // 不显示哈希
                                 login_play.hashed_seed = 0;
-                                // 游戏模式,暂时只写生存,后面支持
-                                login_play.game_mode = 0; // 0:生存,1:创造,2:冒险,3:旁观
+                                login_play.game_mode = player_conn.game_mode; // 0:生存,1:创造,2:冒险,3:旁观
                                 login_play.previous_game_mode = -1; // 上一个游戏模式,暂时不管
                                 login_play.is_debug = false; // 是否调试模式
-                                login_play.is_flat = false; // 是否平坦世界
+                                login_play.is_flat = level.world(dimension).is_some_and(|w| w.is_flat); // 是否平坦世界
                                 login_play.has_death_location = false; // 是否有死亡位置
                                 login_play.portal_cooldown = qexed_net::net_types::var_int::VarInt(0); // 传送门冷却时间
-                                login_play.sea_level = qexed_net::net_types::var_int::VarInt(63); // 海平面高度
+                                login_play.sea_level = qexed_net::net_types::var_int::VarInt(dimension.sea_level()); // 海平面高度
                                 login_play.enforces_secure_chat = false; // 强制安全聊天
                                 let _ = packet_socket.send(&login_play).await;
                                 // Player::load_by_uuid(
@@ -340,11 +507,20 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                                 // 保存数据库
                                 player_conn.save(&db).await.unwrap();
                                 // 传送玩家到指定位置
-                                let mut pp = PlayerPosition::new();
-                                teleport_id_i32 = 1;
-                                pp.teleport_id = qexed_net::net_types::var_int::VarInt(teleport_id_i32);
+                                // 回到上次退出时的位置,第一次进入时送往出生点
+                                let position = match player_conn.position {
+                                    Some(position) => position,
+                                    None => qexed_core::event::dimension::spawn_point(&level, dimension),
+                                };
+                                player_conn.position = Some(position);
                                 player_map.lock().await.insert(player_conn.uuid, Arc::clone(&player_conn_raw));
-                                let _ = packet_socket.send(&pp).await;
+                                let rotation = (player_conn.yaw, player_conn.pitch);
+                                let _ = qexed_core::event::teleport::teleport(
+                                    &mut packet_socket, &mut player_conn, position, rotation, (0.0, 0.0, 0.0),
+                                    qexed_core::event::teleport::TeleportFlags::empty(),
+                                ).await;
+                                // 同步物品栏
+                                let _ = qexed_core::event::inventory::send_inventory(&mut packet_socket, &player_conn).await;
 
                             }
                             }
@@ -364,10 +540,8 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                                         first_tp = true
 
                                     }
-                                    // 暂时没想好如果失败的逻辑
-                                    // if pk.teleport_id == qexed_net::net_types::var_int::VarInt(teleport_id_i32){
-                                    //     
-                                    // }
+                                    // ID 不匹配的确认来自已被取代的传送,直接忽略
+                                    qexed_core::event::teleport::accept_teleport(&mut player_conn, pk.teleport_id.0);
                                 }
                             }
                             0x0c => {
@@ -380,56 +554,36 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                                 }
                             }
                             0x1E => {
-                                if let Some(_pk) = packet3
+                                if let Some(pk) = packet3
                                     .as_any()
                                     .downcast_ref::<qexed_net::packet::packet_pool::MovePlayerPosRot>(
                                 ) {
-                                    // 处理 MovePlayerPosRot 数据包
+                                    // 处理 MovePlayerPosRot 数据包,与进入世界后相同按服务端的方块校验
+                                    let world = physics_world(&worlds, &player_conn);
+                                    let _ = qexed_core::event::movement::handle_move(
+                                        &mut packet_socket, &mut player_conn, Some((pk.x, pk.feet_y, pk.z)), Some((pk.yaw, pk.pitch)), pk.flags,
+                                        world.as_deref(),
+                                    ).await;
                                     if !first_move{
                                         first_move=true;
                                         // 构建 GameEventPacket 数据包
                                         let mut ge = GameEvent::new();
                                         ge.event = 13;
                                         let _ = packet_socket.send(&ge).await;
-                                        // 构建 SetChunkCacheCenter 数据包
-                                        let sccc = qexed_net::packet::packet_pool::SetChunkCacheCenter::new();
-                                        let _ = packet_socket.send(&sccc).await;
-                                        // 发送玩家附近区块
-                                        let radius= config.lock().await.game.chunk_render_distance as i32;
-                                        for x in -radius..=radius {
-                                            for z in -radius..=radius {
-                                                // 创建空区块
-                                                let p_q = LevelChunkWithLight {
-                                                    chunk_x: x,
-                                                    chunk_z: z,
-                                                    data: qexed_net::net_types::chunk::Chunk {
-                                                        // 高度图 - 使用修复后的高度图
-                                                        heightmaps: create_heightmaps(),
-                                                        // 空的区块数据 - 使用修复后的编码函数
-                                                        data: encode_empty_chunk_data_1_21(),
-                                                        // 无方块实体
-                                                        block_entities: vec![],
-                                                    },
-                                                    light: qexed_net::net_types::light::Light {
-                                                        // 空的光照掩码
-                                                        sky_light_mask: qexed_net::net_types::bitset::Bitset(vec![]),
-                                                        block_light_mask: qexed_net::net_types::bitset::Bitset(vec![]),
-                                                        empty_sky_light_mask: qexed_net::net_types::bitset::Bitset(vec![]),
-                                                        empty_block_light_mask: qexed_net::net_types::bitset::Bitset(vec![]),
-                                                        // 空的照明数据
-                                                        sky_light_arrays: vec![],
-                                                        block_light_arrays: vec![],
-                                                    },
-                                                };
-                                            
-                                                // 发送数据包
-                                                let _ = packet_socket.send(&p_q).await;
-                                            }
-                                        }
+                                        // 按视距由近及远排队,区块由游戏刻的区块发送阶段发出
+                                        let radius = view_distance(&config, &packet_socket).await;
+                                        // 移动被拒绝时玩家已被拉回,视野以服务端记录的位置为准
+                                        let (x, _, z) = player_conn.position.unwrap_or((pk.x, pk.feet_y, pk.z));
+                                        let center = qexed_world::ChunkPos::from_entity_pos(x, z);
+                                        let player = &mut *player_conn;
+                                        let _ = qexed_core::event::chunk_view::update_chunk_view(
+                                            &mut packet_socket, &mut player.chunk_view, &mut player.chunk_sender, center, radius,
+                                        ).await;
                                         is_login_finish = true;
                                         log::info!("玩家 {}[{}] 加入了游戏",player_conn.username,player_conn.uuid);
-                                        player_conn.conn = Some(Arc::clone(&packet_socket_raw));
-                                        tokio::spawn(qexed_core::event::join_server::alive_fn(Arc::clone(&alive_id),Arc::clone(&packet_socket_raw)));
+                                        // 游戏刻任务与心跳通过发送队列写数据包,不需要等待这里读取客户端的锁
+                                        player_conn.conn = Some(packet_socket.sender());
+                                        tokio::spawn(qexed_core::event::join_server::alive_fn(Arc::clone(&alive_id),packet_socket.sender()));
                                         
 
                                     }
@@ -448,10 +602,11 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                             0x00 => {
                                 // 玩家在接受服务端后的tp逻辑
 
-                                if let Some(_pk) = packet3
+                                if let Some(pk) = packet3
                                 .as_any()
                                 .downcast_ref::<qexed_net::packet::packet_pool::AcceptTeleportation>(
                                 ) {
+                                    qexed_core::event::teleport::accept_teleport(&mut player_conn, pk.teleport_id.0);
                                 }
                             }
                             0x08 =>{
@@ -463,13 +618,76 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                                     log::info!("ChatMessageCtS 数据包: {:?}", pk);
                                 }
                             }
+                            0x0A => {
+                                if let Some(pk) = packet3
+                                .as_any()
+                                .downcast_ref::<qexed_net::packet::packet_pool::ChunkBatchReceived>(
+                                ) {
+                                    // 客户端处理完一批区块,按它期望的速度继续发送
+                                    player_conn.chunk_sender.on_batch_received(pk.chunks_per_tick);
+                                }
+                            }
                             0x0c => {
                                 if let Some(_pk) = packet3
                                 .as_any()
                                 .downcast_ref::<qexed_net::packet::packet_pool::ChunkBatchStart>(
                                 ) {
-                                    // 处理 ChunkBatchStart 数据包
-                                    // log::info!("ChunkBatchStart 数据包: {:?}", pk);
+                                    // 客户端每 tick 结束时都会发送,借此推进传送门冷却
+                                    let player = &mut *player_conn;
+                                    if player.portal_cooldown > 0 {
+                                        player.portal_cooldown -= 1;
+                                    }
+                                    // 传送长时间没有确认时重发,仍没有确认就断开连接
+                                    match qexed_core::event::teleport::check_teleport(&mut packet_socket, player).await {
+                                        std::result::Result::Ok(true) => {}
+                                        _ => {
+                                            // 关闭连接:发送队列写完断开提示后关闭套接字,心跳随之退出
+                                            let _ = packet_socket.shutdown().await;
+                                            player.conn = None;
+                                            player.is_online = false;
+                                            let _ = player.save(&db).await;
+                                            player_leave(&player_map, &alloc_entity_id, player).await;
+                                            log::info!("玩家 {}[{}] 退出了游戏", player.username, player.uuid);
+                                            break;
+                                        }
+                                    }
+                                }
+                            }
+                            0x0D => {
+                                if let Some(pk) = packet3
+                                    .as_any()
+                                    .downcast_ref::<qexed_net::packet::packet_pool::ClientInformationCtoS>(
+                                ) {
+                                    // 客户端修改了设置,视距变化时重新计算视野
+                                    if let Some(player) = &mut packet_socket.player{
+                                        player.locale = pk.locale.clone();
+                                        player.view_distance = pk.view_distance;
+                                        player.skin_parts = pk.displayed_skin_parts;
+                                        player.main_hand = pk.main_hand.0;
+                                    }
+                                    // 皮肤部分与主手由实体追踪同步给其他玩家
+                                    player_conn.metadata.set_skin_parts(pk.displayed_skin_parts);
+                                    player_conn.metadata.set(
+                                        qexed_net::net_types::entity_metadata::index::PLAYER_MAIN_HAND,
+                                        qexed_net::net_types::entity_metadata::MetadataValue::Byte(pk.main_hand.0 as i8),
+                                    );
+                                    if let Some(center) = player_conn.chunk_view.center() {
+                                        let radius = view_distance(&config, &packet_socket).await;
+                                        let player = &mut *player_conn;
+                                        let _ = qexed_core::event::chunk_view::update_chunk_view(
+                                            &mut packet_socket, &mut player.chunk_view, &mut player.chunk_sender, center, radius,
+                                        ).await;
+                                    }
+                                }
+                            }
+                            0x11 => {
+                                if let Some(pk) = packet3
+                                    .as_any()
+                                    .downcast_ref::<qexed_net::packet::packet_pool::ContainerClick>(
+                                ) {
+                                    let _ = qexed_core::event::inventory::container_click(
+                                        &mut packet_socket, &mut player_conn, pk,
+                                    ).await;
                                 }
                             }
                             0x1B => {
@@ -487,6 +705,21 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                                 ) {
                                     // 处理 MovePlayerPos 数据包
                                     // log::info!("移动数据包(不含视角):{:?}",pk);
+                                    let world = physics_world(&worlds, &player_conn);
+                                    let moved = qexed_core::event::movement::handle_move(
+                                        &mut packet_socket, &mut player_conn, Some((pk.x, pk.feet_y, pk.z)), None, pk.flags,
+                                        world.as_deref(),
+                                    ).await;
+                                    if !matches!(moved, std::result::Result::Ok(qexed_core::event::movement::Move::Accepted)) {
+                                        continue;
+                                    }
+                                    let radius = view_distance(&config, &packet_socket).await;
+                                    let center = qexed_world::ChunkPos::from_entity_pos(pk.x, pk.z);
+                                    let player = &mut *player_conn;
+                                    let _ = qexed_core::event::chunk_view::update_chunk_view(
+                                        &mut packet_socket, &mut player.chunk_view, &mut player.chunk_sender, center, radius,
+                                    ).await;
+                                    let _ = enter_portal(&mut packet_socket, player, &worlds, (pk.x, pk.feet_y, pk.z), radius).await;
                                 }
                             }
                             0x1E => {
@@ -496,6 +729,87 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
                                 ) {
                                     // 处理 MovePlayerPosRot 数据包
                                     // log::info!("移动数据包:{:?}",pk);
+                                    let world = physics_world(&worlds, &player_conn);
+                                    let moved = qexed_core::event::movement::handle_move(
+                                        &mut packet_socket, &mut player_conn, Some((pk.x, pk.feet_y, pk.z)), Some((pk.yaw, pk.pitch)), pk.flags,
+                                        world.as_deref(),
+                                    ).await;
+                                    if !matches!(moved, std::result::Result::Ok(qexed_core::event::movement::Move::Accepted)) {
+                                        continue;
+                                    }
+                                    let radius = view_distance(&config, &packet_socket).await;
+                                    let center = qexed_world::ChunkPos::from_entity_pos(pk.x, pk.z);
+                                    let player = &mut *player_conn;
+                                    let _ = qexed_core::event::chunk_view::update_chunk_view(
+                                        &mut packet_socket, &mut player.chunk_view, &mut player.chunk_sender, center, radius,
+                                    ).await;
+                                    let _ = enter_portal(&mut packet_socket, player, &worlds, (pk.x, pk.feet_y, pk.z), radius).await;
+                                }
+                            }
+                            0x1F => {
+                                if let Some(pk) = packet3
+                                    .as_any()
+                                    .downcast_ref::<qexed_net::packet::packet_pool::MovePlayerRot>(
+                                ) {
+                                    // 只转动视角
+                                    let world = physics_world(&worlds, &player_conn);
+                                    let _ = qexed_core::event::movement::handle_move(
+                                        &mut packet_socket, &mut player_conn, None, Some((pk.yaw, pk.pitch)), pk.flags,
+                                        world.as_deref(),
+                                    ).await;
+                                }
+                            }
+                            0x20 => {
+                                if let Some(pk) = packet3
+                                    .as_any()
+                                    .downcast_ref::<qexed_net::packet::packet_pool::MovePlayerStatusOnly>(
+                                ) {
+                                    // 只更新是否着地
+                                    let world = physics_world(&worlds, &player_conn);
+                                    let _ = qexed_core::event::movement::handle_move(
+                                        &mut packet_socket, &mut player_conn, None, None, pk.flags,
+                                        world.as_deref(),
+                                    ).await;
+                                }
+                            }
+                            0x28 => {
+                                if let Some(pk) = packet3
+                                    .as_any()
+                                    .downcast_ref::<qexed_net::packet::packet_pool::PlayerAction>(
+                                ) {
+                                    let world = player_world(&worlds, &player_conn);
+                                    let _ = qexed_core::event::block_interact::player_action(
+                                        &mut packet_socket, &mut player_conn, world.as_deref(), pk,
+                                    ).await;
+                                }
+                            }
+                            0x34 => {
+                                if let Some(pk) = packet3
+                                    .as_any()
+                                    .downcast_ref::<qexed_net::packet::packet_pool::SetCarriedItem>(
+                                ) {
+                                    qexed_core::event::inventory::set_carried_item(&mut player_conn, pk);
+                                }
+                            }
+                            0x37 => {
+                                if let Some(pk) = packet3
+                                    .as_any()
+                                    .downcast_ref::<qexed_net::packet::packet_pool::SetCreativeModeSlot>(
+                                ) {
+                                    let _ = qexed_core::event::inventory::creative_slot(
+                                        &mut packet_socket, &mut player_conn, pk,
+                                    ).await;
+                                }
+                            }
+                            0x3F => {
+                                if let Some(pk) = packet3
+                                    .as_any()
+                                    .downcast_ref::<qexed_net::packet::packet_pool::UseItemOn>(
+                                ) {
+                                    let world = player_world(&worlds, &player_conn);
+                                    let _ = qexed_core::event::block_interact::use_item_on_packet(
+                                        &mut packet_socket, &mut player_conn, world.as_deref(), pk,
+                                    ).await;
                                 }
                             }
 
@@ -512,6 +826,105 @@ pub async fn start_task(tcplistener: TcpListener) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+// 玩家离开世界:从玩家表中移除并归还实体 ID,其他玩家的实体追踪在下一刻移除这个玩家
+async fn player_leave(
+    player_map: &Mutex<HashMap<Uuid, Arc<Mutex<qexed_core::biology::player::Player>>>>,
+    alloc_entity_id: &Mutex<qexed_core::utils::entity_ids::EntityIds>,
+    player: &qexed_core::biology::player::Player,
+) {
+    player_map.lock().await.remove(&player.uuid);
+    if player.entity_id >= 0 {
+        alloc_entity_id.lock().await.release(player.entity_handle());
+    }
+}
+
+// 玩家实际使用的视距
+async fn view_distance(
+    config: &Arc<Mutex<qexed_config::Config>>,
+    packet_socket: &qexed_net::PacketListener,
+) -> i32 {
+    let server = config.lock().await.game.chunk_render_distance;
+    let client = packet_socket.player.as_ref().map_or(0, |p| p.view_distance);
+    qexed_core::event::chunk_view::effective_view_distance(server, client)
+}
+
+// 玩家所在的命名世界,世界无法加载或所在维度未启用时回到默认世界主世界的出生点
+fn player_level(
+    worlds: &qexed_world::WorldManager,
+    default_world: &str,
+    player: &mut qexed_core::biology::player::Player,
+) -> Arc<qexed_world::Level> {
+    if player.world.is_empty() {
+        player.world = default_world.to_string();
+    }
+    let level = worlds.get(&player.world).or_else(|| match worlds.load(&player.world) {
+        std::result::Result::Ok(level) => Some(level),
+        Err(e) => {
+            log::error!("加载世界 {} 失败: {}", player.world, e);
+            None
+        }
+    });
+    let level = match level {
+        Some(level) if level.world(player.dimension).is_some() => level,
+        _ => {
+            player.dimension = qexed_world::Dimension::Overworld;
+            player.position = None;
+            worlds.get(default_world).expect("默认世界总是已加载")
+        }
+    };
+    player.world = level.name.clone();
+    level
+}
+
+// 玩家所在的世界
+fn player_world(
+    worlds: &qexed_world::WorldManager,
+    player: &qexed_core::biology::player::Player,
+) -> Option<Arc<qexed_world::World>> {
+    worlds
+        .get(&player.world)
+        .and_then(|level| level.world(player.dimension).cloned())
+}
+
+// 需要校验移动时返回玩家所在的世界,世界设置关闭了移动校验时返回 `None`
+fn physics_world(
+    worlds: &qexed_world::WorldManager,
+    player: &qexed_core::biology::player::Player,
+) -> Option<Arc<qexed_world::World>> {
+    worlds
+        .get(&player.world)
+        .filter(|level| level.movement_physics())
+        .and_then(|level| level.world(player.dimension).cloned())
+}
+
+// 玩家站在传送门中时把玩家送往传送门另一侧的维度
+async fn enter_portal(
+    socket: &mut qexed_net::PacketListener,
+    player: &mut qexed_core::biology::player::Player,
+    worlds: &qexed_world::WorldManager,
+    (x, y, z): (f64, f64, f64),
+    radius: i32,
+) -> std::io::Result<()> {
+    use qexed_core::event::dimension;
+    if player.portal_cooldown > 0 {
+        return std::result::Result::Ok(());
+    }
+    let Some(level) = worlds.get(&player.world) else {
+        return std::result::Result::Ok(());
+    };
+    let from = player.dimension;
+    let Some(target) = level.world(from).and_then(|world| dimension::portal_target(qexed_world::block_registry(), world, x, y, z)) else {
+        return std::result::Result::Ok(());
+    };
+    // 目标维度未启用
+    if level.world(target).is_none() {
+        return std::result::Result::Ok(());
+    }
+    let destination = dimension::portal_destination(&level, from, target, x, z);
+    player.portal_cooldown = dimension::PORTAL_COOLDOWN;
+    dimension::change_world(socket, player, &level, target, Some(destination), radius).await
+}
+
 // 下阶段登录检查(是否需要正版验证)
 async fn is_online(player: &String, uuids: uuid::Uuid) -> Result<bool, anyhow::Error> {
     let config = qexed_config::get_global_config()?;
@@ -540,84 +953,3 @@ async fn is_online(player: &String, uuids: uuid::Uuid) -> Result<bool, anyhow::E
     return Ok(true);
 }
 // 构建符合协议的状态响应
-
-// 为 Minecraft 1.21.8 编码空区块数据
-fn encode_empty_chunk_data_1_21() -> Vec<u8> {
-    let mut data = Vec::new();
-
-    // 1.21.8 使用 24 个区块段落 (从 y=-64 到 y=319)
-    for _ in 0..24 {
-        // 段落非空气方块数量为 0
-        data.extend_from_slice(&0i16.to_be_bytes());
-
-        // 方块状态
-        // 使用调色板模式，只有一个空气方块
-        let bits_per_block = 1; // 只需要 1 位，因为只有一种方块
-        data.push(bits_per_block as u8);
-
-        // 调色板长度 - 使用 VarInt 编码
-        data.extend(encode_var_int(1));
-
-        // 空气方块的 ID
-        data.extend(encode_var_int(0));
-
-        // 计算需要多少个 long 来存储 4096 个方块
-        let blocks_per_long = 64 / bits_per_block;
-        let num_longs = (4096 + blocks_per_long - 1) / blocks_per_long;
-
-        // 所有方块都是空气 (调色板索引 0)
-        for _ in 0..num_longs {
-            data.extend_from_slice(&0i64.to_be_bytes());
-        }
-
-        // 生物群系数据
-        // 使用调色板模式，只有一个生物群系
-        let bits_per_biome = 1; // 只需要 1 位，因为只有一种生物群系
-        data.push(bits_per_biome as u8);
-
-        // 生物群系调色板长度 - 使用 VarInt 编码
-        data.extend(encode_var_int(1));
-
-        // 平原生物群系的 ID
-        data.extend(encode_var_int(1));
-
-        // 计算需要多少个 long 来存储 64 个生物群系 (4x4x4)
-        let biomes_per_long = 64 / bits_per_biome;
-        let num_biome_longs = (64 + biomes_per_long - 1) / biomes_per_long;
-
-        // 所有生物群系都是平原 (调色板索引 0)
-        for _ in 0..num_biome_longs {
-            data.extend_from_slice(&0i64.to_be_bytes());
-        }
-    }
-    data
-}
-
-fn encode_var_int(value: i32) -> Vec<u8> {
-    let mut value = value as u32;
-    let mut buf = Vec::new();
-    loop {
-        if value & !0x7F == 0 {
-            buf.push(value as u8);
-            break;
-        } else {
-            buf.push((value as u8 & 0x7F) | 0x80);
-            value >>= 7;
-        }
-    }
-    buf
-}
-fn create_heightmaps() -> Vec<qexed_net::net_types::heightmap::Heightmaps> {
-    vec![
-        qexed_net::net_types::heightmap::Heightmaps {
-            type_id: VarInt(0), // MOTION_BLOCKING
-            // 高度图应该包含 256 个值（16x16），每个值是一个 VarLong
-            // 对于空区块，所有高度都是世界底部（-64）
-            data: vec![0; 36], // 这个大小可能需要调整
-        },
-        qexed_net::net_types::heightmap::Heightmaps {
-            type_id: VarInt(1), // WORLD_SURFACE
-            data: vec![0; 36],  // 这个大小可能需要调整
-        },
-    ]
-}
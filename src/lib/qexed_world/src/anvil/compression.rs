@@ -0,0 +1,195 @@
+//! 区块数据压缩
+//!
+//! 区域文件中每个区块前有一个字节标明压缩方式,最高位置 1 表示数据存放在外部的 `.mcc` 文件中。
+
+use std::io::{Read, Write};
+
+use flate2::{
+    Compression as Level,
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+};
+
+use crate::error::WorldError;
+
+/// 外部文件标记位
+pub const EXTERNAL_FLAG: u8 = 0x80;
+
+/// 压缩方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    Gzip = 1,
+    /// 原版默认
+    #[default]
+    Zlib = 2,
+    None = 3,
+    Lz4 = 4,
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = WorldError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Compression::Gzip),
+            2 => Ok(Compression::Zlib),
+            3 => Ok(Compression::None),
+            4 => Ok(Compression::Lz4),
+            v => Err(WorldError::UnsupportedCompression(v)),
+        }
+    }
+}
+
+impl Compression {
+    /// 根据配置中的名字解析(`gzip`/`zlib`/`none`/`lz4`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "gzip" => Some(Compression::Gzip),
+            "zlib" | "deflate" => Some(Compression::Zlib),
+            "none" | "uncompressed" => Some(Compression::None),
+            "lz4" => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>, WorldError> {
+        Ok(match self {
+            Compression::Gzip => {
+                let mut e = GzEncoder::new(Vec::new(), Level::default());
+                e.write_all(data)?;
+                e.finish()?
+            }
+            Compression::Zlib => {
+                let mut e = ZlibEncoder::new(Vec::new(), Level::default());
+                e.write_all(data)?;
+                e.finish()?
+            }
+            Compression::None => data.to_vec(),
+            Compression::Lz4 => lz4_block::compress(data),
+        })
+    }
+
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>, WorldError> {
+        let mut out = Vec::new();
+        match self {
+            Compression::Gzip => {
+                GzDecoder::new(data).read_to_end(&mut out)?;
+            }
+            Compression::Zlib => {
+                ZlibDecoder::new(data).read_to_end(&mut out)?;
+            }
+            Compression::None => out.extend_from_slice(data),
+            Compression::Lz4 => out = lz4_block::decompress(data)?,
+        }
+        Ok(out)
+    }
+}
+
+/// lz4-java 的 `LZ4BlockOutputStream` 格式
+///
+/// 每个块的头部: 魔数 `LZ4Block`、标记字节(压缩方式 | 压缩级别)、
+/// 压缩后长度、原始长度、XXHash32 校验值(均为小端 i32),以一个空块结尾。
+mod lz4_block {
+    use crate::error::WorldError;
+
+    const MAGIC: &[u8; 8] = b"LZ4Block";
+    const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + 4 + 4;
+    const METHOD_RAW: u8 = 0x10;
+    const METHOD_LZ4: u8 = 0x20;
+    /// 64KiB 块大小对应的压缩级别
+    const LEVEL: u8 = 6;
+    const BLOCK_SIZE: usize = 1 << 16;
+    const SEED: u32 = 0x9747b28c;
+
+    fn checksum(data: &[u8]) -> u32 {
+        twox_hash::XxHash32::oneshot(SEED, data) & 0x0FFF_FFFF
+    }
+
+    fn write_header(out: &mut Vec<u8>, method: u8, compressed: usize, original: usize, check: u32) {
+        out.extend_from_slice(MAGIC);
+        out.push(method | LEVEL);
+        out.extend_from_slice(&(compressed as u32).to_le_bytes());
+        out.extend_from_slice(&(original as u32).to_le_bytes());
+        out.extend_from_slice(&check.to_le_bytes());
+    }
+
+    pub fn compress(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for block in data.chunks(BLOCK_SIZE) {
+            let compressed = lz4_flex::block::compress(block);
+            if compressed.len() < block.len() {
+                write_header(
+                    &mut out,
+                    METHOD_LZ4,
+                    compressed.len(),
+                    block.len(),
+                    checksum(block),
+                );
+                out.extend_from_slice(&compressed);
+            } else {
+                write_header(
+                    &mut out,
+                    METHOD_RAW,
+                    block.len(),
+                    block.len(),
+                    checksum(block),
+                );
+                out.extend_from_slice(block);
+            }
+        }
+        write_header(&mut out, METHOD_RAW, 0, 0, 0);
+        out
+    }
+
+    pub fn decompress(mut data: &[u8]) -> Result<Vec<u8>, WorldError> {
+        let corrupt = |msg: &str| WorldError::CorruptChunk(format!("LZ4: {}", msg));
+        let le = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize;
+        let mut out = Vec::new();
+        while !data.is_empty() {
+            if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+                return Err(corrupt("块头无效"));
+            }
+            let method = data[8] & 0xF0;
+            let compressed = le(&data[9..13]);
+            let original = le(&data[13..17]);
+            let check = le(&data[17..21]) as u32;
+            data = &data[HEADER_LEN..];
+            if original == 0 {
+                break;
+            }
+            if data.len() < compressed {
+                return Err(corrupt("数据长度不足"));
+            }
+            let block = match method {
+                METHOD_RAW => data[..compressed].to_vec(),
+                METHOD_LZ4 => lz4_flex::block::decompress(&data[..compressed], original)
+                    .map_err(|e| corrupt(&e.to_string()))?,
+                _ => return Err(corrupt("未知的压缩方式")),
+            };
+            if block.len() != original || checksum(&block) != check {
+                return Err(corrupt("校验失败"));
+            }
+            out.extend_from_slice(&block);
+            data = &data[compressed..];
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_all_methods() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        for c in [
+            Compression::Gzip,
+            Compression::Zlib,
+            Compression::None,
+            Compression::Lz4,
+        ] {
+            assert_eq!(c.decompress(&c.compress(&data).unwrap()).unwrap(), data);
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! 存档区块 NBT 与内存区块之间的转换
+
+use crab_nbt::{NbtCompound, NbtTag};
+
+use crate::{
+    MIN_Y, SECTION_COUNT,
+    biome::{self, Biome},
+    block::BlockState,
+    chunk::{BlockEntity, Chunk},
+    error::WorldError,
+    heightmap::Heightmaps,
+    palette::{PaletteKind, PalettedContainer},
+    pos::{BlockPos, ChunkPos},
+    registry::BlockRegistry,
+    section::ChunkSection,
+};
+
+/// 1.21.8 的存档数据版本
+pub const DATA_VERSION: i32 = 4440;
+
+/// 由转换代码直接处理的字段,其余字段保存在 `Chunk::extra`
+const KNOWN_KEYS: [&str; 9] = [
+    "DataVersion",
+    "xPos",
+    "zPos",
+    "yPos",
+    "sections",
+    "block_entities",
+    "Heightmaps",
+    "isLightOn",
+    RANDOM_TICKS_KEY,
+];
+
+/// 预先安排的随机刻(原版没有此字段,会原样保留)
+const RANDOM_TICKS_KEY: &str = "qexed:random_ticks";
+
+/// 方块调色板的保存方式
+#[derive(Debug, Clone, Copy)]
+pub enum BlockFormat<'a> {
+    /// 原版格式,按方块名与属性保存,需要完整的方块注册表
+    Named(&'a BlockRegistry),
+    /// 直接保存状态ID(`palette` 为 IntArray),与注册表无关,只用于服务端自己的存储
+    StateIds,
+}
+
+fn corrupt(pos: ChunkPos, msg: impl std::fmt::Display) -> WorldError {
+    WorldError::CorruptChunk(format!("区块 {}: {}", pos, msg))
+}
+
+fn compounds(list: Option<&Vec<NbtTag>>) -> impl Iterator<Item = &NbtCompound> {
+    list.into_iter()
+        .flatten()
+        .filter_map(|t| t.extract_compound())
+}
+
+/// 将存档中的区块 NBT 转换为内存区块
+pub fn chunk_from_nbt(nbt: &NbtCompound, format: BlockFormat) -> Result<Chunk, WorldError> {
+    let (Some(x), Some(z)) = (nbt.get_int("xPos"), nbt.get_int("zPos")) else {
+        return Err(WorldError::CorruptChunk("缺少 xPos/zPos".to_string()));
+    };
+    let pos = ChunkPos::new(x, z);
+    let mut chunk = Chunk::new(pos, biome::PLAINS);
+
+    for section in compounds(nbt.get_list("sections")) {
+        let Some(y) = section.get_byte("Y") else {
+            continue;
+        };
+        let index = y as i32 - (MIN_Y >> 4);
+        if index < 0 || index as usize >= SECTION_COUNT {
+            // 世界高度之外的段落只存放光照
+            continue;
+        }
+        let blocks = match section.get_compound("block_states") {
+            Some(states) => read_blocks(states, format)?
+                .ok_or_else(|| corrupt(pos, format!("段落 {} 方块数据无效", y)))?,
+            None => PalettedContainer::single(PaletteKind::Blocks, crate::block::AIR),
+        };
+        let biomes = match section.get_compound("biomes") {
+            Some(biomes) => read_biomes(biomes)
+                .ok_or_else(|| corrupt(pos, format!("段落 {} 生物群系数据无效", y)))?,
+            None => PalettedContainer::single(PaletteKind::Biomes, biome::PLAINS),
+        };
+        chunk.sections[index as usize] = ChunkSection::from_containers(blocks, biomes);
+    }
+
+    for entity in compounds(nbt.get_list("block_entities")) {
+        let (Some(id), Some(x), Some(y), Some(z)) = (
+            entity.get_string("id"),
+            entity.get_int("x"),
+            entity.get_int("y"),
+            entity.get_int("z"),
+        ) else {
+            log::warn!("区块 {} 中存在无效的方块实体,已忽略", pos);
+            continue;
+        };
+        let data = entity
+            .child_tags
+            .iter()
+            .filter(|(k, _)| !matches!(k.as_str(), "id" | "x" | "y" | "z" | "keepPacked"))
+            .cloned()
+            .collect();
+        chunk.block_entities.insert(
+            BlockPos::new(x, y, z),
+            BlockEntity {
+                id: id.clone(),
+                pos: BlockPos::new(x, y, z),
+                data,
+            },
+        );
+    }
+
+    for tick in compounds(nbt.get_list(RANDOM_TICKS_KEY)) {
+        if let (Some(x), Some(y), Some(z), Some(due)) = (
+            tick.get_int("x"),
+            tick.get_int("y"),
+            tick.get_int("z"),
+            tick.get_long("t"),
+        ) {
+            chunk.random_ticks.insert(BlockPos::new(x, y, z), due);
+        }
+    }
+
+    // 存档中的高度图不完整时重新计算
+    let saved = nbt.get_compound("Heightmaps").and_then(|heightmaps| {
+        let arrays: Vec<(&str, Vec<u64>)> = heightmaps
+            .child_tags
+            .iter()
+            .filter_map(|(name, tag)| {
+                let data = tag.extract_long_array()?;
+                Some((name.as_str(), data.iter().map(|v| *v as u64).collect()))
+            })
+            .collect();
+        Heightmaps::from_packed(|name| {
+            arrays
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, d)| d.as_slice())
+        })
+    });
+    match saved {
+        Some(heightmaps) => chunk.heightmaps = heightmaps,
+        None => chunk.recompute_heightmaps(),
+    }
+
+    chunk.extra = nbt
+        .child_tags
+        .iter()
+        .filter(|(k, _)| !KNOWN_KEYS.contains(&k.as_str()))
+        .cloned()
+        .collect();
+    Ok(chunk)
+}
+
+/// 读取方块调色板与数据,注册表中没有的方块返回错误而不是替换为空气
+fn read_blocks(
+    states: &NbtCompound,
+    format: BlockFormat,
+) -> Result<Option<PalettedContainer>, WorldError> {
+    let data = states
+        .get_long_array("data")
+        .map(|d| d.iter().map(|v| *v as u64).collect())
+        .unwrap_or_default();
+    let registry = match format {
+        BlockFormat::Named(registry) => registry,
+        BlockFormat::StateIds => {
+            let Some(palette) = states.get_int_array("palette") else {
+                return Ok(None);
+            };
+            let palette = palette.iter().map(|id| *id as BlockState).collect();
+            return Ok(PalettedContainer::from_parts(
+                PaletteKind::Blocks,
+                palette,
+                data,
+            ));
+        }
+    };
+    let mut palette = Vec::new();
+    for entry in compounds(states.get_list("palette")) {
+        let Some(name) = entry.get_string("Name") else {
+            return Ok(None);
+        };
+        let properties: Vec<(String, String)> = entry
+            .get_compound("Properties")
+            .map(|p| {
+                p.child_tags
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.extract_string()?.clone())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let id = registry
+            .state_id(name, &properties)
+            .ok_or_else(|| WorldError::UnknownBlockState(name.clone()))?;
+        palette.push(id);
+    }
+    Ok(PalettedContainer::from_parts(
+        PaletteKind::Blocks,
+        palette,
+        data,
+    ))
+}
+
+fn read_biomes(biomes: &NbtCompound) -> Option<PalettedContainer> {
+    let palette: Vec<Biome> = biomes
+        .get_list("palette")?
+        .iter()
+        .filter_map(|t| t.extract_string())
+        .map(|name| {
+            biome::biome_id(name).unwrap_or_else(|| {
+                log::warn!("未知的生物群系 {},使用平原代替", name);
+                biome::PLAINS
+            })
+        })
+        .collect();
+    let data = biomes
+        .get_long_array("data")
+        .map(|d| d.iter().map(|v| *v as u64).collect())
+        .unwrap_or_default();
+    PalettedContainer::from_parts(PaletteKind::Biomes, palette, data)
+}
+
+/// 将内存区块转换为存档 NBT
+///
+/// 注册表中没有的方块状态返回错误,不会保存为空气。
+pub fn chunk_to_nbt(chunk: &Chunk, format: BlockFormat) -> Result<NbtCompound, WorldError> {
+    let mut nbt = NbtCompound::new();
+    nbt.put("DataVersion".to_string(), DATA_VERSION);
+    nbt.put("xPos".to_string(), chunk.pos.x);
+    nbt.put("zPos".to_string(), chunk.pos.z);
+    nbt.put("yPos".to_string(), MIN_Y >> 4);
+    // 光照尚未保存,让原版在加载时重新计算
+    nbt.put("isLightOn".to_string(), false);
+
+    let sections = chunk
+        .sections
+        .iter()
+        .enumerate()
+        .map(|(i, section)| {
+            let mut tag = NbtCompound::new();
+            tag.put("Y".to_string(), (i as i32 + (MIN_Y >> 4)) as i8);
+            tag.put(
+                "block_states".to_string(),
+                write_blocks(&section.blocks, format)?,
+            );
+            tag.put("biomes".to_string(), write_biomes(&section.biomes));
+            Ok(NbtTag::Compound(tag))
+        })
+        .collect::<Result<Vec<_>, WorldError>>()?;
+    nbt.put("sections".to_string(), sections);
+
+    let block_entities = chunk
+        .block_entities
+        .values()
+        .map(|e| {
+            let mut tag = NbtCompound::new();
+            tag.put("id".to_string(), e.id.clone());
+            tag.put("x".to_string(), e.pos.x);
+            tag.put("y".to_string(), e.pos.y);
+            tag.put("z".to_string(), e.pos.z);
+            tag.put("keepPacked".to_string(), false);
+            for (k, v) in &e.data.child_tags {
+                tag.put(k.clone(), v.clone());
+            }
+            NbtTag::Compound(tag)
+        })
+        .collect::<Vec<_>>();
+    nbt.put("block_entities".to_string(), block_entities);
+
+    if !chunk.random_ticks.is_empty() {
+        let ticks = chunk
+            .random_ticks
+            .iter()
+            .map(|(pos, due)| {
+                let mut tag = NbtCompound::new();
+                tag.put("x".to_string(), pos.x);
+                tag.put("y".to_string(), pos.y);
+                tag.put("z".to_string(), pos.z);
+                tag.put("t".to_string(), *due);
+                NbtTag::Compound(tag)
+            })
+            .collect::<Vec<_>>();
+        nbt.put(RANDOM_TICKS_KEY.to_string(), ticks);
+    }
+
+    let heightmaps: NbtCompound = chunk
+        .heightmaps
+        .to_packed()
+        .into_iter()
+        .map(|(k, v)| {
+            (
+                k.to_string(),
+                NbtTag::LongArray(v.iter().map(|l| *l as i64).collect()),
+            )
+        })
+        .collect();
+    nbt.put("Heightmaps".to_string(), heightmaps);
+
+    if chunk.extra.get("Status").is_none() {
+        nbt.put("Status".to_string(), "minecraft:full");
+    }
+    for (k, v) in &chunk.extra.child_tags {
+        nbt.put(k.clone(), v.clone());
+    }
+    Ok(nbt)
+}
+
+fn write_blocks(
+    blocks: &PalettedContainer,
+    format: BlockFormat,
+) -> Result<NbtCompound, WorldError> {
+    let (palette, data) = blocks.to_parts();
+    let palette = match format {
+        BlockFormat::Named(registry) => NbtTag::List(
+            palette
+                .into_iter()
+                .map(|id| {
+                    let info = registry
+                        .state_info(id)
+                        .ok_or_else(|| WorldError::UnknownBlockState(format!("#{}", id)))?;
+                    let mut entry = NbtCompound::new();
+                    entry.put("Name".to_string(), info.name);
+                    if !info.properties.is_empty() {
+                        let props: NbtCompound = info
+                            .properties
+                            .into_iter()
+                            .map(|(k, v)| (k, NbtTag::String(v)))
+                            .collect();
+                        entry.put("Properties".to_string(), props);
+                    }
+                    Ok(NbtTag::Compound(entry))
+                })
+                .collect::<Result<Vec<_>, WorldError>>()?,
+        ),
+        BlockFormat::StateIds => {
+            NbtTag::IntArray(palette.into_iter().map(|id| id as i32).collect())
+        }
+    };
+    let mut tag = NbtCompound::new();
+    tag.put("palette".to_string(), palette);
+    if !data.is_empty() {
+        tag.put(
+            "data".to_string(),
+            NbtTag::LongArray(data.into_iter().map(|l| l as i64).collect()),
+        );
+    }
+    Ok(tag)
+}
+
+fn write_biomes(biomes: &PalettedContainer) -> NbtCompound {
+    let (palette, data) = biomes.to_parts();
+    let palette = palette
+        .into_iter()
+        .map(|id| {
+            NbtTag::String(
+                biome::biome_name(id)
+                    .unwrap_or("minecraft:plains")
+                    .to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut tag = NbtCompound::new();
+    tag.put("palette".to_string(), palette);
+    if !data.is_empty() {
+        tag.put(
+            "data".to_string(),
+            NbtTag::LongArray(data.into_iter().map(|l| l as i64).collect()),
+        );
+    }
+    tag
+}
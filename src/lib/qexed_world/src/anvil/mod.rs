@@ -0,0 +1,238 @@
+//! Anvil 存档格式
+//!
+//! 读写原版与 Paper 的 `region/*.mca` 区域文件,保证存档可以与原版互相迁移。
+//! 方块按名称与属性保存,只有完整的原版方块表才能无损转换,因此不完整的注册表无法打开存档。
+
+pub mod compression;
+pub mod convert;
+pub mod region;
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+pub use compression::Compression;
+pub use convert::{BlockFormat, DATA_VERSION, chunk_from_nbt, chunk_to_nbt};
+pub use region::RegionFile;
+
+use crate::{
+    chunk::Chunk,
+    error::WorldError,
+    pos::ChunkPos,
+    registry::{BlockRegistry, block_registry},
+    storage::ChunkStorage,
+};
+
+/// 一个维度的 Anvil 存档(维度目录下的 `region` 文件夹)
+pub struct AnvilStorage {
+    dir: PathBuf,
+    compression: Compression,
+    regions: Mutex<HashMap<(i32, i32), RegionFile>>,
+    registry: &'static BlockRegistry,
+}
+
+impl AnvilStorage {
+    /// `dimension_dir` 为维度目录,例如 `world` 或 `world/DIM-1`
+    ///
+    /// 使用全局方块注册表,它不是完整的原版方块表时返回错误。
+    pub fn new(dimension_dir: impl AsRef<Path>) -> Result<Self, WorldError> {
+        Self::with_registry(dimension_dir, block_registry())
+    }
+
+    /// 使用指定的方块注册表
+    pub fn with_registry(
+        dimension_dir: impl AsRef<Path>,
+        registry: &'static BlockRegistry,
+    ) -> Result<Self, WorldError> {
+        if !registry.is_complete() {
+            return Err(WorldError::BlockRegistry(
+                "只有内置方块表,无法导入原版存档".to_string(),
+            ));
+        }
+        Ok(AnvilStorage {
+            dir: dimension_dir.as_ref().join("region"),
+            compression: Compression::default(),
+            regions: Mutex::new(HashMap::new()),
+            registry,
+        })
+    }
+
+    /// 设置保存时使用的压缩方式
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    fn with_region<T>(
+        &self,
+        pos: ChunkPos,
+        create: bool,
+        f: impl FnOnce(&mut RegionFile) -> Result<T, WorldError>,
+    ) -> Result<Option<T>, WorldError> {
+        let key = region::region_of(pos);
+        let mut regions = self.regions.lock().unwrap();
+        let region = match regions.entry(key) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let path = self.dir.join(region::region_file_name(key.0, key.1));
+                if !create && !path.exists() {
+                    return Ok(None);
+                }
+                e.insert(RegionFile::open(path)?)
+            }
+        };
+        f(region).map(Some)
+    }
+
+    /// 读取区块,存档中没有时返回 `None`
+    pub fn load_chunk(&self, pos: ChunkPos) -> Result<Option<Chunk>, WorldError> {
+        let nbt = self
+            .with_region(pos, false, |r| r.read_chunk(pos))?
+            .flatten();
+        match nbt {
+            Some(nbt) => {
+                let chunk = chunk_from_nbt(&nbt, BlockFormat::Named(self.registry))?;
+                if chunk.pos != pos {
+                    log::warn!("区块 {} 的存档坐标为 {}", pos, chunk.pos);
+                }
+                Ok(Some(chunk))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// 保存区块
+    pub fn save_chunk(&self, chunk: &Chunk) -> Result<(), WorldError> {
+        let nbt = chunk_to_nbt(chunk, BlockFormat::Named(self.registry))?;
+        let compression = self.compression;
+        self.with_region(chunk.pos, true, |r| {
+            r.write_chunk(chunk.pos, nbt, compression)
+        })?;
+        Ok(())
+    }
+
+    /// 删除区块
+    pub fn delete_chunk(&self, pos: ChunkPos) -> Result<(), WorldError> {
+        self.with_region(pos, false, |r| r.delete_chunk(pos))?;
+        Ok(())
+    }
+
+    /// 存档中是否有该区块
+    pub fn has_chunk(&self, pos: ChunkPos) -> Result<bool, WorldError> {
+        Ok(self
+            .with_region(pos, false, |r| Ok(r.has_chunk(pos)))?
+            .unwrap_or(false))
+    }
+
+    /// 将所有区域文件写入磁盘
+    pub fn flush(&self) -> Result<(), WorldError> {
+        for region in self.regions.lock().unwrap().values_mut() {
+            region.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl ChunkStorage for AnvilStorage {
+    fn load_chunk(&self, pos: ChunkPos) -> Result<Option<Chunk>, WorldError> {
+        AnvilStorage::load_chunk(self, pos)
+    }
+
+    fn save_chunk(&self, chunk: &Chunk) -> Result<(), WorldError> {
+        AnvilStorage::save_chunk(self, chunk)
+    }
+
+    fn delete_chunk(&self, pos: ChunkPos) -> Result<(), WorldError> {
+        AnvilStorage::delete_chunk(self, pos)
+    }
+
+    fn flush(&self) -> Result<(), WorldError> {
+        AnvilStorage::flush(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{block, pos::BlockPos};
+
+    /// 只包含测试用到的方块的原版格式报告
+    fn registry() -> &'static BlockRegistry {
+        let report = r#"{
+            "minecraft:air": {"states": [{"id": 0, "default": true}]},
+            "minecraft:stone": {"states": [{"id": 1, "default": true}]},
+            "minecraft:water": {"states": [
+                {"id": 86, "default": true, "properties": {"level": "0"}},
+                {"id": 87, "properties": {"level": "1"}},
+                {"id": 88, "properties": {"level": "2"}}
+            ]}
+        }"#;
+        Box::leak(Box::new(BlockRegistry::from_report(report).unwrap()))
+    }
+
+    #[test]
+    fn save_and_load_chunk() {
+        let dir = std::env::temp_dir().join(format!("qexed_anvil_{}", std::process::id()));
+        let pos = ChunkPos::new(-33, 5);
+        let mut chunk = Chunk::new(pos, crate::biome::PLAINS);
+        chunk.set_block(3, -60, 7, block::STONE);
+        chunk.set_block(3, 100, 7, block::WATER + 2);
+        chunk.set_biome(0, 0, 0, crate::biome::THE_VOID);
+        chunk.extra.put("InhabitedTime".to_string(), 42i64);
+        chunk.schedule_random_tick(BlockPos::new(pos.min_block_x() + 3, 64, pos.min_block_z()), 1_700_000_000_000);
+        chunk.set_block_entity(crate::chunk::BlockEntity {
+            id: "minecraft:chest".to_string(),
+            pos: BlockPos::new(pos.min_block_x(), 70, pos.min_block_z()),
+            data: Default::default(),
+        });
+
+        for compression in [Compression::Zlib, Compression::Lz4] {
+            let storage = AnvilStorage::with_registry(&dir, registry())
+                .unwrap()
+                .with_compression(compression);
+            storage.save_chunk(&chunk).unwrap();
+            let loaded = storage.load_chunk(pos).unwrap().unwrap();
+            assert!(loaded.sections.iter().zip(&chunk.sections).all(|(a, b)| {
+                a.blocks.iter().eq(b.blocks.iter()) && a.biomes.iter().eq(b.biomes.iter())
+            }));
+            assert_eq!(loaded.block_entities, chunk.block_entities);
+            assert_eq!(loaded.extra.get_long("InhabitedTime"), Some(42));
+            assert_eq!(loaded.random_ticks, chunk.random_ticks);
+            assert!(storage.load_chunk(ChunkPos::new(0, 0)).unwrap().is_none());
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn builtin_registry_cannot_import() {
+        let builtin = Box::leak(Box::new(BlockRegistry::builtin()));
+        assert!(matches!(
+            AnvilStorage::with_registry(std::env::temp_dir(), builtin),
+            Err(WorldError::BlockRegistry(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_block_states_are_errors() {
+        let registry = BlockRegistry::builtin();
+        let pos = ChunkPos::new(0, 0);
+        let mut chunk = Chunk::new(pos, crate::biome::PLAINS);
+        chunk.set_block(0, 64, 0, 60_000);
+        assert!(matches!(
+            chunk_to_nbt(&chunk, BlockFormat::Named(&registry)),
+            Err(WorldError::UnknownBlockState(_))
+        ));
+
+        let log = registry.register_test_state("oak_log", &[]);
+        chunk.set_block(0, 64, 0, log);
+        let nbt = chunk_to_nbt(&chunk, BlockFormat::Named(&registry)).unwrap();
+        assert!(chunk_from_nbt(&nbt, BlockFormat::Named(&registry)).is_ok());
+        // 注册表中没有的方块不会被替换为空气
+        assert!(matches!(
+            chunk_from_nbt(&nbt, BlockFormat::Named(&BlockRegistry::builtin())),
+            Err(WorldError::UnknownBlockState(name)) if name == "minecraft:oak_log"
+        ));
+    }
+}
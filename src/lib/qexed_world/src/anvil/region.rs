@@ -0,0 +1,278 @@
+//! 区域文件(`r.<x>.<z>.mca`)
+//!
+//! 文件以 4KiB 扇区为单位: 第 0 扇区是 1024 个位置项(3 字节扇区偏移 + 1 字节扇区数),
+//! 第 1 扇区是 1024 个修改时间戳,之后是区块数据。每个区块数据以 4 字节长度、
+//! 1 字节压缩方式开头。超过 255 个扇区的区块写入同目录下的 `c.<x>.<z>.mcc`。
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crab_nbt::{Nbt, NbtCompound};
+
+use crate::{
+    anvil::compression::{Compression, EXTERNAL_FLAG},
+    error::WorldError,
+    pos::ChunkPos,
+};
+
+pub const SECTOR_SIZE: usize = 4096;
+const HEADER_SECTORS: usize = 2;
+const MAX_SECTOR_COUNT: usize = 255;
+
+/// 区域文件名
+pub fn region_file_name(region_x: i32, region_z: i32) -> String {
+    format!("r.{}.{}.mca", region_x, region_z)
+}
+
+/// 区块所在的区域坐标
+pub fn region_of(pos: ChunkPos) -> (i32, i32) {
+    (pos.x >> 5, pos.z >> 5)
+}
+
+/// 区块在区域文件中的下标
+fn chunk_index(pos: ChunkPos) -> usize {
+    ((pos.x & 31) + (pos.z & 31) * 32) as usize
+}
+
+pub struct RegionFile {
+    path: PathBuf,
+    file: File,
+    locations: [u32; 1024],
+    timestamps: [u32; 1024],
+    /// 扇区是否被占用
+    used: Vec<bool>,
+}
+
+impl RegionFile {
+    /// 打开区域文件,不存在时创建
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, WorldError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        let len = file.metadata()?.len() as usize;
+        let mut header = vec![0u8; SECTOR_SIZE * HEADER_SECTORS];
+        if len < header.len() {
+            // 新文件或残缺的文件头
+            file.set_len(header.len() as u64)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&header)?;
+        } else {
+            file.seek(SeekFrom::Start(0))?;
+            file.read_exact(&mut header)?;
+        }
+        let mut locations = [0u32; 1024];
+        let mut timestamps = [0u32; 1024];
+        for i in 0..1024 {
+            locations[i] = u32::from_be_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+            let t = SECTOR_SIZE + i * 4;
+            timestamps[i] = u32::from_be_bytes(header[t..t + 4].try_into().unwrap());
+        }
+        let total = len.max(header.len()).div_ceil(SECTOR_SIZE);
+        let mut used = vec![false; total];
+        used[..HEADER_SECTORS].fill(true);
+        for location in locations.iter_mut() {
+            let (offset, count) = ((*location >> 8) as usize, (*location & 0xFF) as usize);
+            if *location == 0 {
+                continue;
+            }
+            if offset < HEADER_SECTORS || offset + count > total {
+                log::warn!("区域文件 {} 中存在越界的区块位置,已忽略", path.display());
+                *location = 0;
+                continue;
+            }
+            used[offset..offset + count].fill(true);
+        }
+        Ok(RegionFile {
+            path,
+            file,
+            locations,
+            timestamps,
+            used,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 区域文件中是否存在该区块
+    pub fn has_chunk(&self, pos: ChunkPos) -> bool {
+        self.locations[chunk_index(pos)] != 0
+    }
+
+    /// 区块的最后修改时间(Unix 秒)
+    pub fn timestamp(&self, pos: ChunkPos) -> u32 {
+        self.timestamps[chunk_index(pos)]
+    }
+
+    /// 区域内已保存的区块(区域内局部坐标)
+    pub fn chunks(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (0..1024)
+            .filter(|i| self.locations[*i] != 0)
+            .map(|i| ((i % 32) as i32, (i / 32) as i32))
+    }
+
+    fn external_path(&self, pos: ChunkPos) -> PathBuf {
+        self.path
+            .with_file_name(format!("c.{}.{}.mcc", pos.x, pos.z))
+    }
+
+    /// 读取区块的原始 NBT,不存在时返回 `None`
+    pub fn read_chunk(&mut self, pos: ChunkPos) -> Result<Option<NbtCompound>, WorldError> {
+        let location = self.locations[chunk_index(pos)];
+        if location == 0 {
+            return Ok(None);
+        }
+        let offset = (location >> 8) as u64 * SECTOR_SIZE as u64;
+        let count = (location & 0xFF) as usize;
+        let mut buf = vec![0u8; count * SECTOR_SIZE];
+        self.file.seek(SeekFrom::Start(offset))?;
+        let read = read_up_to(&mut self.file, &mut buf)?;
+        if read < 5 {
+            return Err(WorldError::CorruptChunk(format!(
+                "区块 {} 数据头不完整",
+                pos
+            )));
+        }
+        let length = u32::from_be_bytes(buf[0..4].try_into().unwrap()) as usize;
+        if length == 0 || length + 4 > read {
+            return Err(WorldError::CorruptChunk(format!(
+                "区块 {} 长度 {} 无效",
+                pos, length
+            )));
+        }
+        let kind = buf[4];
+        let payload = if kind & EXTERNAL_FLAG != 0 {
+            fs::read(self.external_path(pos))?
+        } else {
+            buf[5..4 + length].to_vec()
+        };
+        let data = Compression::try_from(kind & !EXTERNAL_FLAG)?.decompress(&payload)?;
+        if data.is_empty() {
+            return Err(WorldError::CorruptChunk(format!("区块 {} 数据为空", pos)));
+        }
+        Ok(Some(Nbt::read(&mut data.as_slice())?.root_tag))
+    }
+
+    /// 写入区块 NBT
+    pub fn write_chunk(
+        &mut self,
+        pos: ChunkPos,
+        nbt: NbtCompound,
+        compression: Compression,
+    ) -> Result<(), WorldError> {
+        let raw = Nbt::new(String::new(), nbt).write();
+        let payload = compression.compress(&raw)?;
+        let external = self.external_path(pos);
+        let mut data = Vec::with_capacity(payload.len() + 5);
+        if (payload.len() + 5).div_ceil(SECTOR_SIZE) > MAX_SECTOR_COUNT {
+            // 太大的区块写入外部文件,区域文件中只保留一个标记
+            fs::write(&external, &payload)?;
+            data.extend_from_slice(&1u32.to_be_bytes());
+            data.push(compression as u8 | EXTERNAL_FLAG);
+        } else {
+            if external.exists() {
+                fs::remove_file(&external)?;
+            }
+            data.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+            data.push(compression as u8);
+            data.extend_from_slice(&payload);
+        }
+        let count = data.len().div_ceil(SECTOR_SIZE);
+        data.resize(count * SECTOR_SIZE, 0);
+
+        let index = chunk_index(pos);
+        self.free(index);
+        let offset = self.allocate(count);
+        self.file
+            .seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))?;
+        self.file.write_all(&data)?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or_default();
+        self.locations[index] = ((offset as u32) << 8) | count as u32;
+        self.timestamps[index] = now;
+        self.write_header_entry(index)
+    }
+
+    /// 删除区块
+    pub fn delete_chunk(&mut self, pos: ChunkPos) -> Result<(), WorldError> {
+        let index = chunk_index(pos);
+        self.free(index);
+        self.locations[index] = 0;
+        self.timestamps[index] = 0;
+        let external = self.external_path(pos);
+        if external.exists() {
+            fs::remove_file(external)?;
+        }
+        self.write_header_entry(index)
+    }
+
+    pub fn flush(&mut self) -> Result<(), WorldError> {
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    fn free(&mut self, index: usize) {
+        let location = self.locations[index];
+        if location != 0 {
+            let (offset, count) = ((location >> 8) as usize, (location & 0xFF) as usize);
+            self.used[offset..offset + count].fill(false);
+        }
+    }
+
+    /// 首次适配分配连续扇区,没有空位时追加到文件末尾
+    fn allocate(&mut self, count: usize) -> usize {
+        let mut run = 0;
+        for i in HEADER_SECTORS..self.used.len() {
+            if self.used[i] {
+                run = 0;
+                continue;
+            }
+            run += 1;
+            if run == count {
+                let start = i + 1 - count;
+                self.used[start..=i].fill(true);
+                return start;
+            }
+        }
+        let start = self.used.len() - run;
+        self.used.resize(start + count, false);
+        self.used[start..].fill(true);
+        start
+    }
+
+    fn write_header_entry(&mut self, index: usize) -> Result<(), WorldError> {
+        self.file.seek(SeekFrom::Start((index * 4) as u64))?;
+        self.file.write_all(&self.locations[index].to_be_bytes())?;
+        self.file
+            .seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        self.file.write_all(&self.timestamps[index].to_be_bytes())?;
+        Ok(())
+    }
+}
+
+/// 尽量读满缓冲区,返回实际读取的字节数(文件末尾的区块可能没有补齐扇区)
+fn read_up_to(file: &mut File, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read(&mut buf[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
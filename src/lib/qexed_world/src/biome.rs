@@ -0,0 +1,143 @@
+//! 生物群系
+//!
+//! 生物群系ID即 RegistryData 中 `minecraft:worldgen/biome` 注册表的条目顺序,
+//! 必须与 `qexed_core::registry` 下发给客户端的顺序保持一致。
+
+/// 生物群系注册表ID
+pub type Biome = u32;
+
+/// 按注册表顺序排列的生物群系
+pub const BIOMES: [&str; 65] = [
+    "minecraft:badlands",
+    "minecraft:bamboo_jungle",
+    "minecraft:basalt_deltas",
+    "minecraft:beach",
+    "minecraft:birch_forest",
+    "minecraft:cherry_grove",
+    "minecraft:cold_ocean",
+    "minecraft:crimson_forest",
+    "minecraft:dark_forest",
+    "minecraft:deep_cold_ocean",
+    "minecraft:deep_dark",
+    "minecraft:deep_frozen_ocean",
+    "minecraft:deep_lukewarm_ocean",
+    "minecraft:deep_ocean",
+    "minecraft:desert",
+    "minecraft:dripstone_caves",
+    "minecraft:end_barrens",
+    "minecraft:end_highlands",
+    "minecraft:end_midlands",
+    "minecraft:eroded_badlands",
+    "minecraft:flower_forest",
+    "minecraft:forest",
+    "minecraft:frozen_ocean",
+    "minecraft:frozen_peaks",
+    "minecraft:frozen_river",
+    "minecraft:grove",
+    "minecraft:ice_spikes",
+    "minecraft:jagged_peaks",
+    "minecraft:jungle",
+    "minecraft:lukewarm_ocean",
+    "minecraft:lush_caves",
+    "minecraft:mangrove_swamp",
+    "minecraft:meadow",
+    "minecraft:mushroom_fields",
+    "minecraft:nether_wastes",
+    "minecraft:ocean",
+    "minecraft:old_growth_birch_forest",
+    "minecraft:old_growth_pine_taiga",
+    "minecraft:old_growth_spruce_taiga",
+    "minecraft:pale_garden",
+    "minecraft:plains",
+    "minecraft:river",
+    "minecraft:savanna",
+    "minecraft:savanna_plateau",
+    "minecraft:small_end_islands",
+    "minecraft:snowy_beach",
+    "minecraft:snowy_plains",
+    "minecraft:snowy_slopes",
+    "minecraft:snowy_taiga",
+    "minecraft:soul_sand_valley",
+    "minecraft:sparse_jungle",
+    "minecraft:stony_peaks",
+    "minecraft:stony_shore",
+    "minecraft:sunflower_plains",
+    "minecraft:swamp",
+    "minecraft:taiga",
+    "minecraft:the_end",
+    "minecraft:the_void",
+    "minecraft:warm_ocean",
+    "minecraft:warped_forest",
+    "minecraft:windswept_forest",
+    "minecraft:windswept_gravelly_hills",
+    "minecraft:windswept_hills",
+    "minecraft:windswept_savanna",
+    "minecraft:wooded_badlands",
+];
+
+pub const BEACH: Biome = 3;
+pub const BIRCH_FOREST: Biome = 4;
+pub const COLD_OCEAN: Biome = 6;
+pub const DARK_FOREST: Biome = 8;
+pub const DEEP_COLD_OCEAN: Biome = 9;
+pub const DEEP_FROZEN_OCEAN: Biome = 11;
+pub const DEEP_LUKEWARM_OCEAN: Biome = 12;
+pub const DEEP_OCEAN: Biome = 13;
+pub const DESERT: Biome = 14;
+pub const END_BARRENS: Biome = 16;
+pub const END_HIGHLANDS: Biome = 17;
+pub const END_MIDLANDS: Biome = 18;
+pub const FLOWER_FOREST: Biome = 20;
+pub const FOREST: Biome = 21;
+pub const FROZEN_OCEAN: Biome = 22;
+pub const FROZEN_PEAKS: Biome = 23;
+pub const JAGGED_PEAKS: Biome = 27;
+pub const JUNGLE: Biome = 28;
+pub const LUKEWARM_OCEAN: Biome = 29;
+pub const NETHER_WASTES: Biome = 34;
+pub const OCEAN: Biome = 35;
+pub const OLD_GROWTH_SPRUCE_TAIGA: Biome = 38;
+pub const PLAINS: Biome = 40;
+pub const SAVANNA: Biome = 42;
+pub const SMALL_END_ISLANDS: Biome = 44;
+pub const SNOWY_BEACH: Biome = 45;
+pub const SNOWY_PLAINS: Biome = 46;
+pub const SNOWY_TAIGA: Biome = 48;
+pub const STONY_PEAKS: Biome = 51;
+pub const STONY_SHORE: Biome = 52;
+pub const TAIGA: Biome = 55;
+pub const THE_END: Biome = 56;
+pub const THE_VOID: Biome = 57;
+pub const WARM_OCEAN: Biome = 58;
+
+/// 根据名字获取生物群系ID(可省略 `minecraft:` 命名空间)
+pub fn biome_id(name: &str) -> Option<Biome> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    BIOMES
+        .iter()
+        .position(|b| b.strip_prefix("minecraft:") == Some(name))
+        .map(|i| i as Biome)
+}
+
+/// 根据ID获取生物群系名
+pub fn biome_name(id: Biome) -> Option<&'static str> {
+    BIOMES.get(id as usize).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constants_match_registry() {
+        for (id, name) in [
+            (PLAINS, "plains"),
+            (DEEP_OCEAN, "deep_ocean"),
+            (WARM_OCEAN, "warm_ocean"),
+            (NETHER_WASTES, "nether_wastes"),
+            (SMALL_END_ISLANDS, "small_end_islands"),
+        ] {
+            assert_eq!(biome_id(name), Some(id));
+        }
+    }
+}
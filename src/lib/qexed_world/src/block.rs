@@ -0,0 +1,92 @@
+//! 方块状态
+//!
+//! 区块内部统一使用 1.21.8 的全局方块状态ID(即网络协议中直接调色板使用的ID)。
+//! 这里只列出服务端自身会用到的少量方块状态。
+
+/// 全局方块状态ID
+pub type BlockState = u32;
+
+pub const AIR: BlockState = 0;
+pub const STONE: BlockState = 1;
+pub const GRANITE: BlockState = 2;
+pub const DIORITE: BlockState = 4;
+pub const ANDESITE: BlockState = 6;
+/// 草方块(snowy=false)
+pub const GRASS_BLOCK: BlockState = 9;
+pub const DIRT: BlockState = 10;
+pub const COBBLESTONE: BlockState = 14;
+pub const OAK_PLANKS: BlockState = 15;
+pub const BEDROCK: BlockState = 85;
+/// 水源(level=0)
+pub const WATER: BlockState = 86;
+/// 岩浆源(level=0)
+pub const LAVA: BlockState = 102;
+pub const SAND: BlockState = 118;
+pub const RED_SAND: BlockState = 123;
+pub const GRAVEL: BlockState = 124;
+
+/// 是否是空气
+#[inline]
+pub fn is_air(state: BlockState) -> bool {
+    state == AIR
+}
+
+/// 按注册表顺序排列的方块实体类型(`minecraft:block_entity_type`)
+pub const BLOCK_ENTITY_TYPES: [&str; 47] = [
+    "minecraft:furnace",
+    "minecraft:chest",
+    "minecraft:trapped_chest",
+    "minecraft:ender_chest",
+    "minecraft:jukebox",
+    "minecraft:dispenser",
+    "minecraft:dropper",
+    "minecraft:sign",
+    "minecraft:hanging_sign",
+    "minecraft:mob_spawner",
+    "minecraft:creaking_heart",
+    "minecraft:piston",
+    "minecraft:brewing_stand",
+    "minecraft:enchanting_table",
+    "minecraft:end_portal",
+    "minecraft:beacon",
+    "minecraft:skull",
+    "minecraft:daylight_detector",
+    "minecraft:hopper",
+    "minecraft:comparator",
+    "minecraft:banner",
+    "minecraft:structure_block",
+    "minecraft:end_gateway",
+    "minecraft:command_block",
+    "minecraft:shulker_box",
+    "minecraft:bed",
+    "minecraft:conduit",
+    "minecraft:barrel",
+    "minecraft:smoker",
+    "minecraft:blast_furnace",
+    "minecraft:lectern",
+    "minecraft:bell",
+    "minecraft:jigsaw",
+    "minecraft:campfire",
+    "minecraft:beehive",
+    "minecraft:sculk_sensor",
+    "minecraft:calibrated_sculk_sensor",
+    "minecraft:sculk_catalyst",
+    "minecraft:sculk_shrieker",
+    "minecraft:chiseled_bookshelf",
+    "minecraft:brushable_block",
+    "minecraft:decorated_pot",
+    "minecraft:crafter",
+    "minecraft:trial_spawner",
+    "minecraft:vault",
+    "minecraft:test_block",
+    "minecraft:test_instance_block",
+];
+
+/// 根据名字获取方块实体类型ID(可省略 `minecraft:` 命名空间)
+pub fn block_entity_type_id(name: &str) -> Option<i32> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    BLOCK_ENTITY_TYPES
+        .iter()
+        .position(|t| t.strip_prefix("minecraft:") == Some(name))
+        .map(|i| i as i32)
+}
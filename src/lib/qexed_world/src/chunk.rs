@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bytes::BytesMut;
+use crab_nbt::NbtCompound;
+use qexed_net::{
+    net_types::{block_entities::BlockEntities, var_int::VarInt},
+    packet::encode::PacketWriter,
+};
+
+use crate::{
+    MIN_Y, SECTION_COUNT,
+    biome::Biome,
+    block::{self, BlockState},
+    dimension::Dimension,
+    heightmap::Heightmaps,
+    light::ChunkLight,
+    pos::{BlockPos, ChunkPos},
+    section::ChunkSection,
+};
+
+/// 方块实体
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEntity {
+    /// 方块实体类型,例如 `minecraft:chest`
+    pub id: String,
+    /// 世界坐标
+    pub pos: BlockPos,
+    /// 额外数据(不含 id/x/y/z)
+    pub data: NbtCompound,
+}
+
+/// 区块列(从 y=-64 到 y=319,共 24 个段落)
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub pos: ChunkPos,
+    pub sections: Vec<ChunkSection>,
+    /// 方块实体,以世界坐标为键
+    pub block_entities: HashMap<BlockPos, BlockEntity>,
+    /// 高度图,随方块变化自动更新
+    pub heightmaps: Heightmaps,
+    /// 存档中未被解析的字段,保存时原样写回
+    pub extra: NbtCompound,
+    /// 光照(不保存,加载后重新计算)
+    pub light: ChunkLight,
+    /// 预先安排的随机刻:世界坐标 → 到期时间(Unix 毫秒),随区块保存
+    pub random_ticks: BTreeMap<BlockPos, i64>,
+    /// 自上次保存后是否被修改过
+    dirty: bool,
+    /// 自上次广播后变化过的方块
+    changes: Vec<BlockPos>,
+}
+
+impl Chunk {
+    /// 创建一个空区块(全是空气)
+    pub fn new(pos: ChunkPos, biome: Biome) -> Self {
+        Chunk {
+            pos,
+            sections: (0..SECTION_COUNT)
+                .map(|_| ChunkSection::new(biome))
+                .collect(),
+            block_entities: HashMap::new(),
+            heightmaps: Heightmaps::default(),
+            extra: NbtCompound::new(),
+            light: ChunkLight::new(),
+            random_ticks: BTreeMap::new(),
+            dirty: false,
+            changes: vec![],
+        }
+    }
+
+    /// y 坐标对应的段落下标
+    #[inline]
+    pub fn section_index(y: i32) -> Option<usize> {
+        let i = (y - MIN_Y) >> 4;
+        if i < 0 || i as usize >= SECTION_COUNT {
+            None
+        } else {
+            Some(i as usize)
+        }
+    }
+
+    /// 获取方块,x/z 为区块内局部坐标,y 为世界坐标;超出高度范围视为空气
+    pub fn get_block(&self, x: usize, y: i32, z: usize) -> BlockState {
+        match Self::section_index(y) {
+            Some(i) => self.sections[i].get_block(x, (y & 15) as usize, z),
+            None => block::AIR,
+        }
+    }
+
+    /// 设置方块,返回旧方块;超出高度范围返回 `None`
+    pub fn set_block(
+        &mut self,
+        x: usize,
+        y: i32,
+        z: usize,
+        state: BlockState,
+    ) -> Option<BlockState> {
+        let i = Self::section_index(y)?;
+        let old = self.sections[i].set_block(x, (y & 15) as usize, z, state);
+        if old != state {
+            self.heightmaps.update(&self.sections, x, y, z, state);
+            self.dirty = true;
+            self.changes.push(BlockPos::new(
+                self.pos.min_block_x() + x as i32,
+                y,
+                self.pos.min_block_z() + z as i32,
+            ));
+        }
+        Some(old)
+    }
+
+    pub fn get_biome(&self, x: usize, y: i32, z: usize) -> Option<Biome> {
+        let i = Self::section_index(y)?;
+        Some(self.sections[i].get_biome(x, (y & 15) as usize, z))
+    }
+
+    pub fn set_biome(&mut self, x: usize, y: i32, z: usize, biome: Biome) {
+        if let Some(i) = Self::section_index(y) {
+            self.sections[i].set_biome(x, (y & 15) as usize, z, biome);
+            self.dirty = true;
+        }
+    }
+
+    /// 放置或替换方块实体
+    pub fn set_block_entity(&mut self, entity: BlockEntity) {
+        self.block_entities.insert(entity.pos, entity);
+        self.dirty = true;
+    }
+
+    pub fn remove_block_entity(&mut self, pos: &BlockPos) -> Option<BlockEntity> {
+        let removed = self.block_entities.remove(pos);
+        if removed.is_some() {
+            self.dirty = true;
+        }
+        removed
+    }
+
+    /// 安排方块在 `due`(Unix 毫秒)时接受随机刻,覆盖之前的安排
+    pub fn schedule_random_tick(&mut self, pos: BlockPos, due: i64) {
+        self.random_ticks.insert(pos, due);
+        self.dirty = true;
+    }
+
+    /// 取消方块的随机刻安排
+    pub fn cancel_random_tick(&mut self, pos: &BlockPos) -> Option<i64> {
+        let removed = self.random_ticks.remove(pos);
+        if removed.is_some() {
+            self.dirty = true;
+        }
+        removed
+    }
+
+    /// 是否需要保存
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// 保存完成后清除脏标记
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+
+    /// 直接修改段落后重新计算高度图
+    pub fn recompute_heightmaps(&mut self) {
+        self.heightmaps = Heightmaps::compute(&self.sections);
+    }
+
+    /// 取出自上次调用后变化过的方块
+    pub fn take_changes(&mut self) -> Vec<BlockPos> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// 编码维度高度范围内的段落数据(LevelChunkWithLight 中的 Data 字段)
+    pub fn encode_sections(&self, dimension: Dimension) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        let mut w = PacketWriter::new(&mut buf);
+        for section in &self.sections[dimension.sections()] {
+            section.write(&mut w);
+        }
+        buf.to_vec()
+    }
+
+    /// 转换为网络协议中的区块数据
+    pub fn to_network(&self, dimension: Dimension) -> qexed_net::net_types::chunk::Chunk {
+        let height = dimension.min_y()..dimension.min_y() + dimension.height();
+        qexed_net::net_types::chunk::Chunk {
+            heightmaps: self.heightmaps.to_network(dimension),
+            data: self.encode_sections(dimension),
+            block_entities: self
+                .block_entities
+                .values()
+                .filter(|e| height.contains(&e.pos.y))
+                .filter_map(|e| {
+                    block::block_entity_type_id(&e.id).map(|type_id| BlockEntities {
+                        xz: (((e.pos.x & 15) << 4) | (e.pos.z & 15)) as u8,
+                        y: e.pos.y as i16 as u16,
+                        entity_type: VarInt(type_id),
+                        nbt: crab_nbt::Nbt::new(String::new(), e.data.clone()),
+                    })
+                })
+                .collect(),
+        }
+    }
+}
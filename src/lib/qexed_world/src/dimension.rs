@@ -0,0 +1,123 @@
+//! 维度
+//!
+//! 区块在内存中总是按主世界的高度(y=-64..319)存放,下界与末地只使用其中 y=0..255 的部分,
+//! 发送给客户端时再按维度的高度截取段落。
+
+use std::ops::Range;
+
+use crate::{MIN_Y, SECTION_COUNT};
+
+/// 原版维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum Dimension {
+    #[default]
+    Overworld,
+    Nether,
+    End,
+}
+
+impl Dimension {
+    pub const ALL: [Dimension; 3] = [Dimension::Overworld, Dimension::Nether, Dimension::End];
+
+    /// 维度名(LoginPlay/Respawn 中的 dimension_name)
+    pub const fn name(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "minecraft:overworld",
+            Dimension::Nether => "minecraft:the_nether",
+            Dimension::End => "minecraft:the_end",
+        }
+    }
+
+    /// 不带命名空间的维度名,用作存储中的表名
+    pub const fn key(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "overworld",
+            Dimension::Nether => "the_nether",
+            Dimension::End => "the_end",
+        }
+    }
+
+    /// 根据维度名获取维度(可省略 `minecraft:` 命名空间)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        Self::ALL.into_iter().find(|d| d.key() == name)
+    }
+
+    /// `minecraft:dimension_type` 注册表中的下标
+    ///
+    /// 必须与 `qexed_core::registry` 下发的顺序一致:
+    /// overworld, overworld_caves, the_end, the_nether。
+    pub const fn type_id(self) -> i32 {
+        match self {
+            Dimension::Overworld => 0,
+            Dimension::Nether => 3,
+            Dimension::End => 2,
+        }
+    }
+
+    /// 最低 y 坐标
+    pub const fn min_y(self) -> i32 {
+        match self {
+            Dimension::Overworld => -64,
+            Dimension::Nether | Dimension::End => 0,
+        }
+    }
+
+    /// 世界高度
+    pub const fn height(self) -> i32 {
+        match self {
+            Dimension::Overworld => 384,
+            Dimension::Nether | Dimension::End => 256,
+        }
+    }
+
+    /// 海平面高度
+    pub const fn sea_level(self) -> i32 {
+        match self {
+            Dimension::Overworld => 63,
+            Dimension::Nether => 32,
+            Dimension::End => 0,
+        }
+    }
+
+    /// 是否有天空光
+    pub const fn has_skylight(self) -> bool {
+        matches!(self, Dimension::Overworld)
+    }
+
+    /// 坐标缩放(下界中走一格相当于主世界八格)
+    pub const fn coordinate_scale(self) -> f64 {
+        match self {
+            Dimension::Nether => 8.0,
+            _ => 1.0,
+        }
+    }
+
+    /// 区块中属于该维度的段落下标
+    pub const fn sections(self) -> Range<usize> {
+        let start = ((self.min_y() - MIN_Y) >> 4) as usize;
+        let end = start + (self.height() >> 4) as usize;
+        debug_assert!(end <= SECTION_COUNT);
+        start..end
+    }
+
+    /// 属于该维度的光照段落下标(上下各多一个)
+    pub const fn light_sections(self) -> Range<usize> {
+        let sections = self.sections();
+        sections.start..sections.end + 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn section_ranges() {
+        assert_eq!(Dimension::Overworld.sections(), 0..SECTION_COUNT);
+        assert_eq!(Dimension::Nether.sections(), 4..20);
+        assert_eq!(Dimension::End.light_sections(), 4..22);
+        assert_eq!(Dimension::from_name("the_nether"), Some(Dimension::Nether));
+        assert_eq!(Dimension::from_name("minecraft:the_end"), Some(Dimension::End));
+    }
+}
@@ -0,0 +1,41 @@
+use thiserror::Error;
+
+use crate::pos::{BlockPos, ChunkPos};
+
+#[derive(Debug, Error)]
+pub enum WorldError {
+    #[error("区块 {0} 未加载")]
+    ChunkNotLoaded(ChunkPos),
+    #[error("坐标 {0} 超出世界高度范围")]
+    OutOfBounds(BlockPos),
+    #[error("IO错误: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("NBT解析失败: {0}")]
+    Nbt(#[from] crab_nbt::error::Error),
+    #[error("不支持的压缩格式: {0}")]
+    UnsupportedCompression(u8),
+    #[error("区块数据损坏: {0}")]
+    CorruptChunk(String),
+    #[error("未知的方块状态: {0}")]
+    UnknownBlockState(String),
+    #[error("方块注册表不可用: {0}")]
+    BlockRegistry(String),
+    #[error("区块 {0} 校验失败")]
+    ChecksumMismatch(ChunkPos),
+    #[error("数据库错误: {0}")]
+    Database(String),
+    #[error("世界设置无效: {0}")]
+    Settings(#[from] serde_json::Error),
+    #[error("无效的游戏规则: {0}")]
+    InvalidGameRule(String),
+    #[error("无效的世界名: {0}")]
+    InvalidWorldName(String),
+    #[error("世界 {0} 已存在")]
+    WorldExists(String),
+    #[error("世界 {0} 不存在")]
+    UnknownWorld(String),
+    #[error("世界 {0} 仍在使用中")]
+    WorldInUse(String),
+    #[error("世界生成器错误: {0}")]
+    Generator(String),
+}
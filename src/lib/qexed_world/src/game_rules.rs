@@ -0,0 +1,107 @@
+//! 游戏规则
+//!
+//! 每个命名世界各有一份,与世界设置一起保存在 `world.json` 中。
+//! 规则名与原版 `/gamerule` 相同(驼峰命名)。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::WorldError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GameRules {
+    /// 昼夜更替
+    pub do_daylight_cycle: bool,
+    /// 天气变化
+    pub do_weather_cycle: bool,
+    /// 生物自然生成
+    pub do_mob_spawning: bool,
+    /// 火焰蔓延与熄灭
+    pub do_fire_tick: bool,
+    /// 死亡后保留物品栏
+    pub keep_inventory: bool,
+    /// 生物破坏方块
+    pub mob_griefing: bool,
+    /// 跳过重生界面
+    pub do_immediate_respawn: bool,
+    /// 调试界面只显示简化信息
+    pub reduced_debug_info: bool,
+    /// 每个区块段落每 tick 随机刻的方块数
+    pub random_tick_speed: i32,
+    /// 出生点周围随机出生的半径
+    pub spawn_radius: i32,
+}
+
+impl Default for GameRules {
+    /// 原版默认值
+    fn default() -> Self {
+        GameRules {
+            do_daylight_cycle: true,
+            do_weather_cycle: true,
+            do_mob_spawning: true,
+            do_fire_tick: true,
+            keep_inventory: false,
+            mob_griefing: true,
+            do_immediate_respawn: false,
+            reduced_debug_info: false,
+            random_tick_speed: 3,
+            spawn_radius: 10,
+        }
+    }
+}
+
+impl GameRules {
+    /// 所有规则名
+    pub fn names(&self) -> Vec<String> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// 以字符串形式读取规则,规则不存在时返回 `None`
+    pub fn get(&self, name: &str) -> Option<String> {
+        serde_json::to_value(self)
+            .ok()?
+            .get(name)
+            .map(|v| v.to_string())
+    }
+
+    /// 按规则名设置规则,值的类型必须与规则一致
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), WorldError> {
+        let invalid = || WorldError::InvalidGameRule(format!("{} = {}", name, value));
+        let mut rules = serde_json::to_value(&*self)?;
+        let slot = rules
+            .get_mut(name)
+            .ok_or_else(|| WorldError::InvalidGameRule(name.to_string()))?;
+        *slot = match slot {
+            Value::Bool(_) => Value::Bool(value.parse().map_err(|_| invalid())?),
+            Value::Number(_) => Value::from(value.parse::<i32>().map_err(|_| invalid())?),
+            _ => return Err(invalid()),
+        };
+        *self = serde_json::from_value(rules)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_and_set() {
+        let mut rules = GameRules::default();
+        assert_eq!(rules.get("randomTickSpeed").as_deref(), Some("3"));
+        rules.set("randomTickSpeed", "10").unwrap();
+        rules.set("keepInventory", "true").unwrap();
+        assert_eq!(rules.random_tick_speed, 10);
+        assert!(rules.keep_inventory);
+        assert!(rules.set("keepInventory", "10").is_err());
+        assert!(rules.set("noSuchRule", "true").is_err());
+        // 缺少的规则使用默认值
+        let rules: GameRules = serde_json::from_str(r#"{"doFireTick": false}"#).unwrap();
+        assert!(!rules.do_fire_tick);
+        assert_eq!(rules.spawn_radius, 10);
+    }
+}
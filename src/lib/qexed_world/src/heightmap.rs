@@ -0,0 +1,354 @@
+//! 高度图
+//!
+//! 每种高度图记录区块内每一列最高的满足条件的方块之上的 y 坐标,
+//! 以 `y - MIN_Y` 的形式按 [`HEIGHTMAP_BITS`] 位紧凑存放(与存档和协议一致)。
+//!
+//! 方块是否阻挡移动等判断同样来自方块名,与原版的碰撞箱计算结果基本一致。
+
+use std::collections::HashMap;
+
+use qexed_net::net_types::{heightmap::Heightmaps as NetHeightmap, var_int::VarInt};
+
+use crate::{
+    MIN_Y, WORLD_HEIGHT,
+    block::{self, BlockState},
+    dimension::Dimension,
+    palette::{pack, packed_len, unpack},
+    registry::{BlockStateInfo, block_registry},
+    section::ChunkSection,
+};
+
+/// 每个条目的位宽,能够表示 `0..=WORLD_HEIGHT`
+pub const HEIGHTMAP_BITS: u8 = (u32::BITS - (WORLD_HEIGHT as u32).leading_zeros()) as u8;
+/// 紧凑数组的长度
+pub const HEIGHTMAP_LONGS: usize = packed_len(256, HEIGHTMAP_BITS);
+
+/// 高度图类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HeightmapType {
+    /// 任何非空气方块
+    WorldSurface,
+    /// 阻挡移动的方块
+    OceanFloor,
+    /// 阻挡移动的方块或含有流体的方块
+    MotionBlocking,
+    /// 同上,但不含树叶
+    MotionBlockingNoLeaves,
+}
+
+impl HeightmapType {
+    pub const ALL: [HeightmapType; 4] = [
+        HeightmapType::WorldSurface,
+        HeightmapType::OceanFloor,
+        HeightmapType::MotionBlocking,
+        HeightmapType::MotionBlockingNoLeaves,
+    ];
+
+    /// 存档中的名字
+    pub fn name(self) -> &'static str {
+        match self {
+            HeightmapType::WorldSurface => "WORLD_SURFACE",
+            HeightmapType::OceanFloor => "OCEAN_FLOOR",
+            HeightmapType::MotionBlocking => "MOTION_BLOCKING",
+            HeightmapType::MotionBlockingNoLeaves => "MOTION_BLOCKING_NO_LEAVES",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    /// 协议中的类型ID
+    pub fn network_id(self) -> i32 {
+        match self {
+            HeightmapType::WorldSurface => 1,
+            HeightmapType::OceanFloor => 3,
+            HeightmapType::MotionBlocking => 4,
+            HeightmapType::MotionBlockingNoLeaves => 5,
+        }
+    }
+
+    /// 客户端是否需要这个高度图
+    pub fn sent_to_client(self) -> bool {
+        self != HeightmapType::OceanFloor
+    }
+
+    fn matches(self, flags: BlockFlags) -> bool {
+        match self {
+            HeightmapType::WorldSurface => flags.non_air,
+            HeightmapType::OceanFloor => flags.blocks_motion,
+            HeightmapType::MotionBlocking => flags.blocks_motion || flags.fluid,
+            HeightmapType::MotionBlockingNoLeaves => {
+                (flags.blocks_motion || flags.fluid) && !flags.leaves
+            }
+        }
+    }
+}
+
+/// 高度图关心的方块特性
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockFlags {
+    pub non_air: bool,
+    pub blocks_motion: bool,
+    pub fluid: bool,
+    pub leaves: bool,
+}
+
+impl BlockFlags {
+    /// 查询方块状态的特性,未知的状态视为完整方块
+    pub fn of(state: BlockState) -> Self {
+        if block::is_air(state) {
+            return Self::default();
+        }
+        match block_registry().state_info(state) {
+            Some(info) => Self::from_info(&info),
+            None => BlockFlags {
+                non_air: true,
+                blocks_motion: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn from_info(info: &BlockStateInfo) -> Self {
+        let name = info.name.strip_prefix("minecraft:").unwrap_or(&info.name);
+        if matches!(name, "air" | "cave_air" | "void_air") {
+            return Self::default();
+        }
+        BlockFlags {
+            non_air: true,
+            blocks_motion: blocks_motion(name),
+            fluid: matches!(
+                name,
+                "water" | "lava" | "bubble_column" | "kelp" | "kelp_plant" | "seagrass"
+                    | "tall_seagrass"
+            ) || info.property("waterlogged") == Some("true"),
+            leaves: name.ends_with("leaves"),
+        }
+    }
+}
+
+fn blocks_motion(name: &str) -> bool {
+    match name {
+        "water" | "lava" | "bubble_column" | "fire" | "soul_fire" | "light" | "structure_void"
+        | "nether_portal" | "end_portal" | "end_gateway" | "cobweb" | "redstone_wire"
+        | "tripwire" | "lever" | "lily_pad" | "snow" | "powder_snow" | "sugar_cane" | "kelp"
+        | "kelp_plant" | "seagrass" | "tall_seagrass" | "dead_bush" | "short_grass"
+        | "tall_grass" | "fern" | "large_fern" | "vine" | "glow_lichen" | "sculk_vein"
+        | "hanging_roots" | "spore_blossom" | "small_dripleaf" | "big_dripleaf_stem"
+        | "pink_petals" | "frogspawn" | "sweet_berry_bush" | "nether_wart" | "cave_vines"
+        | "cave_vines_plant" | "twisting_vines" | "twisting_vines_plant" | "weeping_vines"
+        | "weeping_vines_plant" | "bamboo_sapling" | "torchflower" | "pitcher_plant"
+        | "crimson_roots" | "warped_roots" | "nether_sprouts" | "crimson_fungus"
+        | "warped_fungus" => false,
+        // 珊瑚块、蘑菇块等名称与非完整方块相近的完整方块
+        _ if name.ends_with("_block") || name == "mushroom_stem" => true,
+        _ => !NON_SOLID_PATTERNS.iter().any(|p| name.contains(p)),
+    }
+}
+
+/// 名称中包含这些片段的方块没有(足够大的)碰撞箱
+const NON_SOLID_PATTERNS: [&str; 26] = [
+    "torch", "sign", "banner", "button", "pressure_plate", "rail", "carpet", "sapling",
+    "flower", "tulip", "orchid", "allium", "dandelion", "poppy", "bluet", "daisy",
+    "lily_of_the_valley", "wither_rose", "mushroom", "wheat", "carrots", "potatoes",
+    "beetroots", "melon_stem", "pumpkin_stem", "coral",
+];
+
+/// 单个高度图
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmap {
+    /// 每列的高度(`y - MIN_Y`),0 表示整列都不满足条件
+    heights: [u16; 256],
+}
+
+impl Default for Heightmap {
+    fn default() -> Self {
+        Heightmap { heights: [0; 256] }
+    }
+}
+
+impl Heightmap {
+    #[inline]
+    fn index(x: usize, z: usize) -> usize {
+        (z << 4) | x
+    }
+
+    /// 列中最高的满足条件的方块之上的 y 坐标,整列都不满足时为 `MIN_Y`
+    pub fn get(&self, x: usize, z: usize) -> i32 {
+        self.heights[Self::index(x, z)] as i32 + MIN_Y
+    }
+
+    pub fn set(&mut self, x: usize, z: usize, y: i32) {
+        self.heights[Self::index(x, z)] = (y - MIN_Y).clamp(0, WORLD_HEIGHT) as u16;
+    }
+
+    /// 紧凑数组
+    pub fn pack(&self) -> Vec<u64> {
+        self.pack_in(Dimension::Overworld)
+    }
+
+    /// 按维度的高度范围换算后的紧凑数组
+    pub fn pack_in(&self, dimension: Dimension) -> Vec<u64> {
+        let offset = (dimension.min_y() - MIN_Y) as u16;
+        let height = dimension.height() as u16;
+        let mut data = vec![0u64; HEIGHTMAP_LONGS];
+        for (i, h) in self.heights.iter().enumerate() {
+            pack(&mut data, HEIGHTMAP_BITS, i, h.saturating_sub(offset).min(height) as u32);
+        }
+        data
+    }
+
+    /// 从紧凑数组读取,长度不对时返回 `None`
+    pub fn unpack(data: &[u64]) -> Option<Self> {
+        if data.len() != HEIGHTMAP_LONGS {
+            return None;
+        }
+        let mut map = Heightmap::default();
+        for (i, h) in map.heights.iter_mut().enumerate() {
+            *h = unpack(data, HEIGHTMAP_BITS, i).min(WORLD_HEIGHT as u32) as u16;
+        }
+        Some(map)
+    }
+}
+
+/// 区块的全部高度图
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Heightmaps {
+    maps: [Heightmap; 4],
+}
+
+impl Heightmaps {
+    pub fn get(&self, kind: HeightmapType) -> &Heightmap {
+        &self.maps[kind as usize]
+    }
+
+    pub fn get_mut(&mut self, kind: HeightmapType) -> &mut Heightmap {
+        &mut self.maps[kind as usize]
+    }
+
+    /// 根据段落内容重新计算全部高度图
+    pub fn compute(sections: &[ChunkSection]) -> Self {
+        let mut maps = Heightmaps::default();
+        let mut cache: HashMap<BlockState, BlockFlags> = HashMap::new();
+        for x in 0..16 {
+            for z in 0..16 {
+                let mut remaining = HeightmapType::ALL.len();
+                let mut found = [false; 4];
+                'column: for (si, section) in sections.iter().enumerate().rev() {
+                    if section.is_empty() {
+                        continue;
+                    }
+                    for ly in (0..16).rev() {
+                        let state = section.get_block(x, ly, z);
+                        let flags = *cache.entry(state).or_insert_with(|| BlockFlags::of(state));
+                        for kind in HeightmapType::ALL {
+                            if !found[kind as usize] && kind.matches(flags) {
+                                found[kind as usize] = true;
+                                remaining -= 1;
+                                maps.get_mut(kind)
+                                    .set(x, z, MIN_Y + (si * 16 + ly) as i32 + 1);
+                            }
+                        }
+                        if remaining == 0 {
+                            break 'column;
+                        }
+                    }
+                }
+            }
+        }
+        maps
+    }
+
+    /// 方块变化后更新高度图,`y` 为世界坐标,`state` 为新方块
+    pub fn update(&mut self, sections: &[ChunkSection], x: usize, y: i32, z: usize, state: BlockState) {
+        let flags = BlockFlags::of(state);
+        for kind in HeightmapType::ALL {
+            let map = self.get_mut(kind);
+            let height = map.get(x, z);
+            if kind.matches(flags) {
+                if y >= height {
+                    map.set(x, z, y + 1);
+                }
+            } else if y == height - 1 {
+                // 原来的最高方块被替换,向下寻找新的最高方块
+                let top = (MIN_Y..y)
+                    .rev()
+                    .find(|&below| {
+                        let section = &sections[((below - MIN_Y) >> 4) as usize];
+                        !section.is_empty()
+                            && kind.matches(BlockFlags::of(section.get_block(
+                                x,
+                                (below & 15) as usize,
+                                z,
+                            )))
+                    })
+                    .map_or(MIN_Y, |below| below + 1);
+                map.set(x, z, top);
+            }
+        }
+    }
+
+    /// 从存档读取,缺少任意一种时返回 `None`
+    pub fn from_packed<'a>(
+        mut get: impl FnMut(&str) -> Option<&'a [u64]>,
+    ) -> Option<Self> {
+        let mut maps = Heightmaps::default();
+        for kind in HeightmapType::ALL {
+            maps.maps[kind as usize] = Heightmap::unpack(get(kind.name())?)?;
+        }
+        Some(maps)
+    }
+
+    /// 按存档格式输出 (名字, 紧凑数组)
+    pub fn to_packed(&self) -> Vec<(&'static str, Vec<u64>)> {
+        HeightmapType::ALL
+            .into_iter()
+            .map(|kind| (kind.name(), self.get(kind).pack()))
+            .collect()
+    }
+
+    /// 网络协议中的高度图
+    pub fn to_network(&self, dimension: Dimension) -> Vec<NetHeightmap> {
+        HeightmapType::ALL
+            .into_iter()
+            .filter(|kind| kind.sent_to_client())
+            .map(|kind| NetHeightmap {
+                type_id: VarInt(kind.network_id()),
+                data: self.get(kind).pack_in(dimension),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chunk::Chunk, pos::ChunkPos};
+
+    #[test]
+    fn compute_update_and_pack() {
+        assert_eq!(HEIGHTMAP_BITS, 9);
+        assert_eq!(HEIGHTMAP_LONGS, 37);
+
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0), crate::biome::PLAINS);
+        chunk.set_block(3, 10, 4, block::STONE);
+        chunk.set_block(3, 12, 4, block::WATER);
+        let maps = &chunk.heightmaps;
+        assert_eq!(maps.get(HeightmapType::WorldSurface).get(3, 4), 13);
+        assert_eq!(maps.get(HeightmapType::MotionBlocking).get(3, 4), 13);
+        assert_eq!(maps.get(HeightmapType::OceanFloor).get(3, 4), 11);
+        assert_eq!(maps.get(HeightmapType::OceanFloor).get(0, 0), MIN_Y);
+
+        chunk.set_block(3, 10, 4, block::AIR);
+        assert_eq!(chunk.heightmaps.get(HeightmapType::OceanFloor).get(3, 4), MIN_Y);
+        assert_eq!(chunk.heightmaps, Heightmaps::compute(&chunk.sections));
+
+        let packed = chunk.heightmaps.to_packed();
+        let restored = Heightmaps::from_packed(|name| {
+            packed.iter().find(|(n, _)| *n == name).map(|(_, d)| d.as_slice())
+        })
+        .unwrap();
+        assert_eq!(restored, chunk.heightmaps);
+    }
+}
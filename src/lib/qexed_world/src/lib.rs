@@ -1,14 +1,54 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! 世界模型
+//!
+//! 内存中的区块、段落与方块访问,多世界管理,以及 Anvil 存档的读写。
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub mod anvil;
+pub mod biome;
+pub mod block;
+pub mod chunk;
+pub mod dimension;
+pub mod error;
+pub mod game_rules;
+pub mod heightmap;
+pub mod light;
+pub mod manager;
+pub mod palette;
+pub mod pathfind;
+pub mod physics;
+pub mod pos;
+pub mod redstone;
+pub mod registry;
+pub mod section;
+pub mod shape;
+pub mod storage;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod tick;
+pub mod view;
+pub mod world;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use chunk::{BlockEntity, Chunk};
+pub use dimension::Dimension;
+pub use error::WorldError;
+pub use game_rules::GameRules;
+pub use heightmap::{HeightmapType, Heightmaps};
+pub use light::{ChunkLight, LightKind};
+pub use manager::{GeneratorFactory, Level, LevelHook, WorldManager, WorldSettings};
+pub use pos::{BlockPos, ChunkPos, Direction};
+pub use redstone::RedstoneEngine;
+pub use registry::{BlockRegistry, block_registry, init_block_registry};
+pub use section::ChunkSection;
+pub use shape::Aabb;
+pub use storage::{ChunkStorage, StorageOptions, WorldDatabase};
+pub use tick::{ScheduledTick, TickQueue};
+pub use view::{ChunkSender, ChunkView};
+pub use world::{BlockSource, ChunkFactory, ChunkLoadHook, World};
+
+/// 世界最低 y 坐标
+pub const MIN_Y: i32 = -64;
+/// 世界最高 y 坐标
+pub const MAX_Y: i32 = 319;
+/// 世界高度
+pub const WORLD_HEIGHT: i32 = MAX_Y - MIN_Y + 1;
+/// 每个区块的段落数
+pub const SECTION_COUNT: usize = (WORLD_HEIGHT / 16) as usize;
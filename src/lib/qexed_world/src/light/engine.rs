@@ -0,0 +1,462 @@
+//! 光照传播
+//!
+//! 以区块为中心锁住周围 3x3 个区块(光照最远传播 15 格,不会超出这个范围),
+//! 在其中进行广度优先的增加/移除传播:
+//!
+//! - 天空光:高度图以上的格子为 15,向下穿过透明方块时不衰减,其余方向每格至少衰减 1;
+//! - 方块光:从发光方块出发,每格至少衰减 1;
+//! - 方块变化时先沿着依赖该位置的格子移除旧光照,再从边界重新传播。
+//!
+//! 只有已经计算过光照的相邻区块会参与传播,尚未计算的区块在计算时会从相邻区块拉取光照。
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::RwLockWriteGuard,
+};
+
+use crate::{
+    MIN_Y,
+    block::BlockState,
+    chunk::Chunk,
+    light::{ChunkLight, LIGHT_MAX_Y, LIGHT_MIN_Y, LightKind, LightSection, LightProperties, MAX_LIGHT},
+    pos::{BlockPos, ChunkPos},
+    registry::block_registry,
+    world::{ChunkRef, World},
+};
+
+type Pos = (i32, i32, i32);
+
+const DOWN: Pos = (0, -1, 0);
+const DIRECTIONS: [Pos; 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), DOWN, (0, 0, 1), (0, 0, -1)];
+
+/// 被锁住的 3x3 区块范围
+struct LightRegion<'a> {
+    /// 西北角区块
+    origin: ChunkPos,
+    chunks: Vec<Option<RwLockWriteGuard<'a, Chunk>>>,
+    properties: HashMap<BlockState, LightProperties>,
+    /// 维度是否有天空光,没有时天空光保持为 0
+    skylight: bool,
+}
+
+impl<'a> LightRegion<'a> {
+    /// 按坐标顺序加锁,保证多个线程同时计算光照时不会死锁
+    fn lock(center: ChunkPos, refs: &'a [Option<ChunkRef>], skylight: bool) -> Self {
+        let chunks = refs
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let guard = chunk.as_ref()?.write().unwrap();
+                // 中心区块总是参与,相邻区块只有计算过光照的才参与
+                (i == 4 || guard.light.is_lit()).then_some(guard)
+            })
+            .collect();
+        LightRegion {
+            origin: ChunkPos::new(center.x - 1, center.z - 1),
+            chunks,
+            properties: HashMap::new(),
+            skylight,
+        }
+    }
+
+    fn center(&mut self) -> &mut Chunk {
+        self.chunks[4].as_mut().unwrap()
+    }
+
+    #[inline]
+    fn chunk_index(&self, x: i32, z: i32) -> Option<usize> {
+        let cx = (x >> 4) - self.origin.x;
+        let cz = (z >> 4) - self.origin.z;
+        if !(0..3).contains(&cx) || !(0..3).contains(&cz) {
+            return None;
+        }
+        let i = (cx * 3 + cz) as usize;
+        self.chunks[i].is_some().then_some(i)
+    }
+
+    fn chunk(&self, x: i32, z: i32) -> Option<&Chunk> {
+        let i = self.chunk_index(x, z)?;
+        self.chunks[i].as_deref()
+    }
+
+    fn properties_of(&mut self, state: BlockState) -> LightProperties {
+        *self
+            .properties
+            .entry(state)
+            .or_insert_with(|| LightProperties::of(block_registry(), state))
+    }
+
+    /// 区块未参与传播时返回 `None`
+    fn properties(&mut self, (x, y, z): Pos) -> Option<LightProperties> {
+        let state = self
+            .chunk(x, z)?
+            .get_block((x & 15) as usize, y, (z & 15) as usize);
+        Some(self.properties_of(state))
+    }
+
+    fn light(&self, kind: LightKind, (x, y, z): Pos) -> Option<u8> {
+        self.chunk(x, z)?
+            .light
+            .get(kind, (x & 15) as usize, y, (z & 15) as usize)
+    }
+
+    fn set_light(&mut self, kind: LightKind, (x, y, z): Pos, level: u8) {
+        if let Some(i) = self.chunk_index(x, z) {
+            let chunk = self.chunks[i].as_mut().unwrap();
+            chunk.light.set(kind, (x & 15) as usize, y, (z & 15) as usize, level);
+        }
+    }
+
+    /// 光照从 `level` 的格子沿 `dir` 进入透光度为 `opacity` 的格子后的等级
+    #[inline]
+    fn attenuate(kind: LightKind, dir: Pos, level: u8, opacity: u8) -> u8 {
+        if kind == LightKind::Sky && dir == DOWN && level == MAX_LIGHT && opacity == 0 {
+            MAX_LIGHT
+        } else {
+            level.saturating_sub(opacity.max(1))
+        }
+    }
+
+    /// 增加传播:队列中的格子把自身亮度扩散给相邻格子
+    fn propagate(&mut self, kind: LightKind, queue: &mut VecDeque<Pos>) {
+        while let Some(pos) = queue.pop_front() {
+            let Some(level) = self.light(kind, pos) else {
+                continue;
+            };
+            if level <= 1 {
+                continue;
+            }
+            for dir in DIRECTIONS {
+                let next = (pos.0 + dir.0, pos.1 + dir.1, pos.2 + dir.2);
+                let Some(current) = self.light(kind, next) else {
+                    continue;
+                };
+                if current >= level {
+                    continue;
+                }
+                let Some(props) = self.properties(next) else {
+                    continue;
+                };
+                let new = Self::attenuate(kind, dir, level, props.opacity);
+                if new > current {
+                    self.set_light(kind, next, new);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    /// 移除传播:清除依赖被移除光源的格子,把遇到的其他光源边界放入 `increase`
+    fn remove(
+        &mut self,
+        kind: LightKind,
+        removal: &mut VecDeque<(Pos, u8)>,
+        increase: &mut VecDeque<Pos>,
+    ) {
+        while let Some((pos, level)) = removal.pop_front() {
+            for dir in DIRECTIONS {
+                let next = (pos.0 + dir.0, pos.1 + dir.1, pos.2 + dir.2);
+                let Some(current) = self.light(kind, next) else {
+                    continue;
+                };
+                if current == 0 {
+                    continue;
+                }
+                // 天空光竖直向下不衰减,下方的 15 同样来自被移除的格子
+                let sky_column = kind == LightKind::Sky
+                    && dir == DOWN
+                    && level == MAX_LIGHT
+                    && current == MAX_LIGHT;
+                if current < level || sky_column {
+                    self.set_light(kind, next, 0);
+                    removal.push_back((next, current));
+                    if kind == LightKind::Block {
+                        let emission = self.properties(next).map_or(0, |p| p.emission);
+                        if emission > 0 {
+                            self.set_light(kind, next, emission);
+                            increase.push_back(next);
+                        }
+                    }
+                } else {
+                    increase.push_back(next);
+                }
+            }
+        }
+    }
+
+    /// 列中第一个可以直接看到天空的 y 坐标(其上方全部透光)
+    fn sky_top(&mut self, x: i32, z: i32) -> Option<i32> {
+        let i = self.chunk_index(x, z)?;
+        let (lx, lz) = ((x & 15) as usize, (z & 15) as usize);
+        let chunk = self.chunks[i].as_deref().unwrap();
+        for (si, section) in chunk.sections.iter().enumerate().rev() {
+            if section.is_empty() {
+                continue;
+            }
+            for ly in (0..16).rev() {
+                let state = section.get_block(lx, ly, lz);
+                let props = *self
+                    .properties
+                    .entry(state)
+                    .or_insert_with(|| LightProperties::of(block_registry(), state));
+                if props.opacity > 0 {
+                    return Some(MIN_Y + si as i32 * 16 + ly as i32 + 1);
+                }
+            }
+        }
+        Some(LIGHT_MIN_Y)
+    }
+
+    /// 天空光:先按高度填满露天部分,返回传播的起点
+    fn fill_sky(&mut self, center: ChunkPos) -> VecDeque<Pos> {
+        let (bx, bz) = (center.min_block_x(), center.min_block_z());
+        let mut tops = [[LIGHT_MIN_Y; 18]; 18];
+        for dx in -1..=16 {
+            for dz in -1..=16 {
+                // 四个角上的列不与本区块相邻
+                if (dx == -1 || dx == 16) && (dz == -1 || dz == 16) {
+                    continue;
+                }
+                tops[(dx + 1) as usize][(dz + 1) as usize] = match self.sky_top(bx + dx, bz + dz) {
+                    Some(top) => top,
+                    // 相邻区块不参与时按本列处理
+                    None => self
+                        .sky_top(bx + dx.clamp(0, 15), bz + dz.clamp(0, 15))
+                        .unwrap(),
+                };
+            }
+        }
+        let inner = || (0..16usize).flat_map(|x| (0..16usize).map(move |z| (x, z)));
+        let (min_top, max_top) = inner().fold((i32::MAX, i32::MIN), |(lo, hi), (x, z)| {
+            let top = tops[x + 1][z + 1];
+            (lo.min(top), hi.max(top))
+        });
+        let chunk = self.center();
+        for (i, section) in chunk.light.sky.iter_mut().enumerate() {
+            let base = LIGHT_MIN_Y + i as i32 * 16;
+            if base >= max_top {
+                *section = LightSection::Uniform(MAX_LIGHT);
+            } else if base + 16 > min_top {
+                for (x, z) in inner() {
+                    for y in tops[x + 1][z + 1].max(base)..base + 16 {
+                        section.set((((y & 15) as usize) << 8) | (z << 4) | x, MAX_LIGHT);
+                    }
+                }
+            }
+        }
+        // 从露天部分的边界开始传播:向下,以及向旁边更低的列
+        let mut sky_queue = VecDeque::new();
+        for (x, z) in inner() {
+            let top = tops[x + 1][z + 1];
+            let neighbors = [
+                tops[x][z + 1],
+                tops[x + 2][z + 1],
+                tops[x + 1][z],
+                tops[x + 1][z + 2],
+            ];
+            let highest = neighbors.into_iter().max().unwrap().max(top);
+            for y in top..=highest.min(LIGHT_MAX_Y) {
+                sky_queue.push_back((bx + x as i32, y, bz + z as i32));
+            }
+        }
+        sky_queue
+    }
+
+    /// 完整计算中心区块的光照
+    fn light_center(&mut self, center: ChunkPos) {
+        let (bx, bz) = (center.min_block_x(), center.min_block_z());
+        self.center().light = ChunkLight::new();
+
+        let mut sky_queue = if self.skylight {
+            self.fill_sky(center)
+        } else {
+            VecDeque::new()
+        };
+
+        // 方块光:从发光方块开始
+        let mut block_queue = VecDeque::new();
+        let mut emitters = Vec::new();
+        for (si, section) in self.chunks[4].as_deref().unwrap().sections.iter().enumerate() {
+            if section.is_empty() {
+                continue;
+            }
+            emitters.push((si, section.blocks.palette().to_vec()));
+        }
+        for (si, palette) in emitters {
+            // 调色板中没有发光方块的段落可以跳过
+            if palette.iter().all(|s| self.properties_of(*s).emission == 0) {
+                continue;
+            }
+            let base = MIN_Y + si as i32 * 16;
+            let states: Vec<BlockState> = self.center().sections[si].blocks.iter().collect();
+            for (index, state) in states.into_iter().enumerate() {
+                let emission = self.properties_of(state).emission;
+                if emission > 0 {
+                    let pos = (
+                        bx + (index & 15) as i32,
+                        base + (index >> 8) as i32,
+                        bz + ((index >> 4) & 15) as i32,
+                    );
+                    self.set_light(LightKind::Block, pos, emission);
+                    block_queue.push_back(pos);
+                }
+            }
+        }
+
+        // 从相邻区块拉取光照
+        for (dx, dz, x_range, z_range) in [
+            (-1, 0, -1..0, 0..16),
+            (16, 0, 16..17, 0..16),
+            (0, -1, 0..16, -1..0),
+            (0, 16, 0..16, 16..17),
+        ] {
+            if self.chunk_index(bx + dx, bz + dz).is_none() {
+                continue;
+            }
+            for x in x_range {
+                for z in z_range.clone() {
+                    for y in LIGHT_MIN_Y..=LIGHT_MAX_Y {
+                        let pos = (bx + x, y, bz + z);
+                        if self.light(LightKind::Sky, pos).unwrap_or(0) > 1 {
+                            sky_queue.push_back(pos);
+                        }
+                        if self.light(LightKind::Block, pos).unwrap_or(0) > 1 {
+                            block_queue.push_back(pos);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.propagate(LightKind::Sky, &mut sky_queue);
+        self.propagate(LightKind::Block, &mut block_queue);
+        self.center().light.mark_lit();
+    }
+
+    /// 方块变化后重新计算该位置附近的光照
+    fn relight(&mut self, pos: BlockPos) {
+        let p = (pos.x, pos.y, pos.z);
+        let Some(props) = self.properties(p) else {
+            return;
+        };
+        for kind in [LightKind::Sky, LightKind::Block] {
+            if kind == LightKind::Sky && !self.skylight {
+                continue;
+            }
+            let old = self.light(kind, p).unwrap_or(0);
+            let mut removal = VecDeque::new();
+            let mut increase = VecDeque::new();
+            self.set_light(kind, p, 0);
+            removal.push_back((p, old));
+            if kind == LightKind::Block && props.emission > 0 {
+                self.set_light(kind, p, props.emission);
+                increase.push_back(p);
+            }
+            self.remove(kind, &mut removal, &mut increase);
+            // 相邻格子可能需要重新照亮该位置
+            for dir in DIRECTIONS {
+                increase.push_back((p.0 + dir.0, p.1 + dir.1, p.2 + dir.2));
+            }
+            self.propagate(kind, &mut increase);
+        }
+    }
+}
+
+/// 中心区块周围 3x3 的区块句柄(按坐标排序,未加载的为 `None`)
+fn region_refs(world: &World, center: ChunkPos) -> Vec<Option<ChunkRef>> {
+    (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dz| ChunkPos::new(center.x + dx, center.z + dz)))
+        .map(|pos| world.get_chunk(pos))
+        .collect()
+}
+
+/// 计算区块的光照,已经计算过的区块不会重复计算;返回是否进行了计算
+pub fn light_chunk(world: &World, pos: ChunkPos) -> bool {
+    let refs = region_refs(world, pos);
+    if refs[4].is_none() {
+        return false;
+    }
+    let mut region = LightRegion::lock(pos, &refs, world.dimension.has_skylight());
+    if region.center().light.is_lit() {
+        return false;
+    }
+    region.light_center(pos);
+    true
+}
+
+/// 方块变化后更新光照,区块尚未计算光照时什么也不做
+pub fn relight_block(world: &World, pos: BlockPos) {
+    let center = pos.chunk_pos();
+    let refs = region_refs(world, center);
+    if refs[4].is_none() {
+        return;
+    }
+    let mut region = LightRegion::lock(center, &refs, world.dimension.has_skylight());
+    if !region.center().light.is_lit() {
+        return;
+    }
+    region.relight(pos);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block;
+
+    #[test]
+    fn sky_and_block_light() {
+        let world = World::new("world");
+        for x in -1..=1 {
+            for z in -1..=1 {
+                world.get_or_create_chunk(ChunkPos::new(x, z));
+            }
+        }
+        let light = |kind, x, y, z| world.light(kind, BlockPos::new(x, y, z));
+        assert_eq!(light(LightKind::Sky, 0, 0, 0), Some(15));
+
+        // 单个方块的遮挡:下方从四周获得 14
+        world.set_block(BlockPos::new(0, 10, 0), block::STONE).unwrap();
+        assert_eq!(light(LightKind::Sky, 0, 9, 0), Some(14));
+        assert_eq!(light(LightKind::Sky, 0, -70, 0), Some(14));
+
+        // 一整层石头下完全黑暗,打通一列后光照从洞口照进来
+        let roof = World::new("world");
+        for x in -1..=1 {
+            for z in -1..=1 {
+                let mut chunk = Chunk::new(ChunkPos::new(x, z), crate::biome::PLAINS);
+                chunk.sections[5].fill(block::STONE);
+                roof.insert_chunk(chunk);
+            }
+        }
+        for x in -1..=1 {
+            for z in -1..=1 {
+                roof.ensure_lit(ChunkPos::new(x, z));
+            }
+        }
+        let roof_light = |x, y, z| roof.light(LightKind::Sky, BlockPos::new(x, y, z));
+        assert_eq!(roof_light(0, 0, 0), Some(0));
+        assert_eq!(roof_light(0, 40, 0), Some(15));
+        for y in 16..32 {
+            roof.set_block(BlockPos::new(1, y, 1), block::AIR).unwrap();
+        }
+        assert_eq!(roof_light(1, 0, 1), Some(15));
+        assert_eq!(roof_light(3, 0, 1), Some(13));
+
+        // 方块光跨越区块边界
+        world.set_block(BlockPos::new(15, 5, 8), block::LAVA).unwrap();
+        assert_eq!(light(LightKind::Block, 16, 5, 8), Some(14));
+        assert_eq!(light(LightKind::Block, 20, 5, 8), Some(10));
+        world.set_block(BlockPos::new(15, 5, 8), block::AIR).unwrap();
+        assert_eq!(light(LightKind::Block, 20, 5, 8), Some(0));
+    }
+
+    #[test]
+    fn new_chunk_pulls_neighbor_light() {
+        let world = World::new("world");
+        world.get_or_create_chunk(ChunkPos::new(0, 0));
+        world.set_block(BlockPos::new(15, 5, 8), block::LAVA).unwrap();
+        world.get_or_create_chunk(ChunkPos::new(1, 0));
+        assert_eq!(world.light(LightKind::Block, BlockPos::new(17, 5, 8)), Some(13));
+        assert!(world.take_light_changes().contains_key(&ChunkPos::new(0, 0)));
+    }
+}
@@ -0,0 +1,214 @@
+//! 光照
+//!
+//! 每个区块保存天空光与方块光两组半字节数组,覆盖世界高度上下各多一个段落
+//! (共 26 个光照段落,与协议一致)。光照由 [`engine`] 以广度优先的方式传播,
+//! 方块变化时只重新计算受影响的范围。
+
+pub mod engine;
+pub mod properties;
+
+use qexed_net::net_types::{bitset::Bitset, light::Light};
+
+use crate::{MIN_Y, SECTION_COUNT, dimension::Dimension};
+
+pub use properties::LightProperties;
+
+/// 光照段落数(世界段落上下各多一个)
+pub const LIGHT_SECTION_COUNT: usize = SECTION_COUNT + 2;
+/// 光照覆盖的最低 y 坐标
+pub const LIGHT_MIN_Y: i32 = MIN_Y - 16;
+/// 光照覆盖的最高 y 坐标
+pub const LIGHT_MAX_Y: i32 = LIGHT_MIN_Y + LIGHT_SECTION_COUNT as i32 * 16 - 1;
+/// 最大光照等级
+pub const MAX_LIGHT: u8 = 15;
+/// 所有光照段落的掩码
+pub const ALL_SECTIONS: u32 = (1 << LIGHT_SECTION_COUNT) - 1;
+
+/// 光照类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LightKind {
+    Sky,
+    Block,
+}
+
+/// 一个光照段落(16x16x16 个半字节)
+///
+/// 整个段落亮度相同时(全暗或露天)不分配数组。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LightSection {
+    Uniform(u8),
+    Data(Box<[u8; 2048]>),
+}
+
+impl Default for LightSection {
+    fn default() -> Self {
+        LightSection::Uniform(0)
+    }
+}
+
+impl LightSection {
+    /// 下标与方块下标相同: y,z,x 顺序
+    #[inline]
+    pub fn get(&self, index: usize) -> u8 {
+        match self {
+            LightSection::Uniform(level) => *level,
+            LightSection::Data(data) => (data[index >> 1] >> ((index & 1) << 2)) & 0xF,
+        }
+    }
+
+    /// 设置亮度,返回是否发生变化
+    pub fn set(&mut self, index: usize, level: u8) -> bool {
+        if let LightSection::Uniform(current) = *self {
+            if current == level {
+                return false;
+            }
+            *self = LightSection::Data(Box::new([current | (current << 4); 2048]));
+        }
+        let LightSection::Data(data) = self else {
+            unreachable!()
+        };
+        let shift = (index & 1) << 2;
+        let byte = &mut data[index >> 1];
+        let old = (*byte >> shift) & 0xF;
+        *byte = (*byte & !(0xF << shift)) | ((level & 0xF) << shift);
+        old != level
+    }
+
+    /// 整个段落是否全暗
+    pub fn is_dark(&self) -> bool {
+        match self {
+            LightSection::Uniform(level) => *level == 0,
+            LightSection::Data(data) => data.iter().all(|b| *b == 0),
+        }
+    }
+
+    /// 协议中的 2048 字节数组
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            LightSection::Uniform(level) => vec![*level | (*level << 4); 2048],
+            LightSection::Data(data) => data.to_vec(),
+        }
+    }
+}
+
+/// 区块的光照数据
+#[derive(Debug, Clone)]
+pub struct ChunkLight {
+    pub sky: Vec<LightSection>,
+    pub block: Vec<LightSection>,
+    /// 光照是否已经计算过
+    lit: bool,
+    /// 自上次广播后变化过的光照段落
+    changes: u32,
+}
+
+impl Default for ChunkLight {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChunkLight {
+    pub fn new() -> Self {
+        ChunkLight {
+            sky: vec![LightSection::default(); LIGHT_SECTION_COUNT],
+            block: vec![LightSection::default(); LIGHT_SECTION_COUNT],
+            lit: false,
+            changes: 0,
+        }
+    }
+
+    /// y 坐标对应的光照段落下标
+    #[inline]
+    pub fn section_index(y: i32) -> Option<usize> {
+        if (LIGHT_MIN_Y..=LIGHT_MAX_Y).contains(&y) {
+            Some(((y - LIGHT_MIN_Y) >> 4) as usize)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn index(x: usize, y: i32, z: usize) -> usize {
+        (((y & 15) as usize) << 8) | (z << 4) | x
+    }
+
+    pub fn sections(&self, kind: LightKind) -> &[LightSection] {
+        match kind {
+            LightKind::Sky => &self.sky,
+            LightKind::Block => &self.block,
+        }
+    }
+
+    pub fn sections_mut(&mut self, kind: LightKind) -> &mut [LightSection] {
+        match kind {
+            LightKind::Sky => &mut self.sky,
+            LightKind::Block => &mut self.block,
+        }
+    }
+
+    /// 获取亮度,x/z 为区块内局部坐标;超出光照范围时返回 `None`
+    pub fn get(&self, kind: LightKind, x: usize, y: i32, z: usize) -> Option<u8> {
+        let i = Self::section_index(y)?;
+        Some(self.sections(kind)[i].get(Self::index(x, y, z)))
+    }
+
+    /// 设置亮度,变化时记录到待广播的段落中
+    pub fn set(&mut self, kind: LightKind, x: usize, y: i32, z: usize, level: u8) {
+        let Some(i) = Self::section_index(y) else {
+            return;
+        };
+        if self.sections_mut(kind)[i].set(Self::index(x, y, z), level) {
+            self.changes |= 1 << i;
+        }
+    }
+
+    pub fn is_lit(&self) -> bool {
+        self.lit
+    }
+
+    pub(crate) fn mark_lit(&mut self) {
+        self.lit = true;
+        self.changes = 0;
+    }
+
+    /// 取出自上次调用后变化过的光照段落掩码
+    pub fn take_changes(&mut self) -> u32 {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// 转换为网络协议中的光照数据,只包含 `mask` 中属于该维度的段落
+    pub fn to_network(&self, dimension: Dimension, mask: u32) -> Light {
+        let mut light = Light::default();
+        let mut masks = [0u64; 4];
+        let sections = dimension.light_sections();
+        let first = sections.start;
+        for i in sections.filter(|i| mask & (1 << i) != 0) {
+            let bit = 1 << (i - first);
+            for (k, (sections, arrays)) in [
+                (&self.sky, &mut light.sky_light_arrays),
+                (&self.block, &mut light.block_light_arrays),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                // 没有天空光的维度不发送天空光
+                if k == 0 && !dimension.has_skylight() {
+                    continue;
+                }
+                if sections[i].is_dark() {
+                    masks[k + 2] |= bit;
+                } else {
+                    masks[k] |= bit;
+                    arrays.push(sections[i].to_bytes());
+                }
+            }
+        }
+        let bitset = |m: u64| Bitset(if m == 0 { vec![] } else { vec![m] });
+        light.sky_light_mask = bitset(masks[0]);
+        light.block_light_mask = bitset(masks[1]);
+        light.empty_sky_light_mask = bitset(masks[2]);
+        light.empty_block_light_mask = bitset(masks[3]);
+        light
+    }
+}
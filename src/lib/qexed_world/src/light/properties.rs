@@ -0,0 +1,163 @@
+//! 方块的光照属性
+//!
+//! 原版的发光等级与透光度写在方块类里,数据报告中没有导出,这里按方块名整理了常见方块,
+//! 未列出的方块视为不发光的完整不透明方块。
+
+use crate::{
+    block::{self, BlockState},
+    registry::{BlockRegistry, BlockStateInfo},
+};
+
+/// 方块的光照属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LightProperties {
+    /// 发光等级
+    pub emission: u8,
+    /// 光线穿过时额外衰减的等级,15 表示完全不透光
+    pub opacity: u8,
+}
+
+impl LightProperties {
+    pub const AIR: LightProperties = LightProperties {
+        emission: 0,
+        opacity: 0,
+    };
+    pub const OPAQUE: LightProperties = LightProperties {
+        emission: 0,
+        opacity: 15,
+    };
+
+    /// 查询方块状态的光照属性,未知的状态视为不透光
+    pub fn of(registry: &BlockRegistry, state: BlockState) -> Self {
+        if block::is_air(state) {
+            return Self::AIR;
+        }
+        match registry.state_info(state) {
+            Some(info) => Self::from_info(&info),
+            None => Self::OPAQUE,
+        }
+    }
+
+    pub fn from_info(info: &BlockStateInfo) -> Self {
+        let name = info.name.strip_prefix("minecraft:").unwrap_or(&info.name);
+        let mut opacity = opacity(name);
+        if info.property("waterlogged") == Some("true") {
+            opacity = opacity.max(1);
+        }
+        let emission = match info.property("lit") {
+            Some("false") => 0,
+            _ => emission(name, info),
+        };
+        LightProperties { emission, opacity }
+    }
+}
+
+fn emission(name: &str, info: &BlockStateInfo) -> u8 {
+    match name {
+        "glowstone" | "sea_lantern" | "jack_o_lantern" | "lantern" | "beacon" | "shroomlight"
+        | "lava" | "fire" | "conduit" | "end_gateway" | "end_portal" | "campfire"
+        | "redstone_lamp" | "ochre_froglight" | "verdant_froglight" | "pearlescent_froglight"
+        | "copper_bulb" | "waxed_copper_bulb" => 15,
+        "torch" | "wall_torch" | "end_rod" => 14,
+        "furnace" | "blast_furnace" | "smoker" => 13,
+        "exposed_copper_bulb" | "waxed_exposed_copper_bulb" => 12,
+        "nether_portal" => 11,
+        "soul_torch" | "soul_wall_torch" | "soul_lantern" | "soul_fire" | "soul_campfire"
+        | "crying_obsidian" => 10,
+        "redstone_ore" | "deepslate_redstone_ore" => 9,
+        "weathered_copper_bulb" | "waxed_weathered_copper_bulb" => 8,
+        "redstone_torch" | "redstone_wall_torch" | "enchanting_table" | "ender_chest"
+        | "glow_lichen" => 7,
+        "amethyst_cluster" => 5,
+        "large_amethyst_bud" | "oxidized_copper_bulb" | "waxed_oxidized_copper_bulb" => 4,
+        "magma_block" => 3,
+        "medium_amethyst_bud" => 2,
+        "small_amethyst_bud" | "brown_mushroom" | "brewing_stand" | "dragon_egg"
+        | "end_portal_frame" | "sculk_sensor" | "calibrated_sculk_sensor" => 1,
+        "light" => info
+            .property("level")
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(15),
+        "sea_pickle" => info
+            .property("pickles")
+            .and_then(|p| p.parse::<u8>().ok())
+            .map_or(0, |p| if info.property("waterlogged") == Some("true") { 3 * p + 3 } else { 0 }),
+        "respawn_anchor" => info
+            .property("charges")
+            .and_then(|c| c.parse::<u8>().ok())
+            .map_or(0, |c| [0, 3, 7, 11, 15][c.min(4) as usize]),
+        _ if name.ends_with("candle") => info
+            .property("candles")
+            .and_then(|c| c.parse::<u8>().ok())
+            .map_or(0, |c| 3 * c),
+        _ => 0,
+    }
+}
+
+fn opacity(name: &str) -> u8 {
+    match name {
+        "air" | "cave_air" | "void_air" | "light" | "barrier" | "structure_void" | "fire"
+        | "soul_fire" | "nether_portal" | "end_portal" | "end_gateway" | "beacon" | "conduit"
+        | "snow" | "cactus" | "chest" | "trapped_chest" | "ender_chest" | "enchanting_table"
+        | "brewing_stand" | "cauldron" | "hopper" | "anvil" | "chipped_anvil" | "damaged_anvil"
+        | "bell" | "cake" | "scaffolding" | "sugar_cane" | "bamboo" | "lily_pad" | "tripwire"
+        | "redstone_wire" | "repeater" | "comparator" | "lever" | "ladder" | "chain"
+        | "end_rod" | "lightning_rod" | "pointed_dripstone" | "dragon_egg" | "sea_pickle"
+        | "frogspawn" | "spore_blossom" | "hanging_roots" | "glow_lichen" | "sculk_vein"
+        | "big_dripleaf" | "big_dripleaf_stem" | "small_dripleaf" | "azalea"
+        | "flowering_azalea" | "moving_piston" | "piston_head" | "dead_bush" | "decorated_pot"
+        | "lectern" | "stonecutter" | "grindstone" | "heavy_core" => 0,
+        "water" | "bubble_column" | "ice" | "frosted_ice" | "lava" | "cobweb" | "slime_block"
+        | "honey_block" | "seagrass" | "tall_seagrass" | "kelp" | "kelp_plant" => 1,
+        _ if name.ends_with("leaves") => 1,
+        // 珊瑚块、蘑菇块等名称与非完整方块相近的完整方块
+        _ if name.ends_with("_block") || name == "mushroom_stem" => 15,
+        _ if TRANSPARENT_PATTERNS.iter().any(|p| name.contains(p)) => 0,
+        _ => 15,
+    }
+}
+
+/// 名称中包含这些片段的方块不是完整方块,光线可以穿过
+const TRANSPARENT_PATTERNS: [&str; 47] = [
+    "glass", "_pane", "torch", "_slab", "_stairs", "fence", "_wall", "door", "sign", "banner",
+    "rail", "button", "pressure_plate", "carpet", "sapling", "flower", "tulip", "orchid",
+    "allium", "dandelion", "poppy", "bluet", "daisy", "lily_of_the_valley", "wither_rose",
+    "short_grass", "tall_grass", "fern", "vine", "lantern", "bars", "potted_", "mushroom",
+    "coral", "skull", "head", "candle", "_bed", "campfire", "amethyst_bud",
+    "amethyst_cluster", "wheat", "carrots", "potatoes", "beetroots", "melon_stem",
+    "pumpkin_stem",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, properties: &[(&str, &str)]) -> BlockStateInfo {
+        BlockStateInfo {
+            name: format!("minecraft:{}", name),
+            properties: properties
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            default: true,
+        }
+    }
+
+    #[test]
+    fn known_blocks() {
+        assert_eq!(
+            LightProperties::from_info(&info("torch", &[])),
+            LightProperties { emission: 14, opacity: 0 }
+        );
+        assert_eq!(LightProperties::from_info(&info("stone", &[])), LightProperties::OPAQUE);
+        assert_eq!(LightProperties::from_info(&info("oak_leaves", &[])).opacity, 1);
+        assert_eq!(
+            LightProperties::from_info(&info("furnace", &[("lit", "false")])).emission,
+            0
+        );
+        assert_eq!(
+            LightProperties::from_info(&info("oak_stairs", &[("waterlogged", "true")])).opacity,
+            1
+        );
+    }
+}
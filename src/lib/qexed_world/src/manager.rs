@@ -0,0 +1,447 @@
+//! 多世界管理
+//!
+//! 每个命名世界([`Level`])保存在 `db_path` 下以世界名命名的子目录中:
+//! 子目录是一个独立的世界数据库,每个启用的维度一张区块表,
+//! 世界设置(生成器、种子、出生点、游戏规则)保存在同目录的 `world.json` 中。
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dimension::Dimension,
+    error::WorldError,
+    game_rules::GameRules,
+    pos::BlockPos,
+    storage::{StorageOptions, WorldDatabase},
+    world::{ChunkFactory, World},
+};
+
+/// 世界设置文件名
+pub const SETTINGS_FILE: &str = "world.json";
+
+/// 命名世界的设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorldSettings {
+    /// 世界生成器: noise / flat / void
+    pub generator: String,
+    pub seed: i64,
+    /// 超平坦世界的预设字符串
+    pub flat_preset: String,
+    /// 主世界出生点,未设置时由服务端在第一次使用时确定
+    pub spawn: Option<BlockPos>,
+    pub game_rules: GameRules,
+    /// 服务端校验玩家移动(碰撞、重力与着地),创造与大厅世界可以关闭
+    pub movement_physics: bool,
+}
+
+impl Default for WorldSettings {
+    fn default() -> Self {
+        WorldSettings {
+            generator: "noise".to_string(),
+            seed: 0,
+            flat_preset: "minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains"
+                .to_string(),
+            spawn: None,
+            game_rules: GameRules::default(),
+            movement_physics: true,
+        }
+    }
+}
+
+impl WorldSettings {
+    /// 由配置文件中的 `[game.world]` 生成
+    pub fn from_config(config: &qexed_config::WorldConfig) -> Self {
+        WorldSettings {
+            generator: config.generator.clone(),
+            seed: config.seed,
+            flat_preset: config.flat_preset.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// 按世界设置为某个维度创建区块生成函数,返回 (生成函数, 是否是平坦世界)
+pub type GeneratorFactory =
+    Arc<dyn Fn(&WorldSettings, Dimension) -> Result<(ChunkFactory, bool), WorldError> + Send + Sync>;
+
+/// 一个命名世界,包含每个启用维度的 [`World`]
+pub struct Level {
+    pub name: String,
+    path: PathBuf,
+    settings: RwLock<WorldSettings>,
+    worlds: BTreeMap<Dimension, Arc<World>>,
+    db: WorldDatabase,
+}
+
+impl std::fmt::Debug for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Level")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("worlds", &self.worlds)
+            .finish()
+    }
+}
+
+impl Level {
+    /// 某个维度的世界,维度未启用时返回 `None`
+    pub fn world(&self, dimension: Dimension) -> Option<&Arc<World>> {
+        self.worlds.get(&dimension)
+    }
+
+    /// 主世界(总是存在)
+    pub fn overworld(&self) -> &Arc<World> {
+        &self.worlds[&Dimension::Overworld]
+    }
+
+    /// 所有维度的世界
+    pub fn worlds(&self) -> impl Iterator<Item = &Arc<World>> {
+        self.worlds.values()
+    }
+
+    /// 已启用的维度
+    pub fn dimensions(&self) -> impl Iterator<Item = Dimension> + '_ {
+        self.worlds.keys().copied()
+    }
+
+    pub fn settings(&self) -> WorldSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    pub fn game_rules(&self) -> GameRules {
+        self.settings.read().unwrap().game_rules.clone()
+    }
+
+    pub fn spawn(&self) -> Option<BlockPos> {
+        self.settings.read().unwrap().spawn
+    }
+
+    /// 是否在服务端校验玩家移动
+    pub fn movement_physics(&self) -> bool {
+        self.settings.read().unwrap().movement_physics
+    }
+
+    /// 修改世界设置并写入 `world.json`
+    pub fn update_settings(&self, f: impl FnOnce(&mut WorldSettings)) -> Result<(), WorldError> {
+        let mut settings = self.settings.write().unwrap();
+        f(&mut settings);
+        write_settings(&self.path, &settings)
+    }
+
+    pub fn set_spawn(&self, spawn: BlockPos) -> Result<(), WorldError> {
+        self.update_settings(|s| s.spawn = Some(spawn))
+    }
+
+    /// 保存所有维度中被修改过的区块,返回保存的区块数
+    pub fn save_all(&self) -> Result<usize, WorldError> {
+        let mut saved = 0;
+        for world in self.worlds.values() {
+            saved += world.save_all()?;
+        }
+        self.db.sync()?;
+        Ok(saved)
+    }
+}
+
+fn write_settings(path: &std::path::Path, settings: &WorldSettings) -> Result<(), WorldError> {
+    let json = serde_json::to_string_pretty(settings)?;
+    fs::write(path.join(SETTINGS_FILE), json)?;
+    Ok(())
+}
+
+/// 维度世界的名字,例如 `world`、`world_nether`、`world_the_end`
+fn world_name(level: &str, dimension: Dimension) -> String {
+    match dimension {
+        Dimension::Overworld => level.to_string(),
+        Dimension::Nether => format!("{}_nether", level),
+        Dimension::End => format!("{}_the_end", level),
+    }
+}
+
+/// 世界打开后、对外可见前对世界的处理(例如挂接调度器)
+pub type LevelHook = Arc<dyn Fn(&Level) + Send + Sync>;
+
+/// 世界管理器:运行时创建、加载、卸载与列出命名世界
+pub struct WorldManager {
+    options: StorageOptions,
+    dimensions: Vec<Dimension>,
+    factory: GeneratorFactory,
+    open_hooks: Vec<LevelHook>,
+    levels: RwLock<HashMap<String, Arc<Level>>>,
+}
+
+impl WorldManager {
+    /// `options.path` 是所有世界的根目录;`dimensions` 是每个世界启用的维度,主世界总是启用
+    pub fn new(
+        options: StorageOptions,
+        dimensions: impl IntoIterator<Item = Dimension>,
+        factory: impl Fn(&WorldSettings, Dimension) -> Result<(ChunkFactory, bool), WorldError>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        let mut dimensions: Vec<Dimension> = dimensions.into_iter().collect();
+        dimensions.push(Dimension::Overworld);
+        dimensions.sort();
+        dimensions.dedup();
+        WorldManager {
+            options,
+            dimensions,
+            factory: Arc::new(factory),
+            open_hooks: Vec::new(),
+            levels: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 添加世界打开钩子,每个之后创建或加载的世界都会调用
+    pub fn with_open_hook(mut self, hook: impl Fn(&Level) + Send + Sync + 'static) -> Self {
+        self.open_hooks.push(Arc::new(hook));
+        self
+    }
+
+    /// 每个世界启用的维度
+    pub fn dimensions(&self) -> &[Dimension] {
+        &self.dimensions
+    }
+
+    fn level_path(&self, name: &str) -> Result<PathBuf, WorldError> {
+        let valid = !name.is_empty()
+            && name != "."
+            && name != ".."
+            && !name.contains(['/', '\\', ':'])
+            && !name.chars().any(char::is_control);
+        if !valid {
+            return Err(WorldError::InvalidWorldName(name.to_string()));
+        }
+        Ok(self.options.path.join(name))
+    }
+
+    fn open(&self, name: &str, path: PathBuf, settings: WorldSettings) -> Result<Arc<Level>, WorldError> {
+        let db = WorldDatabase::open(StorageOptions {
+            path: path.clone(),
+            ..self.options.clone()
+        })?;
+        let mut worlds = BTreeMap::new();
+        for &dimension in &self.dimensions {
+            let (generator, is_flat) = (self.factory)(&settings, dimension)?;
+            let storage = Arc::new(db.chunk_storage(dimension.key())?);
+            let world = World::with_storage(world_name(name, dimension), storage)
+                .with_dimension(dimension)
+                .with_flat(is_flat)
+                .with_generator(move |pos| generator(pos));
+            worlds.insert(dimension, Arc::new(world));
+        }
+        let level = Level {
+            name: name.to_string(),
+            path,
+            settings: RwLock::new(settings),
+            worlds,
+            db,
+        };
+        for hook in &self.open_hooks {
+            hook(&level);
+        }
+        Ok(Arc::new(level))
+    }
+
+    /// 创建新世界,同名世界已存在(无论是否加载)时失败
+    pub fn create(&self, name: &str, settings: WorldSettings) -> Result<Arc<Level>, WorldError> {
+        let path = self.level_path(name)?;
+        let mut levels = self.levels.write().unwrap();
+        if levels.contains_key(name) || path.join(SETTINGS_FILE).exists() {
+            return Err(WorldError::WorldExists(name.to_string()));
+        }
+        fs::create_dir_all(&path)?;
+        write_settings(&path, &settings)?;
+        let level = self.open(name, path, settings)?;
+        levels.insert(name.to_string(), Arc::clone(&level));
+        log::info!("已创建世界 {}", name);
+        Ok(level)
+    }
+
+    /// 加载已有的世界,已加载时直接返回
+    pub fn load(&self, name: &str) -> Result<Arc<Level>, WorldError> {
+        let path = self.level_path(name)?;
+        let mut levels = self.levels.write().unwrap();
+        if let Some(level) = levels.get(name) {
+            return Ok(Arc::clone(level));
+        }
+        let json = match fs::read_to_string(path.join(SETTINGS_FILE)) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(WorldError::UnknownWorld(name.to_string()));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let settings: WorldSettings = serde_json::from_str(&json)?;
+        let level = self.open(name, path, settings)?;
+        levels.insert(name.to_string(), Arc::clone(&level));
+        log::info!("已加载世界 {}", name);
+        Ok(level)
+    }
+
+    /// 加载世界,不存在时按 `settings` 创建
+    pub fn load_or_create(&self, name: &str, settings: WorldSettings) -> Result<Arc<Level>, WorldError> {
+        match self.load(name) {
+            Err(WorldError::UnknownWorld(_)) => self.create(name, settings),
+            result => result,
+        }
+    }
+
+    /// 保存并卸载世界
+    ///
+    /// 调用前应先把世界中的玩家移到其他世界。世界或其中的维度仍被引用时返回
+    /// [`WorldError::WorldInUse`] 且世界保持加载,否则数据库不会关闭,之后无法再次加载同名世界。
+    pub fn unload(&self, name: &str) -> Result<(), WorldError> {
+        let mut levels = self.levels.write().unwrap();
+        let level = levels
+            .get(name)
+            .ok_or_else(|| WorldError::UnknownWorld(name.to_string()))?;
+        if Arc::strong_count(level) > 1 || level.worlds.values().any(|w| Arc::strong_count(w) > 1) {
+            return Err(WorldError::WorldInUse(name.to_string()));
+        }
+        // 先保存再移除,保存失败时世界仍然可用
+        let saved = level.save_all()?;
+        levels.remove(name);
+        log::info!("已卸载世界 {} (保存了 {} 个区块)", name, saved);
+        Ok(())
+    }
+
+    /// 已加载的世界
+    pub fn get(&self, name: &str) -> Option<Arc<Level>> {
+        self.levels.read().unwrap().get(name).cloned()
+    }
+
+    /// 所有已加载的世界
+    pub fn levels(&self) -> Vec<Arc<Level>> {
+        self.levels.read().unwrap().values().cloned().collect()
+    }
+
+    /// 所有世界的名字(包括保存在磁盘上但未加载的),按名字排序
+    pub fn list(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.levels.read().unwrap().keys().cloned().collect();
+        if let Ok(entries) = fs::read_dir(&self.options.path) {
+            for entry in entries.flatten() {
+                if entry.path().join(SETTINGS_FILE).is_file() {
+                    names.extend(entry.file_name().to_str().map(str::to_string));
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// 保存所有已加载的世界,返回保存的区块数
+    pub fn save_all(&self) -> Result<usize, WorldError> {
+        let mut saved = 0;
+        for level in self.levels() {
+            saved += level.save_all()?;
+        }
+        Ok(saved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{biome, block, chunk::Chunk, pos::ChunkPos};
+    use std::time::Duration;
+
+    #[test]
+    fn create_load_unload() {
+        let path = std::env::temp_dir().join(format!("qexed_levels_{}", std::process::id()));
+        let manager = WorldManager::new(
+            StorageOptions {
+                path: path.clone(),
+                verify_chunk_data: true,
+                map_size: 16 << 20,
+                cache_ttl: Duration::from_secs(60),
+                cache_capacity: 1 << 20,
+            },
+            [Dimension::Nether],
+            |settings, dimension| {
+                let flat = settings.generator == "flat" && dimension == Dimension::Overworld;
+                let factory: ChunkFactory = Arc::new(move |pos| {
+                    let mut chunk = Chunk::new(pos, biome::PLAINS);
+                    if flat {
+                        chunk.set_block(0, 0, 0, block::STONE);
+                    }
+                    chunk
+                });
+                Ok((factory, flat))
+            },
+        );
+        assert!(matches!(manager.create("../x", WorldSettings::default()), Err(WorldError::InvalidWorldName(_))));
+        let settings = WorldSettings {
+            generator: "flat".to_string(),
+            ..Default::default()
+        };
+        let lobby = manager.create("lobby", settings.clone()).unwrap();
+        assert!(matches!(manager.create("lobby", settings), Err(WorldError::WorldExists(_))));
+        assert!(lobby.overworld().is_flat);
+        assert_eq!(lobby.dimensions().collect::<Vec<_>>(), vec![Dimension::Overworld, Dimension::Nether]);
+        assert_eq!(lobby.world(Dimension::Nether).unwrap().name, "lobby_nether");
+        lobby.overworld().load_chunk(ChunkPos::new(0, 0)).unwrap();
+        lobby.set_spawn(BlockPos::new(1, 2, 3)).unwrap();
+        drop(lobby);
+        manager.load_or_create("survival", WorldSettings::default()).unwrap();
+        assert_eq!(manager.list(), vec!["lobby".to_string(), "survival".to_string()]);
+
+        manager.unload("lobby").unwrap();
+        assert!(manager.get("lobby").is_none());
+        assert!(matches!(manager.load("nowhere"), Err(WorldError::UnknownWorld(_))));
+        let lobby = manager.load("lobby").unwrap();
+        assert_eq!(lobby.spawn(), Some(BlockPos::new(1, 2, 3)));
+        let chunk = lobby.overworld().load_chunk(ChunkPos::new(0, 0)).unwrap();
+        assert_eq!(chunk.read().unwrap().get_block(0, 0, 0), block::STONE);
+        let _ = fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn unload_waits_for_references() {
+        let path = std::env::temp_dir().join(format!("qexed_levels_ref_{}", std::process::id()));
+        let manager = WorldManager::new(
+            StorageOptions {
+                path: path.clone(),
+                verify_chunk_data: true,
+                map_size: 16 << 20,
+                cache_ttl: Duration::from_secs(60),
+                cache_capacity: 1 << 20,
+            },
+            [],
+            |_, _| {
+                let factory: ChunkFactory = Arc::new(|pos| Chunk::new(pos, biome::PLAINS));
+                Ok((factory, false))
+            },
+        );
+        let level = manager.create("lobby", WorldSettings::default()).unwrap();
+        let pos = BlockPos::new(5, 70, 5);
+        level.overworld().load_chunk(pos.chunk_pos()).unwrap();
+        level.overworld().set_block(pos, block::STONE).unwrap();
+        assert!(matches!(manager.unload("lobby"), Err(WorldError::WorldInUse(_))));
+        let world = Arc::clone(level.overworld());
+        drop(level);
+        assert!(matches!(manager.unload("lobby"), Err(WorldError::WorldInUse(_))));
+        assert!(manager.get("lobby").is_some());
+        drop(world);
+
+        // 最后一个引用释放后卸载会关闭数据库,同名世界可以再次加载
+        manager.unload("lobby").unwrap();
+        assert!(manager.get("lobby").is_none());
+        let level = manager.load("lobby").unwrap();
+        level.overworld().load_chunk(pos.chunk_pos()).unwrap();
+        assert_eq!(level.overworld().get_block(pos), Some(block::STONE));
+        drop(level);
+        manager.unload("lobby").unwrap();
+        manager.load("lobby").unwrap();
+        let _ = fs::remove_dir_all(path);
+    }
+}
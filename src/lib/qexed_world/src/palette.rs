@@ -0,0 +1,304 @@
+//! 调色板容器
+//!
+//! 段落中的方块与生物群系都以"调色板 + 紧凑 long 数组"的方式存储,
+//! 与 Anvil 存档和网络协议的格式保持一致(1.16 起条目不会跨 long 存放)。
+
+use std::collections::HashMap;
+
+use qexed_net::{net_types::var_int::VarInt, packet::encode::PacketWriter};
+
+/// 容器类型,决定条目数与网络编码时的位宽
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteKind {
+    /// 16x16x16 个方块状态
+    Blocks,
+    /// 4x4x4 个生物群系
+    Biomes,
+}
+
+impl PaletteKind {
+    /// 条目数量
+    pub const fn size(self) -> usize {
+        match self {
+            PaletteKind::Blocks => 4096,
+            PaletteKind::Biomes => 64,
+        }
+    }
+    /// 间接调色板的最小位宽
+    pub const fn min_bits(self) -> u8 {
+        match self {
+            PaletteKind::Blocks => 4,
+            PaletteKind::Biomes => 1,
+        }
+    }
+    /// 间接调色板的最大位宽,超过则改用直接调色板
+    pub const fn max_indirect_bits(self) -> u8 {
+        match self {
+            PaletteKind::Blocks => 8,
+            PaletteKind::Biomes => 3,
+        }
+    }
+    /// 直接调色板位宽(全局ID所需位数)
+    pub const fn direct_bits(self) -> u8 {
+        match self {
+            PaletteKind::Blocks => 15,
+            PaletteKind::Biomes => 7,
+        }
+    }
+}
+
+/// 调色板容器
+///
+/// `bits == 0` 时表示整个容器只有 `palette[0]` 一种值,此时 `data` 为空。
+#[derive(Debug, Clone, PartialEq)]
+pub struct PalettedContainer {
+    kind: PaletteKind,
+    palette: Vec<u32>,
+    bits: u8,
+    data: Vec<u64>,
+}
+
+/// 存放 `bits` 位宽 `size` 个条目所需的 long 数量
+pub const fn packed_len(size: usize, bits: u8) -> usize {
+    if bits == 0 {
+        return 0;
+    }
+    let per_long = 64 / bits as usize;
+    size.div_ceil(per_long)
+}
+
+/// 容纳 `len` 个调色板条目所需的最小位宽
+fn bits_for(len: usize) -> u8 {
+    if len <= 1 {
+        0
+    } else {
+        (usize::BITS - (len - 1).leading_zeros()) as u8
+    }
+}
+
+/// 按位宽读取紧凑数组中的第 `index` 个条目
+#[inline]
+pub fn unpack(data: &[u64], bits: u8, index: usize) -> u32 {
+    let per_long = 64 / bits as usize;
+    let long = data[index / per_long];
+    let shift = (index % per_long) * bits as usize;
+    ((long >> shift) & ((1u64 << bits) - 1)) as u32
+}
+
+/// 按位宽写入紧凑数组中的第 `index` 个条目
+#[inline]
+pub fn pack(data: &mut [u64], bits: u8, index: usize, value: u32) {
+    let per_long = 64 / bits as usize;
+    let shift = (index % per_long) * bits as usize;
+    let mask = ((1u64 << bits) - 1) << shift;
+    let slot = &mut data[index / per_long];
+    *slot = (*slot & !mask) | (((value as u64) << shift) & mask);
+}
+
+impl PalettedContainer {
+    /// 创建只包含单一值的容器
+    pub fn single(kind: PaletteKind, value: u32) -> Self {
+        PalettedContainer {
+            kind,
+            palette: vec![value],
+            bits: 0,
+            data: vec![],
+        }
+    }
+
+    /// 由调色板与紧凑数组创建(用于读取存档)
+    ///
+    /// 数据长度不足或调色板为空时返回 `None`。
+    pub fn from_parts(kind: PaletteKind, palette: Vec<u32>, data: Vec<u64>) -> Option<Self> {
+        if palette.is_empty() {
+            return None;
+        }
+        if palette.len() == 1 {
+            return Some(Self::single(kind, palette[0]));
+        }
+        // 存档中的位宽由数据长度推算,至少满足调色板大小
+        let min = bits_for(palette.len()).max(kind.min_bits());
+        let bits = (min..=32).find(|b| packed_len(kind.size(), *b) == data.len())?;
+        let container = PalettedContainer {
+            kind,
+            palette,
+            bits,
+            data,
+        };
+        // 防止索引越界
+        let len = container.palette.len() as u32;
+        if (0..kind.size()).any(|i| unpack(&container.data, bits, i) >= len) {
+            return None;
+        }
+        Some(container)
+    }
+
+    pub fn kind(&self) -> PaletteKind {
+        self.kind
+    }
+
+    /// 获取指定下标的值
+    pub fn get(&self, index: usize) -> u32 {
+        if self.bits == 0 {
+            return self.palette[0];
+        }
+        self.palette[unpack(&self.data, self.bits, index) as usize]
+    }
+
+    /// 设置指定下标的值,返回旧值
+    pub fn set(&mut self, index: usize, value: u32) -> u32 {
+        let old = self.get(index);
+        if old == value {
+            return old;
+        }
+        let palette_index = match self.palette.iter().position(|v| *v == value) {
+            Some(i) => i,
+            None => {
+                self.palette.push(value);
+                let needed = bits_for(self.palette.len());
+                if needed > self.bits {
+                    self.resize(needed);
+                }
+                self.palette.len() - 1
+            }
+        };
+        pack(&mut self.data, self.bits, index, palette_index as u32);
+        old
+    }
+
+    /// 用同一个值填满整个容器
+    pub fn fill(&mut self, value: u32) {
+        *self = Self::single(self.kind, value);
+    }
+
+    /// 调整存储位宽
+    fn resize(&mut self, bits: u8) {
+        let size = self.kind.size();
+        let mut data = vec![0u64; packed_len(size, bits)];
+        if self.bits != 0 {
+            for i in 0..size {
+                pack(&mut data, bits, i, unpack(&self.data, self.bits, i));
+            }
+        }
+        self.bits = bits;
+        self.data = data;
+    }
+
+    /// 当前是否只有一种值
+    pub fn is_single(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// 调色板(可能包含已不再使用的值)
+    pub fn palette(&self) -> &[u32] {
+        &self.palette
+    }
+
+    /// 遍历全部条目
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.kind.size()).map(move |i| self.get(i))
+    }
+
+    /// 统计满足条件的条目数
+    pub fn count(&self, f: impl Fn(u32) -> bool) -> usize {
+        if self.bits == 0 {
+            return if f(self.palette[0]) {
+                self.kind.size()
+            } else {
+                0
+            };
+        }
+        self.iter().filter(|v| f(*v)).count()
+    }
+
+    /// 去掉未使用的调色板条目,返回 (调色板, 每个条目的调色板下标)
+    pub fn compacted(&self) -> (Vec<u32>, Vec<u32>) {
+        let mut palette = Vec::new();
+        let mut lookup: HashMap<u32, u32> = HashMap::new();
+        let indices = self
+            .iter()
+            .map(|v| {
+                *lookup.entry(v).or_insert_with(|| {
+                    palette.push(v);
+                    (palette.len() - 1) as u32
+                })
+            })
+            .collect();
+        (palette, indices)
+    }
+
+    /// 按网络协议写入(1.21.5 起不再写入数据数组长度)
+    pub fn write(&self, w: &mut PacketWriter) {
+        if self.bits == 0 {
+            w.u8(0);
+            w.varint(&VarInt(self.palette[0] as i32));
+            return;
+        }
+        let (palette, indices) = self.compacted();
+        if palette.len() == 1 {
+            w.u8(0);
+            w.varint(&VarInt(palette[0] as i32));
+            return;
+        }
+        let needed = bits_for(palette.len()).max(self.kind.min_bits());
+        let (bits, values): (u8, Vec<u32>) = if needed <= self.kind.max_indirect_bits() {
+            w.u8(needed);
+            w.varint(&VarInt(palette.len() as i32));
+            for v in &palette {
+                w.varint(&VarInt(*v as i32));
+            }
+            (needed, indices)
+        } else {
+            let bits = self.kind.direct_bits();
+            w.u8(bits);
+            (bits, indices.iter().map(|i| palette[*i as usize]).collect())
+        };
+        let mut data = vec![0u64; packed_len(self.kind.size(), bits)];
+        for (i, v) in values.into_iter().enumerate() {
+            pack(&mut data, bits, i, v);
+        }
+        for long in data {
+            w.u64(long);
+        }
+    }
+
+    /// 按存档格式导出 (调色板, 紧凑数组),单值容器的数组为空
+    pub fn to_parts(&self) -> (Vec<u32>, Vec<u64>) {
+        let (palette, indices) = self.compacted();
+        if palette.len() == 1 {
+            return (palette, vec![]);
+        }
+        let bits = bits_for(palette.len()).max(self.kind.min_bits());
+        let mut data = vec![0u64; packed_len(self.kind.size(), bits)];
+        for (i, v) in indices.into_iter().enumerate() {
+            pack(&mut data, bits, i, v);
+        }
+        (palette, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_get_grows_palette() {
+        let mut c = PalettedContainer::single(PaletteKind::Blocks, 0);
+        for i in 0..4096 {
+            c.set(i, (i % 300) as u32);
+        }
+        for i in 0..4096 {
+            assert_eq!(c.get(i), (i % 300) as u32);
+        }
+        let (palette, data) = c.to_parts();
+        let restored = PalettedContainer::from_parts(PaletteKind::Blocks, palette, data).unwrap();
+        assert!(restored.iter().eq(c.iter()));
+    }
+
+    #[test]
+    fn from_parts_rejects_bad_length() {
+        assert!(
+            PalettedContainer::from_parts(PaletteKind::Blocks, vec![0, 1], vec![0; 3]).is_none()
+        );
+    }
+}
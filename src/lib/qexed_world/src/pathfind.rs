@@ -0,0 +1,561 @@
+//! 生物寻路
+//!
+//! 与原版 `WalkNodeEvaluator` 类似,以方块为网格,按实体的碰撞箱尺寸判断每个位置的节点类型:
+//! 可以站立、悬空(会下落)、水、栅栏、门等;A* 在这些节点上搜索,每种类型带有额外代价。
+//! 搜索访问的节点数受 [`PathOptions::max_nodes`] 限制,超出时返回到离目标最近的节点的路径。
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{
+    block::{self, BlockState},
+    pos::BlockPos,
+    registry::BlockRegistry,
+    shape::shape_of,
+    world::BlockSource,
+};
+
+/// 水中的额外代价(与原版相同)
+const WATER_PENALTY: f32 = 8.0;
+/// 对角线移动的代价
+const DIAGONAL_COST: f32 = std::f32::consts::SQRT_2;
+
+/// 寻路节点的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeType {
+    /// 被方块占据
+    Blocked,
+    /// 脚下没有方块,会下落
+    Open,
+    /// 可以站立
+    Walkable,
+    Water,
+    /// 栅栏、围墙、关闭的栅栏门,高度超过一格,跳不过去
+    Fence,
+    DoorOpen,
+    /// 关闭的木门,能开门的生物可以通过
+    DoorClosed,
+    /// 铁门只能由红石打开
+    DoorIron,
+    /// 岩浆、火、仙人掌等会造成伤害的方块
+    Danger,
+}
+
+/// 单个方块对寻路的影响
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BlockClass {
+    Empty,
+    Water,
+    /// 碰撞箱的最高点(方块内坐标)
+    Solid(f64),
+    Fence,
+    DoorOpen,
+    DoorClosed,
+    DoorIron,
+    Danger,
+}
+
+const DANGER: &[&str] = &[
+    "lava",
+    "fire",
+    "soul_fire",
+    "magma_block",
+    "cactus",
+    "sweet_berry_bush",
+    "campfire",
+    "soul_campfire",
+    "wither_rose",
+    "powder_snow",
+];
+
+fn classify(registry: &BlockRegistry, state: BlockState) -> BlockClass {
+    if block::is_air(state) {
+        return BlockClass::Empty;
+    }
+    let Some(info) = registry.state_info(state) else {
+        return BlockClass::Solid(1.0);
+    };
+    let name = info.name.strip_prefix("minecraft:").unwrap_or(&info.name);
+    let open = info.property("open") == Some("true");
+    if DANGER.contains(&name) {
+        return BlockClass::Danger;
+    }
+    if name.ends_with("_door") {
+        return match (open, name) {
+            (true, _) => BlockClass::DoorOpen,
+            (false, "iron_door") => BlockClass::DoorIron,
+            (false, _) => BlockClass::DoorClosed,
+        };
+    }
+    if name.ends_with("_fence")
+        || name.ends_with("_wall")
+        || (name.ends_with("_fence_gate") && !open)
+    {
+        return BlockClass::Fence;
+    }
+    let shape = shape_of(&info);
+    if shape.is_empty() {
+        if name == "water" || info.property("waterlogged") == Some("true") {
+            BlockClass::Water
+        } else {
+            BlockClass::Empty
+        }
+    } else {
+        BlockClass::Solid(shape.iter().map(|b| b.max_y).fold(0.0, f64::max))
+    }
+}
+
+/// 寻路的参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathOptions {
+    /// 碰撞箱的宽与高
+    pub width: f64,
+    pub height: f64,
+    pub can_open_doors: bool,
+    /// 最多可以直接跳下的高度
+    pub max_fall: i32,
+    /// 最多访问的节点数
+    pub max_nodes: usize,
+    /// 距离起点超过这个距离的节点不再搜索
+    pub max_distance: f64,
+    /// 与目标的水平距离不超过这个值时视为到达
+    pub accuracy: f64,
+}
+
+impl PathOptions {
+    pub fn new(width: f64, height: f64) -> Self {
+        PathOptions {
+            width,
+            height,
+            can_open_doors: false,
+            max_fall: 3,
+            max_nodes: 400,
+            max_distance: 32.0,
+            accuracy: 0.0,
+        }
+    }
+
+    /// 节点的额外代价,不能通过时返回 `None`
+    pub fn penalty(&self, node: NodeType) -> Option<f32> {
+        match node {
+            NodeType::Walkable | NodeType::Open | NodeType::DoorOpen => Some(0.0),
+            NodeType::Water => Some(WATER_PENALTY),
+            NodeType::DoorClosed if self.can_open_doors => Some(0.0),
+            _ => None,
+        }
+    }
+
+    fn footprint(&self) -> i32 {
+        self.width.ceil().max(1.0) as i32
+    }
+
+    fn body_height(&self) -> i32 {
+        self.height.ceil().max(1.0) as i32
+    }
+}
+
+/// 实体脚下在 `pos` 时的节点类型,区块未加载时返回 `None`
+pub fn node_type(
+    registry: &BlockRegistry,
+    source: &impl BlockSource,
+    pos: BlockPos,
+    options: &PathOptions,
+) -> Option<NodeType> {
+    let size = options.footprint();
+    let columns = || (0..size).flat_map(move |dx| (0..size).map(move |dz| (dx, dz)));
+    let (mut door_open, mut door_closed, mut water, mut floor) = (false, false, false, false);
+    for dy in 0..options.body_height() {
+        for (dx, dz) in columns() {
+            match classify(registry, source.block(pos.offset(dx, dy, dz))?) {
+                BlockClass::Danger => return Some(NodeType::Danger),
+                BlockClass::Fence => return Some(NodeType::Fence),
+                BlockClass::DoorIron => return Some(NodeType::DoorIron),
+                BlockClass::DoorClosed => door_closed = true,
+                BlockClass::DoorOpen => door_open = true,
+                BlockClass::Water => water = true,
+                // 台阶、地毯这类低矮的方块可以站在上面
+                BlockClass::Solid(top) if dy == 0 && top <= 0.5 => floor = true,
+                BlockClass::Solid(_) => return Some(NodeType::Blocked),
+                BlockClass::Empty => {}
+            }
+        }
+    }
+    if door_closed {
+        return Some(NodeType::DoorClosed);
+    }
+    if door_open {
+        return Some(NodeType::DoorOpen);
+    }
+    if water {
+        return Some(NodeType::Water);
+    }
+    if floor {
+        return Some(NodeType::Walkable);
+    }
+    for (dx, dz) in columns() {
+        match classify(registry, source.block(pos.offset(dx, -1, dz))?) {
+            // 站在栅栏上同样视为栅栏,避免生物走上去
+            BlockClass::Fence => return Some(NodeType::Fence),
+            BlockClass::Danger => return Some(NodeType::Danger),
+            BlockClass::Solid(_)
+            | BlockClass::DoorOpen
+            | BlockClass::DoorClosed
+            | BlockClass::DoorIron => floor = true,
+            BlockClass::Empty | BlockClass::Water => {}
+        }
+    }
+    Some(if floor {
+        NodeType::Walkable
+    } else {
+        NodeType::Open
+    })
+}
+
+/// 寻路的结果
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path {
+    /// 依次经过的位置,不含起点
+    pub nodes: Vec<BlockPos>,
+    /// 是否到达了目标,否则终点是离目标最近的节点
+    pub reached: bool,
+    /// 访问过的节点数
+    pub visited: usize,
+}
+
+impl Path {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn end(&self) -> Option<BlockPos> {
+        self.nodes.last().copied()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Open {
+    f: f32,
+    g: f32,
+    pos: BlockPos,
+}
+
+impl Eq for Open {}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // 最小堆:f 小的优先,相同时 g 大的(离目标近的)优先
+        other.f.total_cmp(&self.f).then(self.g.total_cmp(&other.g))
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn distance(a: BlockPos, b: BlockPos) -> f32 {
+    let (dx, dy, dz) = ((a.x - b.x) as f32, (a.y - b.y) as f32, (a.z - b.z) as f32);
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+struct Search<'a, S> {
+    registry: &'a BlockRegistry,
+    source: &'a S,
+    options: &'a PathOptions,
+    nodes: HashMap<BlockPos, Option<NodeType>>,
+}
+
+impl<S: BlockSource> Search<'_, S> {
+    /// 未加载的区块视为不可通过
+    fn node(&mut self, pos: BlockPos) -> NodeType {
+        *self
+            .nodes
+            .entry(pos)
+            .or_insert_with(|| node_type(self.registry, self.source, pos, self.options))
+            .get_or_insert(NodeType::Blocked)
+    }
+
+    fn passable(&mut self, pos: BlockPos) -> Option<f32> {
+        let node = self.node(pos);
+        if node == NodeType::Open {
+            return None;
+        }
+        self.options.penalty(node)
+    }
+
+    /// 从 `from` 可以到达的相邻节点与代价
+    fn neighbours(&mut self, from: BlockPos) -> Vec<(BlockPos, f32)> {
+        let mut result = Vec::new();
+        let in_water = self.node(from) == NodeType::Water;
+        for (dx, dz) in [
+            (1, 0),
+            (-1, 0),
+            (0, 1),
+            (0, -1),
+            (1, 1),
+            (1, -1),
+            (-1, 1),
+            (-1, -1),
+        ] {
+            let diagonal = dx != 0 && dz != 0;
+            // 斜向移动不能穿过墙角
+            if diagonal
+                && (self.passable(from.offset(dx, 0, 0)).is_none()
+                    || self.passable(from.offset(0, 0, dz)).is_none())
+            {
+                continue;
+            }
+            let step = if diagonal { DIAGONAL_COST } else { 1.0 };
+            let next = from.offset(dx, 0, dz);
+            match self.node(next) {
+                NodeType::Open => {
+                    for fall in 1..=self.options.max_fall {
+                        let below = next.offset(0, -fall, 0);
+                        match self.node(below) {
+                            NodeType::Open => continue,
+                            _ => {
+                                if let Some(penalty) = self.passable(below) {
+                                    result.push((below, step + fall as f32 + penalty));
+                                }
+                                break;
+                            }
+                        }
+                    }
+                }
+                // 跳上一格高的方块,头顶需要留出空间
+                NodeType::Blocked if !diagonal => {
+                    let up = next.offset(0, 1, 0);
+                    if self.node(up) == NodeType::Walkable
+                        && self.node(from.offset(0, 1, 0)) != NodeType::Blocked
+                    {
+                        result.push((up, step + 1.0));
+                    }
+                }
+                _ => {
+                    if let Some(penalty) = self.passable(next) {
+                        result.push((next, step + penalty));
+                    }
+                }
+            }
+        }
+        if in_water {
+            for dy in [1, -1] {
+                let next = from.offset(0, dy, 0);
+                if let Some(penalty) = self.passable(next) {
+                    result.push((next, 1.0 + penalty));
+                }
+            }
+        }
+        result
+    }
+}
+
+/// 从 `start` 到 `goal` 寻路,起点所在区块未加载时返回 `None`
+pub fn find_path(
+    registry: &BlockRegistry,
+    source: &impl BlockSource,
+    start: BlockPos,
+    goal: BlockPos,
+    options: &PathOptions,
+) -> Option<Path> {
+    node_type(registry, source, start, options)?;
+    let mut search = Search {
+        registry,
+        source,
+        options,
+        nodes: HashMap::new(),
+    };
+    let reached = |pos: BlockPos| {
+        let (dx, dz) = ((pos.x - goal.x) as f64, (pos.z - goal.z) as f64);
+        (pos.y - goal.y).abs() <= 1 && (dx * dx + dz * dz).sqrt() <= options.accuracy || pos == goal
+    };
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<BlockPos, BlockPos> = HashMap::new();
+    let mut cost: HashMap<BlockPos, f32> = HashMap::from([(start, 0.0)]);
+    let mut closest = (distance(start, goal), start);
+    let mut visited = 0;
+    let mut found = false;
+    open.push(Open {
+        f: closest.0,
+        g: 0.0,
+        pos: start,
+    });
+    while let Some(Open { g, pos, .. }) = open.pop() {
+        if cost.get(&pos).is_some_and(|best| g > *best) {
+            continue;
+        }
+        if reached(pos) {
+            closest = (0.0, pos);
+            found = true;
+            break;
+        }
+        if visited >= options.max_nodes {
+            break;
+        }
+        visited += 1;
+        for (next, step) in search.neighbours(pos) {
+            if distance(start, next) as f64 > options.max_distance {
+                continue;
+            }
+            let g = g + step;
+            if cost.get(&next).is_some_and(|best| g >= *best) {
+                continue;
+            }
+            cost.insert(next, g);
+            came_from.insert(next, pos);
+            let h = distance(next, goal);
+            if h < closest.0 {
+                closest = (h, next);
+            }
+            open.push(Open {
+                f: g + h,
+                g,
+                pos: next,
+            });
+        }
+    }
+    let mut nodes = Vec::new();
+    let mut pos = closest.1;
+    while pos != start {
+        nodes.push(pos);
+        pos = came_from[&pos];
+    }
+    nodes.reverse();
+    Some(Path {
+        nodes,
+        reached: found,
+        visited,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::stone_floor;
+
+    fn state(registry: &BlockRegistry, name: &str, properties: &[(&str, &str)]) -> BlockState {
+        let properties: Vec<(String, String)> = properties
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        registry.register_test_state(name, &properties)
+    }
+
+    #[test]
+    fn node_types() {
+        let registry = BlockRegistry::builtin();
+        let mut blocks = stone_floor(-8..8, -8..8);
+        let options = PathOptions::new(0.6, 1.95);
+        let at = |x| BlockPos::new(x, 64, 0);
+        blocks.insert(at(1), block::STONE);
+        blocks.insert(at(2), state(&registry, "minecraft:oak_fence", &[]));
+        blocks.insert(
+            at(3),
+            state(&registry, "minecraft:oak_door", &[("open", "false")]),
+        );
+        blocks.insert(
+            at(4),
+            state(&registry, "minecraft:oak_door", &[("open", "true")]),
+        );
+        blocks.insert(at(5), block::WATER);
+        blocks.insert(BlockPos::new(6, 63, 0), block::AIR);
+        blocks.insert(
+            at(7),
+            state(&registry, "minecraft:stone_slab", &[("type", "bottom")]),
+        );
+        let types: Vec<_> = (0..8)
+            .map(|x| node_type(&registry, &blocks, at(x), &options).unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                NodeType::Walkable,
+                NodeType::Blocked,
+                NodeType::Fence,
+                NodeType::DoorClosed,
+                NodeType::DoorOpen,
+                NodeType::Water,
+                NodeType::Open,
+                NodeType::Walkable,
+            ]
+        );
+        // 站在栅栏上同样不能通过
+        assert_eq!(
+            node_type(&registry, &blocks, BlockPos::new(2, 65, 0), &options),
+            Some(NodeType::Fence)
+        );
+    }
+
+    #[test]
+    fn paths_around_walls_and_doors() {
+        let registry = BlockRegistry::builtin();
+        let mut blocks = stone_floor(-8..8, -8..8);
+        // x = 2 处的墙,只在 z = 3 留了一扇关闭的门
+        for z in -8..8 {
+            for y in 64..66 {
+                blocks.insert(BlockPos::new(2, y, z), block::STONE);
+            }
+        }
+        blocks.insert(
+            BlockPos::new(2, 64, 3),
+            state(
+                &registry,
+                "minecraft:oak_door",
+                &[("open", "false"), ("half", "lower")],
+            ),
+        );
+        blocks.insert(
+            BlockPos::new(2, 65, 3),
+            state(
+                &registry,
+                "minecraft:oak_door",
+                &[("open", "false"), ("half", "upper")],
+            ),
+        );
+        let start = BlockPos::new(0, 64, 0);
+        let goal = BlockPos::new(4, 64, 0);
+        let mut options = PathOptions::new(0.6, 1.95);
+
+        let path = find_path(&registry, &blocks, start, goal, &options).unwrap();
+        assert!(!path.reached);
+
+        options.can_open_doors = true;
+        let path = find_path(&registry, &blocks, start, goal, &options).unwrap();
+        assert!(path.reached);
+        assert_eq!(path.end(), Some(goal));
+        assert!(path.nodes.contains(&BlockPos::new(2, 64, 3)));
+
+        // 一格高的台阶跳上去
+        blocks.insert(BlockPos::new(-2, 64, 0), block::STONE);
+        let path = find_path(
+            &registry,
+            &blocks,
+            start,
+            BlockPos::new(-2, 65, 0),
+            &options,
+        )
+        .unwrap();
+        assert!(path.reached);
+        assert_eq!(
+            path.nodes,
+            vec![BlockPos::new(-1, 64, 0), BlockPos::new(-2, 65, 0)]
+        );
+
+        // 超出节点上限时返回离目标最近的位置
+        options.max_nodes = 3;
+        let path = find_path(
+            &registry,
+            &blocks,
+            start,
+            BlockPos::new(-6, 64, 6),
+            &options,
+        )
+        .unwrap();
+        assert!(!path.reached);
+        assert_eq!(path.visited, 3);
+        assert!(!path.is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! 实体移动与碰撞
+//!
+//! 与原版 `Entity.collide` 相同:先沿 y 轴、再沿位移较大的水平轴移动,每个轴都被途经的碰撞箱截断;
+//! 着地或落地时水平方向被挡住会尝试抬升 [`STEP_HEIGHT`] 格越过台阶。
+
+use crate::{
+    block::{self, BlockState},
+    pos::BlockPos,
+    registry::BlockRegistry,
+    shape::{Aabb, collision_boxes},
+    world::BlockSource,
+};
+
+/// 每刻的重力加速度
+pub const GRAVITY: f64 = 0.08;
+/// 每刻竖直速度保留的比例
+pub const VERTICAL_DRAG: f64 = 0.98;
+/// 可以直接走上去的高度
+pub const STEP_HEIGHT: f64 = 0.6;
+/// 起跳速度
+pub const JUMP_VELOCITY: f64 = 0.42;
+pub const PLAYER_WIDTH: f64 = 0.6;
+pub const PLAYER_HEIGHT: f64 = 1.8;
+/// 判断着地时向下探测的距离
+const GROUND_PROBE: f64 = 1.0e-3;
+
+/// 玩家在 (x, y, z) 时的碰撞箱,坐标为脚下中心
+pub fn player_box(x: f64, y: f64, z: f64) -> Aabb {
+    Aabb::at_feet(x, y, z, PLAYER_WIDTH, PLAYER_HEIGHT)
+}
+
+/// `area` 范围内方块的位置,向下多取一格以包含栅栏、墙这类高于一格的方块
+fn block_positions(area: &Aabb) -> impl Iterator<Item = BlockPos> {
+    let range = |min: f64, max: f64| (min.floor() as i32)..=(max.ceil() as i32 - 1);
+    let (xs, ys, zs) = (
+        range(area.min_x, area.max_x),
+        (area.min_y.floor() as i32 - 1)..=(area.max_y.ceil() as i32 - 1),
+        range(area.min_z, area.max_z),
+    );
+    xs.flat_map(move |x| {
+        let zs = zs.clone();
+        ys.clone()
+            .flat_map(move |y| zs.clone().map(move |z| BlockPos::new(x, y, z)))
+    })
+}
+
+/// 与 `area` 相交的方块,区块未加载时返回 `None`
+pub fn blocks_in(source: &impl BlockSource, area: &Aabb) -> Option<Vec<(BlockPos, BlockState)>> {
+    block_positions(area)
+        .map(|pos| source.block(pos).map(|state| (pos, state)))
+        .filter(|b| b.is_none_or(|(_, state)| !block::is_air(state)))
+        .collect()
+}
+
+/// 与 `area` 相交的方块碰撞箱(世界坐标),区块未加载时返回 `None`
+pub fn collisions(
+    registry: &BlockRegistry,
+    source: &impl BlockSource,
+    area: &Aabb,
+) -> Option<Vec<Aabb>> {
+    let mut boxes = Vec::new();
+    for (pos, state) in blocks_in(source, area)? {
+        for shape in collision_boxes(registry, state) {
+            let shape = shape.offset(pos.x as f64, pos.y as f64, pos.z as f64);
+            if shape.intersects(area) {
+                boxes.push(shape);
+            }
+        }
+    }
+    Some(boxes)
+}
+
+/// 在 `boxes` 中移动 `entity`,返回实际的位移
+pub fn collide(boxes: &[Aabb], entity: &Aabb, (dx, dy, dz): (f64, f64, f64)) -> (f64, f64, f64) {
+    let dy = boxes.iter().fold(dy, |d, b| b.clip_y(entity, d));
+    let entity = entity.offset(0.0, dy, 0.0);
+    if dx.abs() < dz.abs() {
+        let dz = boxes.iter().fold(dz, |d, b| b.clip_z(&entity, d));
+        let entity = entity.offset(0.0, 0.0, dz);
+        let dx = boxes.iter().fold(dx, |d, b| b.clip_x(&entity, d));
+        (dx, dy, dz)
+    } else {
+        let dx = boxes.iter().fold(dx, |d, b| b.clip_x(&entity, d));
+        let entity = entity.offset(dx, 0.0, 0.0);
+        let dz = boxes.iter().fold(dz, |d, b| b.clip_z(&entity, d));
+        (dx, dy, dz)
+    }
+}
+
+/// 移动实体并处理台阶,返回实际的位移;区块未加载时返回 `None`
+pub fn move_entity(
+    registry: &BlockRegistry,
+    source: &impl BlockSource,
+    entity: &Aabb,
+    movement: (f64, f64, f64),
+    on_ground: bool,
+) -> Option<(f64, f64, f64)> {
+    let (dx, dy, dz) = movement;
+    let swept = entity.expand_towards(dx, dy, dz);
+    let boxes = collisions(
+        registry,
+        source,
+        &swept.expand_towards(0.0, STEP_HEIGHT, 0.0),
+    )?;
+    let moved = collide(&boxes, entity, movement);
+    let blocked = moved.0 != dx || moved.2 != dz;
+    let landing = dy < 0.0 && moved.1 != dy;
+    if !blocked || !(on_ground || landing) {
+        return Some(moved);
+    }
+    // 先抬升、再水平移动、最后落回台阶上,水平走得更远时采用
+    let up = collide(&boxes, entity, (0.0, STEP_HEIGHT, 0.0)).1;
+    let raised = entity.offset(0.0, up, 0.0);
+    let (sx, _, sz) = collide(&boxes, &raised, (dx, 0.0, dz));
+    let shifted = raised.offset(sx, 0.0, sz);
+    let down = collide(&boxes, &shifted, (0.0, -up + dy.min(0.0), 0.0)).1;
+    let stepped = (sx, up + down, sz);
+    if sx * sx + sz * sz > moved.0 * moved.0 + moved.2 * moved.2 {
+        Some(stepped)
+    } else {
+        Some(moved)
+    }
+}
+
+/// 实体是否站在碰撞箱上,区块未加载时返回 `None`
+pub fn on_ground(
+    registry: &BlockRegistry,
+    source: &impl BlockSource,
+    entity: &Aabb,
+) -> Option<bool> {
+    let below = Aabb::new(
+        entity.min_x,
+        entity.min_y - GROUND_PROBE,
+        entity.min_z,
+        entity.max_x,
+        entity.min_y,
+        entity.max_z,
+    );
+    Some(!collisions(registry, source, &below)?.is_empty())
+}
+
+/// 实体是否与方块重叠
+pub fn is_colliding(
+    registry: &BlockRegistry,
+    source: &impl BlockSource,
+    entity: &Aabb,
+) -> Option<bool> {
+    // 稍微缩小,贴着表面不算重叠
+    Some(!collisions(registry, source, &entity.inflate(-1.0e-7))?.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::test_util::stone_floor;
+
+    fn floor_with_step() -> HashMap<BlockPos, BlockState> {
+        let mut blocks = stone_floor(-4..8, -4..4);
+        blocks.insert(BlockPos::new(2, 64, 0), block::STONE);
+        blocks.insert(BlockPos::new(4, 64, 0), block::STONE);
+        blocks.insert(BlockPos::new(4, 65, 0), block::STONE);
+        blocks
+    }
+
+    #[test]
+    fn gravity_walls_and_steps() {
+        let registry = BlockRegistry::builtin();
+        let blocks = floor_with_step();
+        let entity = player_box(0.5, 64.0, 0.5);
+        assert_eq!(on_ground(&registry, &blocks, &entity), Some(true));
+        assert_eq!(
+            on_ground(&registry, &blocks, &entity.offset(0.0, 0.5, 0.0)),
+            Some(false)
+        );
+
+        // 落到地面上
+        let fall = move_entity(
+            &registry,
+            &blocks,
+            &entity.offset(0.0, 0.5, 0.0),
+            (0.0, -1.0, 0.0),
+            false,
+        );
+        assert!((fall.unwrap().1 + 0.5).abs() < 1e-9);
+
+        // 一格高的台阶走不上去,但可以走到台阶前
+        let walk = move_entity(&registry, &blocks, &entity, (1.5, 0.0, 0.0), true).unwrap();
+        assert!((walk.0 - 1.2).abs() < 1e-6);
+        assert_eq!(walk.1, 0.0);
+
+        // 半格高的方块可以直接走上去
+        let mut blocks = blocks;
+        blocks.insert(
+            BlockPos::new(2, 64, 0),
+            registry
+                .register_test_state("minecraft:stone_slab", &[("type".into(), "bottom".into())]),
+        );
+        let step = move_entity(&registry, &blocks, &entity, (1.5, 0.0, 0.0), true).unwrap();
+        assert!((step.0 - 1.5).abs() < 1e-6);
+        assert!((step.1 - 0.5).abs() < 1e-6);
+
+        // 两格高的墙挡住
+        let entity = player_box(3.5, 64.0, 0.5);
+        let wall = move_entity(&registry, &blocks, &entity, (1.0, 0.0, 0.0), true).unwrap();
+        assert!((wall.0 - 0.2).abs() < 1e-6);
+        assert_eq!(is_colliding(&registry, &blocks, &entity), Some(false));
+        assert_eq!(
+            is_colliding(&registry, &blocks, &entity.offset(0.5, 0.0, 0.0)),
+            Some(true)
+        );
+    }
+}
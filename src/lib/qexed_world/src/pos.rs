@@ -0,0 +1,151 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{MAX_Y, MIN_Y};
+
+/// 方块坐标(世界坐标)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct BlockPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl BlockPos {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        BlockPos { x, y, z }
+    }
+    /// 所在区块坐标
+    pub const fn chunk_pos(&self) -> ChunkPos {
+        ChunkPos::new(self.x >> 4, self.z >> 4)
+    }
+    /// 区块内的局部 x 坐标(0..16)
+    pub const fn local_x(&self) -> usize {
+        (self.x & 15) as usize
+    }
+    /// 区块内的局部 z 坐标(0..16)
+    pub const fn local_z(&self) -> usize {
+        (self.z & 15) as usize
+    }
+    /// 是否在世界高度范围内
+    pub const fn in_height_range(&self) -> bool {
+        self.y >= MIN_Y && self.y <= MAX_Y
+    }
+    pub const fn offset(&self, dx: i32, dy: i32, dz: i32) -> Self {
+        BlockPos::new(self.x + dx, self.y + dy, self.z + dz)
+    }
+    /// 相邻的方块
+    pub const fn relative(&self, direction: Direction) -> Self {
+        let (dx, dy, dz) = direction.offset();
+        self.offset(dx, dy, dz)
+    }
+}
+
+/// 方块的六个面,顺序与原版 `Direction` 相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 6] = [
+        Direction::Down,
+        Direction::Up,
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ];
+    /// 水平的四个方向
+    pub const HORIZONTAL: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ];
+
+    pub const fn offset(&self) -> (i32, i32, i32) {
+        match self {
+            Direction::Down => (0, -1, 0),
+            Direction::Up => (0, 1, 0),
+            Direction::North => (0, 0, -1),
+            Direction::South => (0, 0, 1),
+            Direction::West => (-1, 0, 0),
+            Direction::East => (1, 0, 0),
+        }
+    }
+
+    pub const fn opposite(&self) -> Direction {
+        match self {
+            Direction::Down => Direction::Up,
+            Direction::Up => Direction::Down,
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+        }
+    }
+
+    /// 方块状态属性中使用的名字,例如 `facing=north`
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Direction::Down => "down",
+            Direction::Up => "up",
+            Direction::North => "north",
+            Direction::South => "south",
+            Direction::West => "west",
+            Direction::East => "east",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Direction> {
+        Direction::ALL.into_iter().find(|d| d.name() == name)
+    }
+}
+
+impl fmt::Display for BlockPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
+/// 区块坐标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkPos {
+    pub const fn new(x: i32, z: i32) -> Self {
+        ChunkPos { x, z }
+    }
+    /// 实体坐标所在的区块
+    pub fn from_entity_pos(x: f64, z: f64) -> Self {
+        ChunkPos::new((x.floor() as i32) >> 4, (z.floor() as i32) >> 4)
+    }
+    /// 区块内最小的方块 x 坐标
+    pub const fn min_block_x(&self) -> i32 {
+        self.x << 4
+    }
+    /// 区块内最小的方块 z 坐标
+    pub const fn min_block_z(&self) -> i32 {
+        self.z << 4
+    }
+    /// 切比雪夫距离(视距判断使用的就是这个)
+    pub fn chebyshev_distance(&self, other: &ChunkPos) -> i32 {
+        (self.x - other.x).abs().max((self.z - other.z).abs())
+    }
+}
+
+impl fmt::Display for ChunkPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.x, self.z)
+    }
+}
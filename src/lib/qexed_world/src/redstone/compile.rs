@@ -0,0 +1,261 @@
+//! 预编译
+//!
+//! 把电路化简为逻辑门网络:对每个信号源单独传播一次,记下它能让哪些门的输入、
+//! 哪些红石灯、哪些红石粉(以及强度)得到信号。之后每一刻只需要对网络做布尔运算。
+//!
+//! 再从当前状态开始模拟网络直到状态重复,得到一段过渡过程加上一个循环(稳定状态是周期为 1 的循环),
+//! 运行时只需要按时间回放结果。
+
+use std::collections::HashMap;
+
+use crate::{
+    block::BlockState,
+    pos::BlockPos,
+    redstone::{
+        Grid,
+        component::{self, Component},
+        power::Power,
+        simulate::{GateState, step_gates},
+    },
+    registry::BlockRegistry,
+};
+
+/// 预编译时最多模拟的游戏刻,超过后按最后的状态保持不变
+const MAX_COMPILE_TICKS: usize = 20 * 60;
+
+/// 信号来源
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fanin {
+    /// 是否有常开的信号源(拉杆、红石块)
+    pub constant: bool,
+    /// 能让它得到信号的逻辑门
+    pub gates: Vec<usize>,
+}
+
+impl Fanin {
+    fn add(&mut self, driver: Option<usize>) {
+        match driver {
+            Some(gate) => self.gates.push(gate),
+            None => self.constant = true,
+        }
+    }
+
+    fn active(&self, outputs: &[bool]) -> bool {
+        self.constant || self.gates.iter().any(|g| outputs[*g])
+    }
+}
+
+/// 红石粉在各个信号源输出时的强度,`None` 表示常开的信号源
+pub type WireFanin = Vec<(Option<usize>, u8)>;
+
+/// 化简后的逻辑门网络
+#[derive(Debug, Clone)]
+pub struct Network {
+    /// 逻辑门,按位置排序
+    pub gates: Vec<(BlockPos, Component)>,
+    /// 每个门的输入
+    pub inputs: Vec<Fanin>,
+    pub lamps: Vec<(BlockPos, Fanin)>,
+    pub wires: Vec<(BlockPos, WireFanin)>,
+}
+
+impl Network {
+    pub fn compile(grid: &Grid) -> Network {
+        let gates = grid.gates();
+        let index: HashMap<BlockPos, usize> = gates
+            .iter()
+            .enumerate()
+            .map(|(i, (pos, _))| (*pos, i))
+            .collect();
+        let mut inputs = vec![Fanin::default(); gates.len()];
+        let mut lamps: HashMap<BlockPos, Fanin> =
+            grid.lamps().map(|pos| (pos, Fanin::default())).collect();
+        let mut wires: HashMap<BlockPos, WireFanin> = HashMap::new();
+
+        let drivers = gates
+            .iter()
+            .enumerate()
+            .map(|(i, (pos, _))| (*pos, Some(i)))
+            .chain(grid.sources().map(|pos| (pos, None)));
+        for (pos, driver) in drivers {
+            let power = Power::propagate(grid, [pos]);
+            for gate in &power.inputs {
+                inputs[index[gate]].add(driver);
+            }
+            for lamp in &power.lamps {
+                if let Some(fanin) = lamps.get_mut(lamp) {
+                    fanin.add(driver);
+                }
+            }
+            for (wire, level) in &power.wires {
+                wires.entry(*wire).or_default().push((driver, *level));
+            }
+        }
+
+        let mut lamps: Vec<_> = lamps.into_iter().collect();
+        lamps.sort_by_key(|(pos, _)| *pos);
+        let mut wires: Vec<_> = wires.into_iter().collect();
+        wires.sort_by_key(|(pos, _)| *pos);
+        Network {
+            gates,
+            inputs,
+            lamps,
+            wires,
+        }
+    }
+
+    /// 各个门的输入
+    pub fn gate_inputs(&self, outputs: &[bool]) -> Vec<bool> {
+        self.inputs.iter().map(|f| f.active(outputs)).collect()
+    }
+
+    /// 各个红石灯是否点亮
+    pub fn lamp_states(&self, outputs: &[bool]) -> Vec<bool> {
+        self.lamps.iter().map(|(_, f)| f.active(outputs)).collect()
+    }
+
+    /// 红石粉的信号强度
+    pub fn wire_power(&self, wire: usize, outputs: &[bool]) -> u8 {
+        self.wires[wire]
+            .1
+            .iter()
+            .filter(|(driver, _)| driver.is_none_or(|g| outputs[g]))
+            .map(|(_, level)| *level)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn step(&self, state: &mut GateState) {
+        let components: Vec<Component> = self.gates.iter().map(|(_, c)| *c).collect();
+        step_gates(&components, state, |outputs| self.gate_inputs(outputs));
+    }
+
+    /// 从 `outputs` 开始模拟,生成回放用的程序
+    pub fn precompile(&self, outputs: Vec<bool>) -> Program {
+        let mut state = GateState::new(outputs);
+        let mut seen: HashMap<GateState, usize> = HashMap::new();
+        let mut timeline = Vec::new();
+        let (cycle_start, period) = loop {
+            if let Some(start) = seen.get(&state) {
+                break (*start, timeline.len() - start);
+            }
+            if timeline.len() >= MAX_COMPILE_TICKS {
+                log::warn!(
+                    "红石电路在 {} 刻内没有进入循环,按最后的状态处理",
+                    MAX_COMPILE_TICKS
+                );
+                break (timeline.len() - 1, 1);
+            }
+            seen.insert(state.clone(), timeline.len());
+            timeline.push(state.outputs.clone());
+            self.step(&mut state);
+        };
+        let lamps = timeline.iter().map(|o| self.lamp_states(o)).collect();
+        Program {
+            timeline,
+            lamps,
+            cycle_start,
+            period,
+        }
+    }
+}
+
+/// 预编译的结果:第 t 刻(从编译完成时算起)的门输出与红石灯状态
+#[derive(Debug, Clone)]
+pub struct Program {
+    timeline: Vec<Vec<bool>>,
+    lamps: Vec<Vec<bool>>,
+    cycle_start: usize,
+    period: usize,
+}
+
+impl Program {
+    fn index(&self, t: u64) -> usize {
+        let t = t as usize;
+        if t < self.timeline.len() {
+            t
+        } else {
+            self.cycle_start + (t - self.cycle_start) % self.period
+        }
+    }
+
+    /// 第 `t` 刻各个门的输出
+    pub fn outputs_at(&self, t: u64) -> &[bool] {
+        &self.timeline[self.index(t)]
+    }
+
+    /// 第 `t` 刻各个红石灯是否点亮
+    pub fn lamps_at(&self, t: u64) -> &[bool] {
+        &self.lamps[self.index(t)]
+    }
+
+    /// 过渡过程的长度与循环的周期
+    pub fn cycle(&self) -> (usize, usize) {
+        (self.cycle_start, self.period)
+    }
+
+    /// 循环中至少有一刻输出信号的门,按不可视红石的规则它们显示为激活
+    pub fn active_gates(&self) -> Vec<bool> {
+        let mut active = vec![false; self.timeline.first().map_or(0, Vec::len)];
+        for outputs in &self.timeline[self.cycle_start..] {
+            for (a, o) in active.iter_mut().zip(outputs) {
+                *a |= *o;
+            }
+        }
+        active
+    }
+}
+
+/// 不可视红石的显示状态
+///
+/// - 循环中可能激活的火把、中继器显示为激活,否则显示为未激活;
+/// - 红石粉显示为它在循环中可能达到的最大强度,没有信号也照样显示;
+/// - 红石灯是电路的输出,按回放结果实时显示,这里给出第 0 刻的状态。
+pub fn render(
+    registry: &BlockRegistry,
+    grid: &Grid,
+    network: &Network,
+    program: &Program,
+) -> Vec<(BlockPos, BlockState)> {
+    let active = program.active_gates();
+    let mut changes = Vec::new();
+    for ((pos, _), on) in network.gates.iter().zip(&active) {
+        if let Some(state) = grid
+            .state(*pos)
+            .and_then(|s| component::render_active(registry, s, *on))
+        {
+            changes.push((*pos, state));
+        }
+    }
+    for (i, (pos, _)) in network.wires.iter().enumerate() {
+        let level = network.wire_power(i, &active);
+        if let Some(state) = grid
+            .state(*pos)
+            .and_then(|s| component::render_wire(registry, s, level))
+        {
+            changes.push((*pos, state));
+        }
+    }
+    // 没有任何信号源能到达的红石粉
+    for pos in grid.wires() {
+        if network
+            .wires
+            .binary_search_by_key(&pos, |(p, _)| *p)
+            .is_err()
+            && let Some(state) = grid
+                .state(pos)
+                .and_then(|s| component::render_wire(registry, s, 0))
+        {
+            changes.push((pos, state));
+        }
+    }
+    for ((pos, _), lit) in network.lamps.iter().zip(program.lamps_at(0)) {
+        if let Some(state) = grid
+            .state(*pos)
+            .and_then(|s| component::render_active(registry, s, *lit))
+        {
+            changes.push((*pos, state));
+        }
+    }
+    changes
+}
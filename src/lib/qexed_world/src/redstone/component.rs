@@ -0,0 +1,119 @@
+//! 红石元件
+//!
+//! 从方块状态识别参与红石电路的方块。
+
+use crate::{
+    block::{self, BlockState},
+    light::LightProperties,
+    pos::Direction,
+    registry::BlockRegistry,
+};
+
+/// 电路中的方块
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    /// 红石粉
+    Wire,
+    /// 红石火把,`attached` 为火把附着的方块所在的方向
+    Torch { attached: Direction },
+    /// 中继器,从 `facing` 方向输入,向相反方向输出;`delay` 为 1~4 个红石刻
+    Repeater { facing: Direction, delay: u8 },
+    /// 拉杆,`attached` 为拉杆附着的方块所在的方向
+    Lever { attached: Direction, powered: bool },
+    /// 红石块
+    RedstoneBlock,
+    /// 红石灯
+    Lamp,
+    /// 可以被充能的完整不透明方块
+    Conductor,
+}
+
+impl Component {
+    /// 识别方块,不参与红石电路的方块返回 `None`
+    pub fn from_state(registry: &BlockRegistry, state: BlockState) -> Option<Component> {
+        if block::is_air(state) {
+            return None;
+        }
+        let info = registry.state_info(state)?;
+        let direction = |key: &str| info.property(key).and_then(Direction::from_name);
+        let component = match info.name.as_str() {
+            "minecraft:redstone_wire" => Component::Wire,
+            "minecraft:redstone_torch" => Component::Torch {
+                attached: Direction::Down,
+            },
+            // 墙上的火把朝向远离墙的方向
+            "minecraft:redstone_wall_torch" => Component::Torch {
+                attached: direction("facing")?.opposite(),
+            },
+            "minecraft:repeater" => Component::Repeater {
+                facing: direction("facing")?,
+                delay: info.property("delay")?.parse().ok()?,
+            },
+            "minecraft:lever" => Component::Lever {
+                attached: match info.property("face")? {
+                    "floor" => Direction::Down,
+                    "ceiling" => Direction::Up,
+                    _ => direction("facing")?.opposite(),
+                },
+                powered: info.property("powered")? == "true",
+            },
+            "minecraft:redstone_block" => Component::RedstoneBlock,
+            "minecraft:redstone_lamp" => Component::Lamp,
+            _ if LightProperties::from_info(&info).opacity >= 15 => Component::Conductor,
+            _ => return None,
+        };
+        Some(component)
+    }
+
+    /// 带延迟的逻辑门(火把与中继器)
+    pub fn is_gate(&self) -> bool {
+        matches!(self, Component::Torch { .. } | Component::Repeater { .. })
+    }
+
+    /// 始终输出信号的信号源
+    pub fn is_source(&self) -> bool {
+        matches!(
+            self,
+            Component::Lever { powered: true, .. } | Component::RedstoneBlock
+        )
+    }
+
+    /// 逻辑门的延迟(游戏刻),一个红石刻是两个游戏刻
+    pub fn delay(&self) -> u64 {
+        match self {
+            Component::Torch { .. } => 2,
+            Component::Repeater { delay, .. } => 2 * (*delay).max(1) as u64,
+            _ => 0,
+        }
+    }
+
+    /// 逻辑门方块上记录的输出状态
+    pub fn gate_output(&self, registry: &BlockRegistry, state: BlockState) -> bool {
+        let property = match self {
+            Component::Torch { .. } => "lit",
+            Component::Repeater { .. } => "powered",
+            _ => return false,
+        };
+        registry
+            .state_info(state)
+            .is_some_and(|info| info.property(property) == Some("true"))
+    }
+}
+
+/// 红石粉显示的信号强度
+pub fn render_wire(registry: &BlockRegistry, state: BlockState, power: u8) -> Option<BlockState> {
+    registry.with_property(state, "power", &power.to_string())
+}
+
+/// 火把、红石灯显示为点亮,中继器显示为激活
+pub fn render_active(
+    registry: &BlockRegistry,
+    state: BlockState,
+    active: bool,
+) -> Option<BlockState> {
+    let key = match registry.name_of(state)?.as_str() {
+        "minecraft:repeater" => "powered",
+        _ => "lit",
+    };
+    registry.with_property(state, key, if active { "true" } else { "false" })
+}
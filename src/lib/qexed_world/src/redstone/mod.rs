@@ -0,0 +1,503 @@
+//! 红石
+//!
+//! 见 `doc/block_update.md` 的红石部分。红石不逐刻模拟,而是预编译:
+//! 1. 玩家修改电路后,从修改的位置找出连通的电路([`Grid`]),电路中的红石先显示为未激活;
+//! 2. 下一刻把电路化简为逻辑门网络并模拟到进入循环([`compile`]),之后按时间回放结果;
+//! 3. 按不可视红石的规则,火把、中继器与红石粉只显示它们在循环中可能达到的状态,
+//!    只有红石灯随回放实时变化,时钟电路因此不会每刻都产生方块更新。
+//!
+//! [`simulate`] 是逐刻传播信号的参照实现,用于校验预编译的结果。
+//! 红石属于高频运算,不受玩家观察态影响。
+
+pub mod compile;
+pub mod component;
+pub mod power;
+pub mod simulate;
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::{
+    block::BlockState,
+    pos::{BlockPos, Direction},
+    registry::BlockRegistry,
+};
+
+pub use compile::{Network, Program};
+pub use component::Component;
+pub use power::Power;
+pub use simulate::Simulator;
+
+pub use crate::world::BlockSource;
+
+/// 一个电路最多包含的方块数
+const MAX_CIRCUIT_BLOCKS: usize = 4096;
+
+/// 连通的一组红石元件,以及被它们充能的方块
+#[derive(Debug, Clone, Default)]
+pub struct Grid {
+    blocks: BTreeMap<BlockPos, (Component, BlockState)>,
+}
+
+impl Grid {
+    /// 从 `seed` 开始找出连通的电路,`seed` 不是红石元件时返回 `None`
+    ///
+    /// 元件与相邻的元件、方块相连;方块只与相邻的元件相连,方块之间不会连成电路。
+    pub fn flood(registry: &BlockRegistry, source: &impl BlockSource, seed: BlockPos) -> Option<Grid> {
+        let state = source.block(seed)?;
+        let component = Component::from_state(registry, state)?;
+        if component == Component::Conductor {
+            return None;
+        }
+        let mut grid = Grid::default();
+        grid.blocks.insert(seed, (component, state));
+        let mut queue = VecDeque::from([seed]);
+        while let Some(pos) = queue.pop_front() {
+            let from_conductor = grid.component(pos) == Some(Component::Conductor);
+            for direction in Direction::ALL {
+                let next = pos.relative(direction);
+                if grid.blocks.contains_key(&next) {
+                    continue;
+                }
+                let Some(state) = source.block(next) else {
+                    continue;
+                };
+                let Some(component) = Component::from_state(registry, state) else {
+                    continue;
+                };
+                if from_conductor && component == Component::Conductor {
+                    continue;
+                }
+                if grid.blocks.len() >= MAX_CIRCUIT_BLOCKS {
+                    log::warn!(
+                        "红石电路超过 {} 个方块,只编译其中一部分",
+                        MAX_CIRCUIT_BLOCKS
+                    );
+                    return Some(grid);
+                }
+                grid.blocks.insert(next, (component, state));
+                queue.push_back(next);
+            }
+        }
+        Some(grid)
+    }
+
+    /// 由给定方块组成的电路,方块之间不要求连通
+    pub fn from_blocks(
+        registry: &BlockRegistry,
+        blocks: impl IntoIterator<Item = (BlockPos, BlockState)>,
+    ) -> Grid {
+        let blocks = blocks
+            .into_iter()
+            .filter_map(|(pos, state)| Some((pos, (Component::from_state(registry, state)?, state))))
+            .collect();
+        Grid { blocks }
+    }
+
+    pub fn component(&self, pos: BlockPos) -> Option<Component> {
+        self.blocks.get(&pos).map(|(c, _)| *c)
+    }
+
+    /// 建立电路时的方块状态
+    pub fn state(&self, pos: BlockPos) -> Option<BlockState> {
+        self.blocks.get(&pos).map(|(_, s)| *s)
+    }
+
+    pub fn contains(&self, pos: BlockPos) -> bool {
+        self.blocks.contains_key(&pos)
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// 电路中的所有位置
+    pub fn positions(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        self.blocks.keys().copied()
+    }
+
+    /// 逻辑门,按位置排序
+    pub fn gates(&self) -> Vec<(BlockPos, Component)> {
+        self.blocks
+            .iter()
+            .filter(|(_, (c, _))| c.is_gate())
+            .map(|(pos, (c, _))| (*pos, *c))
+            .collect()
+    }
+
+    /// 常开的信号源
+    pub fn sources(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        self.filter(Component::is_source)
+    }
+
+    pub fn lamps(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        self.filter(|c| *c == Component::Lamp)
+    }
+
+    pub fn wires(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        self.filter(|c| *c == Component::Wire)
+    }
+
+    fn filter<'a>(
+        &'a self,
+        f: impl Fn(&Component) -> bool + 'a,
+    ) -> impl Iterator<Item = BlockPos> + 'a {
+        self.blocks
+            .iter()
+            .filter(move |(_, (c, _))| f(c))
+            .map(|(pos, _)| *pos)
+    }
+}
+
+/// 已编译的电路
+struct Circuit {
+    grid: Grid,
+    network: Network,
+    program: Program,
+    /// 编译完成时引擎的游戏刻
+    start: u64,
+}
+
+impl Circuit {
+    fn touches(&self, pos: BlockPos) -> bool {
+        self.grid.contains(pos)
+            || Direction::ALL
+                .iter()
+                .any(|d| self.grid.contains(pos.relative(*d)))
+    }
+}
+
+/// 世界的红石引擎
+#[derive(Default)]
+pub struct RedstoneEngine {
+    time: u64,
+    circuits: Vec<Circuit>,
+    /// 本刻被修改的位置
+    dirty: HashSet<BlockPos>,
+    /// 等待下一刻编译的位置
+    pending: Vec<BlockPos>,
+    /// 被拆散的电路中逻辑门的输出,重新编译时沿用
+    carried: HashMap<BlockPos, bool>,
+}
+
+impl std::fmt::Debug for RedstoneEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedstoneEngine")
+            .field("time", &self.time)
+            .field("circuits", &self.circuits.len())
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+impl RedstoneEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录方块被修改,涉及的电路会在之后的刻中重新编译
+    pub fn mark_dirty(&mut self, pos: BlockPos) {
+        self.dirty.insert(pos);
+    }
+
+    /// 已编译的电路数
+    pub fn circuit_count(&self) -> usize {
+        self.circuits.len()
+    }
+
+    /// 推进一个游戏刻,返回需要写入世界的方块变化
+    ///
+    /// 写入这些变化不应再调用 [`mark_dirty`](Self::mark_dirty)。
+    pub fn tick(
+        &mut self,
+        registry: &BlockRegistry,
+        source: &impl BlockSource,
+    ) -> Vec<(BlockPos, BlockState)> {
+        self.time += 1;
+        let mut changes = Vec::new();
+
+        // 编译上一刻被修改的电路
+        let pending = std::mem::take(&mut self.pending);
+        for seed in pending {
+            if self.circuits.iter().any(|c| c.grid.contains(seed)) {
+                continue;
+            }
+            let Some(grid) = Grid::flood(registry, source, seed) else {
+                continue;
+            };
+            let network = Network::compile(&grid);
+            let outputs = network
+                .gates
+                .iter()
+                .map(|(pos, gate)| match self.carried.get(pos) {
+                    Some(output) => *output,
+                    None => gate.gate_output(registry, grid.state(*pos).unwrap_or_default()),
+                })
+                .collect();
+            let program = network.precompile(outputs);
+            changes.extend(compile::render(registry, &grid, &network, &program));
+            self.circuits.push(Circuit {
+                grid,
+                network,
+                program,
+                start: self.time,
+            });
+        }
+        self.carried.clear();
+
+        // 拆散本刻被修改的电路,在重新编译前显示为未激活
+        let dirty = std::mem::take(&mut self.dirty);
+        for pos in dirty {
+            self.pending.push(pos);
+            self.pending
+                .extend(Direction::ALL.iter().map(|d| pos.relative(*d)));
+            let mut i = 0;
+            while i < self.circuits.len() {
+                if !self.circuits[i].touches(pos) {
+                    i += 1;
+                    continue;
+                }
+                let circuit = self.circuits.swap_remove(i);
+                let outputs = circuit.program.outputs_at(self.time - circuit.start);
+                for ((gate, _), output) in circuit.network.gates.iter().zip(outputs) {
+                    self.carried.insert(*gate, *output);
+                }
+                for member in circuit.grid.positions() {
+                    let Some(state) = source.block(member) else {
+                        continue;
+                    };
+                    let inactive = match Component::from_state(registry, state) {
+                        Some(Component::Wire) => component::render_wire(registry, state, 0),
+                        Some(Component::Torch { .. } | Component::Repeater { .. }) => {
+                            component::render_active(registry, state, false)
+                        }
+                        _ => None,
+                    };
+                    if let Some(inactive) = inactive.filter(|s| *s != state) {
+                        changes.push((member, inactive));
+                    }
+                    if circuit.grid.component(member) != Some(Component::Conductor) {
+                        self.pending.push(member);
+                    }
+                }
+            }
+        }
+
+        // 回放红石灯
+        for circuit in &self.circuits {
+            let t = self.time - circuit.start;
+            if t == 0 {
+                continue;
+            }
+            let (before, now) = (circuit.program.lamps_at(t - 1), circuit.program.lamps_at(t));
+            for (((pos, _), was), lit) in circuit.network.lamps.iter().zip(before).zip(now) {
+                if was != lit
+                    && let Some(state) = source
+                        .block(*pos)
+                        .and_then(|s| component::render_active(registry, s, *lit))
+                {
+                    changes.push((*pos, state));
+                }
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block;
+
+    fn state(r: &BlockRegistry, name: &str, properties: &[(&str, &str)]) -> BlockState {
+        let properties: Vec<(String, String)> = properties
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        r.register_test_state(name, &properties)
+    }
+
+    /// 同时注册各个信号强度,显示状态才能找到对应的方块状态
+    fn wire(r: &BlockRegistry) -> BlockState {
+        for power in (1..=15).rev() {
+            state(r, "redstone_wire", &[("power", &power.to_string())]);
+        }
+        state(r, "redstone_wire", &[("power", "0")])
+    }
+
+    fn lamp(r: &BlockRegistry) -> BlockState {
+        state(r, "redstone_lamp", &[("lit", "true")]);
+        state(r, "redstone_lamp", &[("lit", "false")])
+    }
+
+    fn lever(r: &BlockRegistry, powered: bool) -> BlockState {
+        let powered = if powered { "true" } else { "false" };
+        state(
+            r,
+            "lever",
+            &[("face", "floor"), ("facing", "north"), ("powered", powered)],
+        )
+    }
+
+    fn wall_torch(r: &BlockRegistry, facing: &str) -> BlockState {
+        state(
+            r,
+            "redstone_wall_torch",
+            &[("facing", facing), ("lit", "true")],
+        );
+        state(
+            r,
+            "redstone_wall_torch",
+            &[("facing", facing), ("lit", "false")],
+        )
+    }
+
+    fn repeater(r: &BlockRegistry, facing: &str, delay: u8) -> BlockState {
+        let delay = delay.to_string();
+        let repeater = |powered| {
+            state(
+                r,
+                "repeater",
+                &[
+                    ("delay", &delay),
+                    ("facing", facing),
+                    ("locked", "false"),
+                    ("powered", powered),
+                ],
+            )
+        };
+        repeater("true");
+        repeater("false")
+    }
+
+    fn at(x: i32, z: i32) -> BlockPos {
+        BlockPos::new(x, 64, z)
+    }
+
+    /// 拉杆 -> 红石粉 -> 中继器(延迟 2) -> 红石灯
+    fn delay_line(r: &BlockRegistry, on: bool) -> HashMap<BlockPos, BlockState> {
+        HashMap::from([
+            (at(0, 0), lever(r, on)),
+            (at(0, 0).offset(0, -1, 0), block::STONE),
+            (at(1, 0), wire(r)),
+            (at(2, 0), wire(r)),
+            (at(3, 0), repeater(r, "west", 2)),
+            (at(4, 0), lamp(r)),
+        ])
+    }
+
+    /// 火把时钟:火把 -> 红石粉 -> 中继器 -> 火把附着的方块,红石灯接在红石粉旁边
+    fn clock(r: &BlockRegistry) -> HashMap<BlockPos, BlockState> {
+        HashMap::from([
+            (at(0, 0), block::STONE),
+            (at(1, 0), wall_torch(r, "east")),
+            (at(2, 0), wire(r)),
+            (at(2, 1), wire(r)),
+            (at(2, 2), wire(r)),
+            (at(1, 2), wire(r)),
+            (at(0, 2), wire(r)),
+            (at(0, 1), repeater(r, "south", 1)),
+            (at(3, 0), lamp(r)),
+        ])
+    }
+
+    /// 预编译的网络与逐刻模拟在每一刻都得到相同的结果
+    fn check(r: &BlockRegistry, blocks: &HashMap<BlockPos, BlockState>, ticks: u64) -> Program {
+        let grid = Grid::from_blocks(r, blocks.iter().map(|(p, s)| (*p, *s)));
+        let network = Network::compile(&grid);
+        let mut simulator = Simulator::new(r, grid.clone());
+        assert_eq!(
+            simulator.gate_positions(),
+            network.gates.iter().map(|(p, _)| *p).collect::<Vec<_>>()
+        );
+        let program = network.precompile(simulator.outputs().to_vec());
+        for t in 0..ticks {
+            assert_eq!(
+                program.outputs_at(t),
+                simulator.outputs(),
+                "第 {} 刻逻辑门不一致",
+                t
+            );
+            let power = simulator.power();
+            let lamps: Vec<bool> = network
+                .lamps
+                .iter()
+                .map(|(p, _)| power.lamps.contains(p))
+                .collect();
+            assert_eq!(program.lamps_at(t), lamps, "第 {} 刻红石灯不一致", t);
+            for (i, (pos, _)) in network.wires.iter().enumerate() {
+                let level = power.wires.get(pos).copied().unwrap_or(0);
+                assert_eq!(network.wire_power(i, simulator.outputs()), level);
+            }
+            simulator.step();
+        }
+        program
+    }
+
+    #[test]
+    fn compiled_matches_simulation() {
+        let r = BlockRegistry::builtin();
+        let program = check(&r, &delay_line(&r, true), 50);
+        // 第 1 刻发现输入变化,4 刻之后中继器输出
+        assert_eq!(program.lamps_at(4), [false]);
+        assert_eq!(program.lamps_at(5), [true]);
+        assert_eq!(program.cycle().1, 1);
+        check(&r, &delay_line(&r, false), 50);
+
+        // 火把与两格延迟的中继器组成周期为 8 刻的时钟
+        let program = check(&r, &clock(&r), 200);
+        assert_eq!(program.cycle().1, 8);
+        assert_eq!(program.active_gates(), vec![true, true]);
+    }
+
+    #[test]
+    fn engine_recompiles_on_edit() {
+        let r = BlockRegistry::builtin();
+        let mut blocks = delay_line(&r, false);
+        let mut engine = RedstoneEngine::new();
+        let apply = |blocks: &mut HashMap<BlockPos, BlockState>,
+                     changes: Vec<(BlockPos, BlockState)>| {
+            for (pos, state) in changes {
+                blocks.insert(pos, state);
+            }
+        };
+        let lit = |blocks: &HashMap<BlockPos, BlockState>| {
+            r.state_info(blocks[&at(4, 0)])
+                .unwrap()
+                .property("lit")
+                == Some("true")
+        };
+
+        engine.mark_dirty(at(0, 0));
+        let changes = engine.tick(&r, &blocks);
+        apply(&mut blocks, changes);
+        let changes = engine.tick(&r, &blocks);
+        apply(&mut blocks, changes);
+        assert_eq!(engine.circuit_count(), 1);
+        assert!(!lit(&blocks));
+
+        // 打开拉杆:先显示为未激活,下一刻重新编译,之后按中继器的延迟点亮红石灯
+        blocks.insert(at(0, 0), lever(&r, true));
+        engine.mark_dirty(at(0, 0));
+        for _ in 0..2 {
+            let changes = engine.tick(&r, &blocks);
+            apply(&mut blocks, changes);
+        }
+        assert_eq!(
+            r.state_info(blocks[&at(1, 0)])
+                .unwrap()
+                .property("power"),
+            Some("15")
+        );
+        assert!(!lit(&blocks));
+        for _ in 0..5 {
+            let changes = engine.tick(&r, &blocks);
+            apply(&mut blocks, changes);
+        }
+        assert!(lit(&blocks));
+        assert_eq!(engine.circuit_count(), 1);
+    }
+}
@@ -0,0 +1,181 @@
+//! 信号传播
+//!
+//! 给定一组正在输出信号的元件,计算整张电路的充能情况。规则是原版的简化版:
+//! - 火把向附着方块以外的五个方向输出信号,并强充能正上方的方块;
+//! - 中继器只向输出方向输出信号并强充能该方块;
+//! - 拉杆向六个方向输出信号并强充能附着的方块,红石块向六个方向输出信号;
+//! - 强充能的方块向六个方向输出信号;
+//! - 红石粉只在同一高度水平相连,每格衰减 1,弱充能下方与水平相邻的方块;
+//! - 弱充能的方块只影响附着在它上面的火把与以它为输入的中继器。
+//!
+//! 所有规则对信号源都是单调的(信号只会取最大值),因此每个信号源单独传播后取并集
+//! 与所有信号源一起传播的结果相同,预编译正是依赖这一点。
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    pos::{BlockPos, Direction},
+    redstone::{Grid, component::Component},
+};
+
+/// 最大信号强度
+pub const MAX_POWER: u8 = 15;
+
+/// 一次传播的结果
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Power {
+    /// 红石粉的信号强度,只包含大于 0 的
+    pub wires: HashMap<BlockPos, u8>,
+    /// 强充能的方块
+    pub strong: HashSet<BlockPos>,
+    /// 弱充能的方块
+    pub weak: HashSet<BlockPos>,
+    /// 输入端有信号的逻辑门
+    pub inputs: HashSet<BlockPos>,
+    /// 被点亮的红石灯
+    pub lamps: HashSet<BlockPos>,
+}
+
+impl Power {
+    /// 从 `drivers` 中的元件开始传播,`drivers` 中不输出信号的元件会被忽略
+    pub fn propagate(grid: &Grid, drivers: impl IntoIterator<Item = BlockPos>) -> Power {
+        let mut power = Power::default();
+        let mut seeds = Vec::new();
+
+        for pos in drivers {
+            let Some(component) = grid.component(pos) else {
+                continue;
+            };
+            match component {
+                Component::Torch { attached } => {
+                    for direction in Direction::ALL {
+                        if direction != attached {
+                            power.receive(grid, pos.relative(direction), pos, &mut seeds);
+                        }
+                    }
+                    if attached != Direction::Up {
+                        power.charge(grid, pos.relative(Direction::Up));
+                    }
+                }
+                Component::Repeater { facing, .. } => {
+                    let output = pos.relative(facing.opposite());
+                    power.receive(grid, output, pos, &mut seeds);
+                    power.charge(grid, output);
+                }
+                Component::Lever {
+                    attached,
+                    powered: true,
+                } => {
+                    for direction in Direction::ALL {
+                        power.receive(grid, pos.relative(direction), pos, &mut seeds);
+                    }
+                    power.charge(grid, pos.relative(attached));
+                }
+                Component::RedstoneBlock => {
+                    for direction in Direction::ALL {
+                        power.receive(grid, pos.relative(direction), pos, &mut seeds);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let strong: Vec<BlockPos> = power.strong.iter().copied().collect();
+        for pos in &strong {
+            for direction in Direction::ALL {
+                power.receive(grid, pos.relative(direction), *pos, &mut seeds);
+            }
+        }
+
+        // 红石粉按距离衰减,广度优先时先到达的就是最大强度
+        let mut queue: VecDeque<(BlockPos, u8)> = VecDeque::new();
+        for pos in seeds {
+            if power.wires.insert(pos, MAX_POWER).is_none() {
+                queue.push_back((pos, MAX_POWER));
+            }
+        }
+        while let Some((pos, level)) = queue.pop_front() {
+            if level <= 1 {
+                continue;
+            }
+            for direction in Direction::HORIZONTAL {
+                let next = pos.relative(direction);
+                if grid.component(next) == Some(Component::Wire) && !power.wires.contains_key(&next)
+                {
+                    power.wires.insert(next, level - 1);
+                    queue.push_back((next, level - 1));
+                }
+            }
+        }
+
+        let wires: Vec<BlockPos> = power.wires.keys().copied().collect();
+        for pos in wires {
+            for direction in [
+                Direction::Down,
+                Direction::North,
+                Direction::South,
+                Direction::West,
+                Direction::East,
+            ] {
+                let target = pos.relative(direction);
+                match grid.component(target) {
+                    Some(Component::Conductor) => {
+                        power.weak.insert(target);
+                    }
+                    Some(Component::Lamp) => {
+                        power.lamps.insert(target);
+                    }
+                    Some(Component::Repeater { facing, .. }) if target.relative(facing) == pos => {
+                        power.inputs.insert(target);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let charged: Vec<BlockPos> = power.strong.union(&power.weak).copied().collect();
+        for pos in charged {
+            for direction in Direction::ALL {
+                power.receive_gate(grid, pos.relative(direction), pos);
+            }
+        }
+        power
+    }
+
+    /// `from` 处的元件向 `target` 输出信号
+    fn receive(
+        &mut self,
+        grid: &Grid,
+        target: BlockPos,
+        from: BlockPos,
+        seeds: &mut Vec<BlockPos>,
+    ) {
+        match grid.component(target) {
+            Some(Component::Wire) => seeds.push(target),
+            Some(Component::Lamp) => {
+                self.lamps.insert(target);
+            }
+            _ => self.receive_gate(grid, target, from),
+        }
+    }
+
+    /// `from` 是 `target` 处逻辑门的输入时,输入端有信号
+    fn receive_gate(&mut self, grid: &Grid, target: BlockPos, from: BlockPos) {
+        match grid.component(target) {
+            Some(Component::Torch { attached }) if target.relative(attached) == from => {
+                self.inputs.insert(target);
+            }
+            Some(Component::Repeater { facing, .. }) if target.relative(facing) == from => {
+                self.inputs.insert(target);
+            }
+            _ => {}
+        }
+    }
+
+    /// 强充能 `target` 处的方块
+    fn charge(&mut self, grid: &Grid, target: BlockPos) {
+        if grid.component(target) == Some(Component::Conductor) {
+            self.strong.insert(target);
+        }
+    }
+}
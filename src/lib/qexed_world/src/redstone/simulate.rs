@@ -0,0 +1,129 @@
+//! 逐刻模拟
+//!
+//! 每个游戏刻都对整张电路重新传播信号,是预编译结果的参照实现,用于校验。
+//!
+//! 逻辑门的时序(两种实现共用):
+//! 1. 计划到期的门按到期前的输入更新输出(同一刻到期的门同时更新);
+//! 2. 输出更新后,期望输出与当前输出不同且没有计划的门安排在延迟之后更新。
+
+use crate::{
+    pos::BlockPos,
+    redstone::{Grid, component::Component, power::Power},
+    registry::BlockRegistry,
+};
+
+/// 所有逻辑门的状态
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GateState {
+    /// 当前输出
+    pub outputs: Vec<bool>,
+    /// 距离计划更新还剩的游戏刻
+    pub pending: Vec<Option<u64>>,
+}
+
+impl GateState {
+    pub fn new(outputs: Vec<bool>) -> Self {
+        let pending = vec![None; outputs.len()];
+        GateState { outputs, pending }
+    }
+}
+
+/// 逻辑门的期望输出:火把取反,中继器照原样输出
+fn desired(gate: Component, input: bool) -> bool {
+    match gate {
+        Component::Torch { .. } => !input,
+        _ => input,
+    }
+}
+
+/// 推进一个游戏刻
+///
+/// `gates` 为逻辑门,`inputs` 根据所有门的输出计算每个门的输入。
+pub fn step_gates(
+    gates: &[Component],
+    state: &mut GateState,
+    inputs: impl Fn(&[bool]) -> Vec<bool>,
+) {
+    let before = inputs(&state.outputs);
+    let mut outputs = state.outputs.clone();
+    for (i, pending) in state.pending.iter_mut().enumerate() {
+        if let Some(left) = pending {
+            *left -= 1;
+            if *left == 0 {
+                *pending = None;
+                outputs[i] = desired(gates[i], before[i]);
+            }
+        }
+    }
+    state.outputs = outputs;
+    let after = inputs(&state.outputs);
+    for (i, gate) in gates.iter().enumerate() {
+        if state.pending[i].is_none() && desired(*gate, after[i]) != state.outputs[i] {
+            state.pending[i] = Some(gate.delay());
+        }
+    }
+}
+
+/// 逐刻模拟器
+pub struct Simulator {
+    grid: Grid,
+    gates: Vec<(BlockPos, Component)>,
+    state: GateState,
+}
+
+impl Simulator {
+    /// 逻辑门的初始输出取方块上记录的状态
+    pub fn new(registry: &BlockRegistry, grid: Grid) -> Self {
+        let gates = grid.gates();
+        let outputs = gates
+            .iter()
+            .map(|(pos, gate)| gate.gate_output(registry, grid.state(*pos).unwrap_or_default()))
+            .collect();
+        Simulator {
+            grid,
+            gates,
+            state: GateState::new(outputs),
+        }
+    }
+
+    pub fn with_outputs(mut self, outputs: Vec<bool>) -> Self {
+        self.state = GateState::new(outputs);
+        self
+    }
+
+    /// 逻辑门的位置,顺序与 [`outputs`](Self::outputs) 相同
+    pub fn gate_positions(&self) -> Vec<BlockPos> {
+        self.gates.iter().map(|(pos, _)| *pos).collect()
+    }
+
+    pub fn outputs(&self) -> &[bool] {
+        &self.state.outputs
+    }
+
+    /// 当前输出下整张电路的充能情况
+    pub fn power(&self) -> Power {
+        power_of(&self.grid, &self.gates, &self.state.outputs)
+    }
+
+    pub fn step(&mut self) {
+        let components: Vec<Component> = self.gates.iter().map(|(_, c)| *c).collect();
+        let (grid, gates) = (&self.grid, &self.gates);
+        step_gates(&components, &mut self.state, |outputs| {
+            let power = power_of(grid, gates, outputs);
+            gates
+                .iter()
+                .map(|(pos, _)| power.inputs.contains(pos))
+                .collect()
+        });
+    }
+}
+
+fn power_of(grid: &Grid, gates: &[(BlockPos, Component)], outputs: &[bool]) -> Power {
+    let drivers = gates
+        .iter()
+        .zip(outputs)
+        .filter(|(_, on)| **on)
+        .map(|((pos, _), _)| *pos)
+        .chain(grid.sources());
+    Power::propagate(grid, drivers)
+}
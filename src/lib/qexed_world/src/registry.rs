@@ -0,0 +1,420 @@
+//! 方块状态注册表
+//!
+//! 存档中的方块以 `Name` + `Properties` 的形式保存,网络与内存中使用全局状态ID,
+//! 两者之间的转换由这里负责。完整的映射来自原版数据生成器导出的 `blocks.json`
+//! (`java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports`)。
+//! 服务端启动时文件不存在会尝试用工作目录下的 `server.jar` 生成,仍然没有时拒绝启动;
+//! 只包含常用方块的内置表只在未初始化时(测试、工具)使用,不能用于导入原版存档。
+//!
+//! 状态ID会直接发给客户端,因此不会为无法识别的方块状态分配ID:读写存档时遇到它们会返回
+//! [`WorldError::UnknownBlockState`](crate::error::WorldError::UnknownBlockState),不会悄悄替换为空气。
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    process::Command,
+    sync::RwLock,
+};
+
+use once_cell::sync::OnceCell;
+
+use crate::{block::BlockState, error::WorldError};
+
+/// 默认的 `blocks.json` 路径
+pub const BLOCKS_REPORT_PATH: &str = "data/reports/blocks.json";
+/// 用于生成 `blocks.json` 的原版服务端(1.21.8)
+pub const SERVER_JAR_PATH: &str = "server.jar";
+
+/// 单个方块状态的描述
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockStateInfo {
+    /// 方块名,例如 `minecraft:oak_log`
+    pub name: String,
+    /// 按属性名排序的属性
+    pub properties: Vec<(String, String)>,
+    /// 是否是该方块的默认状态
+    pub default: bool,
+}
+
+impl BlockStateInfo {
+    /// 获取属性值
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    states: HashMap<BlockState, BlockStateInfo>,
+    lookup: HashMap<String, BlockState>,
+    defaults: HashMap<String, BlockState>,
+    next_id: BlockState,
+}
+
+/// 方块状态注册表
+#[derive(Debug, Default)]
+pub struct BlockRegistry {
+    inner: RwLock<Inner>,
+    /// 是否来自原版数据生成器导出的完整方块表
+    complete: bool,
+}
+
+/// 生成查找键,例如 `minecraft:water[level=0]`
+fn state_key(name: &str, properties: &[(String, String)]) -> String {
+    if properties.is_empty() {
+        return name.to_string();
+    }
+    let mut props: Vec<_> = properties.iter().collect();
+    props.sort();
+    let props: Vec<String> = props.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    format!("{}[{}]", name, props.join(","))
+}
+
+/// 补全 `minecraft:` 命名空间
+fn full_name(name: &str) -> String {
+    if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("minecraft:{}", name)
+    }
+}
+
+impl BlockRegistry {
+    /// 内置的最小注册表
+    pub fn builtin() -> Self {
+        let registry = BlockRegistry::default();
+        let simple: [(&str, BlockState); 16] = [
+            ("air", 0),
+            ("stone", 1),
+            ("granite", 2),
+            ("polished_granite", 3),
+            ("diorite", 4),
+            ("polished_diorite", 5),
+            ("andesite", 6),
+            ("polished_andesite", 7),
+            ("dirt", 10),
+            ("coarse_dirt", 11),
+            ("cobblestone", 14),
+            ("oak_planks", 15),
+            ("bedrock", 85),
+            ("sand", 118),
+            ("red_sand", 123),
+            ("gravel", 124),
+        ];
+        for (name, id) in simple {
+            registry.register(
+                id,
+                BlockStateInfo {
+                    name: full_name(name),
+                    properties: vec![],
+                    default: true,
+                },
+            );
+        }
+        for (name, base) in [("grass_block", 8), ("podzol", 12)] {
+            for (i, snowy) in ["true", "false"].iter().enumerate() {
+                registry.register(
+                    base + i as BlockState,
+                    BlockStateInfo {
+                        name: full_name(name),
+                        properties: vec![("snowy".to_string(), snowy.to_string())],
+                        default: i == 1,
+                    },
+                );
+            }
+        }
+        for (name, base) in [("water", 86), ("lava", 102)] {
+            for level in 0..16 {
+                registry.register(
+                    base + level,
+                    BlockStateInfo {
+                        name: full_name(name),
+                        properties: vec![("level".to_string(), level.to_string())],
+                        default: level == 0,
+                    },
+                );
+            }
+        }
+        for dusted in 0..4 {
+            registry.register(
+                119 + dusted,
+                BlockStateInfo {
+                    name: full_name("suspicious_sand"),
+                    properties: vec![("dusted".to_string(), dusted.to_string())],
+                    default: dusted == 0,
+                },
+            );
+        }
+        registry
+    }
+
+    /// 解析原版数据生成器导出的 `blocks.json`
+    pub fn from_report(json: &str) -> Result<Self, serde_json::Error> {
+        let root: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)?;
+        let registry = BlockRegistry {
+            complete: true,
+            ..Default::default()
+        };
+        for (name, block) in root {
+            let Some(states) = block.get("states").and_then(|s| s.as_array()) else {
+                continue;
+            };
+            for state in states {
+                let Some(id) = state.get("id").and_then(|i| i.as_u64()) else {
+                    continue;
+                };
+                let mut properties: Vec<(String, String)> = state
+                    .get("properties")
+                    .and_then(|p| p.as_object())
+                    .map(|p| {
+                        p.iter()
+                            .map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                properties.sort();
+                registry.register(
+                    id as BlockState,
+                    BlockStateInfo {
+                        name: name.clone(),
+                        properties,
+                        default: state
+                            .get("default")
+                            .and_then(|d| d.as_bool())
+                            .unwrap_or(false),
+                    },
+                );
+            }
+        }
+        Ok(registry)
+    }
+
+    /// 从文件加载
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WorldError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            WorldError::BlockRegistry(format!(
+                "无法读取 {}: {}。请用 `java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar \
+                 --reports --output data` 生成,或把 1.21.8 的 server.jar 放到工作目录",
+                path.display(),
+                e
+            ))
+        })?;
+        let registry = Self::from_report(&json)
+            .map_err(|e| WorldError::BlockRegistry(format!("{} 解析失败: {}", path.display(), e)))?;
+        log::info!(
+            "已加载方块注册表 {}: {} 个方块状态",
+            path.display(),
+            registry.len()
+        );
+        Ok(registry)
+    }
+
+    /// 是否是原版数据生成器导出的完整方块表,内置表与测试注册表返回 `false`
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    fn register(&self, id: BlockState, info: BlockStateInfo) {
+        let mut inner = self.inner.write().unwrap();
+        let key = state_key(&info.name, &info.properties);
+        if info.default || !inner.defaults.contains_key(&info.name) {
+            inner.defaults.insert(info.name.clone(), id);
+        }
+        inner.lookup.insert(key, id);
+        inner.states.insert(id, info);
+        inner.next_id = inner.next_id.max(id + 1);
+    }
+
+    /// 已注册的方块状态数量
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 根据方块名与属性获取状态ID
+    ///
+    /// 属性不完整时返回该方块的默认状态。
+    pub fn state_id(&self, name: &str, properties: &[(String, String)]) -> Option<BlockState> {
+        let name = full_name(name);
+        let inner = self.inner.read().unwrap();
+        inner
+            .lookup
+            .get(&state_key(&name, properties))
+            .or_else(|| inner.defaults.get(&name))
+            .copied()
+    }
+
+    /// 方块的默认状态ID
+    pub fn default_state(&self, name: &str) -> Option<BlockState> {
+        self.inner
+            .read()
+            .unwrap()
+            .defaults
+            .get(&full_name(name))
+            .copied()
+    }
+
+    /// 为注册表中没有的方块状态分配ID,只用于测试
+    ///
+    /// 分配的ID与原版无关,不能发给客户端。
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn register_test_state(&self, name: &str, properties: &[(String, String)]) -> BlockState {
+        let name = full_name(name);
+        let (id, has_default) = {
+            let inner = self.inner.read().unwrap();
+            if let Some(id) = inner.lookup.get(&state_key(&name, properties)) {
+                return *id;
+            }
+            (inner.next_id, inner.defaults.contains_key(&name))
+        };
+        let mut properties = properties.to_vec();
+        properties.sort();
+        self.register(
+            id,
+            BlockStateInfo {
+                name,
+                properties,
+                default: !has_default,
+            },
+        );
+        id
+    }
+
+    /// 根据状态ID获取方块描述
+    pub fn state_info(&self, id: BlockState) -> Option<BlockStateInfo> {
+        self.inner.read().unwrap().states.get(&id).cloned()
+    }
+
+    /// 修改方块状态的一个属性,方块没有这个属性或新状态未注册时返回 `None`
+    pub fn with_property(&self, id: BlockState, key: &str, value: &str) -> Option<BlockState> {
+        let info = self.state_info(id)?;
+        info.property(key)?;
+        let properties: Vec<(String, String)> = info
+            .properties
+            .iter()
+            .map(|(k, v)| {
+                let v = if k == key { value } else { v.as_str() };
+                (k.clone(), v.to_string())
+            })
+            .collect();
+        let inner = self.inner.read().unwrap();
+        inner
+            .lookup
+            .get(&state_key(&info.name, &properties))
+            .copied()
+    }
+
+    /// 方块名
+    pub fn name_of(&self, id: BlockState) -> Option<String> {
+        self.inner
+            .read()
+            .unwrap()
+            .states
+            .get(&id)
+            .map(|s| s.name.clone())
+    }
+}
+
+static REGISTRY: OnceCell<BlockRegistry> = OnceCell::new();
+
+/// 运行原版数据生成器导出报告,`blocks.json` 写到 `output/reports/blocks.json`
+pub fn generate_block_report(
+    server_jar: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+) -> Result<(), WorldError> {
+    let server_jar = server_jar.as_ref();
+    log::info!("正在用 {} 生成方块注册表", server_jar.display());
+    let status = Command::new("java")
+        .arg("-DbundlerMainClass=net.minecraft.data.Main")
+        .arg("-jar")
+        .arg(server_jar)
+        .arg("--reports")
+        .arg("--output")
+        .arg(output.as_ref())
+        .status()
+        .map_err(|e| WorldError::BlockRegistry(format!("无法运行 java: {}", e)))?;
+    if !status.success() {
+        return Err(WorldError::BlockRegistry(format!(
+            "数据生成器退出: {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// 初始化全局方块注册表,只有第一次调用生效
+///
+/// `path` 不存在而工作目录下有 [`SERVER_JAR_PATH`] 时先生成报告;无法得到完整的方块表时
+/// 返回错误,调用方应当拒绝启动。
+pub fn init_block_registry(path: impl AsRef<Path>) -> Result<(), WorldError> {
+    let path = path.as_ref();
+    if !path.exists()
+        && Path::new(SERVER_JAR_PATH).exists()
+        && let Some(output) = path.parent().and_then(Path::parent)
+    {
+        generate_block_report(SERVER_JAR_PATH, output)?;
+    }
+    let registry = BlockRegistry::load(path)?;
+    let _ = REGISTRY.set(registry);
+    Ok(())
+}
+
+/// 全局方块注册表,未初始化时使用内置表(不完整,见 [`BlockRegistry::is_complete`])
+pub fn block_registry() -> &'static BlockRegistry {
+    REGISTRY.get_or_init(BlockRegistry::builtin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block;
+
+    #[test]
+    fn lookup_and_unknown_states() {
+        let registry = BlockRegistry::builtin();
+        assert!(!registry.is_complete());
+        assert!(BlockRegistry::from_report("{}").unwrap().is_complete());
+        assert!(matches!(
+            BlockRegistry::load("missing/blocks.json"),
+            Err(WorldError::BlockRegistry(_))
+        ));
+        let level = |l: &str| vec![("level".to_string(), l.to_string())];
+        assert_eq!(registry.state_id("minecraft:water", &level("3")), Some(89));
+        assert_eq!(registry.state_id("grass_block", &[]), Some(9));
+        // 未知的方块不会分配ID
+        let axis = |a: &str| vec![("axis".to_string(), a.to_string())];
+        assert_eq!(registry.state_id("oak_log", &axis("y")), None);
+        // 已知方块的属性不匹配时退回默认状态
+        assert_eq!(registry.state_id("water", &level("99")), Some(block::WATER));
+
+        let id = registry.register_test_state(
+            "minecraft:oak_log",
+            &[("axis".to_string(), "y".to_string())],
+        );
+        assert_eq!(registry.state_info(id).unwrap().property("axis"), Some("y"));
+        assert_eq!(
+            registry.state_id(
+                "minecraft:oak_log",
+                &[("axis".to_string(), "y".to_string())]
+            ),
+            Some(id)
+        );
+        // 同一方块的其他状态分配不同的ID,默认状态不变
+        let x = registry.register_test_state(
+            "oak_log",
+            &[("axis".to_string(), "x".to_string())],
+        );
+        assert_ne!(x, id);
+        assert_eq!(registry.default_state("oak_log"), Some(id));
+        assert_eq!(registry.with_property(id, "axis", "x"), Some(x));
+        assert_eq!(registry.with_property(id, "axis", "z"), None);
+        assert_eq!(registry.with_property(89, "level", "0"), Some(86));
+    }
+}
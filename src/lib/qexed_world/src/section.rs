@@ -0,0 +1,100 @@
+use qexed_net::packet::encode::PacketWriter;
+
+use crate::{
+    biome::{self, Biome},
+    block::{self, BlockState},
+    palette::{PaletteKind, PalettedContainer},
+};
+
+/// 区块段落(16x16x16)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkSection {
+    /// 非空气方块数量
+    block_count: u16,
+    pub blocks: PalettedContainer,
+    pub biomes: PalettedContainer,
+}
+
+impl Default for ChunkSection {
+    fn default() -> Self {
+        Self::new(biome::PLAINS)
+    }
+}
+
+impl ChunkSection {
+    /// 创建一个全是空气的段落
+    pub fn new(biome: Biome) -> Self {
+        ChunkSection {
+            block_count: 0,
+            blocks: PalettedContainer::single(PaletteKind::Blocks, block::AIR),
+            biomes: PalettedContainer::single(PaletteKind::Biomes, biome),
+        }
+    }
+
+    /// 由已有的方块与生物群系容器创建,自动统计非空气方块数
+    pub fn from_containers(blocks: PalettedContainer, biomes: PalettedContainer) -> Self {
+        let block_count = blocks.count(|s| !block::is_air(s)) as u16;
+        ChunkSection {
+            block_count,
+            blocks,
+            biomes,
+        }
+    }
+
+    /// 方块下标(与存档/协议一致: y,z,x 顺序)
+    #[inline]
+    pub const fn block_index(x: usize, y: usize, z: usize) -> usize {
+        (y << 8) | (z << 4) | x
+    }
+
+    /// 生物群系下标(4x4x4 格)
+    #[inline]
+    pub const fn biome_index(x: usize, y: usize, z: usize) -> usize {
+        ((y >> 2) << 4) | ((z >> 2) << 2) | (x >> 2)
+    }
+
+    pub fn block_count(&self) -> u16 {
+        self.block_count
+    }
+
+    /// 段落是否全是空气
+    pub fn is_empty(&self) -> bool {
+        self.block_count == 0
+    }
+
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> BlockState {
+        self.blocks.get(Self::block_index(x, y, z))
+    }
+
+    /// 设置方块,返回旧方块
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, state: BlockState) -> BlockState {
+        let old = self.blocks.set(Self::block_index(x, y, z), state);
+        match (block::is_air(old), block::is_air(state)) {
+            (true, false) => self.block_count += 1,
+            (false, true) => self.block_count -= 1,
+            _ => {}
+        }
+        old
+    }
+
+    /// 用同一种方块填满整个段落
+    pub fn fill(&mut self, state: BlockState) {
+        self.blocks.fill(state);
+        self.block_count = if block::is_air(state) { 0 } else { 4096 };
+    }
+
+    pub fn get_biome(&self, x: usize, y: usize, z: usize) -> Biome {
+        self.biomes.get(Self::biome_index(x, y, z))
+    }
+
+    pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: Biome) {
+        self.biomes.set(Self::biome_index(x, y, z), biome);
+    }
+
+    /// 按网络协议写入
+    pub fn write(&self, w: &mut PacketWriter) {
+        w.i16(self.block_count as i16);
+        self.blocks.write(w);
+        self.biomes.write(w);
+    }
+}
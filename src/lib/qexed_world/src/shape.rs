@@ -0,0 +1,473 @@
+//! 方块的碰撞箱
+//!
+//! 与光照属性一样,原版的碰撞形状写在方块类里,数据报告中没有导出。这里按方块名整理了常见的
+//! 非完整方块,楼梯只按直楼梯处理,未列出的方块视为完整方块。坐标以方块的西北下角为原点,单位为格。
+
+use crate::{
+    block::{self, BlockState},
+    registry::{BlockRegistry, BlockStateInfo},
+};
+
+/// 轴对齐包围盒
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub min_z: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+    pub max_z: f64,
+}
+
+impl Aabb {
+    pub const FULL: Aabb = Aabb::new(0.0, 0.0, 0.0, 1.0, 1.0, 1.0);
+
+    pub const fn new(
+        min_x: f64,
+        min_y: f64,
+        min_z: f64,
+        max_x: f64,
+        max_y: f64,
+        max_z: f64,
+    ) -> Self {
+        Aabb {
+            min_x,
+            min_y,
+            min_z,
+            max_x,
+            max_y,
+            max_z,
+        }
+    }
+
+    /// 以 1/16 格为单位的方块内形状,与原版 `Block.box` 相同
+    const fn pixels(
+        min_x: f64,
+        min_y: f64,
+        min_z: f64,
+        max_x: f64,
+        max_y: f64,
+        max_z: f64,
+    ) -> Self {
+        Aabb::new(
+            min_x / 16.0,
+            min_y / 16.0,
+            min_z / 16.0,
+            max_x / 16.0,
+            max_y / 16.0,
+            max_z / 16.0,
+        )
+    }
+
+    /// 以脚下中心为基准、宽 `width` 高 `height` 的包围盒(实体的碰撞箱)
+    pub fn at_feet(x: f64, y: f64, z: f64, width: f64, height: f64) -> Self {
+        let half = width / 2.0;
+        Aabb::new(x - half, y, z - half, x + half, y + height, z + half)
+    }
+
+    pub fn offset(&self, dx: f64, dy: f64, dz: f64) -> Self {
+        Aabb::new(
+            self.min_x + dx,
+            self.min_y + dy,
+            self.min_z + dz,
+            self.max_x + dx,
+            self.max_y + dy,
+            self.max_z + dz,
+        )
+    }
+
+    /// 向移动方向延伸,得到移动过程中扫过的范围
+    pub fn expand_towards(&self, dx: f64, dy: f64, dz: f64) -> Self {
+        let mut aabb = *self;
+        if dx < 0.0 {
+            aabb.min_x += dx
+        } else {
+            aabb.max_x += dx
+        }
+        if dy < 0.0 {
+            aabb.min_y += dy
+        } else {
+            aabb.max_y += dy
+        }
+        if dz < 0.0 {
+            aabb.min_z += dz
+        } else {
+            aabb.max_z += dz
+        }
+        aabb
+    }
+
+    /// 各方向向外扩大 `d`
+    pub fn inflate(&self, d: f64) -> Self {
+        Aabb::new(
+            self.min_x - d,
+            self.min_y - d,
+            self.min_z - d,
+            self.max_x + d,
+            self.max_y + d,
+            self.max_z + d,
+        )
+    }
+
+    /// 内部相交(只接触表面不算)
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min_x < other.max_x
+            && self.max_x > other.min_x
+            && self.min_y < other.max_y
+            && self.max_y > other.min_y
+            && self.min_z < other.max_z
+            && self.max_z > other.min_z
+    }
+
+    /// `other` 沿 x 轴移动 `dx` 时被本包围盒挡住后实际能移动的距离
+    pub fn clip_x(&self, other: &Aabb, dx: f64) -> f64 {
+        if other.max_y <= self.min_y
+            || other.min_y >= self.max_y
+            || other.max_z <= self.min_z
+            || other.min_z >= self.max_z
+        {
+            return dx;
+        }
+        clip(dx, other.min_x, other.max_x, self.min_x, self.max_x)
+    }
+
+    pub fn clip_y(&self, other: &Aabb, dy: f64) -> f64 {
+        if other.max_x <= self.min_x
+            || other.min_x >= self.max_x
+            || other.max_z <= self.min_z
+            || other.min_z >= self.max_z
+        {
+            return dy;
+        }
+        clip(dy, other.min_y, other.max_y, self.min_y, self.max_y)
+    }
+
+    pub fn clip_z(&self, other: &Aabb, dz: f64) -> f64 {
+        if other.max_x <= self.min_x
+            || other.min_x >= self.max_x
+            || other.max_y <= self.min_y
+            || other.min_y >= self.max_y
+        {
+            return dz;
+        }
+        clip(dz, other.min_z, other.max_z, self.min_z, self.max_z)
+    }
+}
+
+/// 单轴上从 [min, max] 移动 `d` 时被 [wall_min, wall_max] 挡住后的距离
+fn clip(d: f64, min: f64, max: f64, wall_min: f64, wall_max: f64) -> f64 {
+    // 与原版相同留出 1e-7 的余量,避免浮点误差导致贴着墙的实体卡进方块
+    const EPSILON: f64 = 1.0e-7;
+    if d > 0.0 && max <= wall_min + EPSILON {
+        d.min(wall_min - max)
+    } else if d < 0.0 && min >= wall_max - EPSILON {
+        d.max(wall_max - min)
+    } else {
+        d
+    }
+}
+
+/// 查询方块状态的碰撞箱(方块内坐标),未知的状态视为完整方块
+pub fn collision_boxes(registry: &BlockRegistry, state: BlockState) -> Vec<Aabb> {
+    if block::is_air(state) {
+        return Vec::new();
+    }
+    match registry.state_info(state) {
+        Some(info) => shape_of(&info),
+        None => vec![Aabb::FULL],
+    }
+}
+
+pub fn shape_of(info: &BlockStateInfo) -> Vec<Aabb> {
+    let name = info.name.strip_prefix("minecraft:").unwrap_or(&info.name);
+    let property = |key: &str| info.property(key).unwrap_or_default();
+    let connected = |key: &str| matches!(info.property(key), Some("true" | "low" | "tall"));
+    let height = |h: f64| vec![Aabb::pixels(0.0, 0.0, 0.0, 16.0, h, 16.0)];
+
+    if NO_COLLISION.contains(&name)
+        || (!name.ends_with("_block") && NO_COLLISION_PATTERNS.iter().any(|p| name.contains(p)))
+    {
+        return Vec::new();
+    }
+    match name {
+        _ if name.ends_with("_slab") => match property("type") {
+            "bottom" => height(8.0),
+            "top" => vec![Aabb::pixels(0.0, 8.0, 0.0, 16.0, 16.0, 16.0)],
+            _ => vec![Aabb::FULL],
+        },
+        _ if name.ends_with("_stairs") => stairs(property("facing"), property("half")),
+        _ if name.ends_with("carpet") || name == "moss_carpet" => height(1.0),
+        "snow" => {
+            let layers: f64 = property("layers").parse().unwrap_or(1.0);
+            if layers <= 1.0 {
+                Vec::new()
+            } else {
+                height((layers - 1.0) * 2.0)
+            }
+        }
+        _ if name.ends_with("_fence_gate") => {
+            if property("open") == "true" {
+                Vec::new()
+            } else if matches!(property("facing"), "north" | "south") {
+                vec![Aabb::pixels(0.0, 0.0, 6.0, 16.0, 24.0, 10.0)]
+            } else {
+                vec![Aabb::pixels(6.0, 0.0, 0.0, 10.0, 24.0, 16.0)]
+            }
+        }
+        _ if name.ends_with("_fence") => cross(2.0, 2.0, 24.0, true, connected),
+        _ if name.ends_with("_wall") => cross(4.0, 3.0, 24.0, property("up") == "true", connected),
+        _ if name.ends_with("_pane") || name.ends_with("_bars") || name == "glass_pane" => {
+            cross(1.0, 1.0, 16.0, true, connected)
+        }
+        _ if name.ends_with("_trapdoor") => {
+            trapdoor(property("facing"), property("half"), property("open"))
+        }
+        _ if name.ends_with("_door") => {
+            door(property("facing"), property("open"), property("hinge"))
+        }
+        "farmland" | "dirt_path" => height(15.0),
+        "soul_sand" | "mud" => height(14.0),
+        "chest" | "trapped_chest" | "ender_chest" => {
+            vec![Aabb::pixels(1.0, 0.0, 1.0, 15.0, 14.0, 15.0)]
+        }
+        "cactus" => vec![Aabb::pixels(1.0, 0.0, 1.0, 15.0, 15.0, 15.0)],
+        "honey_block" => vec![Aabb::pixels(1.0, 0.0, 1.0, 15.0, 15.0, 15.0)],
+        "enchanting_table" => height(12.0),
+        "end_portal_frame" => height(13.0),
+        "daylight_detector" => height(6.0),
+        "stonecutter" => height(9.0),
+        "campfire" | "soul_campfire" => height(7.0),
+        "cake" => vec![Aabb::pixels(1.0, 0.0, 1.0, 15.0, 8.0, 15.0)],
+        "repeater" | "comparator" => height(2.0),
+        "lily_pad" => height(1.5),
+        "sculk_sensor" | "calibrated_sculk_sensor" => height(8.0),
+        _ if name.ends_with("_bed") => height(9.0),
+        _ => vec![Aabb::FULL],
+    }
+}
+
+/// 直楼梯:下半砖加上朝向一侧的上半部分,倒置楼梯上下相反
+fn stairs(facing: &str, half: &str) -> Vec<Aabb> {
+    let (slab, step) = if half == "top" {
+        ((8.0, 16.0), (0.0, 8.0))
+    } else {
+        ((0.0, 8.0), (8.0, 16.0))
+    };
+    let (x0, z0, x1, z1) = match facing {
+        "north" => (0.0, 0.0, 16.0, 8.0),
+        "south" => (0.0, 8.0, 16.0, 16.0),
+        "west" => (0.0, 0.0, 8.0, 16.0),
+        _ => (8.0, 0.0, 16.0, 16.0),
+    };
+    vec![
+        Aabb::pixels(0.0, slab.0, 0.0, 16.0, slab.1, 16.0),
+        Aabb::pixels(x0, step.0, z0, x1, step.1, z1),
+    ]
+}
+
+/// 栅栏、墙与玻璃板:中间的柱子加上连向四周的臂,宽度以柱子中心为基准
+fn cross(
+    post: f64,
+    arm: f64,
+    height: f64,
+    has_post: bool,
+    connected: impl Fn(&str) -> bool,
+) -> Vec<Aabb> {
+    let mut boxes = Vec::new();
+    if has_post {
+        boxes.push(Aabb::pixels(
+            8.0 - post,
+            0.0,
+            8.0 - post,
+            8.0 + post,
+            height,
+            8.0 + post,
+        ));
+    }
+    let (lo, hi) = (8.0 - arm, 8.0 + arm);
+    if connected("north") {
+        boxes.push(Aabb::pixels(lo, 0.0, 0.0, hi, height, hi));
+    }
+    if connected("south") {
+        boxes.push(Aabb::pixels(lo, 0.0, lo, hi, height, 16.0));
+    }
+    if connected("west") {
+        boxes.push(Aabb::pixels(0.0, 0.0, lo, hi, height, hi));
+    }
+    if connected("east") {
+        boxes.push(Aabb::pixels(lo, 0.0, lo, 16.0, height, hi));
+    }
+    boxes
+}
+
+/// 门:关闭时贴在朝向的反方向一侧,打开后按铰链转到侧面
+fn door(facing: &str, open: &str, hinge: &str) -> Vec<Aabb> {
+    let (closed, right) = (open != "true", hinge == "right");
+    let side = match (facing, closed, right) {
+        ("east", true, _) | ("south", false, true) | ("north", false, false) => "west",
+        ("west", true, _) | ("north", false, true) | ("south", false, false) => "east",
+        ("south", true, _) | ("west", false, true) | ("east", false, false) => "north",
+        _ => "south",
+    };
+    vec![panel(side)]
+}
+
+/// 活板门:关闭时是上或下的薄板,打开后贴在朝向的反方向一侧
+fn trapdoor(facing: &str, half: &str, open: &str) -> Vec<Aabb> {
+    if open == "true" {
+        let side = match facing {
+            "north" => "south",
+            "south" => "north",
+            "west" => "east",
+            _ => "west",
+        };
+        vec![panel(side)]
+    } else if half == "top" {
+        vec![Aabb::pixels(0.0, 13.0, 0.0, 16.0, 16.0, 16.0)]
+    } else {
+        vec![Aabb::pixels(0.0, 0.0, 0.0, 16.0, 3.0, 16.0)]
+    }
+}
+
+/// 贴在方块某一侧、厚 3/16 格的竖直薄板
+fn panel(side: &str) -> Aabb {
+    match side {
+        "north" => Aabb::pixels(0.0, 0.0, 0.0, 16.0, 16.0, 3.0),
+        "south" => Aabb::pixels(0.0, 0.0, 13.0, 16.0, 16.0, 16.0),
+        "west" => Aabb::pixels(0.0, 0.0, 0.0, 3.0, 16.0, 16.0),
+        _ => Aabb::pixels(13.0, 0.0, 0.0, 16.0, 16.0, 16.0),
+    }
+}
+
+/// 没有碰撞箱的方块
+const NO_COLLISION: [&str; 57] = [
+    "air",
+    "cave_air",
+    "void_air",
+    "water",
+    "lava",
+    "light",
+    "structure_void",
+    "fire",
+    "soul_fire",
+    "nether_portal",
+    "end_portal",
+    "end_gateway",
+    "cobweb",
+    "bubble_column",
+    "sugar_cane",
+    "bamboo_sapling",
+    "seagrass",
+    "tall_seagrass",
+    "kelp",
+    "kelp_plant",
+    "tripwire",
+    "tripwire_hook",
+    "redstone_wire",
+    "lever",
+    "ladder",
+    "vine",
+    "cave_vines",
+    "cave_vines_plant",
+    "weeping_vines",
+    "weeping_vines_plant",
+    "twisting_vines",
+    "twisting_vines_plant",
+    "glow_lichen",
+    "sculk_vein",
+    "hanging_roots",
+    "spore_blossom",
+    "powder_snow",
+    "sweet_berry_bush",
+    "nether_wart",
+    "frogspawn",
+    "brown_mushroom",
+    "red_mushroom",
+    "crimson_fungus",
+    "warped_fungus",
+    "crimson_roots",
+    "warped_roots",
+    "nether_sprouts",
+    "dead_bush",
+    "cornflower",
+    "sunflower",
+    "lilac",
+    "rose_bush",
+    "peony",
+    "pink_petals",
+    "melon_stem",
+    "pumpkin_stem",
+    "pitcher_plant",
+];
+
+/// 名称中包含这些片段的方块没有碰撞箱(以 `_block` 结尾的完整方块除外)
+const NO_COLLISION_PATTERNS: [&str; 24] = [
+    "torch",
+    "sign",
+    "banner",
+    "rail",
+    "button",
+    "pressure_plate",
+    "sapling",
+    "tulip",
+    "orchid",
+    "allium",
+    "dandelion",
+    "poppy",
+    "bluet",
+    "daisy",
+    "lily_of_the_valley",
+    "wither_rose",
+    "short_grass",
+    "tall_grass",
+    "fern",
+    "coral",
+    "wheat",
+    "carrots",
+    "potatoes",
+    "beetroots",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, properties: &[(&str, &str)]) -> BlockStateInfo {
+        BlockStateInfo {
+            name: format!("minecraft:{}", name),
+            properties: properties
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            default: false,
+        }
+    }
+
+    #[test]
+    fn shapes() {
+        assert_eq!(shape_of(&info("stone", &[])), vec![Aabb::FULL]);
+        assert!(shape_of(&info("water", &[("level", "0")])).is_empty());
+        assert!(shape_of(&info("poppy", &[])).is_empty());
+        let slab = shape_of(&info("oak_slab", &[("type", "bottom")]));
+        assert_eq!(slab[0].max_y, 0.5);
+        let fence = shape_of(&info("oak_fence", &[("north", "true"), ("east", "false")]));
+        assert_eq!(fence.len(), 2);
+        assert!(fence.iter().all(|b| b.max_y == 1.5));
+        // 关闭的门贴在朝向的反方向一侧
+        let door = shape_of(&info(
+            "oak_door",
+            &[("facing", "east"), ("open", "false"), ("hinge", "left")],
+        ));
+        assert_eq!(door, vec![Aabb::pixels(0.0, 0.0, 0.0, 3.0, 16.0, 16.0)]);
+    }
+
+    #[test]
+    fn clip_against_wall() {
+        let wall = Aabb::FULL.offset(1.0, 0.0, 0.0);
+        let entity = Aabb::at_feet(0.5, 0.0, 0.5, 0.6, 1.8);
+        assert!((wall.clip_x(&entity, 1.0) - 0.2).abs() < 1e-9);
+        assert_eq!(wall.clip_x(&entity, -1.0), -1.0);
+        // 不在同一高度时不受影响
+        assert_eq!(wall.offset(0.0, 2.0, 0.0).clip_x(&entity, 1.0), 1.0);
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+
+use crate::pos::ChunkPos;
+
+struct Entry {
+    data: Arc<Vec<u8>>,
+    inserted: Instant,
+}
+
+struct Inner {
+    lru: LruCache<ChunkPos, Entry>,
+    /// 当前缓存的字节数
+    size: usize,
+}
+
+/// 区块数据缓存
+///
+/// 按字节数限制容量,超出时淘汰最久未使用的条目;条目超过生存时间后视为失效。
+pub struct ChunkCache {
+    inner: Mutex<Inner>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl ChunkCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        ChunkCache {
+            inner: Mutex::new(Inner {
+                lru: LruCache::unbounded(),
+                size: 0,
+            }),
+            capacity,
+            ttl,
+        }
+    }
+
+    pub fn get(&self, pos: ChunkPos) -> Option<Arc<Vec<u8>>> {
+        let mut inner = self.inner.lock().unwrap();
+        let expired = match inner.lru.get(&pos) {
+            Some(entry) if entry.inserted.elapsed() <= self.ttl => return Some(entry.data.clone()),
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            inner.remove(pos);
+        }
+        None
+    }
+
+    pub fn insert(&self, pos: ChunkPos, data: Arc<Vec<u8>>) {
+        if data.len() > self.capacity {
+            self.remove(pos);
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.size += data.len();
+        if let Some(old) = inner.lru.put(
+            pos,
+            Entry {
+                data,
+                inserted: Instant::now(),
+            },
+        ) {
+            inner.size -= old.data.len();
+        }
+        while inner.size > self.capacity {
+            match inner.lru.pop_lru() {
+                Some((_, entry)) => inner.size -= entry.data.len(),
+                None => break,
+            }
+        }
+    }
+
+    pub fn remove(&self, pos: ChunkPos) {
+        self.inner.lock().unwrap().remove(pos);
+    }
+
+    /// 清理所有过期条目
+    pub fn purge_expired(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let expired: Vec<ChunkPos> = inner
+            .lru
+            .iter()
+            .filter(|(_, e)| e.inserted.elapsed() > self.ttl)
+            .map(|(pos, _)| *pos)
+            .collect();
+        for pos in expired {
+            inner.remove(pos);
+        }
+    }
+
+    /// 当前缓存的字节数
+    pub fn size(&self) -> usize {
+        self.inner.lock().unwrap().size
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().lru.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Inner {
+    fn remove(&mut self, pos: ChunkPos) {
+        if let Some(entry) = self.lru.pop(&pos) {
+            self.size -= entry.data.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_by_size_and_ttl() {
+        let cache = ChunkCache::new(10, Duration::from_secs(60));
+        cache.insert(ChunkPos::new(0, 0), Arc::new(vec![0; 4]));
+        cache.insert(ChunkPos::new(1, 0), Arc::new(vec![0; 4]));
+        cache.get(ChunkPos::new(0, 0));
+        cache.insert(ChunkPos::new(2, 0), Arc::new(vec![0; 4]));
+        assert!(cache.get(ChunkPos::new(1, 0)).is_none());
+        assert!(cache.get(ChunkPos::new(0, 0)).is_some());
+        assert_eq!(cache.size(), 8);
+
+        let cache = ChunkCache::new(10, Duration::ZERO);
+        cache.insert(ChunkPos::new(0, 0), Arc::new(vec![0; 4]));
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(cache.get(ChunkPos::new(0, 0)).is_none());
+        assert!(cache.is_empty());
+    }
+}
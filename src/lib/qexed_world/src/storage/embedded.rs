@@ -0,0 +1,314 @@
+//! 基于 LMDB 的嵌入式区块存储
+//!
+//! 每个维度一张表,键为区块坐标(x、z 各 4 字节大端),值的格式:
+//!
+//! | 字节 | 含义 |
+//! |------|------|
+//! | 0    | 格式版本 |
+//! | 1    | 压缩方式(同 Anvil) |
+//! | 2..6 | 压缩数据的 CRC32 |
+//! | 6..  | 压缩后的区块 NBT |
+//!
+//! 区块 NBT 与 Anvil 相同,只是方块调色板直接保存状态ID([`BlockFormat::StateIds`]),
+//! 保存与读取都不依赖方块注册表,注册表不完整时也不会丢失方块。
+//! 版本 1 的数据按方块名保存,读取时仍然使用全局注册表转换。
+
+use std::{fs, path::Path, sync::Arc};
+
+use crab_nbt::Nbt;
+use heed::{Database, Env, EnvOpenOptions, types::Bytes};
+
+use crate::{
+    anvil::{BlockFormat, Compression, chunk_from_nbt, chunk_to_nbt},
+    chunk::Chunk,
+    error::WorldError,
+    pos::ChunkPos,
+    registry::block_registry,
+    storage::{ChunkCache, ChunkStorage, StorageOptions},
+};
+
+const FORMAT_VERSION: u8 = 2;
+/// 按方块名保存调色板的旧版本
+const NAMED_FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = 6;
+/// 一个数据库中最多的表数量(维度数)
+const MAX_DBS: u32 = 64;
+
+impl From<heed::Error> for WorldError {
+    fn from(e: heed::Error) -> Self {
+        WorldError::Database(e.to_string())
+    }
+}
+
+fn chunk_key(pos: ChunkPos) -> [u8; 8] {
+    let mut key = [0u8; 8];
+    key[..4].copy_from_slice(&pos.x.to_be_bytes());
+    key[4..].copy_from_slice(&pos.z.to_be_bytes());
+    key
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+/// 世界数据库,一个 `db_path` 对应一个
+#[derive(Clone)]
+pub struct WorldDatabase {
+    env: Env,
+    options: StorageOptions,
+}
+
+impl WorldDatabase {
+    pub fn open(options: StorageOptions) -> Result<Self, WorldError> {
+        fs::create_dir_all(&options.path)?;
+        // 内存映射大小必须是页大小的整数倍
+        let map_size = options.map_size.next_multiple_of(1 << 16);
+        // SAFETY: 同一个目录只会通过 heed 打开,heed 内部保证同一进程不会重复打开
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(map_size)
+                .max_dbs(MAX_DBS)
+                .open(&options.path)?
+        };
+        log::info!(
+            "已打开世界数据库 {} (映射上限 {} MB)",
+            options.path.display(),
+            map_size >> 20
+        );
+        Ok(WorldDatabase { env, options })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.options.path
+    }
+
+    /// 打开(不存在时创建)某个维度的区块表
+    pub fn chunk_storage(&self, name: &str) -> Result<EmbeddedStorage, WorldError> {
+        let mut wtxn = self.env.write_txn()?;
+        let db = self
+            .env
+            .create_database::<Bytes, Bytes>(&mut wtxn, Some(name))?;
+        wtxn.commit()?;
+        Ok(EmbeddedStorage {
+            env: self.env.clone(),
+            db,
+            name: name.to_string(),
+            verify: self.options.verify_chunk_data,
+            compression: Compression::Lz4,
+            cache: Arc::new(ChunkCache::new(
+                self.options.cache_capacity,
+                self.options.cache_ttl,
+            )),
+        })
+    }
+
+    /// 强制将数据写入磁盘
+    pub fn sync(&self) -> Result<(), WorldError> {
+        self.env.force_sync()?;
+        Ok(())
+    }
+}
+
+/// 单个维度的区块表
+pub struct EmbeddedStorage {
+    env: Env,
+    db: Database<Bytes, Bytes>,
+    name: String,
+    verify: bool,
+    compression: Compression,
+    cache: Arc<ChunkCache>,
+}
+
+impl EmbeddedStorage {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cache(&self) -> &ChunkCache {
+        &self.cache
+    }
+
+    /// 已保存的区块数量
+    pub fn len(&self) -> Result<u64, WorldError> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self.db.len(&rtxn)?)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, WorldError> {
+        Ok(self.len()? == 0)
+    }
+
+    fn encode(&self, chunk: &Chunk) -> Result<Vec<u8>, WorldError> {
+        let raw = Nbt::new(String::new(), chunk_to_nbt(chunk, BlockFormat::StateIds)?).write();
+        let payload = self.compression.compress(&raw)?;
+        let mut value = Vec::with_capacity(HEADER_LEN + payload.len());
+        value.push(FORMAT_VERSION);
+        value.push(self.compression as u8);
+        value.extend_from_slice(&checksum(&payload).to_be_bytes());
+        value.extend_from_slice(&payload);
+        Ok(value)
+    }
+
+    fn decode(&self, pos: ChunkPos, value: &[u8]) -> Result<Chunk, WorldError> {
+        let format = match value.first() {
+            _ if value.len() < HEADER_LEN => None,
+            Some(&FORMAT_VERSION) => Some(BlockFormat::StateIds),
+            Some(&NAMED_FORMAT_VERSION) => Some(BlockFormat::Named(block_registry())),
+            _ => None,
+        };
+        let Some(format) = format else {
+            return Err(WorldError::CorruptChunk(format!("区块 {} 数据头无效", pos)));
+        };
+        let payload = &value[HEADER_LEN..];
+        if self.verify {
+            let expected = u32::from_be_bytes(value[2..6].try_into().unwrap());
+            if checksum(payload) != expected {
+                return Err(WorldError::ChecksumMismatch(pos));
+            }
+        }
+        let raw = Compression::try_from(value[1])?.decompress(payload)?;
+        if raw.is_empty() {
+            return Err(WorldError::CorruptChunk(format!("区块 {} 数据为空", pos)));
+        }
+        let nbt = Nbt::read(&mut raw.as_slice())?;
+        chunk_from_nbt(&nbt.root_tag, format)
+    }
+}
+
+impl ChunkStorage for EmbeddedStorage {
+    fn load_chunk(&self, pos: ChunkPos) -> Result<Option<Chunk>, WorldError> {
+        let value = match self.cache.get(pos) {
+            Some(value) => value,
+            None => {
+                let rtxn = self.env.read_txn()?;
+                let Some(value) = self.db.get(&rtxn, &chunk_key(pos))? else {
+                    return Ok(None);
+                };
+                let value = Arc::new(value.to_vec());
+                self.cache.insert(pos, value.clone());
+                value
+            }
+        };
+        match self.decode(pos, &value) {
+            Ok(chunk) => Ok(Some(chunk)),
+            Err(e) => {
+                self.cache.remove(pos);
+                Err(e)
+            }
+        }
+    }
+
+    fn save_chunk(&self, chunk: &Chunk) -> Result<(), WorldError> {
+        self.save_chunks(&[chunk])
+    }
+
+    fn save_chunks(&self, chunks: &[&Chunk]) -> Result<(), WorldError> {
+        let encoded = chunks
+            .iter()
+            .map(|c| Ok((c.pos, self.encode(c)?)))
+            .collect::<Result<Vec<_>, WorldError>>()?;
+        let mut wtxn = self.env.write_txn()?;
+        for (pos, value) in &encoded {
+            self.db.put(&mut wtxn, &chunk_key(*pos), value)?;
+        }
+        wtxn.commit()?;
+        for (pos, value) in encoded {
+            self.cache.insert(pos, Arc::new(value));
+        }
+        Ok(())
+    }
+
+    fn delete_chunk(&self, pos: ChunkPos) -> Result<(), WorldError> {
+        let mut wtxn = self.env.write_txn()?;
+        self.db.delete(&mut wtxn, &chunk_key(pos))?;
+        wtxn.commit()?;
+        self.cache.remove(pos);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), WorldError> {
+        self.cache.purge_expired();
+        self.env.force_sync()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block;
+    use std::time::Duration;
+
+    #[test]
+    fn save_load_and_verify() {
+        let path = std::env::temp_dir().join(format!("qexed_db_{}", std::process::id()));
+        let db = WorldDatabase::open(StorageOptions {
+            path: path.clone(),
+            verify_chunk_data: true,
+            map_size: 16 << 20,
+            cache_ttl: Duration::from_secs(60),
+            cache_capacity: 1 << 20,
+        })
+        .unwrap();
+        let storage = db.chunk_storage("overworld").unwrap();
+        let pos = ChunkPos::new(7, -3);
+        let mut chunk = Chunk::new(pos, crate::biome::PLAINS);
+        chunk.set_block(1, 64, 1, block::DIRT);
+        storage.save_chunk(&chunk).unwrap();
+        storage.cache().remove(pos);
+
+        let loaded = storage.load_chunk(pos).unwrap().unwrap();
+        assert_eq!(loaded.get_block(1, 64, 1), block::DIRT);
+        assert!(storage.load_chunk(ChunkPos::new(0, 0)).unwrap().is_none());
+
+        // 破坏缓存中的数据,校验应当失败
+        let mut value = storage.encode(&chunk).unwrap();
+        let last = value.len() - 1;
+        value[last] ^= 0xFF;
+        storage.cache().insert(pos, Arc::new(value));
+        assert!(matches!(
+            storage.load_chunk(pos),
+            Err(WorldError::ChecksumMismatch(_))
+        ));
+        let _ = fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn states_outside_registry_round_trip() {
+        let path = std::env::temp_dir().join(format!("qexed_db_raw_{}", std::process::id()));
+        let db = WorldDatabase::open(StorageOptions {
+            path: path.clone(),
+            verify_chunk_data: true,
+            map_size: 16 << 20,
+            cache_ttl: Duration::from_secs(60),
+            cache_capacity: 1 << 20,
+        })
+        .unwrap();
+        let storage = db.chunk_storage("overworld").unwrap();
+        let pos = ChunkPos::new(-2, 4);
+        // 内置表中没有的状态ID(原版的方块状态)
+        let unknown: block::BlockState = 27_000;
+        assert!(block_registry().state_info(unknown).is_none());
+        let mut chunk = Chunk::new(pos, crate::biome::PLAINS);
+        chunk.set_block(0, -64, 0, unknown);
+        chunk.set_block(15, 200, 15, unknown + 1);
+        chunk.set_block(8, 64, 8, block::STONE);
+        storage.save_chunk(&chunk).unwrap();
+        storage.cache().remove(pos);
+
+        let loaded = storage.load_chunk(pos).unwrap().unwrap();
+        assert_eq!(loaded.get_block(0, -64, 0), unknown);
+        assert_eq!(loaded.get_block(15, 200, 15), unknown + 1);
+        assert_eq!(loaded.get_block(8, 64, 8), block::STONE);
+        assert!(
+            loaded
+                .sections
+                .iter()
+                .zip(&chunk.sections)
+                .all(|(a, b)| a.blocks.iter().eq(b.blocks.iter()))
+        );
+        let _ = fs::remove_dir_all(path);
+    }
+}
@@ -0,0 +1,63 @@
+//! 区块存储
+//!
+//! 区块默认保存在 `db_path` 下的嵌入式键值数据库(LMDB)中,
+//! Anvil 区域文件则用于与原版存档互相迁移。
+
+pub mod cache;
+pub mod embedded;
+
+use std::{path::PathBuf, time::Duration};
+
+pub use cache::ChunkCache;
+pub use embedded::{EmbeddedStorage, WorldDatabase};
+
+use crate::{chunk::Chunk, error::WorldError, pos::ChunkPos};
+
+/// 区块存储后端
+pub trait ChunkStorage: Send + Sync {
+    /// 读取区块,存储中没有时返回 `None`
+    fn load_chunk(&self, pos: ChunkPos) -> Result<Option<Chunk>, WorldError>;
+    /// 保存区块
+    fn save_chunk(&self, chunk: &Chunk) -> Result<(), WorldError>;
+    /// 批量保存区块,默认逐个保存
+    fn save_chunks(&self, chunks: &[&Chunk]) -> Result<(), WorldError> {
+        for chunk in chunks {
+            self.save_chunk(chunk)?;
+        }
+        Ok(())
+    }
+    /// 删除区块
+    fn delete_chunk(&self, pos: ChunkPos) -> Result<(), WorldError>;
+    /// 将缓冲的数据写入磁盘
+    fn flush(&self) -> Result<(), WorldError> {
+        Ok(())
+    }
+}
+
+/// 嵌入式存储的参数,对应配置文件中的 `[game.world]`
+#[derive(Debug, Clone)]
+pub struct StorageOptions {
+    /// 数据库目录
+    pub path: PathBuf,
+    /// 加载时校验区块数据
+    pub verify_chunk_data: bool,
+    /// 内存映射上限(字节)
+    pub map_size: usize,
+    /// 缓存条目的生存时间
+    pub cache_ttl: Duration,
+    /// 缓存容量(字节)
+    pub cache_capacity: usize,
+}
+
+impl StorageOptions {
+    /// 由配置生成,`map_size` 的单位为 GB,`cache_capacity` 的单位为 KB
+    pub fn from_config(config: &qexed_config::WorldConfig) -> Self {
+        StorageOptions {
+            path: PathBuf::from(&config.db_path),
+            verify_chunk_data: config.verify_chunk_data,
+            map_size: (config.map_size.max(1) as usize).saturating_mul(1 << 30),
+            cache_ttl: Duration::from_secs(config.cache_ttl),
+            cache_capacity: (config.cache_capacity as usize).saturating_mul(1024),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+//! 测试用的方块场景
+//!
+//! 其他 crate 的测试通过 `test-util` 特性使用。
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::{
+    BlockPos,
+    block::{self, BlockState},
+};
+
+/// 地面的高度,地面之上第一格的 y 为 64
+pub const FLOOR_Y: i32 = 63;
+
+/// 一层石头地面,覆盖 `xs` × `zs`
+pub fn stone_floor(xs: Range<i32>, zs: Range<i32>) -> HashMap<BlockPos, BlockState> {
+    let mut blocks = HashMap::new();
+    for x in xs {
+        for z in zs.clone() {
+            blocks.insert(BlockPos::new(x, FLOOR_Y, z), block::STONE);
+        }
+    }
+    blocks
+}
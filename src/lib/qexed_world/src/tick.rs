@@ -0,0 +1,175 @@
+//! 计划刻
+//!
+//! 每个世界一个按游戏刻排序的优先队列,保存方块在若干刻之后需要执行的更新
+//! (例如沙子下落、红石元件延迟)。同一游戏刻内按优先级、再按加入顺序执行。
+//!
+//! 见 `doc/block_update.md` 的玩家观察态:到期时所在区块附近没有玩家的计划刻不会立即执行,
+//! 而是暂存在区块下,等到有玩家靠近时再一次性补上。
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::{
+    block::BlockState,
+    pos::{BlockPos, ChunkPos},
+};
+
+/// 一个计划刻
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledTick {
+    pub pos: BlockPos,
+    /// 安排计划刻时的方块,执行时方块已经变化则跳过
+    pub block: BlockState,
+    /// 到期的游戏刻
+    pub due: u64,
+    /// 数值越小越先执行
+    pub priority: i32,
+    seq: u64,
+}
+
+impl ScheduledTick {
+    fn key(&self) -> (u64, i32, u64) {
+        (self.due, self.priority, self.seq)
+    }
+}
+
+impl Ord for ScheduledTick {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap 是大顶堆,反过来比较使最早到期的在堆顶
+        other.key().cmp(&self.key())
+    }
+}
+
+impl PartialOrd for ScheduledTick {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 世界的计划刻队列
+#[derive(Debug, Default)]
+pub struct TickQueue {
+    /// 当前游戏刻
+    time: u64,
+    seq: u64,
+    queue: BinaryHeap<ScheduledTick>,
+    /// 已安排的 (位置, 方块),同一方块不会重复安排
+    scheduled: HashSet<(BlockPos, BlockState)>,
+    /// 到期时无人观察的计划刻,按区块暂存
+    deferred: HashMap<ChunkPos, Vec<ScheduledTick>>,
+}
+
+impl TickQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前游戏刻
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// 在 `delay` 刻之后执行 `pos` 处方块的计划刻,已经安排过时返回 `false`
+    pub fn schedule(
+        &mut self,
+        pos: BlockPos,
+        block: BlockState,
+        delay: u64,
+        priority: i32,
+    ) -> bool {
+        if !self.scheduled.insert((pos, block)) {
+            return false;
+        }
+        self.seq += 1;
+        self.queue.push(ScheduledTick {
+            pos,
+            block,
+            due: self.time + delay.max(1),
+            priority,
+            seq: self.seq,
+        });
+        true
+    }
+
+    /// 是否已经安排了计划刻(包括暂存的)
+    pub fn is_scheduled(&self, pos: BlockPos, block: BlockState) -> bool {
+        self.scheduled.contains(&(pos, block))
+    }
+
+    /// 等待执行的计划刻数量,包括暂存的
+    pub fn len(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scheduled.is_empty()
+    }
+
+    /// 暂存了计划刻的区块
+    pub fn deferred_chunks(&self) -> impl Iterator<Item = &ChunkPos> {
+        self.deferred.keys()
+    }
+
+    /// 推进一个游戏刻,按顺序返回需要执行的计划刻
+    ///
+    /// `observed` 判断区块附近是否有玩家:无人观察的区块中到期的计划刻会被暂存,
+    /// 之前暂存、现在有人观察的区块中的计划刻会在这一刻补上。
+    pub fn advance(&mut self, observed: impl Fn(ChunkPos) -> bool) -> Vec<ScheduledTick> {
+        self.time += 1;
+        let mut due = Vec::new();
+        let woken: Vec<ChunkPos> = self
+            .deferred
+            .keys()
+            .filter(|pos| observed(**pos))
+            .copied()
+            .collect();
+        for pos in woken {
+            due.extend(self.deferred.remove(&pos).unwrap_or_default());
+        }
+        while self.queue.peek().is_some_and(|t| t.due <= self.time) {
+            let tick = self.queue.pop().unwrap();
+            let chunk = tick.pos.chunk_pos();
+            if observed(chunk) {
+                due.push(tick);
+            } else {
+                self.deferred.entry(chunk).or_default().push(tick);
+            }
+        }
+        due.sort_by_key(ScheduledTick::key);
+        for tick in &due {
+            self.scheduled.remove(&(tick.pos, tick.block));
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_and_deferral() {
+        let mut queue = TickQueue::new();
+        let near = BlockPos::new(0, 64, 0);
+        let far = BlockPos::new(1000, 64, 0);
+        assert!(queue.schedule(near, 1, 2, 0));
+        assert!(!queue.schedule(near, 1, 5, 0));
+        assert!(queue.schedule(near.offset(1, 0, 0), 1, 2, -1));
+        assert!(queue.schedule(far, 1, 1, 0));
+        let observed = |pos: ChunkPos| pos == near.chunk_pos();
+
+        assert!(queue.advance(observed).is_empty());
+        assert_eq!(queue.deferred_chunks().count(), 1);
+        let due: Vec<BlockPos> = queue.advance(observed).iter().map(|t| t.pos).collect();
+        assert_eq!(due, vec![near.offset(1, 0, 0), near]);
+        assert_eq!(queue.len(), 1);
+
+        // 远处区块有人靠近后补上暂存的计划刻
+        let due = queue.advance(|_| true);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].pos, far);
+        assert!(queue.is_empty());
+    }
+}
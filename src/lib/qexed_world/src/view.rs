@@ -0,0 +1,362 @@
+//! 玩家的区块视野
+//!
+//! 记录已经发送给客户端的区块,玩家移动或视距变化时计算需要新发送与需要遗忘的区块。
+//! 新区块不会立即发送,而是交给 [`ChunkSender`] 按客户端的处理速度分批发送,
+//! 真正写入连接后才通过 [`ChunkView::mark_sent`] 记为已发送。
+
+use std::collections::HashSet;
+
+use crate::pos::ChunkPos;
+
+/// 以 `center` 为中心、由内向外螺旋遍历边长为 `2 * radius + 1` 的正方形
+pub fn spiral(center: ChunkPos, radius: i32) -> impl Iterator<Item = ChunkPos> {
+    std::iter::once(center).chain((1..=radius.max(0)).flat_map(move |r| {
+        // 每一圈从左上角开始顺时针走一周
+        let top = (-r..r).map(move |i| (i, -r));
+        let right = (-r..r).map(move |i| (r, i));
+        let bottom = (-r..r).map(move |i| (-i, r));
+        let left = (-r..r).map(move |i| (-r, -i));
+        top.chain(right)
+            .chain(bottom)
+            .chain(left)
+            .map(move |(dx, dz)| ChunkPos::new(center.x + dx, center.z + dz))
+    }))
+}
+
+/// 一次视野更新的结果
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ViewUpdate {
+    /// 视野中心发生变化时的新中心(需要发送 `SetChunkCacheCenter`)
+    pub new_center: Option<ChunkPos>,
+    /// 视距发生变化时的新视距
+    pub new_radius: Option<i32>,
+    /// 需要发送的区块,按螺旋顺序由近及远
+    pub load: Vec<ChunkPos>,
+    /// 需要让客户端遗忘的区块(只包含已经发送过的区块)
+    pub unload: Vec<ChunkPos>,
+    /// 离开视野时还没有发送的区块,需要从发送队列中取消
+    pub cancel: Vec<ChunkPos>,
+}
+
+impl ViewUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.new_center.is_none()
+            && self.new_radius.is_none()
+            && self.load.is_empty()
+            && self.unload.is_empty()
+            && self.cancel.is_empty()
+    }
+}
+
+/// 玩家的区块视野
+#[derive(Debug, Default, Clone)]
+pub struct ChunkView {
+    center: Option<ChunkPos>,
+    radius: i32,
+    /// 已发送给客户端的区块
+    sent: HashSet<ChunkPos>,
+    /// 已加入发送队列但还没有发送的区块
+    queued: HashSet<ChunkPos>,
+}
+
+impl ChunkView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn center(&self) -> Option<ChunkPos> {
+        self.center
+    }
+
+    pub fn radius(&self) -> i32 {
+        self.radius
+    }
+
+    /// 区块是否已发送给客户端
+    pub fn is_sent(&self, pos: ChunkPos) -> bool {
+        self.sent.contains(&pos)
+    }
+
+    pub fn sent_chunks(&self) -> impl Iterator<Item = &ChunkPos> {
+        self.sent.iter()
+    }
+
+    /// 区块是否在当前视野内
+    pub fn in_range(&self, pos: ChunkPos) -> bool {
+        self.center
+            .is_some_and(|c| c.chebyshev_distance(&pos) <= self.radius)
+    }
+
+    /// 移动视野中心或修改视距
+    pub fn update(&mut self, center: ChunkPos, radius: i32) -> ViewUpdate {
+        let radius = radius.max(0);
+        let mut update = ViewUpdate::default();
+        if self.center != Some(center) {
+            update.new_center = Some(center);
+        }
+        if self.radius != radius || self.center.is_none() {
+            update.new_radius = Some(radius);
+        }
+        self.center = Some(center);
+        self.radius = radius;
+
+        update.unload = self
+            .sent
+            .iter()
+            .filter(|p| p.chebyshev_distance(&center) > radius)
+            .copied()
+            .collect();
+        for pos in &update.unload {
+            self.sent.remove(pos);
+        }
+        update.cancel = self
+            .queued
+            .iter()
+            .filter(|p| p.chebyshev_distance(&center) > radius)
+            .copied()
+            .collect();
+        for pos in &update.cancel {
+            self.queued.remove(pos);
+        }
+        update.load = spiral(center, radius)
+            .filter(|p| !self.sent.contains(p) && !self.queued.contains(p))
+            .collect();
+        self.queued.extend(update.load.iter().copied());
+        update
+    }
+
+    /// 区块已经写入连接,返回它是否仍在等待发送
+    pub fn mark_sent(&mut self, pos: ChunkPos) -> bool {
+        if self.queued.remove(&pos) {
+            self.sent.insert(pos);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 重新发送某个区块(例如区块被重新生成)
+    pub fn invalidate(&mut self, pos: ChunkPos) {
+        self.sent.remove(&pos);
+    }
+
+    /// 清空视野(切换维度或断开连接时),返回之前已发送的区块
+    pub fn clear(&mut self) -> Vec<ChunkPos> {
+        self.center = None;
+        self.queued.clear();
+        self.sent.drain().collect()
+    }
+}
+
+/// 客户端每 tick 期望接收区块数的下限
+pub const MIN_CHUNKS_PER_TICK: f32 = 0.01;
+/// 客户端每 tick 期望接收区块数的上限
+pub const MAX_CHUNKS_PER_TICK: f32 = 64.0;
+/// 收到客户端第一次回复前使用的发送速度
+const START_CHUNKS_PER_TICK: f32 = 9.0;
+/// 允许同时未确认的批次数
+const MAX_UNACKNOWLEDGED_BATCHES: u32 = 10;
+
+/// 区块发送队列
+///
+/// 与原版一致:每 tick 按客户端期望的速度累积配额,配额满一个区块时发送一批,
+/// 客户端处理完一批后回复 `ChunkBatchReceived` 并告知新的期望速度。
+/// 在收到第一次回复之前只允许一个未确认的批次,避免登录时一次性塞满慢速客户端。
+#[derive(Debug, Clone)]
+pub struct ChunkSender {
+    /// 等待发送的区块
+    pending: Vec<ChunkPos>,
+    /// 客户端期望的每 tick 区块数
+    desired_chunks_per_tick: f32,
+    /// 本 tick 可发送的区块配额
+    batch_quota: f32,
+    /// 已发送但尚未确认的批次数
+    unacknowledged_batches: u32,
+    max_unacknowledged_batches: u32,
+}
+
+impl Default for ChunkSender {
+    fn default() -> Self {
+        ChunkSender {
+            pending: Vec::new(),
+            desired_chunks_per_tick: START_CHUNKS_PER_TICK,
+            batch_quota: 0.0,
+            unacknowledged_batches: 0,
+            max_unacknowledged_batches: 1,
+        }
+    }
+}
+
+impl ChunkSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 加入等待发送的区块
+    pub fn enqueue(&mut self, chunks: impl IntoIterator<Item = ChunkPos>) {
+        self.pending.extend(chunks);
+    }
+
+    /// 取消等待中的区块,返回它是否还没有发送
+    pub fn cancel(&mut self, pos: ChunkPos) -> bool {
+        match self.pending.iter().position(|p| *p == pos) {
+            Some(i) => {
+                self.pending.swap_remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn desired_chunks_per_tick(&self) -> f32 {
+        self.desired_chunks_per_tick
+    }
+
+    /// 清空等待队列(切换维度或断开连接时)
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// 每 tick 调用一次,返回这一 tick 要发送的批次(离 `center` 最近的区块优先)
+    ///
+    /// 取出的区块写入连接后需要调用 [`ChunkSender::on_batch_sent`],没写出去的区块重新放回队列。
+    pub fn next_batch(&mut self, center: ChunkPos) -> Vec<ChunkPos> {
+        if self.pending.is_empty() || self.unacknowledged_batches >= self.max_unacknowledged_batches {
+            return Vec::new();
+        }
+        let max_quota = self.desired_chunks_per_tick.max(1.0);
+        self.batch_quota = (self.batch_quota + self.desired_chunks_per_tick).min(max_quota);
+        if self.batch_quota < 1.0 {
+            return Vec::new();
+        }
+        let count = (self.batch_quota.floor() as usize).min(self.pending.len());
+        // 由远及近排序,从末尾取出最近的区块
+        self.pending.sort_unstable_by_key(|p| {
+            let (dx, dz) = ((p.x - center.x) as i64, (p.z - center.z) as i64);
+            std::cmp::Reverse(dx * dx + dz * dz)
+        });
+        self.pending.drain(self.pending.len() - count..).rev().collect()
+    }
+
+    /// 一个批次已经写入连接,`count` 为实际写出的区块数
+    pub fn on_batch_sent(&mut self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.batch_quota -= count as f32;
+        self.unacknowledged_batches += 1;
+    }
+
+    /// 客户端确认了一个批次
+    pub fn on_batch_received(&mut self, chunks_per_tick: f32) {
+        self.unacknowledged_batches = self.unacknowledged_batches.saturating_sub(1);
+        self.desired_chunks_per_tick = if chunks_per_tick.is_nan() {
+            MIN_CHUNKS_PER_TICK
+        } else {
+            chunks_per_tick.clamp(MIN_CHUNKS_PER_TICK, MAX_CHUNKS_PER_TICK)
+        };
+        if self.unacknowledged_batches == 0 {
+            self.batch_quota = 1.0;
+        }
+        self.max_unacknowledged_batches = MAX_UNACKNOWLEDGED_BATCHES;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spiral_covers_square_in_order() {
+        let c = ChunkPos::new(3, -2);
+        let all: Vec<_> = spiral(c, 2).collect();
+        assert_eq!(all.len(), 25);
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), 25);
+        assert!(all.windows(2).all(|w| w[0].chebyshev_distance(&c) <= w[1].chebyshev_distance(&c)));
+    }
+
+    #[test]
+    fn moving_loads_and_unloads_edges() {
+        let mut view = ChunkView::new();
+        let first = view.update(ChunkPos::new(0, 0), 2);
+        assert_eq!(first.load.len(), 25);
+        assert_eq!(first.new_center, Some(ChunkPos::new(0, 0)));
+        for pos in &first.load {
+            assert!(view.mark_sent(*pos));
+        }
+
+        let moved = view.update(ChunkPos::new(1, 0), 2);
+        assert_eq!(moved.load.len(), 5);
+        assert_eq!(moved.unload.len(), 5);
+        assert!(moved.unload.iter().all(|p| p.x == -2));
+        assert!(moved.load.iter().all(|p| p.x == 3));
+        for pos in &moved.load {
+            view.mark_sent(*pos);
+        }
+
+        assert!(view.update(ChunkPos::new(1, 0), 2).is_empty());
+        let shrink = view.update(ChunkPos::new(1, 0), 1);
+        assert_eq!(shrink.unload.len(), 16);
+        assert!(shrink.load.is_empty());
+    }
+
+    #[test]
+    fn chunks_count_as_sent_only_after_written() {
+        let mut view = ChunkView::new();
+        let first = view.update(ChunkPos::new(0, 0), 1);
+        assert_eq!(first.load.len(), 9);
+        assert!(!view.is_sent(ChunkPos::new(0, 0)));
+        assert!(view.mark_sent(ChunkPos::new(0, 0)));
+        assert!(view.is_sent(ChunkPos::new(0, 0)));
+        // 排队中的区块不会重复排队
+        assert!(view.update(ChunkPos::new(0, 0), 1).is_empty());
+
+        // 离开视野时已发送的需要遗忘,没发送的只需取消
+        let moved = view.update(ChunkPos::new(5, 0), 1);
+        assert_eq!(moved.unload, vec![ChunkPos::new(0, 0)]);
+        assert_eq!(moved.cancel.len(), 8);
+        assert!(!view.mark_sent(ChunkPos::new(1, 1)));
+        assert!(!view.is_sent(ChunkPos::new(1, 1)));
+    }
+
+    #[test]
+    fn sender_paces_by_client_rate() {
+        let center = ChunkPos::new(0, 0);
+        let mut sender = ChunkSender::new();
+        sender.enqueue(spiral(center, 3));
+        let first = sender.next_batch(center);
+        assert_eq!(first.len(), 9);
+        assert!(first.iter().all(|p| p.chebyshev_distance(&center) <= 1));
+        sender.on_batch_sent(first.len());
+        // 第一批未确认前不再发送
+        assert!(sender.next_batch(center).is_empty());
+
+        sender.on_batch_received(0.5);
+        let second = sender.next_batch(center);
+        assert_eq!(second.len(), 1);
+        sender.on_batch_sent(second.len());
+        sender.on_batch_received(0.5);
+        let sizes: Vec<usize> = (0..4)
+            .map(|_| {
+                let batch = sender.next_batch(center);
+                sender.on_batch_sent(batch.len());
+                batch.len()
+            })
+            .collect();
+        assert_eq!(sizes, vec![1, 0, 1, 0]);
+        assert!(sender.cancel(ChunkPos::new(3, 3)));
+        assert!(!sender.cancel(ChunkPos::new(0, 0)));
+
+        // 没有写出去的批次不计入未确认批次
+        let mut sender = ChunkSender::new();
+        sender.enqueue(spiral(center, 1));
+        let batch = sender.next_batch(center);
+        sender.enqueue(batch);
+        sender.on_batch_sent(0);
+        assert_eq!(sender.next_batch(center).len(), 9);
+    }
+}
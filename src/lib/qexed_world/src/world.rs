@@ -0,0 +1,439 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, OnceLock, RwLock},
+};
+
+use crate::{
+    biome::{self, Biome},
+    block::{self, BlockState},
+    chunk::Chunk,
+    dimension::Dimension,
+    error::WorldError,
+    light::{self, LightKind},
+    pos::{BlockPos, ChunkPos},
+    redstone::RedstoneEngine,
+    storage::ChunkStorage,
+    tick::TickQueue,
+};
+
+/// 共享的区块句柄
+pub type ChunkRef = Arc<RwLock<Chunk>>;
+
+/// 生成新区块的函数
+pub type ChunkFactory = Arc<dyn Fn(ChunkPos) -> Chunk + Send + Sync>;
+
+/// 从存储中读出区块后、放入世界前对区块的处理
+pub type ChunkLoadHook = Arc<dyn Fn(&mut Chunk) + Send + Sync>;
+
+/// 内存中的世界
+///
+/// 区块表与单个区块各自加锁,读写不同区块的方块时互不阻塞。
+pub struct World {
+    pub name: String,
+    /// 新建空区块时使用的生物群系
+    pub default_biome: Biome,
+    /// 所在维度,决定发送给客户端的高度范围与是否有天空光
+    pub dimension: Dimension,
+    /// 是否是平坦世界(客户端据此把地平线画在世界底部)
+    pub is_flat: bool,
+    /// 方块计划刻
+    pub block_ticks: Mutex<TickQueue>,
+    /// 红石电路
+    pub redstone: Mutex<RedstoneEngine>,
+    chunks: RwLock<HashMap<ChunkPos, ChunkRef>>,
+    storage: Option<Arc<dyn ChunkStorage>>,
+    /// 未设置时新区块全是空气
+    generator: Option<ChunkFactory>,
+    load_hook: OnceLock<ChunkLoadHook>,
+}
+
+impl fmt::Debug for World {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("World")
+            .field("name", &self.name)
+            .field("dimension", &self.dimension)
+            .field("is_flat", &self.is_flat)
+            .field("loaded_chunks", &self.loaded_count())
+            .field("has_storage", &self.storage.is_some())
+            .field("has_generator", &self.generator.is_some())
+            .finish()
+    }
+}
+
+impl World {
+    pub fn new(name: impl Into<String>) -> Self {
+        World {
+            name: name.into(),
+            default_biome: biome::PLAINS,
+            dimension: Dimension::Overworld,
+            is_flat: false,
+            block_ticks: Mutex::new(TickQueue::new()),
+            redstone: Mutex::new(RedstoneEngine::new()),
+            chunks: RwLock::new(HashMap::new()),
+            storage: None,
+            generator: None,
+            load_hook: OnceLock::new(),
+        }
+    }
+
+    /// 创建带有存储后端的世界
+    pub fn with_storage(name: impl Into<String>, storage: Arc<dyn ChunkStorage>) -> Self {
+        World {
+            storage: Some(storage),
+            ..Self::new(name)
+        }
+    }
+
+    /// 设置维度
+    pub fn with_dimension(mut self, dimension: Dimension) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    /// 设置是否是平坦世界
+    pub fn with_flat(mut self, is_flat: bool) -> Self {
+        self.is_flat = is_flat;
+        self
+    }
+
+    /// 设置新区块的生成函数
+    pub fn with_generator(
+        mut self,
+        generator: impl Fn(ChunkPos) -> Chunk + Send + Sync + 'static,
+    ) -> Self {
+        self.generator = Some(Arc::new(generator));
+        self
+    }
+
+    /// 设置区块加载钩子(例如补算离线期间的作物生长),只能设置一次,已设置时返回 `false`
+    pub fn set_load_hook(&self, hook: impl Fn(&mut Chunk) + Send + Sync + 'static) -> bool {
+        self.load_hook.set(Arc::new(hook)).is_ok()
+    }
+
+    pub fn storage(&self) -> Option<&Arc<dyn ChunkStorage>> {
+        self.storage.as_ref()
+    }
+
+    /// 生成新区块
+    fn generate_chunk(&self, pos: ChunkPos) -> Chunk {
+        let mut chunk = match &self.generator {
+            Some(generator) => generator(pos),
+            None => Chunk::new(pos, self.default_biome),
+        };
+        // 新区块会完整发送给客户端,生成过程中的方块变化不需要再广播
+        chunk.take_changes();
+        chunk
+    }
+
+    /// 获取已加载的区块
+    pub fn get_chunk(&self, pos: ChunkPos) -> Option<ChunkRef> {
+        self.chunks.read().unwrap().get(&pos).cloned()
+    }
+
+    pub fn is_loaded(&self, pos: ChunkPos) -> bool {
+        self.chunks.read().unwrap().contains_key(&pos)
+    }
+
+    /// 放入区块,返回被替换的旧区块
+    pub fn insert_chunk(&self, chunk: Chunk) -> Option<ChunkRef> {
+        let pos = chunk.pos;
+        self.chunks
+            .write()
+            .unwrap()
+            .insert(pos, Arc::new(RwLock::new(chunk)))
+    }
+
+    /// 获取区块,未加载时生成一个新区块
+    pub fn get_or_create_chunk(&self, pos: ChunkPos) -> ChunkRef {
+        if let Some(chunk) = self.get_chunk(pos) {
+            return chunk;
+        }
+        // 生成可能较慢,不在持有区块表锁时进行
+        let chunk = self.generate_chunk(pos);
+        let chunk = self
+            .chunks
+            .write()
+            .unwrap()
+            .entry(pos)
+            .or_insert_with(|| Arc::new(RwLock::new(chunk)))
+            .clone();
+        self.ensure_lit(pos);
+        chunk
+    }
+
+    /// 获取区块,未加载时先从存储中读取,存储中也没有时生成新区块
+    pub fn load_chunk(&self, pos: ChunkPos) -> Result<ChunkRef, WorldError> {
+        if let Some(chunk) = self.get_chunk(pos) {
+            return Ok(chunk);
+        }
+        let loaded = match &self.storage {
+            Some(storage) => storage.load_chunk(pos)?,
+            None => None,
+        };
+        let chunk = match loaded {
+            Some(mut chunk) => {
+                if let Some(hook) = self.load_hook.get() {
+                    hook(&mut chunk);
+                    chunk.take_changes();
+                }
+                chunk
+            }
+            None => self.generate_chunk(pos),
+        };
+        let chunk = self
+            .chunks
+            .write()
+            .unwrap()
+            .entry(pos)
+            .or_insert_with(|| Arc::new(RwLock::new(chunk)))
+            .clone();
+        self.ensure_lit(pos);
+        Ok(chunk)
+    }
+
+    /// 计算尚未计算过光照的区块
+    pub fn ensure_lit(&self, pos: ChunkPos) {
+        let lit = self
+            .get_chunk(pos)
+            .is_none_or(|c| c.read().unwrap().light.is_lit());
+        if !lit {
+            light::engine::light_chunk(self, pos);
+        }
+    }
+
+    /// 保存所有被修改过的区块,返回保存的区块数
+    pub fn save_all(&self) -> Result<usize, WorldError> {
+        let Some(storage) = &self.storage else {
+            return Ok(0);
+        };
+        let mut dirty = self.dirty_chunks();
+        if dirty.is_empty() {
+            return Ok(0);
+        }
+        // 与光照计算一样按坐标顺序加锁
+        dirty.sort_by_cached_key(|c| c.read().unwrap().pos);
+        let mut guards: Vec<_> = dirty.iter().map(|c| c.write().unwrap()).collect();
+        let chunks: Vec<&Chunk> = guards.iter().map(|g| &**g).collect();
+        storage.save_chunks(&chunks)?;
+        for guard in guards.iter_mut() {
+            guard.mark_saved();
+        }
+        storage.flush()?;
+        Ok(guards.len())
+    }
+
+    /// 卸载区块,被修改过的区块会先保存
+    ///
+    /// 保存失败时区块会放回世界中,修改不会丢失。
+    pub fn unload_and_save(&self, pos: ChunkPos) -> Result<(), WorldError> {
+        let Some(chunk) = self.unload_chunk(pos) else {
+            return Ok(());
+        };
+        if let Some(storage) = &self.storage {
+            let mut guard = chunk.write().unwrap();
+            if guard.is_dirty() {
+                if let Err(e) = storage.save_chunk(&guard) {
+                    drop(guard);
+                    self.chunks.write().unwrap().entry(pos).or_insert(chunk);
+                    return Err(e);
+                }
+                guard.mark_saved();
+            }
+        }
+        Ok(())
+    }
+
+    /// 卸载并保存 `keep` 返回 `false` 的所有区块,返回卸载的区块数
+    pub fn unload_unused(&self, keep: impl Fn(ChunkPos) -> bool) -> Result<usize, WorldError> {
+        let unused: Vec<ChunkPos> = self
+            .loaded_chunks()
+            .into_iter()
+            .filter(|pos| !keep(*pos))
+            .collect();
+        for pos in &unused {
+            self.unload_and_save(*pos)?;
+        }
+        if let (false, Some(storage)) = (unused.is_empty(), &self.storage) {
+            storage.flush()?;
+        }
+        Ok(unused.len())
+    }
+
+    /// 卸载区块,返回被卸载的区块(调用者负责保存)
+    pub fn unload_chunk(&self, pos: ChunkPos) -> Option<ChunkRef> {
+        self.chunks.write().unwrap().remove(&pos)
+    }
+
+    /// 已加载的区块坐标
+    pub fn loaded_chunks(&self) -> Vec<ChunkPos> {
+        self.chunks.read().unwrap().keys().copied().collect()
+    }
+
+    pub fn loaded_count(&self) -> usize {
+        self.chunks.read().unwrap().len()
+    }
+
+    /// 获取方块,区块未加载时返回 `None`,超出高度范围视为空气
+    pub fn get_block(&self, pos: BlockPos) -> Option<BlockState> {
+        let chunk = self.get_chunk(pos.chunk_pos())?;
+        if !pos.in_height_range() {
+            return Some(block::AIR);
+        }
+        let chunk = chunk.read().unwrap();
+        Some(chunk.get_block(pos.local_x(), pos.y, pos.local_z()))
+    }
+
+    /// 设置方块,返回旧方块
+    pub fn set_block(&self, pos: BlockPos, state: BlockState) -> Result<BlockState, WorldError> {
+        let chunk = self
+            .get_chunk(pos.chunk_pos())
+            .ok_or(WorldError::ChunkNotLoaded(pos.chunk_pos()))?;
+        let old = chunk
+            .write()
+            .unwrap()
+            .set_block(pos.local_x(), pos.y, pos.local_z(), state)
+            .ok_or(WorldError::OutOfBounds(pos))?;
+        if old != state {
+            light::engine::relight_block(self, pos);
+        }
+        Ok(old)
+    }
+
+    /// 获取光照等级,区块未加载或超出光照范围时返回 `None`
+    pub fn light(&self, kind: LightKind, pos: BlockPos) -> Option<u8> {
+        let chunk = self.get_chunk(pos.chunk_pos())?;
+        let chunk = chunk.read().unwrap();
+        chunk.light.get(kind, pos.local_x(), pos.y, pos.local_z())
+    }
+
+    /// 需要保存的区块
+    pub fn dirty_chunks(&self) -> Vec<ChunkRef> {
+        self.chunks
+            .read()
+            .unwrap()
+            .values()
+            .filter(|c| c.read().unwrap().is_dirty())
+            .cloned()
+            .collect()
+    }
+
+    /// 取出所有区块自上次调用后变化过的方块
+    pub fn take_block_changes(&self) -> HashMap<ChunkPos, Vec<BlockPos>> {
+        let chunks = self.chunks.read().unwrap();
+        let mut changes = HashMap::new();
+        for (pos, chunk) in chunks.iter() {
+            let list = chunk.write().unwrap().take_changes();
+            if !list.is_empty() {
+                changes.insert(*pos, list);
+            }
+        }
+        changes
+    }
+
+    /// 取出所有区块自上次调用后光照变化过的段落掩码
+    pub fn take_light_changes(&self) -> HashMap<ChunkPos, u32> {
+        let chunks = self.chunks.read().unwrap();
+        let mut changes = HashMap::new();
+        for (pos, chunk) in chunks.iter() {
+            let mask = chunk.write().unwrap().light.take_changes();
+            if mask != 0 {
+                changes.insert(*pos, mask);
+            }
+        }
+        changes
+    }
+}
+
+/// 读取方块,区块未加载时返回 `None`
+pub trait BlockSource {
+    fn block(&self, pos: BlockPos) -> Option<BlockState>;
+}
+
+impl BlockSource for World {
+    fn block(&self, pos: BlockPos) -> Option<BlockState> {
+        self.get_block(pos)
+    }
+}
+
+impl<T: BlockSource + ?Sized> BlockSource for &T {
+    fn block(&self, pos: BlockPos) -> Option<BlockState> {
+        (**self).block(pos)
+    }
+}
+
+/// 不在表中的位置视为空气
+impl BlockSource for HashMap<BlockPos, BlockState> {
+    fn block(&self, pos: BlockPos) -> Option<BlockState> {
+        Some(self.get(&pos).copied().unwrap_or(block::AIR))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_get_block() {
+        let world = World::new("world");
+        let pos = BlockPos::new(-1, -64, 17);
+        assert!(world.set_block(pos, block::STONE).is_err());
+        world.get_or_create_chunk(pos.chunk_pos());
+        assert_eq!(world.set_block(pos, block::STONE).unwrap(), block::AIR);
+        assert_eq!(world.get_block(pos), Some(block::STONE));
+        assert!(matches!(
+            world.set_block(pos.offset(0, -1, 0), block::STONE),
+            Err(WorldError::OutOfBounds(_))
+        ));
+        assert_eq!(world.dirty_chunks().len(), 1);
+        assert_eq!(world.take_block_changes()[&pos.chunk_pos()], vec![pos]);
+        let chunk = world.get_chunk(pos.chunk_pos()).unwrap();
+        assert_eq!(chunk.read().unwrap().sections[0].block_count(), 1);
+    }
+
+    #[test]
+    fn new_chunks_use_generator() {
+        let world = World::new("world").with_generator(|pos| {
+            let mut chunk = Chunk::new(pos, biome::PLAINS);
+            chunk.sections[0].fill(block::STONE);
+            chunk.recompute_heightmaps();
+            chunk
+        });
+        let pos = ChunkPos::new(2, -3);
+        world.load_chunk(pos).unwrap();
+        assert_eq!(world.get_block(BlockPos::new(32, -60, -48)), Some(block::STONE));
+        assert_eq!(world.get_block(BlockPos::new(32, -48, -48)), Some(block::AIR));
+    }
+
+    #[test]
+    fn unload_unused_saves_chunks() {
+        use crate::storage::{StorageOptions, WorldDatabase};
+        use std::time::Duration;
+
+        let path = std::env::temp_dir().join(format!("qexed_db_unload_{}", std::process::id()));
+        let db = WorldDatabase::open(StorageOptions {
+            path: path.clone(),
+            verify_chunk_data: true,
+            map_size: 16 << 20,
+            cache_ttl: Duration::from_secs(60),
+            cache_capacity: 1 << 20,
+        })
+        .unwrap();
+        let world = World::with_storage("world", Arc::new(db.chunk_storage("overworld").unwrap()));
+        let near = ChunkPos::new(0, 0);
+        let far = ChunkPos::new(20, 0);
+        world.load_chunk(near).unwrap();
+        world.load_chunk(far).unwrap();
+        let pos = BlockPos::new(320, 64, 3);
+        world.set_block(pos, block::STONE).unwrap();
+
+        assert_eq!(world.unload_unused(|p| p == near).unwrap(), 1);
+        assert_eq!(world.loaded_chunks(), vec![near]);
+        assert_eq!(world.get_block(pos), None);
+        // 重新加载时读取卸载前保存的方块
+        world.load_chunk(far).unwrap();
+        assert_eq!(world.get_block(pos), Some(block::STONE));
+        assert!(world.dirty_chunks().is_empty());
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
@@ -0,0 +1,173 @@
+//! 气候参数与生物群系选择
+//!
+//! 与原版的多噪声生物群系类似:温度与湿度决定陆地生物群系,大陆性与高度区分海洋、
+//! 海滩与山峰。这里只实现二维版本,同一列上的生物群系相同。
+
+use qexed_world::biome::{self, Biome};
+
+use crate::{SEA_LEVEL, noise::NoiseField};
+
+/// 某一列的气候参数,除 `ridges` 在 [0, 1] 外均在 [-1, 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Climate {
+    pub temperature: f32,
+    pub humidity: f32,
+    /// 大陆性,越大离海越远
+    pub continentalness: f32,
+    /// 侵蚀度,越大地形越平坦
+    pub erosion: f32,
+    /// 山脊强度,1 为山脊线
+    pub ridges: f32,
+}
+
+/// 气候噪声采样器
+#[derive(Debug, Clone)]
+pub struct ClimateSampler {
+    temperature: NoiseField,
+    humidity: NoiseField,
+    continentalness: NoiseField,
+    erosion: NoiseField,
+    ridges: NoiseField,
+}
+
+impl ClimateSampler {
+    pub fn new(seed: i64) -> Self {
+        ClimateSampler {
+            temperature: NoiseField::new(seed, 1, 1.0 / 2048.0, 4),
+            humidity: NoiseField::new(seed, 2, 1.0 / 2048.0, 4),
+            continentalness: NoiseField::new(seed, 3, 1.0 / 1536.0, 6),
+            erosion: NoiseField::new(seed, 4, 1.0 / 1024.0, 4),
+            ridges: NoiseField::new(seed, 5, 1.0 / 512.0, 3),
+        }
+    }
+
+    pub fn sample(&self, x: i32, z: i32) -> Climate {
+        let (x, z) = (x as f64, z as f64);
+        Climate {
+            temperature: spread(self.temperature.fbm(x, z), 1.0),
+            humidity: spread(self.humidity.fbm(x, z), 1.0),
+            continentalness: spread(self.continentalness.fbm(x, z), 1.4),
+            erosion: spread(self.erosion.fbm(x, z), 2.0),
+            ridges: self.ridges.ridge(x, z),
+        }
+    }
+}
+
+/// 分形噪声的取值集中在 0 附近,拉伸后各档位的占比更均匀
+fn spread(v: f32, factor: f32) -> f32 {
+    (v * factor).clamp(-1.0, 1.0)
+}
+
+/// 温度档位(0 最冷,4 最热)
+fn temperature_level(t: f32) -> usize {
+    level(t, [-0.45, -0.15, 0.2, 0.55])
+}
+
+/// 湿度档位(0 最干,4 最湿)
+fn humidity_level(h: f32) -> usize {
+    level(h, [-0.35, -0.1, 0.1, 0.3])
+}
+
+fn level(v: f32, thresholds: [f32; 4]) -> usize {
+    thresholds.iter().take_while(|t| v >= **t).count()
+}
+
+/// 陆地生物群系,按 [温度][湿度] 排列
+const MIDDLE_BIOMES: [[Biome; 5]; 5] = [
+    [
+        biome::SNOWY_PLAINS,
+        biome::SNOWY_PLAINS,
+        biome::SNOWY_PLAINS,
+        biome::SNOWY_TAIGA,
+        biome::TAIGA,
+    ],
+    [
+        biome::PLAINS,
+        biome::PLAINS,
+        biome::FOREST,
+        biome::TAIGA,
+        biome::OLD_GROWTH_SPRUCE_TAIGA,
+    ],
+    [
+        biome::FLOWER_FOREST,
+        biome::PLAINS,
+        biome::FOREST,
+        biome::BIRCH_FOREST,
+        biome::DARK_FOREST,
+    ],
+    [
+        biome::SAVANNA,
+        biome::SAVANNA,
+        biome::FOREST,
+        biome::JUNGLE,
+        biome::JUNGLE,
+    ],
+    [biome::DESERT; 5],
+];
+
+/// 海洋生物群系,按温度排列,分别为 (浅海, 深海)
+const OCEANS: [(Biome, Biome); 5] = [
+    (biome::FROZEN_OCEAN, biome::DEEP_FROZEN_OCEAN),
+    (biome::COLD_OCEAN, biome::DEEP_COLD_OCEAN),
+    (biome::OCEAN, biome::DEEP_OCEAN),
+    (biome::LUKEWARM_OCEAN, biome::DEEP_LUKEWARM_OCEAN),
+    (biome::WARM_OCEAN, biome::DEEP_LUKEWARM_OCEAN),
+];
+
+/// 深海的水深
+pub const DEEP_OCEAN_DEPTH: i32 = 18;
+/// 高于海平面多少格算作山峰
+pub const PEAK_HEIGHT: i32 = 90;
+
+/// 根据气候与地表高度选择生物群系
+pub fn pick_biome(climate: &Climate, height: i32) -> Biome {
+    let t = temperature_level(climate.temperature);
+    if height < SEA_LEVEL {
+        let (shallow, deep) = OCEANS[t];
+        return if height < SEA_LEVEL - DEEP_OCEAN_DEPTH {
+            deep
+        } else {
+            shallow
+        };
+    }
+    if height <= SEA_LEVEL + 2 && climate.continentalness < 0.0 {
+        return match t {
+            0 => biome::SNOWY_BEACH,
+            _ if climate.erosion < -0.4 => biome::STONY_SHORE,
+            _ => biome::BEACH,
+        };
+    }
+    if height > SEA_LEVEL + PEAK_HEIGHT {
+        return match t {
+            0 | 1 => biome::FROZEN_PEAKS,
+            2 => biome::JAGGED_PEAKS,
+            _ => biome::STONY_PEAKS,
+        };
+    }
+    MIDDLE_BIOMES[t][humidity_level(climate.humidity)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn climate(temperature: f32, humidity: f32) -> Climate {
+        Climate {
+            temperature,
+            humidity,
+            continentalness: 0.5,
+            erosion: 0.0,
+            ridges: 0.0,
+        }
+    }
+
+    #[test]
+    fn biome_by_climate() {
+        assert_eq!(pick_biome(&climate(0.9, 0.0), 80), biome::DESERT);
+        assert_eq!(pick_biome(&climate(-0.9, -0.9), 80), biome::SNOWY_PLAINS);
+        assert_eq!(pick_biome(&climate(0.0, -0.2), 80), biome::PLAINS);
+        assert_eq!(pick_biome(&climate(0.0, 0.0), 30), biome::DEEP_OCEAN);
+        assert_eq!(pick_biome(&climate(0.9, 0.0), 55), biome::WARM_OCEAN);
+        assert_eq!(pick_biome(&climate(-0.9, 0.0), 200), biome::FROZEN_PEAKS);
+    }
+}
@@ -0,0 +1,120 @@
+//! 末地地形生成
+//!
+//! 原点附近是一座主岛,距原点 1000 格以外散布着由噪声决定的小岛,其余都是虚空。
+//! 玩家进入末地时落在 [`END_SPAWN`] 处的黑曜石平台上。
+
+use qexed_world::{
+    BlockPos, Chunk, ChunkPos,
+    biome,
+    block::{self, BlockState},
+    block_registry,
+};
+
+use crate::{generator::ChunkGenerator, noise::NoiseField};
+
+/// 进入末地时的落脚点(黑曜石平台之上)
+pub const END_SPAWN: BlockPos = BlockPos::new(100, 50, 0);
+/// 主岛半径
+const MAIN_ISLAND_RADIUS: f32 = 80.0;
+/// 外围小岛出现的最小距离
+const OUTER_ISLANDS_DISTANCE: f32 = 1000.0;
+/// 岛屿表面的基准高度
+const ISLAND_Y: f32 = 56.0;
+
+/// 末地生成器
+#[derive(Debug, Clone)]
+pub struct EndGenerator {
+    shape: NoiseField,
+    islands: NoiseField,
+    end_stone: BlockState,
+    obsidian: BlockState,
+}
+
+impl EndGenerator {
+    pub fn new(seed: i64) -> Self {
+        EndGenerator {
+            shape: NoiseField::new(seed, 201, 1.0 / 48.0, 3),
+            islands: NoiseField::new(seed, 202, 1.0 / 96.0, 3),
+            // 只有未初始化注册表的测试中才会用石头代替
+            end_stone: block_registry().default_state("end_stone").unwrap_or(block::STONE),
+            obsidian: block_registry().default_state("obsidian").unwrap_or(block::STONE),
+        }
+    }
+
+    /// 岛屿在该列的 (底部, 顶部) 高度,没有岛屿时返回 `None`
+    fn column(&self, x: i32, z: i32) -> Option<(i32, i32)> {
+        let (fx, fz) = (x as f64, z as f64);
+        let distance = ((x as f32).powi(2) + (z as f32).powi(2)).sqrt();
+        let shape = self.shape.fbm(fx, fz);
+        // 主岛:越靠近中心越厚
+        let radius = MAIN_ISLAND_RADIUS * (1.0 + 0.25 * shape);
+        if distance < radius {
+            let t = 1.0 - distance / radius;
+            let top = ISLAND_Y + shape * 3.0 + t * 8.0;
+            let bottom = top - 4.0 - t.sqrt() * 48.0;
+            return Some((bottom as i32, top as i32));
+        }
+        if distance < OUTER_ISLANDS_DISTANCE {
+            return None;
+        }
+        let density = self.islands.fbm(fx, fz) - 0.35;
+        if density <= 0.0 {
+            return None;
+        }
+        let top = ISLAND_Y + shape * 4.0 + density * 10.0;
+        let bottom = top - density * 60.0;
+        Some((bottom as i32, top as i32))
+    }
+}
+
+impl ChunkGenerator for EndGenerator {
+    fn generate_chunk(&self, pos: ChunkPos) -> Chunk {
+        let (min_x, min_z) = (pos.min_block_x(), pos.min_block_z());
+        let center = ((min_x + 8) as f32).hypot((min_z + 8) as f32);
+        let biome = if center < OUTER_ISLANDS_DISTANCE {
+            biome::THE_END
+        } else {
+            biome::SMALL_END_ISLANDS
+        };
+        let mut chunk = Chunk::new(pos, biome);
+        for z in 0..16 {
+            for x in 0..16 {
+                let (wx, wz) = (min_x + x as i32, min_z + z as i32);
+                if let Some((bottom, top)) = self.column(wx, wz) {
+                    for y in bottom.max(1)..=top {
+                        chunk.set_block(x, y, z, self.end_stone);
+                    }
+                }
+                // 落脚点下方 5x5 的黑曜石平台,上方留出空间
+                if (wx - END_SPAWN.x).abs() <= 2 && (wz - END_SPAWN.z).abs() <= 2 {
+                    chunk.set_block(x, END_SPAWN.y - 1, z, self.obsidian);
+                    for y in END_SPAWN.y..END_SPAWN.y + 3 {
+                        chunk.set_block(x, y, z, block::AIR);
+                    }
+                }
+            }
+        }
+        chunk.recompute_heightmaps();
+        chunk.take_changes();
+        chunk.mark_dirty();
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn main_island_and_platform() {
+        let generator = EndGenerator::new(3);
+        let chunk = generator.generate_chunk(ChunkPos::new(0, 0));
+        assert_eq!(chunk.get_block(0, 50, 0), generator.end_stone);
+        let spawn = generator.generate_chunk(END_SPAWN.chunk_pos());
+        let (x, z) = (END_SPAWN.local_x(), END_SPAWN.local_z());
+        assert_eq!(spawn.get_block(x, END_SPAWN.y - 1, z), generator.obsidian);
+        assert!(block::is_air(spawn.get_block(x, END_SPAWN.y, z)));
+        let void = generator.generate_chunk(ChunkPos::new(20, 20));
+        assert!(void.sections.iter().all(|s| s.is_empty()));
+    }
+}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GeneratorError {
+    #[error("未知的世界生成器: {0}")]
+    UnknownGenerator(String),
+    #[error("超平坦预设中的层 {0:?} 格式错误")]
+    InvalidLayer(String),
+    #[error("未知的方块: {0}")]
+    UnknownBlock(String),
+    #[error("未知的生物群系: {0}")]
+    UnknownBiome(String),
+    #[error("超平坦预设共 {0} 层,超出世界高度")]
+    TooManyLayers(u32),
+}
@@ -0,0 +1,219 @@
+//! 超平坦与虚空世界
+//!
+//! 预设字符串与原版超平坦的"自定义预设"格式相同,例如
+//! `minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains`:
+//! 分号前是从世界底部往上的方块层,每层可写作 `数量*方块`;分号后是生物群系,省略时为平原。
+
+use std::str::FromStr;
+
+use qexed_world::{
+    Chunk, ChunkPos, WORLD_HEIGHT,
+    biome::{self, Biome},
+    block::{self, BlockState},
+    block_registry,
+};
+
+use crate::{error::GeneratorError, generator::ChunkGenerator};
+
+/// 超平坦预设
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatPreset {
+    /// 从下往上的方块层: (方块, 层数)
+    pub layers: Vec<(BlockState, u32)>,
+    pub biome: Biome,
+}
+
+impl FlatPreset {
+    /// 总层数
+    pub fn height(&self) -> u32 {
+        self.layers.iter().map(|(_, n)| n).sum()
+    }
+}
+
+impl Default for FlatPreset {
+    /// 原版默认的超平坦预设
+    fn default() -> Self {
+        FlatPreset {
+            layers: vec![
+                (block::BEDROCK, 1),
+                (block::DIRT, 2),
+                (block::GRASS_BLOCK, 1),
+            ],
+            biome: biome::PLAINS,
+        }
+    }
+}
+
+impl FromStr for FlatPreset {
+    type Err = GeneratorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (layers, biome) = match s.split_once(';') {
+            Some((layers, biome)) => (layers, Some(biome.trim())),
+            None => (s, None),
+        };
+        let biome = match biome {
+            Some(name) if !name.is_empty() => biome::biome_id(name)
+                .ok_or_else(|| GeneratorError::UnknownBiome(name.to_string()))?,
+            _ => biome::PLAINS,
+        };
+        let mut parsed = Vec::new();
+        for layer in layers.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+            let (count, name) = match layer.split_once('*') {
+                Some((count, name)) => {
+                    let count: u32 = count
+                        .trim()
+                        .parse()
+                        .map_err(|_| GeneratorError::InvalidLayer(layer.to_string()))?;
+                    (count, name.trim())
+                }
+                None => (1, layer),
+            };
+            let state = block_registry()
+                .default_state(name)
+                .ok_or_else(|| GeneratorError::UnknownBlock(name.to_string()))?;
+            if count > 0 {
+                parsed.push((state, count));
+            }
+        }
+        let preset = FlatPreset {
+            layers: parsed,
+            biome,
+        };
+        if preset.height() > WORLD_HEIGHT as u32 {
+            return Err(GeneratorError::TooManyLayers(preset.height()));
+        }
+        Ok(preset)
+    }
+}
+
+/// 超平坦世界生成器
+#[derive(Debug, Clone)]
+pub struct FlatGenerator {
+    preset: FlatPreset,
+}
+
+impl FlatGenerator {
+    pub fn new(preset: FlatPreset) -> Self {
+        FlatGenerator { preset }
+    }
+
+    pub fn preset(&self) -> &FlatPreset {
+        &self.preset
+    }
+}
+
+impl ChunkGenerator for FlatGenerator {
+    fn generate_chunk(&self, pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new(pos, self.preset.biome);
+        // 从世界底部开始每一层的方块
+        let column: Vec<BlockState> = self
+            .preset
+            .layers
+            .iter()
+            .flat_map(|(state, count)| std::iter::repeat_n(*state, *count as usize))
+            .collect();
+        for (section, layers) in chunk.sections.iter_mut().zip(column.chunks(16)) {
+            if layers.len() == 16 && layers.iter().all(|s| *s == layers[0]) {
+                section.fill(layers[0]);
+                continue;
+            }
+            for (y, state) in layers.iter().enumerate() {
+                if block::is_air(*state) {
+                    continue;
+                }
+                for z in 0..16 {
+                    for x in 0..16 {
+                        section.set_block(x, y, z, *state);
+                    }
+                }
+            }
+        }
+        chunk.recompute_heightmaps();
+        chunk.mark_dirty();
+        chunk
+    }
+
+    fn is_flat(&self) -> bool {
+        true
+    }
+}
+
+/// 虚空世界生成器:不生成任何方块
+#[derive(Debug, Clone)]
+pub struct VoidGenerator {
+    biome: Biome,
+}
+
+impl VoidGenerator {
+    pub fn new(biome: Biome) -> Self {
+        VoidGenerator { biome }
+    }
+}
+
+impl Default for VoidGenerator {
+    fn default() -> Self {
+        Self::new(biome::THE_VOID)
+    }
+}
+
+impl ChunkGenerator for VoidGenerator {
+    fn generate_chunk(&self, pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new(pos, self.biome);
+        chunk.mark_dirty();
+        chunk
+    }
+
+    fn is_flat(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qexed_world::MIN_Y;
+
+    #[test]
+    fn parse_preset() {
+        let preset: FlatPreset =
+            "minecraft:bedrock,2*minecraft:dirt,minecraft:grass_block;minecraft:plains"
+                .parse()
+                .unwrap();
+        assert_eq!(preset, FlatPreset::default());
+        let preset: FlatPreset = "bedrock, 20*stone".parse().unwrap();
+        assert_eq!(preset.layers, vec![(block::BEDROCK, 1), (block::STONE, 20)]);
+        assert_eq!(preset.biome, biome::PLAINS);
+        assert!(matches!(
+            "x*stone".parse::<FlatPreset>(),
+            Err(GeneratorError::InvalidLayer(_))
+        ));
+        assert!(matches!(
+            "stone;minecraft:nowhere".parse::<FlatPreset>(),
+            Err(GeneratorError::UnknownBiome(_))
+        ));
+        assert!(matches!(
+            "400*stone".parse::<FlatPreset>(),
+            Err(GeneratorError::TooManyLayers(400))
+        ));
+    }
+
+    #[test]
+    fn flat_layers() {
+        let generator = FlatGenerator::new("bedrock,30*stone,dirt;desert".parse().unwrap());
+        let chunk = generator.generate_chunk(ChunkPos::new(5, -2));
+        assert_eq!(chunk.get_block(3, MIN_Y, 7), block::BEDROCK);
+        assert_eq!(chunk.get_block(3, MIN_Y + 30, 7), block::STONE);
+        assert_eq!(chunk.get_block(3, MIN_Y + 31, 7), block::DIRT);
+        assert_eq!(chunk.get_block(3, MIN_Y + 32, 7), block::AIR);
+        assert_eq!(chunk.get_biome(0, 0, 0), Some(biome::DESERT));
+        assert_eq!(chunk.sections[1].block_count(), 4096);
+        assert!(
+            VoidGenerator::default()
+                .generate_chunk(ChunkPos::new(0, 0))
+                .sections
+                .iter()
+                .all(|s| s.is_empty())
+        );
+    }
+}
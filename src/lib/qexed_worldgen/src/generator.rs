@@ -0,0 +1,79 @@
+//! 区块生成器接口
+
+use std::sync::Arc;
+
+use qexed_world::{Chunk, ChunkFactory, ChunkPos, Dimension, WorldError, WorldSettings};
+use rayon::prelude::*;
+
+use crate::{
+    EndGenerator, NetherGenerator, OverworldGenerator,
+    error::GeneratorError,
+    flat::{FlatGenerator, FlatPreset, VoidGenerator},
+};
+
+/// 区块生成器
+///
+/// 生成结果只能取决于区块坐标与生成器自身的设置,同一个区块可能被多个线程同时生成。
+pub trait ChunkGenerator: Send + Sync {
+    /// 生成一个区块
+    fn generate_chunk(&self, pos: ChunkPos) -> Chunk;
+
+    /// 是否是平坦世界(客户端据此把地平线画在世界底部)
+    fn is_flat(&self) -> bool {
+        false
+    }
+
+    /// 并行生成多个区块,结果与 `positions` 的顺序一致
+    fn generate_chunks(&self, positions: &[ChunkPos]) -> Vec<Chunk> {
+        positions
+            .par_iter()
+            .map(|pos| self.generate_chunk(*pos))
+            .collect()
+    }
+}
+
+impl ChunkGenerator for OverworldGenerator {
+    fn generate_chunk(&self, pos: ChunkPos) -> Chunk {
+        OverworldGenerator::generate_chunk(self, pos)
+    }
+}
+
+/// 按配置创建世界生成器
+pub fn from_config(
+    config: &qexed_config::WorldConfig,
+) -> Result<Arc<dyn ChunkGenerator>, GeneratorError> {
+    for_settings(&WorldSettings::from_config(config), Dimension::Overworld)
+}
+
+/// 按命名世界的设置为某个维度创建世界生成器
+///
+/// 主世界使用设置中的生成器,下界与末地使用各自的地形。
+pub fn for_settings(
+    settings: &WorldSettings,
+    dimension: Dimension,
+) -> Result<Arc<dyn ChunkGenerator>, GeneratorError> {
+    Ok(match dimension {
+        Dimension::Overworld => match settings.generator.as_str() {
+            "noise" | "normal" | "default" => Arc::new(OverworldGenerator::new(settings.seed)),
+            "flat" => Arc::new(FlatGenerator::new(settings.flat_preset.parse()?)),
+            "void" => {
+                let preset: FlatPreset = settings.flat_preset.parse()?;
+                Arc::new(VoidGenerator::new(preset.biome))
+            }
+            other => return Err(GeneratorError::UnknownGenerator(other.to_string())),
+        },
+        Dimension::Nether => Arc::new(NetherGenerator::new(settings.seed)),
+        Dimension::End => Arc::new(EndGenerator::new(settings.seed)),
+    })
+}
+
+/// [`qexed_world::WorldManager`] 使用的生成器工厂
+pub fn chunk_factory(
+    settings: &WorldSettings,
+    dimension: Dimension,
+) -> Result<(ChunkFactory, bool), WorldError> {
+    let generator =
+        for_settings(settings, dimension).map_err(|e| WorldError::Generator(e.to_string()))?;
+    let is_flat = generator.is_flat();
+    Ok((Arc::new(move |pos| generator.generate_chunk(pos)), is_flat))
+}
@@ -1,14 +1,22 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! 世界生成
+//!
+//! 由种子确定的地形生成器,生成结果为 `qexed_world` 的区块。
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub mod climate;
+pub mod end;
+pub mod error;
+pub mod flat;
+pub mod generator;
+pub mod nether;
+pub mod noise;
+pub mod overworld;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use end::EndGenerator;
+pub use error::GeneratorError;
+pub use flat::{FlatGenerator, FlatPreset, VoidGenerator};
+pub use generator::{ChunkGenerator, chunk_factory, for_settings, from_config};
+pub use nether::NetherGenerator;
+pub use overworld::OverworldGenerator;
+
+/// 海平面高度(与 LoginPlay 中的 `sea_level` 一致)
+pub const SEA_LEVEL: i32 = 63;
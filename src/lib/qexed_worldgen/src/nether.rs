@@ -0,0 +1,113 @@
+//! 下界地形生成
+//!
+//! 地形只占 y=0..127:上下各有一层基岩,中间是由两组噪声决定高度的地面与洞顶,
+//! 地面低于 y=31 的部分被岩浆海淹没。
+
+use qexed_world::{
+    Chunk, ChunkPos, Dimension,
+    biome,
+    block::{self, BlockState},
+    block_registry,
+};
+
+use crate::{
+    generator::ChunkGenerator,
+    noise::{NoiseField, derive_seed, hash},
+};
+
+/// 岩浆海的高度
+pub const LAVA_SEA_LEVEL: i32 = 31;
+/// 基岩顶棚所在的高度
+pub const ROOF_Y: i32 = 127;
+
+/// 下界生成器
+#[derive(Debug, Clone)]
+pub struct NetherGenerator {
+    floor: NoiseField,
+    ceiling: NoiseField,
+    bedrock_seed: i32,
+    netherrack: BlockState,
+}
+
+impl NetherGenerator {
+    pub fn new(seed: i64) -> Self {
+        NetherGenerator {
+            floor: NoiseField::new(seed, 101, 1.0 / 96.0, 4),
+            ceiling: NoiseField::new(seed, 102, 1.0 / 64.0, 4),
+            bedrock_seed: derive_seed(seed, 103),
+            // 只有未初始化注册表的测试中才会用石头代替
+            netherrack: block_registry().default_state("netherrack").unwrap_or(block::STONE),
+        }
+    }
+
+    /// (地面最高的方块, 洞顶最低的方块)
+    fn column(&self, x: i32, z: i32) -> (i32, i32) {
+        let (fx, fz) = (x as f64, z as f64);
+        let floor = 36.0 + self.floor.fbm(fx, fz) * 20.0;
+        let ceiling = 100.0 + self.ceiling.fbm(fx, fz) * 18.0;
+        let floor = floor as i32;
+        (floor, (ceiling as i32).max(floor + 4))
+    }
+
+    fn block_at(&self, (floor, ceiling): (i32, i32), x: i32, y: i32, z: i32) -> BlockState {
+        // 上下的基岩层越往里越稀疏
+        let depth = y.min(ROOF_Y - y);
+        if depth == 0 || (depth < 5 && hash(self.bedrock_seed, x, y, z) % 5 >= depth as u32) {
+            return block::BEDROCK;
+        }
+        if y <= floor || y >= ceiling {
+            self.netherrack
+        } else if y <= LAVA_SEA_LEVEL {
+            block::LAVA
+        } else {
+            block::AIR
+        }
+    }
+}
+
+impl ChunkGenerator for NetherGenerator {
+    fn generate_chunk(&self, pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new(pos, biome::NETHER_WASTES);
+        let (min_x, min_z) = (pos.min_block_x(), pos.min_block_z());
+        let first = Dimension::Nether.sections().start;
+        for z in 0..16 {
+            for x in 0..16 {
+                let (wx, wz) = (min_x + x as i32, min_z + z as i32);
+                let column = self.column(wx, wz);
+                for y in 0..=ROOF_Y {
+                    let state = self.block_at(column, wx, y, wz);
+                    if !block::is_air(state) {
+                        chunk.sections[first + (y >> 4) as usize].set_block(
+                            x,
+                            (y & 15) as usize,
+                            z,
+                            state,
+                        );
+                    }
+                }
+            }
+        }
+        chunk.recompute_heightmaps();
+        chunk.mark_dirty();
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bedrock_floor_and_roof() {
+        let generator = NetherGenerator::new(7);
+        let chunk = generator.generate_chunk(ChunkPos::new(1, -1));
+        for z in 0..16 {
+            for x in 0..16 {
+                assert_eq!(chunk.get_block(x, 0, z), block::BEDROCK);
+                assert_eq!(chunk.get_block(x, ROOF_Y, z), block::BEDROCK);
+                assert_eq!(chunk.get_block(x, ROOF_Y + 1, z), block::AIR);
+                assert_eq!(chunk.get_block(x, -1, z), block::AIR);
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! 噪声采样
+//!
+//! 逐点调用 simdnoise 的标量单纯形噪声。SIMD 版本在不同指令集下结果可能有细微差别,
+//! 标量实现则保证同一个种子在任何机器上都生成完全相同的地形。
+
+use simdnoise::scalar;
+
+/// 由世界种子与用途编号派生出独立的 32 位噪声种子(splitmix64)
+pub fn derive_seed(seed: i64, salt: u64) -> i32 {
+    let mut z = (seed as u64).wrapping_add(salt.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)) as i32
+}
+
+/// 方块坐标的整数哈希
+pub fn hash(seed: i32, x: i32, y: i32, z: i32) -> u32 {
+    let mut h = (seed as u32)
+        ^ (x as u32).wrapping_mul(0x27d4_eb2d)
+        ^ (y as u32).wrapping_mul(0x1656_67b1)
+        ^ (z as u32).wrapping_mul(0x9e37_79b9);
+    h = (h ^ (h >> 15)).wrapping_mul(0x85eb_ca6b);
+    h = (h ^ (h >> 13)).wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+/// 单个二维分形噪声场
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseField {
+    seed: i32,
+    /// 第一层的频率(每格方块)
+    frequency: f64,
+    octaves: u8,
+    lacunarity: f32,
+    gain: f32,
+    /// 各层振幅之和,用于把结果归一化到 [-1, 1] 附近
+    amplitude: f32,
+}
+
+impl NoiseField {
+    pub fn new(seed: i64, salt: u64, frequency: f64, octaves: u8) -> Self {
+        let octaves = octaves.max(1);
+        let gain = 0.5;
+        NoiseField {
+            seed: derive_seed(seed, salt),
+            frequency,
+            octaves,
+            lacunarity: 2.0,
+            gain,
+            amplitude: (0..octaves).map(|i| gain.powi(i as i32)).sum(),
+        }
+    }
+
+    #[inline]
+    fn simplex(x: f32, z: f32, seed: i32) -> f32 {
+        // SAFETY: 标量实现不依赖任何特定的 CPU 指令集
+        unsafe { scalar::simplex_2d(x, z, seed) }
+    }
+
+    /// 分层叠加的噪声,结果大致在 [-1, 1]
+    pub fn fbm(&self, x: f64, z: f64) -> f32 {
+        let (mut x, mut z) = ((x * self.frequency) as f32, (z * self.frequency) as f32);
+        let mut amp = 1.0;
+        let mut sum = 0.0;
+        for i in 0..self.octaves {
+            // 每层使用不同的种子,避免原点附近各层特征重叠
+            sum += Self::simplex(x, z, self.seed.wrapping_add(i as i32)) * amp;
+            x *= self.lacunarity;
+            z *= self.lacunarity;
+            amp *= self.gain;
+        }
+        (sum / self.amplitude * NORMALIZE).clamp(-1.0, 1.0)
+    }
+
+    /// 山脊噪声:在噪声过零处形成尖锐的脊线,结果在 [0, 1],1 为脊线
+    pub fn ridge(&self, x: f64, z: f64) -> f32 {
+        let (mut x, mut z) = ((x * self.frequency) as f32, (z * self.frequency) as f32);
+        let mut amp = 1.0;
+        let mut sum = 0.0;
+        for i in 0..self.octaves {
+            let n = (Self::simplex(x, z, self.seed.wrapping_add(i as i32)) * NORMALIZE).abs();
+            sum += (1.0 - n.min(1.0)).powi(2) * amp;
+            x *= self.lacunarity;
+            z *= self.lacunarity;
+            amp *= self.gain;
+        }
+        sum / self.amplitude
+    }
+}
+
+/// simdnoise 的单纯形噪声未经缩放(绝对值最大约 0.0221),乘以该系数后落在 [-1, 1]
+const NORMALIZE: f32 = 1.0 / 0.0221;
@@ -0,0 +1,299 @@
+//! 主世界地形生成
+//!
+//! 先由气候噪声算出每一列的地表高度,再按高度与生物群系逐列填充方块:
+//! 底部为基岩,地表以下为石头,表层按生物群系铺草方块/泥土或沙子/沙砾,
+//! 低于海平面的部分灌满水。
+
+use qexed_world::{
+    Chunk, ChunkPos, MIN_Y,
+    biome::{self, Biome},
+    block::{self, BlockState},
+    section::ChunkSection,
+};
+
+use crate::{
+    SEA_LEVEL,
+    climate::{self, Climate, ClimateSampler},
+    noise::{NoiseField, derive_seed, hash},
+};
+
+/// 地表高度的取值范围,保证地表上下都留有余量
+const MIN_SURFACE: i32 = MIN_Y + 16;
+const MAX_SURFACE: i32 = 256;
+/// 表层方块(草方块/泥土、沙子等)的厚度
+const SURFACE_DEPTH: i32 = 4;
+/// 基岩层的最高高度
+const BEDROCK_TOP: i32 = MIN_Y + 4;
+
+/// 某一列的生成结果
+#[derive(Debug, Clone, Copy)]
+struct Column {
+    height: i32,
+    biome: Biome,
+}
+
+/// 基于噪声的主世界生成器
+///
+/// 生成结果只取决于种子与区块坐标,可以在多个线程中同时生成不同的区块。
+#[derive(Debug, Clone)]
+pub struct OverworldGenerator {
+    seed: i64,
+    climate: ClimateSampler,
+    detail: NoiseField,
+    bedrock_seed: i32,
+}
+
+impl OverworldGenerator {
+    pub fn new(seed: i64) -> Self {
+        OverworldGenerator {
+            seed,
+            climate: ClimateSampler::new(seed),
+            detail: NoiseField::new(seed, 6, 1.0 / 64.0, 4),
+            bedrock_seed: derive_seed(seed, 7),
+        }
+    }
+
+    pub fn seed(&self) -> i64 {
+        self.seed
+    }
+
+    pub fn climate(&self, x: i32, z: i32) -> Climate {
+        self.climate.sample(x, z)
+    }
+
+    /// 地表高度(最高的实心方块的 y 坐标)
+    pub fn surface_height(&self, x: i32, z: i32) -> i32 {
+        self.height_from(&self.climate(x, z), x, z)
+    }
+
+    /// 地表所在的生物群系
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let climate = self.climate(x, z);
+        climate::pick_biome(&climate, self.height_from(&climate, x, z))
+    }
+
+    fn height_from(&self, c: &Climate, x: i32, z: i32) -> i32 {
+        let offset = continent_offset(c.continentalness);
+        // 侵蚀度越高地形越平坦,山脊只出现在内陆
+        let roughness = (1.0 - c.erosion) * 0.5;
+        let inland = smoothstep(-0.15, 0.3, c.continentalness);
+        let mountains = c.ridges.powi(3) * 140.0 * roughness * inland;
+        let hills = self.detail.fbm(x as f64, z as f64) * (3.0 + 12.0 * roughness);
+        let height = SEA_LEVEL as f32 + offset + mountains + hills;
+        (height.floor() as i32).clamp(MIN_SURFACE, MAX_SURFACE)
+    }
+
+    fn column(&self, x: i32, z: i32) -> Column {
+        let climate = self.climate(x, z);
+        let height = self.height_from(&climate, x, z);
+        Column {
+            height,
+            biome: climate::pick_biome(&climate, height),
+        }
+    }
+
+    /// 生成一个区块
+    pub fn generate_chunk(&self, pos: ChunkPos) -> Chunk {
+        let (min_x, min_z) = (pos.min_block_x(), pos.min_block_z());
+        let columns: Vec<Column> = (0..256)
+            .map(|i| self.column(min_x + (i & 15), min_z + (i >> 4)))
+            .collect();
+
+        let mut chunk = Chunk::new(pos, biome::PLAINS);
+        let lowest = columns.iter().map(|c| c.height).min().unwrap_or(SEA_LEVEL);
+        let highest = columns
+            .iter()
+            .map(|c| c.height)
+            .max()
+            .unwrap_or(SEA_LEVEL)
+            .max(SEA_LEVEL);
+        for (i, section) in chunk.sections.iter_mut().enumerate() {
+            let bottom = MIN_Y + (i as i32) * 16;
+            let top = bottom + 15;
+            if bottom > highest {
+                // 地表与海面以上全是空气
+            } else if bottom > BEDROCK_TOP && top <= lowest - SURFACE_DEPTH {
+                section.fill(block::STONE);
+            } else {
+                self.fill_section(section, bottom, min_x, min_z, &columns);
+            }
+            // 生物群系以 4x4 列为单位,取每格中间一列的结果
+            for cz in 0..4 {
+                for cx in 0..4 {
+                    let biome = columns[(cz * 4 + 2) * 16 + cx * 4 + 2].biome;
+                    for cy in 0..4 {
+                        section.set_biome(cx * 4, cy * 4, cz * 4, biome);
+                    }
+                }
+            }
+        }
+        chunk.recompute_heightmaps();
+        // 新生成的区块尚未保存过
+        chunk.mark_dirty();
+        chunk
+    }
+
+    fn fill_section(
+        &self,
+        section: &mut ChunkSection,
+        bottom: i32,
+        min_x: i32,
+        min_z: i32,
+        columns: &[Column],
+    ) {
+        for z in 0..16 {
+            for x in 0..16 {
+                let column = columns[z * 16 + x];
+                let (wx, wz) = (min_x + x as i32, min_z + z as i32);
+                for y in 0..16 {
+                    let state = self.block_at(column, wx, bottom + y as i32, wz);
+                    if !block::is_air(state) {
+                        section.set_block(x, y, z, state);
+                    }
+                }
+            }
+        }
+    }
+
+    fn block_at(&self, column: Column, x: i32, y: i32, z: i32) -> BlockState {
+        if y <= BEDROCK_TOP && self.is_bedrock(x, y, z) {
+            return block::BEDROCK;
+        }
+        if y > column.height {
+            return if y <= SEA_LEVEL {
+                block::WATER
+            } else {
+                block::AIR
+            };
+        }
+        if y <= column.height - SURFACE_DEPTH {
+            return block::STONE;
+        }
+        surface_block(column, y)
+    }
+
+    /// 最底层全是基岩,往上逐层变得稀疏
+    fn is_bedrock(&self, x: i32, y: i32, z: i32) -> bool {
+        if y == MIN_Y {
+            return true;
+        }
+        let chance = (BEDROCK_TOP + 1 - y) as u32;
+        hash(self.bedrock_seed, x, y, z) % 5 < chance
+    }
+}
+
+/// 表层方块
+fn surface_block(column: Column, y: i32) -> BlockState {
+    let top = y == column.height;
+    match column.biome {
+        _ if column.height < SEA_LEVEL - climate::DEEP_OCEAN_DEPTH => block::GRAVEL,
+        _ if column.height < SEA_LEVEL => block::SAND,
+        biome::BEACH | biome::SNOWY_BEACH | biome::DESERT => block::SAND,
+        biome::STONY_SHORE | biome::STONY_PEAKS | biome::JAGGED_PEAKS | biome::FROZEN_PEAKS => {
+            block::STONE
+        }
+        _ if top => block::GRASS_BLOCK,
+        _ => block::DIRT,
+    }
+}
+
+/// 大陆性到相对海平面高度的分段线性映射
+fn continent_offset(c: f32) -> f32 {
+    const POINTS: [(f32, f32); 8] = [
+        (-1.0, -45.0),
+        (-0.6, -30.0),
+        (-0.4, -12.0),
+        (-0.25, -2.0),
+        (-0.18, 2.0),
+        (0.2, 12.0),
+        (0.6, 24.0),
+        (1.0, 40.0),
+    ];
+    let i = POINTS.iter().position(|p| c < p.0).unwrap_or(POINTS.len());
+    if i == 0 {
+        return POINTS[0].1;
+    }
+    if i == POINTS.len() {
+        return POINTS[i - 1].1;
+    }
+    let (x0, y0) = POINTS[i - 1];
+    let (x1, y1) = POINTS[i];
+    y0 + (c - x0) / (x1 - x0) * (y1 - y0)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunkGenerator;
+
+    /// 区块内容的 FNV-1a 摘要
+    fn digest(chunk: &Chunk) -> u64 {
+        let mut h = 0xcbf2_9ce4_8422_2325u64;
+        let mut feed = |v: u32| {
+            for b in v.to_le_bytes() {
+                h = (h ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        for section in &chunk.sections {
+            for y in 0..16 {
+                for z in 0..16 {
+                    for x in 0..16 {
+                        feed(section.get_block(x, y, z));
+                    }
+                }
+            }
+            for i in 0..64 {
+                feed(section.get_biome((i & 3) * 4, (i >> 4) * 4, ((i >> 2) & 3) * 4));
+            }
+        }
+        h
+    }
+
+    /// 固定种子下各区块的摘要,修改生成算法时需要同步更新
+    const GOLDEN: [u64; 3] = [0xa468d6201cad89a0, 0xc01e49c38b3de6c4, 0x8761be5323d8ed75];
+
+    #[test]
+    fn fixed_seed_is_stable() {
+        let generator = OverworldGenerator::new(20250101);
+        let positions = [
+            ChunkPos::new(0, 0),
+            ChunkPos::new(-7, 12),
+            ChunkPos::new(300, -41),
+        ];
+        let digests: Vec<u64> = positions
+            .iter()
+            .map(|p| digest(&generator.generate_chunk(*p)))
+            .collect();
+        assert_eq!(digests, GOLDEN);
+
+        // 并行生成与另一个同种子生成器的结果都应一致
+        let again = OverworldGenerator::new(20250101).generate_chunks(&positions);
+        assert!(again.iter().map(digest).eq(digests.iter().copied()));
+        let other = OverworldGenerator::new(20250102).generate_chunk(positions[0]);
+        assert_ne!(digest(&other), digests[0]);
+    }
+
+    #[test]
+    fn terrain_shape() {
+        let generator = OverworldGenerator::new(1);
+        let chunk = generator.generate_chunk(ChunkPos::new(3, 3));
+        assert_eq!(chunk.get_block(0, MIN_Y, 0), block::BEDROCK);
+        for z in 0..16 {
+            for x in 0..16 {
+                let height = generator.surface_height(48 + x as i32, 48 + z as i32);
+                assert!(!block::is_air(chunk.get_block(x, height, z)));
+                let above = chunk.get_block(x, height + 1, z);
+                if height < SEA_LEVEL {
+                    assert_eq!(above, block::WATER);
+                } else {
+                    assert_eq!(above, block::AIR);
+                }
+            }
+        }
+    }
+}